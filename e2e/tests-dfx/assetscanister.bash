@@ -81,6 +81,30 @@ check_permission_failure() {
   assert_contains "Caller does not have Prepare permission"
 }
 
+@test "deploy --dry-run lists changes without uploading them" {
+  install_asset assetscanister
+  dfx_start
+  assert_command dfx deploy
+  echo -n "new file content" > src/e2e_project_frontend/assets/new_file.txt
+  echo -n "changed content" > src/e2e_project_frontend/assets/text-with-newlines.txt
+  rm src/e2e_project_frontend/assets/binary/noise.txt
+
+  assert_command dfx deploy e2e_project_frontend --dry-run
+  assert_contains "+ /new_file.txt (identity 16 bytes)"
+  assert_contains "~ /text-with-newlines.txt (identity 15 bytes"
+  assert_contains "- /binary/noise.txt"
+  assert_contains "1 added, 1 changed, 1 deleted, 31 bytes to upload"
+
+  assert_command dfx deploy e2e_project_frontend --dry-run --output json
+  assert_eq "1" "$(echo "$stdout" | jq '.e2e_project_frontend.added | length')"
+  assert_eq '"/binary/noise.txt"' "$(echo "$stdout" | jq '.e2e_project_frontend.deleted[0]')"
+  assert_eq "31" "$(echo "$stdout" | jq '.e2e_project_frontend.upload_bytes')"
+
+  assert_command dfx canister call --query e2e_project_frontend list '(record {})'
+  assert_not_contains "new_file.txt"
+  assert_contains "noise.txt"
+}
+
 @test "deploy --by-proposal extravaganza" {
   assert_command dfx identity new controller --storage-mode plaintext
   assert_command dfx identity new prepare --storage-mode plaintext
@@ -1316,6 +1340,67 @@ EOF
   assert_match "etag: my-custom-etag"
 }
 
+@test "asset configuration via .ic-assets.json5 - security policy" {
+  rm src/e2e_project_frontend/assets/.ic-assets.json5
+  install_asset assetscanister
+
+  dfx_start
+
+  echo '<html><head><script>alert(1)</script></head></html>' > src/e2e_project_frontend/assets/page.html
+  assert_command dfx deploy
+  assert_match "HTML assets without a Content-Security-Policy in .*: .*/page.html"
+
+  echo '[
+    {
+      "match": "**/*",
+      "security_policy": "hardened",
+      "headers": {
+        "X-Frame-Options": "SAMEORIGIN"
+      }
+    }
+  ]' > src/e2e_project_frontend/assets/.ic-assets.json5
+  assert_command dfx deploy
+  assert_not_contains "without a Content-Security-Policy"
+
+  ID=$(dfx canister id e2e_project_frontend)
+  PORT=$(get_webserver_port)
+  assert_command curl --fail --head "http://localhost:$PORT/page.html?canisterId=$ID"
+  assert_contains "script-src 'self' 'wasm-unsafe-eval' 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI=';"
+  assert_match "x-frame-options: SAMEORIGIN"
+  assert_match "x-content-type-options: nosniff"
+  assert_match "strict-transport-security: max-age=31536000; includeSubDomains"
+}
+
+@test "custom domains are listed in a certified /.well-known/ic-domains" {
+  install_asset assetscanister
+  dfx_start
+
+  jq '.canisters.e2e_project_frontend.custom_domains=["example.com", "www.example.com"]' dfx.json | sponge dfx.json
+  assert_command dfx deploy
+
+  ID=$(dfx canister id e2e_project_frontend)
+  PORT=$(get_webserver_port)
+  assert_command curl --fail "http://localhost:$PORT/.well-known/ic-domains?canisterId=$ID"
+  assert_eq "example.com
+www.example.com"
+
+  assert_command dfx canister custom-domains e2e_project_frontend
+  assert_contains "e2e_project_frontend serves a certified /.well-known/ic-domains that lists example.com, www.example.com."
+
+  assert_command dfx canister custom-domains e2e_project_frontend --dns-records
+  assert_contains "TXT   _canister-id.www.example.com  $ID"
+  assert_contains "CNAME _acme-challenge.example.com  _acme-challenge.example.com.icp2.io"
+
+  # listed in dfx.json, but not deployed yet
+  jq '.canisters.e2e_project_frontend.custom_domains=["example.com", "example.org"]' dfx.json | sponge dfx.json
+  assert_command_fail dfx canister custom-domains e2e_project_frontend
+  assert_contains "example.org is not listed in /.well-known/ic-domains."
+
+  jq '.canisters.e2e_project_frontend.custom_domains=["https://example.com"]' dfx.json | sponge dfx.json
+  assert_command_fail dfx build e2e_project_frontend
+  assert_contains "'https://example.com' is not a valid custom domain."
+}
+
 @test "aliasing rules: <filename> to <filename>.html or <filename>/index.html" {
   echo "test alias file" >'src/e2e_project_frontend/assets/test_alias_file.html'
   mkdir 'src/e2e_project_frontend/assets/index_test'
@@ -1822,3 +1907,36 @@ WARN: {
   assert_command dfx canister call e2e_project_frontend list_permitted '(record { permission = variant { ManagePermissions }; })'
   assert_match "$(dfx identity get-principal)"
 }
+
+@test "scoped permissions limit changes to their paths" {
+  dfx_start
+  mkdir -p src/e2e_project_frontend/assets/blog
+  echo "first post" > src/e2e_project_frontend/assets/blog/post.txt
+  assert_command dfx deploy
+
+  dfx identity new marketing --storage-mode plaintext
+  MARKETING="$(dfx --identity marketing identity get-principal)"
+
+  assert_command dfx canister asset-permissions e2e_project_frontend --grant marketing --scope /blog/
+  assert_command dfx canister asset-permissions e2e_project_frontend --permission commit
+  assert_contains "$MARKETING (scopes: /blog/)"
+  assert_contains "$(dfx identity get-principal)"
+  assert_command dfx canister call e2e_project_frontend list_permitted '(record { permission = variant { Commit }; })'
+  assert_not_contains "$MARKETING"
+
+  echo "edited post" > src/e2e_project_frontend/assets/blog/post.txt
+  assert_command dfx deploy e2e_project_frontend --identity marketing
+  assert_command curl --fail -s "http://localhost:$(get_webserver_port)/blog/post.txt?canisterId=$(dfx canister id e2e_project_frontend)"
+  assert_eq "edited post"
+
+  echo "not marketing's" > src/e2e_project_frontend/assets/index2.txt
+  assert_command_fail dfx deploy e2e_project_frontend --identity marketing
+  assert_contains "Caller does not have Commit permission for /index2.txt"
+
+  assert_command_fail dfx canister asset-permissions e2e_project_frontend --grant marketing --permission manage-permissions --scope /blog/
+  assert_contains "ManagePermissions permission cannot be scoped"
+
+  assert_command dfx canister asset-permissions e2e_project_frontend --revoke marketing
+  assert_command dfx canister asset-permissions e2e_project_frontend --permission commit
+  assert_not_contains "$MARKETING"
+}
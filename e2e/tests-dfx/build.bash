@@ -38,6 +38,63 @@ teardown() {
   assert_match "$ID"
 }
 
+@test "verifies the hash of a custom canister downloaded from a url" {
+  install_asset wasm/identity
+  mkdir -p www/wasm
+  mv main.wasm www/wasm/
+  mv main.did www/wasm
+  start_webserver --directory www
+  dfx_start
+
+  dfx_new
+
+  jq '.canisters={}' dfx.json | sponge dfx.json
+
+  jq '.canisters.e2e_project.candid="http://localhost:'"$E2E_WEB_SERVER_PORT"'/wasm/main.did"' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project.wasm="http://localhost:'"$E2E_WEB_SERVER_PORT"'/wasm/main.wasm"' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project.type="custom"' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project.wasm_hash="'"$(printf '0%.0s' {1..64})"'"' dfx.json | sponge dfx.json
+
+  assert_command_fail dfx deploy
+  assert_contains "but dfx.json expects 0000000000000000000000000000000000000000000000000000000000000000"
+
+  WASM_HASH=$(sha256sum ../www/wasm/main.wasm | cut -d ' ' -f 1)
+  jq '.canisters.e2e_project.wasm_hash="'"$WASM_HASH"'"' dfx.json | sponge dfx.json
+  assert_command dfx deploy
+}
+
+@test "records custom canister downloads in dfx.lock.json and reuses them offline" {
+  install_asset wasm/identity
+  mkdir -p www/wasm
+  mv main.wasm www/wasm/
+  mv main.did www/wasm
+  start_webserver --directory www
+  dfx_start
+
+  dfx_new
+
+  jq '.canisters={}' dfx.json | sponge dfx.json
+
+  WASM_URL="http://localhost:$E2E_WEB_SERVER_PORT/wasm/main.wasm"
+  jq '.canisters.e2e_project.candid="http://localhost:'"$E2E_WEB_SERVER_PORT"'/wasm/main.did"' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project.wasm="'"$WASM_URL"'"' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project.type="custom"' dfx.json | sponge dfx.json
+
+  assert_command dfx deploy
+  WASM_HASH=$(sha256sum ../www/wasm/main.wasm | cut -d ' ' -f 1)
+  assert_eq "\"$WASM_HASH\"" "$(jq --arg url "$WASM_URL" '.downloads[$url].sha256' dfx.lock.json)"
+
+  # The server no longer has the files, so they must come from the cache.
+  mv ../www/wasm ../www/wasm-moved
+  assert_command dfx build e2e_project
+
+  mv ../www/wasm-moved ../www/wasm
+  echo "changed" >> ../www/wasm/main.wasm
+  rm -rf "$DFX_CACHE_ROOT/.cache/dfinity/downloads"
+  assert_command_fail dfx build e2e_project
+  assert_contains "changed since it was recorded in dfx.lock.json"
+}
+
 @test "report an error if a canister defines both a wasm url and a build step" {
   install_asset wasm/identity
   mkdir -p www/wasm
@@ -209,6 +266,43 @@ teardown() {
   assert_match "warning \[M0145\], this pattern of type"
 }
 
+@test "build applies the Motoko compiler options of dfx.json" {
+  install_asset warning
+  jq '.canisters.e2e_project_backend.moc.warnings="error"' dfx.json | sponge dfx.json
+  dfx_start
+  dfx canister create --all
+  assert_command_fail dfx build e2e_project_backend
+  assert_match "warning \[M0145\], this pattern of type"
+
+  jq '.profile="Release" | .canisters.e2e_project_backend.moc.profiles.Release.warnings="hide"' dfx.json | sponge dfx.json
+  assert_command dfx build e2e_project_backend
+  assert_not_contains "M0145"
+
+  jq '.canisters.e2e_project_backend.moc.public_metadata=["motoko:compiler"]' dfx.json | sponge dfx.json
+  dfx deploy e2e_project_backend
+  assert_command dfx canister metadata e2e_project_backend motoko:compiler
+  assert_match "0\.[0-9]+\.[0-9]+"
+
+  jq '.canisters.e2e_project_backend.moc.gc="none"' dfx.json | sponge dfx.json
+  assert_command_fail dfx build e2e_project_backend
+  assert_match "unknown variant"
+
+  jq 'del(.canisters.e2e_project_backend.moc.gc) | .canisters.e2e_project_backend.moc.warning="error"' dfx.json | sponge dfx.json
+  assert_command_fail dfx build e2e_project_backend
+  assert_match "unknown field .warning."
+
+  jq 'del(.canisters.e2e_project_backend.moc.warning) | .canisters.e2e_project_backend.moc.profiles.Release.stripdebug=true' dfx.json | sponge dfx.json
+  assert_command_fail dfx build e2e_project_backend
+  assert_match "unknown field .stripdebug."
+}
+
+@test "Motoko compiler options are rejected for other canister types" {
+  install_asset custom_canister
+  jq '.canisters.custom.moc.warnings="error"' dfx.json | sponge dfx.json
+  assert_command_fail dfx build custom
+  assert_contains "Canister 'custom' has Motoko compiler options (moc), but it is not a Motoko canister"
+}
+
 @test "build fails on unknown imports" {
   install_asset import_error
   dfx_start
@@ -285,3 +285,67 @@ If you want to get reliable result, you can make an update call to the secure al
 #   # shellcheck disable=SC2154
 #   assert_contains "bitcoin_get_utxos_query $WARNING bitcoin_get_utxos" "$stderr"
 }
+
+@test "repl runs a script of calls with variables" {
+  install_asset counter
+  dfx_start
+  dfx deploy
+
+  cat > session.repl <<'SCRIPT'
+# Methods are called as queries or updates as the interface says.
+inc
+let count = inc_read
+write ($count)
+inc_read
+write ($_)
+read
+SCRIPT
+  assert_command dfx canister repl hello_backend --script session.repl
+  assert_contains "(2 : nat)"
+  assert_command dfx canister call hello_backend read
+  assert_eq "(3 : nat)"
+
+  assert_command_fail dfx canister repl hello_backend --script <(echo "no_such_method")
+  assert_contains "has no method no_such_method"
+
+  assert_command dfx canister repl hello_backend <<< '.methods'
+  assert_contains "inc_read : () -> (nat)"
+}
+
+@test "run-script checks replies and writes a report" {
+  install_asset counter
+  dfx_start
+  dfx deploy
+
+  cat > smoke.json <<'SCRIPT'
+{
+  "canister": "hello_backend",
+  "steps": [
+    { "method": "write", "args": "(41)" },
+    { "name": "increments", "method": "inc_read", "expect": "(42)", "capture": "count" },
+    { "method": "read", "expect": "($count)" },
+    { "method": "read", "expect_pattern": "^\\(42 : nat\\)$" },
+    { "name": "users cannot call raw_rand", "canister": "aaaaa-aa", "method": "raw_rand", "expect_reject": "." }
+  ]
+}
+SCRIPT
+  assert_command dfx canister run-script smoke.json --report report.xml
+  assert_contains "All 5 steps passed."
+  assert_command cat report.xml
+  assert_contains '<testcase name="increments" classname="hello_backend.inc_read"'
+  assert_not_contains "<failure"
+
+  cat > failing.json <<'SCRIPT'
+{
+  "canister": "hello_backend",
+  "steps": [
+    { "name": "wrong count", "method": "read", "expect": "(0)" },
+    { "method": "inc" }
+  ]
+}
+SCRIPT
+  assert_command_fail dfx canister run-script failing.json --report report.json --report-format json --fail-fast
+  assert_contains "FAILED wrong count: Expected (0 : nat), but the reply is (42 : nat)."
+  assert_contains "1 of 2 steps failed."
+  assert_eq "$(jq -r '.steps[0].passed, .skipped, .steps[1].skipped' report.json | tr '\n' ' ')" "false 1 true "
+}
@@ -0,0 +1,52 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+  standard_setup
+
+  dfx_new hello
+}
+
+teardown() {
+  dfx_stop
+
+  standard_teardown
+}
+
+@test "candid encode and decode with the types of a Candid file" {
+  echo 'service : { register : (record { name : text; age : nat8 }) -> (opt nat32) }' > service.did
+
+  assert_command dfx candid encode --candid service.did --method register '(record { name = "Ada"; age = 36 })' --output-file message.bin
+  assert_command dfx candid decode --candid service.did --method register message.bin
+  assert_contains 'name = "Ada"'
+  assert_contains "age = 36 : nat8"
+
+  assert_command dfx candid encode --candid service.did --method register --rets '(opt 7)'
+  assert_eq "4449444c016e7901000107000000"
+  assert_command dfx candid decode --candid service.did --method register --rets "0x$stdout"
+  assert_contains "opt"
+  assert_contains "7 : nat32"
+
+  assert_command_fail dfx candid encode --candid service.did --method register '("Ada")'
+  assert_command_fail dfx candid decode --candid service.did --method unregister 4449444c0000
+  assert_contains "Cannot find the type of method unregister."
+}
+
+@test "candid decode with the interface of a canister" {
+  install_asset method_signatures
+  dfx_start
+  dfx deploy
+
+  assert_command dfx canister call hello_backend returns_object --output raw
+  message="$stdout"
+
+  assert_command dfx candid decode "$message"
+  assert_not_contains "foo"
+
+  assert_command dfx candid decode --canister hello_backend --method returns_object --rets "$message"
+  assert_contains 'foo = "baz"'
+
+  assert_command dfx candid encode --canister hello_backend --method returns_int32 '(42)'
+  assert_eq "4449444c0001752a000000"
+}
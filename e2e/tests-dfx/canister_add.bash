@@ -0,0 +1,65 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+  standard_setup
+
+  dfx_new
+}
+
+teardown() {
+  dfx_stop
+
+  standard_teardown
+}
+
+@test "dfx canister add creates canisters from the project templates" {
+  assert_command dfx canister add counter --dependency e2e_project_backend --declarations src/declarations/counter
+  assert_file_exists src/counter/main.mo
+  assert_command jq -r '.canisters.counter.type' dfx.json
+  assert_eq "motoko"
+  assert_command jq -r '.canisters.counter.main' dfx.json
+  assert_eq "src/counter/main.mo"
+  assert_command jq -c '.canisters.counter.dependencies' dfx.json
+  assert_eq '["e2e_project_backend"]'
+  assert_command jq -r '.canisters.counter.declarations.output' dfx.json
+  assert_eq "src/declarations/counter"
+
+  assert_command dfx canister add www --type assets
+  assert_file_exists src/www/assets/sample-asset.txt
+  assert_command jq -r '.canisters.www.type' dfx.json
+  assert_eq "assets"
+  assert_command jq -r '.canisters.www.dependencies' dfx.json
+  assert_eq "null"
+
+  assert_command dfx canister add proxy --type rust
+  assert_file_exists src/proxy/Cargo.toml
+  assert_command grep '"src/proxy"' Cargo.toml
+  assert_command dfx canister add other_proxy --type rust
+  assert_command grep '"src/proxy"' Cargo.toml
+  assert_command grep '"src/other_proxy"' Cargo.toml
+
+  dfx_start
+  assert_command dfx deploy counter
+  assert_command dfx canister call counter greet '("you")'
+  assert_eq '("Hello, you!")'
+}
+
+@test "dfx canister add rejects existing canisters and unknown types" {
+  assert_command_fail dfx canister add e2e_project_backend
+  assert_contains "already exists in dfx.json"
+  assert_command_fail dfx canister add counter --dependency nonexistent
+  assert_contains "Canister 'nonexistent' does not exist in dfx.json."
+  assert_command_fail dfx canister add counter --type nonexistent
+  assert_contains "no extension with that name is installed"
+  assert_command_fail dfx canister add lib/counter
+  assert_contains "Invalid character"
+  assert_command_fail dfx canister add ../counter
+  assert_contains "Must start with a letter."
+
+  assert_command dfx canister add counter --dry-run
+  assert_file_not_exists src/counter/main.mo
+  assert_command jq -r '.canisters.counter' dfx.json
+  assert_eq "null"
+}
@@ -215,3 +215,40 @@ set_shared_local_network_canister_http_empty() {
   assert_contains "Git Operations"
   assert_contains "API Requests"
 }
+
+@test "dfx start --canister-http-mock answers outcalls from fixtures" {
+  dfx_new
+  install_asset canister_http
+  cat > outcalls.json <<'FIXTURES'
+{
+  "outcalls": [
+    { "url": "https://api\\.example\\.com/price\\?.*", "method": "GET", "body": { "price": 42 } },
+    { "url": "https://down\\.example\\.com/.*", "error": "unavailable" }
+  ]
+}
+FIXTURES
+
+  dfx_start --canister-http-mock outcalls.json
+  assert_file_empty "$E2E_SHARED_LOCAL_NETWORK_DATA_DIRECTORY/ic-https-outcalls-adapter-pid"
+
+  dfx deploy
+
+  assert_command dfx canister call e2e_project_backend get_url '("api.example.com","https://api.example.com/price?coin=icp")'
+  assert_contains '{\"price\":42}'
+
+  assert_command_fail dfx canister call e2e_project_backend get_url '("down.example.com","https://down.example.com/")'
+  assert_command_fail dfx canister call e2e_project_backend get_url '("example.org","https://example.org/")'
+
+  LOG="$E2E_SHARED_LOCAL_NETWORK_DATA_DIRECTORY/canister-http-mock-log.jsonl"
+  assert_command jq -r '[.url, (.fixture | tostring)] | join(" ")' "$LOG"
+  assert_contains "https://api.example.com/price?coin=icp 0"
+  assert_contains "https://down.example.com/ 1"
+  assert_contains "https://example.org/ null"
+}
+
+@test "dfx start --canister-http-mock rejects an invalid fixture file" {
+  echo '{ "outcalls": [ { "url": "(" } ] }' > outcalls.json
+
+  assert_command_fail dfx start --background --canister-http-mock outcalls.json
+  assert_contains "Fixture 0: invalid url pattern."
+}
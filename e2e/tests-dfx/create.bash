@@ -333,3 +333,26 @@ teardown() {
   assert_contains 'WASM Memory Limit: 1_073_741_824'
   assert_contains 'Freezing threshold: 604_800'
 }
+
+@test "create with per-network dfx.json settings" {
+  jq '.canisters.e2e_project_backend.initialization_values={
+    "compute_allocation": 5,
+    "freezing_threshold": "7days",
+  }' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project_backend.networks.actuallylocal.initialization_values.freezing_threshold="1day"' dfx.json | sponge dfx.json
+  dfx_start
+  setup_actuallylocal_project_network
+
+  assert_command dfx deploy e2e_project_backend --no-wallet
+  assert_command dfx canister status e2e_project_backend
+  assert_contains 'Freezing threshold: 604_800'
+
+  assert_command dfx deploy e2e_project_backend --no-wallet --network actuallylocal
+  assert_command dfx canister status e2e_project_backend --network actuallylocal
+  assert_contains 'Compute allocation: 5'
+  assert_contains 'Freezing threshold: 86_400'
+
+  jq '.canisters.e2e_project_backend.networks.actuallylocal.main="src/other.mo"' dfx.json | sponge dfx.json
+  assert_command_fail dfx canister status e2e_project_backend --network actuallylocal
+  assert_contains "unknown field \`main\`"
+}
@@ -39,6 +39,80 @@ teardown() {
   assert_not_match "Module hash.*is already installed"
 }
 
+@test "deploy --plan shows what would happen without changing anything" {
+  dfx_start
+  assert_command dfx deploy hello_backend --plan
+  assert_contains "hello_backend (not created):"
+  assert_contains "create with the default amount of cycles"
+  assert_command_fail dfx canister id hello_backend
+
+  assert_command dfx deploy hello_backend
+  assert_command dfx deploy hello_backend --plan
+  assert_match "skip: module [0-9a-f]* is already installed"
+
+  echo 'actor { public query func other() : async Text { "other" } };' > src/hello_backend/main.mo
+  assert_command dfx deploy hello_backend --plan --output json
+  echo "$stdout" > plan.json
+  assert_eq '"upgrade"' "$(jq '.canisters[0].action' plan.json)"
+  assert_eq '"incompatible"' "$(jq '.canisters[0].candid_compatibility.result' plan.json)"
+
+  assert_command dfx deploy --apply-plan plan.json
+  assert_command dfx canister call hello_backend other
+  assert_eq '("other")'
+}
+
+@test "deploy --plan hashes the modules of existing canisters without writing project files" {
+  dfx_start
+  assert_command dfx deploy hello_backend
+  assert_command dfx deploy --plan --output json --output-env-file plan.env
+  echo "$stdout" > plan.json
+  assert_file_not_exists plan.env
+  assert_match "^[0-9a-f]{64}$" "$(jq -r '.canisters[] | select(.name == "hello_backend") | .module_hash' plan.json)"
+  assert_eq "null" "$(jq '.canisters[] | select(.name == "hello_frontend") | .module_hash' plan.json)"
+
+  assert_command dfx deploy --apply-plan plan.json
+  assert_command dfx canister id hello_frontend
+}
+
+@test "deploy --apply-plan fails if the project changed since the plan was made" {
+  dfx_start
+  assert_command dfx deploy hello_backend
+  echo 'actor { public query func other() : async Text { "other" } };' > src/hello_backend/main.mo
+  assert_command dfx deploy hello_backend --plan --output json
+  echo "$stdout" > plan.json
+
+  echo 'actor { public query func other() : async Text { "changed" } };' > src/hello_backend/main.mo
+  assert_command_fail dfx deploy --apply-plan plan.json
+  assert_contains "is not the planned module"
+}
+
+@test "deploy --plan shows and --apply-plan applies settings that differ from dfx.json" {
+  dfx_start
+  assert_command dfx deploy hello_backend
+  jq '.canisters.hello_backend.initialization_values.freezing_threshold="1day"' dfx.json | sponge dfx.json
+
+  assert_command dfx deploy hello_backend --plan
+  assert_contains "update settings"
+  assert_contains "freezing_threshold: 2592000 -> 86400"
+
+  assert_command dfx deploy hello_backend --plan --output json
+  echo "$stdout" > plan.json
+  assert_eq '"86400"' "$(jq '.canisters[0].settings_changes.freezing_threshold.planned' plan.json)"
+  assert_command dfx deploy --apply-plan plan.json
+  assert_command dfx canister status hello_backend
+  assert_contains "Freezing threshold: 86_400"
+
+  assert_command dfx deploy hello_backend --plan
+  assert_not_contains "update settings"
+
+  assert_command dfx deploy hello_backend --plan --output json
+  echo "$stdout" > plan.json
+  dfx identity new --storage-mode plaintext alice
+  assert_command dfx canister update-settings hello_backend --add-controller "$(dfx identity get-principal --identity alice)"
+  assert_command_fail dfx deploy --apply-plan plan.json
+  assert_contains "The controllers of canister hello_backend have changed."
+}
+
 @test "deploy without --no-wallet sets wallet and self as the controllers" {
   dfx_start
   WALLET=$(dfx identity get-wallet)
@@ -241,3 +315,90 @@ teardown() {
   assert_command dfx canister call fake-cmc last_create_canister_args
   assert_contains 'subnet_type = opt "custom_subnet_type"'
 }
+
+@test "deploy runs the hooks of the project and of canisters with the deployment as JSON" {
+  dfx_start
+  echo 'cat >> hooks.log; echo >> hooks.log' > log-hook.sh
+  jq '.hooks.pre_deploy="sh log-hook.sh" | .hooks.post_deploy="sh log-hook.sh"' dfx.json | sponge dfx.json
+  jq '.canisters.hello_backend.hooks.pre_deploy="sh log-hook.sh" | .canisters.hello_backend.hooks.pre_upgrade_check="sh log-hook.sh"' dfx.json | sponge dfx.json
+
+  assert_command dfx deploy hello_backend
+  BACKEND_ID=$(dfx canister id hello_backend)
+  assert_eq "pre_deploy pre_deploy post_deploy" "$(jq -r .hook hooks.log | tr '\n' ' ' | xargs)"
+  assert_eq "install" "$(jq -r 'select(.canister == "hello_backend") | .install_mode' hooks.log)"
+  assert_eq "$BACKEND_ID" "$(jq -r 'select(.hook == "post_deploy") | .canister_ids.hello_backend' hooks.log)"
+  assert_eq "local" "$(jq -r 'select(.hook == "post_deploy") | .network' hooks.log)"
+  assert_eq "$(dfx identity get-principal)" "$(jq -r 'select(.hook == "post_deploy") | .identity' hooks.log)"
+
+  rm hooks.log
+  assert_command dfx deploy hello_backend --upgrade-unchanged
+  assert_eq "pre_deploy pre_upgrade_check pre_deploy post_deploy" "$(jq -r .hook hooks.log | tr '\n' ' ' | xargs)"
+  assert_eq "upgrade" "$(jq -r 'select(.hook == "pre_upgrade_check") | .install_mode' hooks.log)"
+  OLD_HASH=$(jq -r 'select(.hook == "pre_upgrade_check") | .old_module_hash' hooks.log)
+  assert_eq "$OLD_HASH" "$(jq -r 'select(.hook == "pre_upgrade_check") | .new_module_hash' hooks.log)"
+
+  # dfx canister install only runs the hooks of the canister
+  rm hooks.log
+  jq '.hooks.pre_upgrade_check="sh log-hook.sh"' dfx.json | sponge dfx.json
+  assert_command dfx canister install hello_backend --mode upgrade --upgrade-unchanged
+  assert_eq "pre_upgrade_check pre_deploy" "$(jq -r .hook hooks.log | tr '\n' ' ' | xargs)"
+  assert_eq "hello_backend" "$(jq -r 'select(.hook == "pre_upgrade_check") | .canister' hooks.log)"
+}
+
+@test "a failing hook aborts the deployment and runs the on_failure hooks" {
+  dfx_start
+  jq '.canisters.hello_backend.hooks.pre_deploy="sh -c \"exit 3\""' dfx.json | sponge dfx.json
+  jq '.hooks.on_failure="sh -c \"cat > failure.json\""' dfx.json | sponge dfx.json
+
+  assert_command_fail dfx deploy hello_backend
+  assert_contains "The pre_deploy hook \`sh -c \"exit 3\"\` failed with exit code 3, so the deployment was aborted."
+  assert_command dfx canister info hello_backend
+  assert_contains "Module hash: None"
+  assert_eq "on_failure" "$(jq -r .hook failure.json)"
+  assert_contains "failed with exit code 3" "$(jq -r .error failure.json)"
+}
+
+@test "deploy and canister commands select canisters by group and tag" {
+  dfx_start
+  jq '.groups.backend=["hello_backend"] | .groups.broken=["missing"] | .canisters.hello_frontend.tags=["web"]' dfx.json | sponge dfx.json
+
+  assert_command dfx deploy --group backend
+  assert_contains "Deploying canisters hello_backend."
+  assert_command dfx canister id hello_backend
+  assert_command_fail dfx canister id hello_frontend
+
+  assert_command dfx canister status --group backend
+  assert_contains "Canister status call result for hello_backend."
+  assert_not_contains "hello_frontend"
+
+  assert_command dfx deploy --group backend --plan
+  assert_contains "hello_backend"
+  assert_not_contains "hello_frontend"
+  assert_command dfx deploy --group backend --plan --output json
+  echo "$stdout" > plan.json
+  assert_eq '["hello_backend"]' "$(jq -c .selected_canisters plan.json)"
+  assert_command dfx deploy --apply-plan plan.json
+  assert_command_fail dfx canister id hello_frontend
+
+  assert_command dfx deploy --tag web
+  assert_command dfx canister id hello_frontend
+
+  assert_command dfx canister stop --tag web
+  assert_command dfx canister status hello_frontend
+  assert_contains "Status: Stopped"
+  assert_command dfx canister status hello_backend
+  assert_contains "Status: Running"
+
+  assert_command_fail dfx canister status --group frontend
+  assert_contains "Group 'frontend' is not defined in the groups of dfx.json."
+  assert_command_fail dfx canister status --group broken
+  assert_contains "Canister 'missing' of group 'broken' is not defined in dfx.json."
+  assert_command_fail dfx canister status --tag none
+  assert_contains "No canister in dfx.json has the tag 'none'."
+  assert_command_fail dfx canister status hello_backend --group backend
+  assert_contains "cannot be used with"
+
+  assert_command dfx canister delete --group backend --yes
+  assert_command_fail dfx canister id hello_backend
+  assert_command dfx canister id hello_frontend
+}
@@ -155,3 +155,33 @@ assert_dotenv_contains_all_variables_after_command() {
   assert_contains "foo=bar" "$env"
   assert_contains "baz=quux" "$env"
 }
+
+@test "passes env from dfx.json to post-install tasks, without writing secrets" {
+  dfx_start
+  cat > print-env.sh <<'SH'
+#!/usr/bin/env bash
+echo "$API_URL $API_TOKEN $GREETING" > env-out.txt
+SH
+  chmod +x print-env.sh
+  echo "API_TOKEN=from-dotenv" > .env.local
+  jq '.env.API_URL={"value":"http://localhost:3000","networks":{"ic":"https://api.example.com"}}' dfx.json | sponge dfx.json
+  jq '.env.API_TOKEN={"value":"token-${API_TOKEN}","secret":true}' dfx.json | sponge dfx.json
+  jq '.env.GREETING="project"' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project_backend.env.GREETING="hello ${USER_NAME}"' dfx.json | sponge dfx.json
+  jq '.canisters.e2e_project_backend.post_install="./print-env.sh"' dfx.json | sponge dfx.json
+
+  USER_NAME=alice assert_command dfx deploy e2e_project_backend
+  assert_eq "http://localhost:3000 token-from-dotenv hello alice" "$(< env-out.txt)"
+  env=$(< .env)
+  assert_contains "API_URL='http://localhost:3000'" "$env"
+  assert_contains "GREETING='hello alice'" "$env"
+  assert_not_contains "API_TOKEN" "$env"
+  assert_not_contains "from-dotenv" "$env"
+
+  # the shell takes precedence over .env.<network>
+  API_TOKEN=from-shell USER_NAME=bob assert_command dfx deploy e2e_project_backend --mode reinstall --yes
+  assert_eq "http://localhost:3000 token-from-shell hello bob" "$(< env-out.txt)"
+
+  assert_command_fail dfx deploy e2e_project_backend --mode reinstall --yes
+  assert_contains "Variable 'USER_NAME' is not set"
+}
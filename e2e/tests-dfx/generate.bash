@@ -139,6 +139,23 @@ teardown() {
   assert_command dfx generate
 }
 
+@test "dfx generate selects canisters by group and tag" {
+  dfx_new hello
+  jq '.groups.backend=["hello_backend"] | .canisters.hello_backend.tags=["api"]' dfx.json | sponge dfx.json
+
+  assert_command dfx generate --group backend
+  assert_file_exists "src/declarations/hello_backend/hello_backend.did"
+
+  rm -r src/declarations
+  assert_command dfx generate --tag api
+  assert_file_exists "src/declarations/hello_backend/hello_backend.did"
+
+  assert_command_fail dfx generate --group frontend
+  assert_contains "Group 'frontend' is not defined in the groups of dfx.json."
+  assert_command_fail dfx generate hello_backend --tag api
+  assert_contains "cannot be used with"
+}
+
 @test "dfx generate --network is still valid" {
   # The option has no effect, but is still accepted to not break existing scripts
   dfx_new hello
@@ -441,6 +441,33 @@ default'
   assert_eq 'alice'
 }
 
+@test "dfx identity whoami: uses the identity configured for the network" {
+  dfx_new
+  assert_command dfx identity new charlie --storage-mode plaintext
+  assert_command dfx identity new alice --storage-mode plaintext
+  jq '.networks.ic.identity="charlie"' dfx.json | sponge dfx.json
+
+  assert_command dfx identity whoami
+  assert_eq 'default' "$stdout"
+  assert_command dfx identity whoami --network ic
+  assert_eq 'charlie' "$stdout"
+  assert_match "Identity configured for network 'ic' in .*dfx.json." "$stderr"
+  assert_command dfx identity get-principal --network ic
+  assert_eq "$(dfx identity get-principal --identity charlie)" "$stdout"
+
+  # --identity and DFX_IDENTITY take precedence
+  assert_command dfx identity whoami --network ic --identity alice
+  assert_eq 'alice' "$stdout"
+  DFX_IDENTITY=alice assert_command dfx identity whoami --network ic
+  assert_eq 'alice' "$stdout"
+  assert_command dfx identity whoami --network ic --identity alice -v
+  assert_match 'Identity selected with --identity or DFX_IDENTITY.' "$stderr"
+
+  jq '.networks.ic.identity="nobody"' dfx.json | sponge dfx.json
+  assert_command_fail dfx identity whoami --network ic
+  assert_match "The identity configured for network 'ic' must exist"
+}
+
 @test "dfx (command) --identity does not persistently change the selected identity" {
   assert_command dfx identity whoami
   assert_eq 'default' "$stdout"
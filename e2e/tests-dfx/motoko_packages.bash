@@ -0,0 +1,109 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+  standard_setup
+
+  dfx_new
+}
+
+teardown() {
+  stop_webserver
+  dfx_stop
+
+  standard_teardown
+}
+
+write_package() {
+  mkdir -p "$1"
+  cat > "$1/lib.mo" <<MOTOKO
+module {
+  public func answer() : Nat { $2 };
+}
+MOTOKO
+}
+
+use_package() {
+  cat > src/e2e_project_backend/main.mo <<'MOTOKO'
+import Answer "mo:answer";
+import Nat "mo:base/Nat";
+
+actor {
+  public query func answer() : async Text {
+    Nat.toText(Answer.answer())
+  };
+};
+MOTOKO
+}
+
+@test "canisters import local Motoko packages and are rebuilt when they change" {
+  write_package answer 42
+  use_package
+  jq '.defaults.build.packages.answer.path="answer"' dfx.json | sponge dfx.json
+
+  dfx_start
+  assert_command dfx deploy e2e_project_backend
+  assert_command dfx canister call e2e_project_backend answer
+  assert_eq '("42")'
+
+  write_package answer 43
+  assert_command dfx deploy e2e_project_backend
+  assert_command dfx canister call e2e_project_backend answer
+  assert_eq '("43")'
+
+  assert_command dfx motoko deps
+  assert_contains "canister e2e_project_backend"
+  assert_contains "package answer (answer)"
+  assert_command dfx motoko deps --json
+  assert_eq '["answer","base"]' "$(echo "$stdout" | jq -c '.canisters.e2e_project_backend')"
+}
+
+@test "Motoko packages are downloaded, recorded in dfx.lock.json and built offline" {
+  write_package ../www/answer-1.0.0/src 42
+  tar -czf ../www/answer.tar.gz -C ../www answer-1.0.0
+  ARCHIVE_HASH=$(sha256sum ../www/answer.tar.gz | cut -d ' ' -f 1)
+  start_webserver --directory ../www
+  ARCHIVE_URL="http://localhost:$E2E_WEB_SERVER_PORT/answer.tar.gz"
+  use_package
+  jq '.defaults.build.packages.answer.url="'"$ARCHIVE_URL"'"' dfx.json | sponge dfx.json
+
+  assert_command dfx motoko deps
+  assert_contains "sha256 $ARCHIVE_HASH"
+  assert_eq "\"$ARCHIVE_HASH\"" "$(jq --arg url "$ARCHIVE_URL" '.downloads[$url].sha256' dfx.lock.json)"
+
+  # The server no longer has the archive, so the package must come from the cache.
+  rm ../www/answer.tar.gz
+  dfx_start
+  assert_command dfx deploy e2e_project_backend
+  assert_command dfx canister call e2e_project_backend answer
+  assert_eq '("42")'
+}
+
+@test "reports Motoko packages with a wrong hash" {
+  write_package ../www/answer-1.0.0/src 42
+  tar -czf ../www/answer.tar.gz -C ../www answer-1.0.0
+  start_webserver --directory ../www
+  jq '.defaults.build.packages.answer.url="http://localhost:'"$E2E_WEB_SERVER_PORT"'/answer.tar.gz"' dfx.json | sponge dfx.json
+  jq '.defaults.build.packages.answer.sha256="0000000000000000000000000000000000000000000000000000000000000000"' dfx.json | sponge dfx.json
+
+  assert_command_fail dfx motoko deps
+  assert_contains "but dfx.json expects 0000000000000000000000000000000000000000000000000000000000000000"
+}
+
+@test "canisters are rebuilt when a Motoko package switches back to a downloaded version" {
+  write_package ../www/answer-1.0.0/src 42
+  tar -czf ../www/answer-1.tar.gz -C ../www answer-1.0.0
+  write_package ../www/answer-2.0.0/src 43
+  tar -czf ../www/answer-2.tar.gz -C ../www answer-2.0.0
+  start_webserver --directory ../www
+  use_package
+
+  dfx_start
+  for version in 1 2 1; do
+    jq '.defaults.build.packages.answer.url="http://localhost:'"$E2E_WEB_SERVER_PORT"'/answer-'"$version"'.tar.gz"' dfx.json | sponge dfx.json
+    assert_command dfx deploy e2e_project_backend
+    assert_command dfx canister call e2e_project_backend answer
+    assert_eq "(\"4$((version + 1))\")"
+  done
+}
@@ -111,3 +111,96 @@ teardown() {
   assert_not_contains "identity is not stored securely"
   assert_eq "No problems found"
 }
+
+@test "network snapshot save and restore" {
+  dfx_start
+  assert_command dfx deploy e2e_project_backend
+  ID=$(dfx canister id e2e_project_backend)
+  # dfx_stop would delete .dfx, which holds the canister ids
+  assert_command dfx stop
+
+  assert_command dfx network snapshot save seeded
+  assert_command dfx network snapshot list
+  assert_match "seeded"
+
+  dfx_start --clean
+  assert_command_fail dfx network snapshot restore seeded
+  assert_match "The local network is running"
+  assert_command_fail dfx canister id e2e_project_backend
+  dfx_stop
+
+  assert_command dfx network snapshot restore seeded
+  dfx_start
+  assert_command dfx canister id e2e_project_backend
+  assert_eq "$ID"
+  assert_command dfx canister call e2e_project_backend greet '("snapshot")'
+  assert_match "Hello, snapshot!"
+}
+
+@test "dfx start --save-state saves a snapshot when the network stops" {
+  dfx_start --save-state on-stop
+  assert_command dfx deploy e2e_project_backend
+  ID=$(dfx canister id e2e_project_backend)
+  assert_command dfx stop
+  assert_command dfx network snapshot list
+  assert_match "on-stop"
+
+  dfx_start --clean
+  assert_command_fail dfx canister id e2e_project_backend
+  dfx_stop
+
+  assert_command dfx network snapshot restore on-stop
+  dfx_start
+  assert_command dfx canister id e2e_project_backend
+  assert_eq "$ID"
+  assert_command dfx canister call e2e_project_backend greet '("saved")'
+  assert_match "Hello, saved!"
+}
+
+@test "dfx stop does not wait for a snapshot that a failed save left behind" {
+  dfx_start
+  stale="$E2E_NETWORK_DATA_DIRECTORY/../../snapshots/local/.crashed.partial"
+  mkdir -p "$stale"
+  echo 999999999 > "$stale/saving.pid"
+  assert_command timeout 60s dfx stop
+  assert_not_contains "Waiting for the network snapshot to be saved."
+}
+
+@test "network snapshot rejects unknown and invalid names" {
+  assert_command_fail dfx network snapshot restore nothing-here
+  assert_match "There is no snapshot named 'nothing-here'"
+  assert_command_fail dfx network snapshot save ../escape
+  assert_match "Invalid snapshot name"
+}
+
+@test "dfx network time requires the replica clock to be configured" {
+  dfx_new hello
+  define_project_network
+
+  assert_command_fail dfx network time advance 1h
+  assert_contains "The replica clock is not enabled for this network"
+}
+
+@test "dfx network time sets, advances and freezes the replica clock" {
+  [ -n "$(find /usr/lib /usr/local/lib /opt/homebrew/lib -name 'libfaketime*' 2>/dev/null)" ] || skip "libfaketime is not installed"
+
+  dfx_new hello
+  define_project_network
+  jq '.networks.local.replica.time={"start":"2030-01-01T00:00:00Z","frozen":true}' dfx.json | sponge dfx.json
+
+  dfx_start
+
+  assert_command dfx network time advance 1h
+  assert_eq "2030-01-01T01:00:00Z (frozen)"
+  assert_command dfx network time set 2030-06-01T00:00:00Z
+  assert_eq "2030-06-01T00:00:00Z (frozen)"
+  assert_command_fail dfx network time set 2029-01-01T00:00:00Z
+  assert_contains "Cannot move the replica's clock back"
+
+  assert_command dfx network time unfreeze
+  assert_match "^2030-06-01T00:00:0.Z$"
+
+  dfx_stop
+  assert_command dfx network time freeze
+  assert_match "^2030-06-01T00:00:..Z \(frozen\)$"
+}
@@ -155,3 +155,65 @@ EOF
   assert_contains "npm could not be found. Skipping installing the frontend example code."
   assert_contains "You can bypass this check by using the --frontend flag."
 }
+
+@test "dfx new --template creates a project from a template directory" {
+  mkdir -p template/files/src/__project_name__ template/overlay
+  cat > template/dfx-template.json <<'JSON'
+{
+  "description": "Test template",
+  "variables": {
+    "greeting": { "default": "Hello" },
+    "company": {}
+  },
+  "files": ["files", "overlay"],
+  "post_create": ["echo {company} > post_create.txt"]
+}
+JSON
+  echo '{ "canisters": { "{project_name}": { "type": "motoko", "main": "src/{project_name}/main.mo" } } }' > template/files/dfx.json
+  echo 'actor { public query func greet() : async Text { "{greeting}, {company}" } }' > template/files/src/__project_name__/main.mo
+  echo '[{ "op": "add", "path": "/canisters/{project_name}/declarations", "value": { "output": "decl" } }]' > template/overlay/dfx.json-patch
+
+  assert_command_fail dfx new e2e_project --template ./template
+  assert_contains "company"
+
+  assert_command dfx new e2e_project --template ./template --var company=Acme
+  assert_contains "Test template"
+  assert_command cat e2e_project/src/e2e_project/main.mo
+  assert_contains '"Hello, Acme"'
+  assert_command jq -r '.canisters.e2e_project.declarations.output' e2e_project/dfx.json
+  assert_eq "decl"
+  assert_command cat e2e_project/post_create.txt
+  assert_eq "Acme"
+  assert_file_not_exists e2e_project/dfx-template.json
+
+  assert_command_fail dfx new other --template ./template --var company=Acme --type rust
+  assert_command_fail dfx new other --template ./template --var nonexistent=1 --var company=Acme
+  assert_contains "no variable 'nonexistent'"
+}
+
+@test "dfx new --template quotes variables in commands and keeps paths in the project" {
+  mkdir -p template/files/__dir__
+  cat > template/dfx-template.json <<'JSON'
+{
+  "variables": { "company": {}, "dir": { "default": "src" } },
+  "post_create": ["echo {company} > post_create.txt"]
+}
+JSON
+  echo '{ "canisters": {} }' > template/files/dfx.json
+  echo 'x' > template/files/__dir__/file.txt
+
+  assert_command dfx new e2e_project --template ./template --var 'company=Acme; touch pwned'
+  assert_command cat e2e_project/post_create.txt
+  assert_eq "Acme; touch pwned"
+  assert_file_not_exists e2e_project/pwned
+  assert_file_exists e2e_project/src/file.txt
+
+  assert_command_fail dfx new other --template ./template --var company=Acme --var dir=../outside
+  assert_contains "path separator"
+  assert_file_not_exists outside/file.txt
+}
+
+@test "dfx new --template lists the templates of extensions" {
+  assert_command_fail dfx new e2e_project --template no-such-template
+  assert_contains "No installed extension provides project templates."
+}
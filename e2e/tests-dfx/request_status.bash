@@ -29,3 +29,19 @@ teardown() {
   assert_eq '4449444c0001710b48656c6c6f2c20426f6221'
 
 }
+
+@test "request-status decodes the reply with the method's types" {
+  install_asset method_signatures
+  dfx_start
+  dfx deploy
+
+  assert_command dfx canister call --async hello_backend returns_object
+  # shellcheck disable=SC2154
+  request_id="$stdout"
+
+  assert_command dfx canister request-status "$request_id" hello_backend
+  assert_not_contains "foo"
+
+  assert_command dfx canister request-status --method returns_object "$request_id" hello_backend
+  assert_contains 'foo = "baz"'
+}
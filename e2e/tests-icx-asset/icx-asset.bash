@@ -50,6 +50,21 @@ icx_asset_upload() {
   assert_command "$ICX_ASSET" --pem "$IDENTITY" --replica "$REPLICA_ADDRESS" upload "$CANISTER_ID" $1 $2
 }
 
+icx_asset_download() {
+  IDENTITY="$DFX_CONFIG_ROOT"/.config/dfx/identity/default/identity.pem
+  REPLICA_ADDRESS="http://localhost:$(get_webserver_port)"
+  CANISTER_ID=$(dfx canister id e2e_project_frontend)
+  assert_command "$ICX_ASSET" --pem "$IDENTITY" --replica "$REPLICA_ADDRESS" download "$CANISTER_ID" "$1"
+}
+
+icx_asset_mirror() {
+  IDENTITY="$DFX_CONFIG_ROOT"/.config/dfx/identity/default/identity.pem
+  REPLICA_ADDRESS="http://localhost:$(get_webserver_port)"
+  SOURCE_CANISTER_ID=$(dfx canister id e2e_project_frontend)
+  TARGET_CANISTER_ID=$(dfx canister id "$1")
+  assert_command "$ICX_ASSET" --pem "$IDENTITY" --replica "$REPLICA_ADDRESS" mirror "$SOURCE_CANISTER_ID" "$TARGET_CANISTER_ID"
+}
+
 @test "lists assets" {
   for i in $(seq 1 400); do
     echo "some easily duplicate text $i" >>src/e2e_project_frontend/assets/notreally.js
@@ -108,6 +123,25 @@ icx_asset_upload() {
   assert_not_match '"/will-delete-this.txt"'
 }
 
+@test "sync --dry-run lists changes without uploading them" {
+  touch src/e2e_project_frontend/assets/will-delete-this.txt
+  dfx deploy
+
+  rm src/e2e_project_frontend/assets/will-delete-this.txt
+  echo -n "new file content" >src/e2e_project_frontend/assets/new-asset.txt
+
+  icx_asset_sync src/e2e_project_frontend/assets --dry-run
+  assert_contains "+ /new-asset.txt (identity 16 bytes)"
+  assert_contains "- /will-delete-this.txt"
+
+  icx_asset_sync src/e2e_project_frontend/assets "--dry-run --json"
+  assert_eq '"/new-asset.txt"' "$(echo "$stdout" | jq '.added[0].key')"
+
+  assert_command dfx canister call --query e2e_project_frontend list  '(record{})'
+  assert_match '"/will-delete-this.txt"'
+  assert_not_match '"/new-asset.txt"'
+}
+
 @test "unsets asset encodings that are removed from project" {
 
   # shellcheck disable=SC2086
@@ -293,3 +327,52 @@ icx_asset_upload() {
   assert_match " /a.txt.*text/plain.*identity"
   assert_match " /b.txt.*text/plain.*identity"
 }
+
+@test "downloads assets with all encodings and properties" {
+  mkdir src/e2e_project_frontend/assets/sub
+  echo "downloaded content" >src/e2e_project_frontend/assets/sub/download-me.txt
+  for i in $(seq 1 400); do
+    echo "some easily duplicate text $i" >>src/e2e_project_frontend/assets/sub/compressible.js
+  done
+  echo '[{"match": "download-me.txt", "cache": {"max_age": 17}, "headers": {"x-extra": "yes"}}]' >src/e2e_project_frontend/assets/sub/.ic-assets.json
+  icx_asset_sync
+
+  icx_asset_download downloaded
+  assert_match "/sub/compressible.js \(gzip\)"
+
+  assert_command diff src/e2e_project_frontend/assets/sub/download-me.txt downloaded/sub/download-me.txt
+  assert_command diff src/e2e_project_frontend/assets/sub/compressible.js downloaded/sub/compressible.js
+  gunzip -c downloaded/.ic-assets-encodings/gzip/sub/compressible.js >decompressed.js
+  assert_command diff src/e2e_project_frontend/assets/sub/compressible.js decompressed.js
+
+  assert_command jq -r '.[] | select(.match == "sub/download-me.txt") | .cache.max_age' downloaded/.ic-assets.json
+  assert_eq "17"
+  assert_command jq -r '.[] | select(.match == "sub/download-me.txt") | .headers["x-extra"]' downloaded/.ic-assets.json
+  assert_eq "yes"
+
+  # synchronizing the download uploads the stored encodings, which the canister already has
+  icx_asset_sync downloaded
+  assert_match "/sub/compressible.js \(gzip\).*is already installed"
+  assert_not_match "/.ic-assets-encodings"
+}
+
+@test "mirrors assets to another asset canister" {
+  mkdir empty
+  jq '.canisters.mirror={"type": "assets", "source": ["empty"]}' dfx.json | sponge dfx.json
+  assert_command dfx deploy mirror
+  echo "mirrored content" >src/e2e_project_frontend/assets/mirrored.txt
+  icx_asset_sync
+
+  icx_asset_mirror mirror
+  # shellcheck disable=SC2086
+  assert_command dfx canister ${DFX_NO_WALLET:-} call --query mirror get '(record{key="/mirrored.txt";accept_encodings=vec{"identity"}})'
+  assert_match "mirrored content"
+
+  # unchanged encodings are not copied again, and assets the source does not have are deleted
+  rm src/e2e_project_frontend/assets/mirrored.txt
+  icx_asset_sync
+  icx_asset_mirror mirror
+  assert_match "is already installed"
+  # shellcheck disable=SC2086
+  assert_command_fail dfx canister ${DFX_NO_WALLET:-} call --query mirror get '(record{key="/mirrored.txt";accept_encodings=vec{"identity"}})'
+}
@@ -1,3 +1,4 @@
+use crate::asset::security_policy::SecurityPolicy;
 use crate::error::AssetLoadConfigError;
 use crate::error::AssetLoadConfigError::{LoadRuleFailed, MalformedAssetConfigFile};
 use crate::error::GetAssetConfigError;
@@ -25,6 +26,7 @@ pub struct AssetConfig {
     pub(crate) enable_aliasing: Option<bool>,
     #[derivative(Default(value = "Some(true)"))]
     pub(crate) allow_raw_access: Option<bool>,
+    pub(crate) security_policy: Option<SecurityPolicy>,
 }
 
 pub(crate) type HeadersConfig = BTreeMap<String, String>;
@@ -60,6 +62,9 @@ pub struct AssetConfigRule {
     /// Redirects the traffic from .raw.icp0.io domain to .icp0.io
     #[serde(skip_serializing_if = "Option::is_none")]
     allow_raw_access: Option<bool>,
+    /// Adds a maintained set of security headers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    security_policy: Option<SecurityPolicy>,
 }
 
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -256,6 +261,10 @@ impl AssetConfig {
         if other.allow_raw_access.is_some() {
             self.allow_raw_access = other.allow_raw_access;
         }
+
+        if other.security_policy.is_some() {
+            self.security_policy = other.security_policy;
+        }
         self
     }
 }
@@ -263,7 +272,7 @@ impl AssetConfig {
 /// This module contains various utilities needed for serialization/deserialization
 /// and pretty-printing of the `AssetConfigRule` data structure.
 mod rule_utils {
-    use super::{AssetConfig, AssetConfigRule, CacheConfig, HeadersConfig, Maybe};
+    use super::{AssetConfig, AssetConfigRule, CacheConfig, HeadersConfig, Maybe, SecurityPolicy};
     use crate::error::LoadRuleError;
     use globset::{Glob, GlobMatcher};
     use serde::{Deserialize, Serializer};
@@ -345,6 +354,7 @@ mod rule_utils {
         ignore: Option<bool>,
         enable_aliasing: Option<bool>,
         allow_raw_access: Option<bool>,
+        security_policy: Option<SecurityPolicy>,
     }
 
     impl AssetConfigRule {
@@ -356,6 +366,7 @@ mod rule_utils {
                 ignore,
                 enable_aliasing,
                 allow_raw_access,
+                security_policy,
             }: InterimAssetConfigRule,
             config_file_parent_dir: &Path,
         ) -> Result<Self, LoadRuleError> {
@@ -378,6 +389,7 @@ mod rule_utils {
                 used: false,
                 enable_aliasing,
                 allow_raw_access,
+                security_policy,
             })
         }
     }
@@ -438,6 +450,9 @@ mod rule_utils {
                     if aliasing { "enabled" } else { "disabled" }
                 ));
             }
+            if let Some(security_policy) = self.security_policy {
+                s.push_str(&format!("  - security policy: {}\n", security_policy));
+            }
             if let Some(ref headers) = self.headers {
                 for (key, value) in headers {
                     s.push_str(&format!(
@@ -931,6 +946,31 @@ mod with_tempdir {
         );
     }
 
+    #[test]
+    fn security_policy() {
+        let cfg = Some(HashMap::from([
+            (
+                "".to_string(),
+                r#"[{"match": "**/*", "security_policy": "hardened"}]"#.to_string(),
+            ),
+            (
+                "nested".to_string(),
+                r#"[{"match": "*", "security_policy": "disabled"}]"#.to_string(),
+            ),
+        ]));
+        let assets_temp_dir = create_temporary_assets_directory(cfg, 1);
+        let assets_dir = assets_temp_dir.path().canonicalize().unwrap();
+        let mut assets_config = AssetSourceDirectoryConfiguration::load(&assets_dir).unwrap();
+        let root = assets_config
+            .get_asset_config(assets_dir.join("index.html").as_path())
+            .unwrap();
+        let nested = assets_config
+            .get_asset_config(assets_dir.join("nested/index.html").as_path())
+            .unwrap();
+        assert_eq!(root.security_policy, Some(SecurityPolicy::Hardened));
+        assert_eq!(nested.security_policy, Some(SecurityPolicy::Disabled));
+    }
+
     #[test]
     fn default_value_for_allow_raw_access_flag() {
         let cfg = Some(HashMap::from([("".to_string(), "[]".to_string())]));
@@ -1,3 +1,4 @@
 pub(crate) mod config;
 pub(crate) mod content;
 pub(crate) mod content_encoder;
+pub(crate) mod security_policy;
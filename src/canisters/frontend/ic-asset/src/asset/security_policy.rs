@@ -0,0 +1,227 @@
+use crate::asset::config::{AssetConfig, HeadersConfig};
+use crate::asset::content::Content;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const CONTENT_SECURITY_POLICY: &str = "Content-Security-Policy";
+
+/// Disables all features that a frontend rarely needs. Activate the ones your site uses.
+const PERMISSIONS_POLICY: &str = "accelerometer=(), ambient-light-sensor=(), autoplay=(), battery=(), camera=(), cross-origin-isolated=(), display-capture=(), document-domain=(), encrypted-media=(), execution-while-not-rendered=(), execution-while-out-of-viewport=(), fullscreen=(), geolocation=(), gyroscope=(), keyboard-map=(), magnetometer=(), microphone=(), midi=(), navigation-override=(), payment=(), picture-in-picture=(), publickey-credentials-get=(), screen-wake-lock=(), sync-xhr=(), usb=(), web-share=(), xr-spatial-tracking=(), clipboard-read=(), clipboard-write=(), gamepad=(), speaker-selection=(), conversion-measurement=(), focus-without-user-activation=(), hid=(), idle-detection=(), interest-cohort=(), serial=(), sync-script=(), trust-token-redemption=(), window-placement=(), vertical-scroll=()";
+
+/// A set of security headers that is maintained with ic-asset, so that projects don't have to write their own.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SecurityPolicy {
+    /// No security headers are added.
+    Disabled,
+    /// Headers that work with most apps, including ones that use inline scripts and styles.
+    Standard,
+    /// A strict Content-Security-Policy. Inline scripts and styles of HTML assets are allowed by their hash.
+    Hardened,
+}
+
+impl std::fmt::Display for SecurityPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Disabled => write!(f, "disabled"),
+            Self::Standard => write!(f, "standard"),
+            Self::Hardened => write!(f, "hardened"),
+        }
+    }
+}
+
+impl SecurityPolicy {
+    fn headers(self, content: &Content) -> HeadersConfig {
+        let csp = match self {
+            Self::Disabled => return HeadersConfig::new(),
+            // 'unsafe-eval' is required because agent-js uses a WebAssembly module to validate bls signatures.
+            Self::Standard => "default-src 'self';script-src 'self' 'unsafe-eval' 'unsafe-inline';connect-src 'self' http://localhost:* https://icp0.io https://*.icp0.io https://icp-api.io;img-src 'self' data:;style-src * 'unsafe-inline';style-src-elem * 'unsafe-inline';font-src *;object-src 'none';base-uri 'self';frame-ancestors 'none';form-action 'self';upgrade-insecure-requests;".to_string(),
+            Self::Hardened => {
+                let (script_hashes, style_hashes) = if content.media_type == mime::TEXT_HTML {
+                    let html = String::from_utf8_lossy(&content.data);
+                    (
+                        inline_hashes(&html, "script"),
+                        inline_hashes(&html, "style"),
+                    )
+                } else {
+                    (String::new(), String::new())
+                };
+                format!("default-src 'none';script-src 'self' 'wasm-unsafe-eval'{script_hashes};connect-src 'self' https://icp0.io https://*.icp0.io https://icp-api.io;img-src 'self' data:;style-src 'self'{style_hashes};font-src 'self';manifest-src 'self';object-src 'none';base-uri 'self';frame-ancestors 'none';form-action 'self';upgrade-insecure-requests;")
+            }
+        };
+        HeadersConfig::from([
+            (CONTENT_SECURITY_POLICY.to_string(), csp),
+            (
+                "Permissions-Policy".to_string(),
+                PERMISSIONS_POLICY.to_string(),
+            ),
+            ("Referrer-Policy".to_string(), "same-origin".to_string()),
+            (
+                "Strict-Transport-Security".to_string(),
+                "max-age=31536000; includeSubDomains".to_string(),
+            ),
+            ("X-Content-Type-Options".to_string(), "nosniff".to_string()),
+            ("X-Frame-Options".to_string(), "DENY".to_string()),
+        ])
+    }
+}
+
+impl AssetConfig {
+    /// Adds the headers of the security policy. Headers that are configured explicitly take precedence.
+    pub(crate) fn apply_security_policy(&mut self, content: &Content) {
+        let Some(policy) = self.security_policy else {
+            return;
+        };
+        let mut headers = policy.headers(content);
+        if headers.is_empty() {
+            return;
+        }
+        let configured = self.headers.take().unwrap_or_default();
+        // Header names are case-insensitive, so `x-frame-options` replaces `X-Frame-Options`.
+        headers.retain(|name, _| !configured.keys().any(|k| k.eq_ignore_ascii_case(name)));
+        headers.extend(configured);
+        self.headers = Some(headers);
+    }
+
+    /// Whether an HTML asset with this configuration would be served without any Content-Security-Policy.
+    pub(crate) fn lacks_security_policy(&self) -> bool {
+        self.security_policy.is_none()
+            && !self.headers.as_ref().map_or(false, |headers| {
+                headers
+                    .keys()
+                    .any(|k| k.eq_ignore_ascii_case(CONTENT_SECURITY_POLICY))
+            })
+    }
+}
+
+/// The CSP sources that allow the inline elements with this tag, e.g. " 'sha256-...' 'sha256-...'".
+fn inline_hashes(html: &str, tag: &str) -> String {
+    inline_contents(html, tag)
+        .into_iter()
+        .map(|content| {
+            format!(
+                " 'sha256-{}'",
+                base64::encode(Sha256::digest(content.as_bytes()))
+            )
+        })
+        .collect()
+}
+
+/// The non-empty contents of all `<tag ...>...</tag>` elements.
+/// Inline event handlers and `style` attributes are not covered.
+fn inline_contents<'a>(html: &'a str, tag: &str) -> Vec<&'a str> {
+    // ASCII lowercasing keeps all byte offsets, so they can be used to slice `html`.
+    let lowercase = html.to_ascii_lowercase();
+    let open = format!("<{tag}");
+    let close = format!("</{tag}");
+    let mut contents = vec![];
+    let mut position = 0;
+    while let Some(start) = lowercase[position..].find(&open) {
+        let name_end = position + start + open.len();
+        match lowercase[name_end..].chars().next() {
+            Some(c) if c == '>' || c == '/' || c.is_ascii_whitespace() => {}
+            _ => {
+                // e.g. <scripts> or <styles>
+                position = name_end;
+                continue;
+            }
+        }
+        let Some(open_len) = lowercase[name_end..].find('>') else {
+            break;
+        };
+        let content_start = name_end + open_len + 1;
+        let Some(content_len) = lowercase[content_start..].find(&close) else {
+            break;
+        };
+        let content = &html[content_start..content_start + content_len];
+        if !content.is_empty() {
+            contents.push(content);
+        }
+        position = content_start + content_len + close.len();
+    }
+    contents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(data: &str) -> Content {
+        Content {
+            data: data.as_bytes().to_vec(),
+            media_type: mime::TEXT_HTML,
+        }
+    }
+
+    #[test]
+    fn finds_inline_scripts_and_styles() {
+        let page = r#"<html><head><SCRIPT type="module">alert(1)</SCRIPT><script src="/a.js"></script>
+            <style>body { color: red }</style><scripts>no</scripts></head></html>"#;
+        assert_eq!(inline_contents(page, "script"), vec!["alert(1)"]);
+        assert_eq!(inline_contents(page, "style"), vec!["body { color: red }"]);
+        assert!(inline_contents("<script>unterminated", "script").is_empty());
+    }
+
+    #[test]
+    fn hardened_policy_allows_inline_hashes() {
+        let mut config = AssetConfig {
+            security_policy: Some(SecurityPolicy::Hardened),
+            headers: Some(HeadersConfig::from([(
+                "X-Frame-Options".to_string(),
+                "SAMEORIGIN".to_string(),
+            )])),
+            ..Default::default()
+        };
+        config.apply_security_policy(&html("<script>alert(1)</script>"));
+        let headers = config.headers.unwrap();
+        let csp = &headers[CONTENT_SECURITY_POLICY];
+        // echo -n 'alert(1)' | openssl sha256 -binary | base64
+        assert!(csp.contains(
+            "script-src 'self' 'wasm-unsafe-eval' 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI=';"
+        ));
+        assert!(csp.contains("style-src 'self';"));
+        assert_eq!(headers["X-Frame-Options"], "SAMEORIGIN");
+        assert_eq!(headers["X-Content-Type-Options"], "nosniff");
+    }
+
+    #[test]
+    fn configured_headers_replace_policy_headers_of_any_case() {
+        let mut config = AssetConfig {
+            security_policy: Some(SecurityPolicy::Standard),
+            headers: Some(HeadersConfig::from([
+                ("x-frame-options".to_string(), "SAMEORIGIN".to_string()),
+                (
+                    "content-security-policy".to_string(),
+                    "default-src 'self'".to_string(),
+                ),
+            ])),
+            ..Default::default()
+        };
+        config.apply_security_policy(&html("<html></html>"));
+        let headers = config.headers.unwrap();
+        assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+        assert_eq!(headers["content-security-policy"], "default-src 'self'");
+        assert!(!headers.contains_key("X-Frame-Options"));
+        assert!(!headers.contains_key(CONTENT_SECURITY_POLICY));
+        assert_eq!(headers["Referrer-Policy"], "same-origin");
+    }
+
+    #[test]
+    fn missing_policy() {
+        let mut config = AssetConfig::default();
+        assert!(config.lacks_security_policy());
+        config.headers = Some(HeadersConfig::from([(
+            "content-security-policy".to_string(),
+            "default-src 'self'".to_string(),
+        )]));
+        assert!(!config.lacks_security_policy());
+
+        let mut config = AssetConfig {
+            security_policy: Some(SecurityPolicy::Disabled),
+            ..Default::default()
+        };
+        assert!(!config.lacks_security_policy());
+        config.apply_security_policy(&html("<script>alert(1)</script>"));
+        assert_eq!(config.headers, None);
+    }
+}
@@ -1,4 +1,5 @@
 pub(crate) mod operations;
 pub(crate) mod plumbing;
+pub(crate) mod progress;
 pub(crate) mod retryable;
 pub(crate) mod semaphores;
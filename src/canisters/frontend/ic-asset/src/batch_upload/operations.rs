@@ -11,6 +11,8 @@ use candid::Nat;
 use std::collections::HashMap;
 
 pub(crate) const BATCH_UPLOAD_API_VERSION: u16 = 1;
+/// Since this version, several encodings set by a commit can use the same chunk.
+pub(crate) const SHARED_CHUNKS_API_VERSION: u16 = 2;
 
 pub(crate) fn assemble_batch_operations(
     project_assets: &HashMap<String, ProjectAsset>,
@@ -266,6 +268,7 @@ mod test_update_properties {
                 key: key.to_string(),
                 source: PathBuf::from(""),
                 config: asset_props,
+                stored_encodings: BTreeMap::new(),
             },
         }
     }
@@ -1,6 +1,7 @@
 use crate::asset::config::AssetConfig;
 use crate::asset::content::Content;
 use crate::asset::content_encoder::ContentEncoder;
+use crate::batch_upload::progress::UploadProgress;
 use crate::batch_upload::semaphores::Semaphores;
 use crate::canister_api::methods::chunk::create_chunk;
 use crate::canister_api::types::asset::AssetDetails;
@@ -11,15 +12,17 @@ use crate::error::CreateProjectAssetError;
 use candid::Nat;
 use futures::future::try_join_all;
 use futures::TryFutureExt;
+use futures_intrusive::sync::ManualResetEvent;
 use ic_utils::Canister;
 use mime::Mime;
-use slog::{debug, info, Logger};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use slog::{debug, info, warn, Logger};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-const CONTENT_ENCODING_IDENTITY: &str = "identity";
+pub(crate) const CONTENT_ENCODING_IDENTITY: &str = "identity";
 
 // The most mb any one file is considered to have for purposes of limiting data loaded at once.
 // Any file counts as at least 1 mb.
@@ -32,12 +35,16 @@ pub(crate) struct AssetDescriptor {
     pub(crate) source: PathBuf,
     pub(crate) key: String,
     pub(crate) config: AssetConfig,
+    /// Files with encodings of the asset other than identity, by content encoding, as written
+    /// by `download`. They are uploaded as they are instead of being computed from the source.
+    pub(crate) stored_encodings: BTreeMap<String, PathBuf>,
 }
 
 pub(crate) struct ProjectAssetEncoding {
     pub(crate) chunk_ids: Vec<Nat>,
     pub(crate) sha256: Vec<u8>,
     pub(crate) already_in_place: bool,
+    pub(crate) size: usize,
 }
 
 pub(crate) struct ProjectAsset {
@@ -46,11 +53,26 @@ pub(crate) struct ProjectAsset {
     pub(crate) encodings: HashMap<String, ProjectAssetEncoding>,
 }
 
+enum SharedChunk {
+    Uploading(Arc<ManualResetEvent>),
+    Uploaded(Nat),
+}
+
+/// Chunks that can be used by more than one encoding, by the SHA-256 of their content.
+/// Requires an asset canister that keeps chunks until their batch is committed.
+struct SharedChunks {
+    chunks: Mutex<HashMap<String, SharedChunk>>,
+    progress: Mutex<UploadProgress>,
+    progress_file: Option<PathBuf>,
+    logger: Logger,
+}
+
 pub(crate) struct ChunkUploader<'agent> {
     canister: Canister<'agent>,
     batch_id: Nat,
     chunks: Arc<AtomicUsize>,
     bytes: Arc<AtomicUsize>,
+    shared_chunks: Option<SharedChunks>,
 }
 impl<'agent> ChunkUploader<'agent> {
     pub(crate) fn new(canister: Canister<'agent>, batch_id: Nat) -> Self {
@@ -59,13 +81,85 @@ impl<'agent> ChunkUploader<'agent> {
             batch_id,
             chunks: Arc::new(AtomicUsize::new(0)),
             bytes: Arc::new(AtomicUsize::new(0)),
+            shared_chunks: None,
         }
     }
 
+    /// Uploads identical chunks only once, starting with the chunks in `progress`,
+    /// and saves the uploaded chunks to `progress_file` as they complete.
+    pub(crate) fn with_shared_chunks(
+        mut self,
+        progress: UploadProgress,
+        progress_file: Option<PathBuf>,
+        logger: &Logger,
+    ) -> Self {
+        let chunks = progress
+            .chunks
+            .iter()
+            .map(|(sha256, chunk_id)| (sha256.clone(), SharedChunk::Uploaded(Nat::from(*chunk_id))))
+            .collect();
+        self.shared_chunks = Some(SharedChunks {
+            chunks: Mutex::new(chunks),
+            progress: Mutex::new(progress),
+            progress_file,
+            logger: logger.clone(),
+        });
+        self
+    }
+
     pub(crate) async fn create_chunk(
         &self,
         contents: &[u8],
         semaphores: &Semaphores,
+    ) -> Result<Nat, CreateChunkError> {
+        let Some(shared) = &self.shared_chunks else {
+            return self.upload_chunk(contents, semaphores).await;
+        };
+        let sha256 = hex::encode(Sha256::digest(contents));
+        let uploading = loop {
+            let other_upload = {
+                let mut chunks = shared.chunks.lock().unwrap();
+                match chunks.get(&sha256) {
+                    Some(SharedChunk::Uploaded(chunk_id)) => return Ok(chunk_id.clone()),
+                    Some(SharedChunk::Uploading(done)) => done.clone(),
+                    None => {
+                        let done = Arc::new(ManualResetEvent::new(false));
+                        chunks.insert(sha256.clone(), SharedChunk::Uploading(done.clone()));
+                        break done;
+                    }
+                }
+            };
+            // Another encoding is uploading the same chunk. If that upload fails, try again.
+            other_upload.wait().await;
+        };
+
+        let result = self.upload_chunk(contents, semaphores).await;
+        {
+            let mut chunks = shared.chunks.lock().unwrap();
+            match &result {
+                Ok(chunk_id) => {
+                    chunks.insert(sha256.clone(), SharedChunk::Uploaded(chunk_id.clone()));
+                    let mut progress = shared.progress.lock().unwrap();
+                    progress.insert_chunk(sha256, chunk_id);
+                    if let Some(progress_file) = &shared.progress_file {
+                        if let Err(e) = progress.save(progress_file) {
+                            warn!(shared.logger, "Failed to save upload progress: {}", e);
+                        }
+                    }
+                }
+                Err(_) => {
+                    chunks.remove(&sha256);
+                }
+            }
+        }
+        uploading.set();
+        result
+    }
+
+    async fn upload_chunk(
+        &self,
+        contents: &[u8],
+        semaphores: &Semaphores,
     ) -> Result<Nat, CreateChunkError> {
         self.chunks.fetch_add(1, Ordering::SeqCst);
         self.bytes.fetch_add(contents.len(), Ordering::SeqCst);
@@ -147,6 +241,7 @@ async fn make_project_asset_encoding(
         chunk_ids,
         sha256,
         already_in_place,
+        size: content.data.len(),
     })
 }
 
@@ -208,12 +303,19 @@ async fn make_encodings(
     asset_descriptor: &AssetDescriptor,
     canister_assets: &HashMap<String, AssetDetails>,
     content: &Content,
+    stored_encodings: &[(String, Content)],
     semaphores: &Semaphores,
     logger: &Logger,
 ) -> Result<HashMap<String, ProjectAssetEncoding>, CreateEncodingError> {
     let mut encoders = vec![None];
     for encoder in applicable_encoders(&content.media_type) {
-        encoders.push(Some(encoder));
+        let content_encoding = encoder.to_string();
+        if !stored_encodings
+            .iter()
+            .any(|(stored, _)| *stored == content_encoding)
+        {
+            encoders.push(Some(encoder));
+        }
     }
 
     let encoding_futures: Vec<_> = encoders
@@ -231,11 +333,30 @@ async fn make_encodings(
         })
         .collect();
 
+    let stored_encoding_futures: Vec<_> = stored_encodings
+        .iter()
+        .map(|(content_encoding, encoded)| {
+            make_project_asset_encoding(
+                chunk_upload_target,
+                asset_descriptor,
+                canister_assets,
+                encoded,
+                content_encoding,
+                semaphores,
+                logger,
+            )
+            .map_ok(|project_asset_encoding| (content_encoding.clone(), project_asset_encoding))
+        })
+        .collect();
+
     let encodings = try_join_all(encoding_futures).await?;
+    let stored_encodings = try_join_all(stored_encoding_futures)
+        .await
+        .map_err(CreateEncodingError::CreateChunkFailed)?;
 
     let mut result: HashMap<String, ProjectAssetEncoding> = HashMap::new();
 
-    for (key, value) in encodings.into_iter().flatten() {
+    for (key, value) in encodings.into_iter().flatten().chain(stored_encodings) {
         result.insert(key, value);
     }
     Ok(result)
@@ -261,12 +382,20 @@ async fn make_project_asset(
     let _releaser = semaphores.file.acquire(permits).await;
     let content = Content::load(&asset_descriptor.source)
         .map_err(CreateProjectAssetError::LoadContentFailed)?;
+    let mut stored_encodings = vec![];
+    for (content_encoding, path) in &asset_descriptor.stored_encodings {
+        let encoded = Content::load(path).map_err(CreateProjectAssetError::LoadContentFailed)?;
+        stored_encodings.push((content_encoding.clone(), encoded));
+    }
+    let mut asset_descriptor = asset_descriptor;
+    asset_descriptor.config.apply_security_policy(&content);
 
     let encodings = make_encodings(
         chunk_upload_target,
         &asset_descriptor,
         canister_assets,
         &content,
+        &stored_encodings,
         semaphores,
         logger,
     )
@@ -359,7 +488,7 @@ async fn upload_content_chunks(
     try_join_all(chunks_futures).await
 }
 
-fn content_encoding_descriptive_suffix(content_encoding: &str) -> String {
+pub(crate) fn content_encoding_descriptive_suffix(content_encoding: &str) -> String {
     if content_encoding == CONTENT_ENCODING_IDENTITY {
         "".to_string()
     } else {
@@ -0,0 +1,89 @@
+use candid::{Nat, Principal};
+use dfx_core::error::structured_file::StructuredFileError;
+use dfx_core::json::{load_json_file, save_json_file};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The chunks uploaded to a batch, saved after each chunk so that a failed sync
+/// can continue the batch instead of uploading everything again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UploadProgress {
+    pub(crate) canister_id: Principal,
+    pub(crate) batch_id: u64,
+    /// Chunk ids by the hex-encoded SHA-256 of the chunk content.
+    pub(crate) chunks: BTreeMap<String, u64>,
+}
+
+impl UploadProgress {
+    pub(crate) fn new(canister_id: Principal, batch_id: &Nat) -> Option<Self> {
+        Some(Self {
+            canister_id,
+            batch_id: nat_to_u64(batch_id)?,
+            chunks: BTreeMap::new(),
+        })
+    }
+
+    /// Loads the progress of an earlier sync with this canister, if there is any.
+    pub(crate) fn load(path: &Path, canister_id: Principal) -> Option<Self> {
+        if !path.exists() {
+            return None;
+        }
+        // A progress file that cannot be read is no reason to fail the sync: everything is uploaded again.
+        load_json_file::<Self>(path)
+            .ok()
+            .filter(|progress| progress.canister_id == canister_id)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), StructuredFileError> {
+        if let Some(parent) = path.parent() {
+            dfx_core::fs::create_dir_all(parent)
+                .map_err(StructuredFileError::WriteJsonFileFailed)?;
+        }
+        save_json_file(path, self)
+    }
+
+    pub(crate) fn remove(path: &Path) {
+        if path.exists() {
+            let _ = dfx_core::fs::remove_file(path);
+        }
+    }
+
+    pub(crate) fn batch_id(&self) -> Nat {
+        Nat::from(self.batch_id)
+    }
+
+    pub(crate) fn insert_chunk(&mut self, sha256: String, chunk_id: &Nat) {
+        if let Some(chunk_id) = nat_to_u64(chunk_id) {
+            self.chunks.insert(sha256, chunk_id);
+        }
+    }
+}
+
+fn nat_to_u64(n: &Nat) -> Option<u64> {
+    u64::try_from(&n.0).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_of_another_canister_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.json");
+        let canister_id = Principal::from_text("rrkah-fqaaa-aaaaa-aaaaq-cai").unwrap();
+
+        let mut progress = UploadProgress::new(canister_id, &Nat::from(7_u64)).unwrap();
+        progress.insert_chunk("ab".to_string(), &Nat::from(3_u64));
+        progress.save(&path).unwrap();
+
+        let loaded = UploadProgress::load(&path, canister_id).unwrap();
+        assert_eq!(loaded, progress);
+        assert_eq!(loaded.batch_id(), Nat::from(7_u64));
+        assert_eq!(UploadProgress::load(&path, Principal::anonymous()), None);
+
+        UploadProgress::remove(&path);
+        assert_eq!(UploadProgress::load(&path, canister_id), None);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::canister_api::methods::method_names::{GET, GET_CHUNK};
+use crate::canister_api::types::asset::{
+    GetArguments, GetChunkArguments, GetChunkResponse, GetResponse,
+};
+use candid::Nat;
+use ic_agent::AgentError;
+use ic_utils::call::SyncCall;
+use ic_utils::Canister;
+use serde_bytes::ByteBuf;
+
+pub(crate) async fn get(
+    canister: &Canister<'_>,
+    key: &str,
+    content_encoding: &str,
+) -> Result<GetResponse, AgentError> {
+    let (response,): (GetResponse,) = canister
+        .query(GET)
+        .with_arg(GetArguments {
+            key: key.to_string(),
+            accept_encodings: vec![content_encoding.to_string()],
+        })
+        .build()
+        .call()
+        .await?;
+    Ok(response)
+}
+
+pub(crate) async fn get_chunk(
+    canister: &Canister<'_>,
+    key: &str,
+    content_encoding: &str,
+    index: usize,
+    sha256: Option<ByteBuf>,
+) -> Result<ByteBuf, AgentError> {
+    let (response,): (GetChunkResponse,) = canister
+        .query(GET_CHUNK)
+        .with_arg(GetChunkArguments {
+            key: key.to_string(),
+            content_encoding: content_encoding.to_string(),
+            index: Nat::from(index),
+            sha256,
+        })
+        .build()
+        .call()
+        .await?;
+    Ok(response.content)
+}
@@ -0,0 +1,26 @@
+use crate::canister_api::methods::method_names::HTTP_REQUEST;
+use crate::canister_api::types::http::{HttpRequest, HttpResponse};
+use ic_agent::AgentError;
+use ic_utils::call::SyncCall;
+use ic_utils::Canister;
+use serde_bytes::ByteBuf;
+
+/// Requests `url` the way a boundary node would, without any Accept-Encoding, with a v1 certificate.
+pub(crate) async fn http_get(
+    canister: &Canister<'_>,
+    url: &str,
+) -> Result<HttpResponse, AgentError> {
+    let (response,): (HttpResponse,) = canister
+        .query(HTTP_REQUEST)
+        .with_arg(HttpRequest {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            headers: vec![],
+            body: ByteBuf::new(),
+            certificate_version: None,
+        })
+        .build()
+        .call()
+        .await?;
+    Ok(response)
+}
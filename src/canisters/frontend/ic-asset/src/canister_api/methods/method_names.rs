@@ -3,6 +3,12 @@ pub(crate) const COMMIT_BATCH: &str = "commit_batch";
 pub(crate) const COMPUTE_EVIDENCE: &str = "compute_evidence";
 pub(crate) const CREATE_BATCH: &str = "create_batch";
 pub(crate) const CREATE_CHUNK: &str = "create_chunk";
+pub(crate) const GET: &str = "get";
 pub(crate) const GET_ASSET_PROPERTIES: &str = "get_asset_properties";
+pub(crate) const GET_CHUNK: &str = "get_chunk";
+pub(crate) const GRANT_PERMISSION: &str = "grant_permission";
+pub(crate) const HTTP_REQUEST: &str = "http_request";
 pub(crate) const LIST: &str = "list";
+pub(crate) const LIST_SCOPED_PERMISSIONS: &str = "list_scoped_permissions";
 pub(crate) const PROPOSE_COMMIT_BATCH: &str = "propose_commit_batch";
+pub(crate) const REVOKE_PERMISSION: &str = "revoke_permission";
@@ -0,0 +1,59 @@
+use crate::canister_api::methods::method_names::{
+    GRANT_PERMISSION, LIST_SCOPED_PERMISSIONS, REVOKE_PERMISSION,
+};
+use crate::canister_api::types::permissions::{
+    GrantPermissionArguments, ListPermittedArguments, Permission, RevokePermissionArguments,
+    ScopedPermission,
+};
+use candid::Principal;
+use ic_agent::AgentError;
+use ic_utils::Canister;
+
+pub(crate) async fn grant_permission(
+    canister: &Canister<'_>,
+    to_principal: Principal,
+    permission: Permission,
+    scope: Option<String>,
+) -> Result<(), AgentError> {
+    canister
+        .update(GRANT_PERMISSION)
+        .with_arg(GrantPermissionArguments {
+            to_principal,
+            permission,
+            scope,
+        })
+        .build()
+        .call_and_wait()
+        .await
+}
+
+pub(crate) async fn revoke_permission(
+    canister: &Canister<'_>,
+    of_principal: Principal,
+    permission: Permission,
+    scope: Option<String>,
+) -> Result<(), AgentError> {
+    canister
+        .update(REVOKE_PERMISSION)
+        .with_arg(RevokePermissionArguments {
+            of_principal,
+            permission,
+            scope,
+        })
+        .build()
+        .call_and_wait()
+        .await
+}
+
+pub(crate) async fn list_scoped_permissions(
+    canister: &Canister<'_>,
+    permission: Permission,
+) -> Result<Vec<ScopedPermission>, AgentError> {
+    let (permissions,): (Vec<ScopedPermission>,) = canister
+        .update(LIST_SCOPED_PERMISSIONS)
+        .with_arg(ListPermittedArguments { permission })
+        .build()
+        .call_and_wait()
+        .await?;
+    Ok(permissions)
+}
@@ -1,9 +1,10 @@
-use candid::CandidType;
+use candid::{CandidType, Nat};
 use serde::Deserialize;
+use serde_bytes::ByteBuf;
 use std::collections::HashMap;
 
 /// Information about a content encoding stored for an asset.
-#[derive(CandidType, Debug, Deserialize)]
+#[derive(CandidType, Clone, Debug, Deserialize)]
 pub struct AssetEncodingDetails {
     /// A content encoding, such as "gzip".
     pub content_encoding: String,
@@ -14,7 +15,7 @@ pub struct AssetEncodingDetails {
 }
 
 /// Information about an asset stored in the canister.
-#[derive(CandidType, Debug, Deserialize)]
+#[derive(CandidType, Clone, Debug, Deserialize)]
 pub struct AssetDetails {
     /// The key identifies the asset.
     pub key: String,
@@ -25,7 +26,7 @@ pub struct AssetDetails {
 }
 
 /// Information about the properties stored for an asset.
-#[derive(CandidType, Debug, Deserialize, Default)]
+#[derive(CandidType, Clone, Debug, Deserialize, Default)]
 pub struct AssetProperties {
     /// Asset's cache max_age property
     pub max_age: Option<u64>,
@@ -50,3 +51,35 @@ pub struct SetAssetPropertiesArguments {
 /// The arguments to the `get_asset_properties` method.
 #[derive(CandidType, Debug)]
 pub struct GetAssetPropertiesArgument(pub String);
+
+/// The arguments to the `get` method.
+#[derive(CandidType, Debug)]
+pub struct GetArguments {
+    pub key: String,
+    pub accept_encodings: Vec<String>,
+}
+
+/// The first chunk of an asset encoding, as returned by the `get` method.
+#[derive(CandidType, Debug, Deserialize)]
+pub struct GetResponse {
+    pub content: ByteBuf,
+    pub content_type: String,
+    pub content_encoding: String,
+    pub sha256: Option<ByteBuf>,
+    pub total_length: Nat,
+}
+
+/// The arguments to the `get_chunk` method.
+#[derive(CandidType, Debug)]
+pub struct GetChunkArguments {
+    pub key: String,
+    pub content_encoding: String,
+    pub index: Nat,
+    pub sha256: Option<ByteBuf>,
+}
+
+/// A chunk of an asset encoding, as returned by the `get_chunk` method.
+#[derive(CandidType, Debug, Deserialize)]
+pub struct GetChunkResponse {
+    pub content: ByteBuf,
+}
@@ -0,0 +1,18 @@
+use candid::{CandidType, Deserialize};
+use serde_bytes::ByteBuf;
+
+#[derive(CandidType, Debug)]
+pub(crate) struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: ByteBuf,
+    pub certificate_version: Option<u16>,
+}
+
+#[derive(CandidType, Debug, Deserialize)]
+pub(crate) struct HttpResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: ByteBuf,
+}
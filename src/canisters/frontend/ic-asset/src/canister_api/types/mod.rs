@@ -1,3 +1,5 @@
 pub(crate) mod asset;
 pub(crate) mod batch_upload;
+pub(crate) mod http;
 pub(crate) mod list;
+pub(crate) mod permissions;
@@ -0,0 +1,50 @@
+use candid::{CandidType, Deserialize, Principal};
+
+/// A permission to change the assets canister.
+#[derive(CandidType, Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Permission {
+    /// Permits committing changes to the assets.
+    Commit,
+    /// Permits granting and revoking permissions.
+    ManagePermissions,
+    /// Permits uploading changes to be committed later.
+    Prepare,
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Permission::Commit => f.write_str("Commit"),
+            Permission::ManagePermissions => f.write_str("ManagePermissions"),
+            Permission::Prepare => f.write_str("Prepare"),
+        }
+    }
+}
+
+/// A principal's permission, with the path prefixes it is limited to.
+#[derive(CandidType, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ScopedPermission {
+    /// The principal that has the permission.
+    pub principal: Principal,
+    /// The path prefixes the permission is limited to. Empty if it applies to all assets.
+    pub scopes: Vec<String>,
+}
+
+#[derive(CandidType, Debug)]
+pub(crate) struct GrantPermissionArguments {
+    pub to_principal: Principal,
+    pub permission: Permission,
+    pub scope: Option<String>,
+}
+
+#[derive(CandidType, Debug)]
+pub(crate) struct RevokePermissionArguments {
+    pub of_principal: Principal,
+    pub permission: Permission,
+    pub scope: Option<String>,
+}
+
+#[derive(CandidType, Debug)]
+pub(crate) struct ListPermittedArguments {
+    pub permission: Permission,
+}
@@ -0,0 +1,224 @@
+use crate::asset::config::ASSETS_CONFIG_FILENAME_JSON;
+use crate::canister_api::methods::http::http_get;
+use crate::error::CheckCustomDomainsError;
+use crate::error::CheckCustomDomainsError::{
+    ContentNotCertified, DomainNotListed, HttpRequestFailed, InvalidCertificate,
+    MalformedCertificate, MissingCertificate, NotServed,
+};
+use crate::error::WriteCustomDomainsError;
+use candid::Principal;
+use ic_agent::hash_tree::{HashTree, LookupResult};
+use ic_agent::{lookup_value, Agent};
+use ic_utils::Canister;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// The key of the asset that lists the custom domains the asset canister may be served from.
+pub const IC_DOMAINS_KEY: &str = "/.well-known/ic-domains";
+
+/// `.well-known` starts with a dot, so it has to be included explicitly.
+const IC_DOMAINS_ASSET_CONFIG: &str = r#"[
+  {
+    "match": ".well-known",
+    "ignore": false
+  }
+]
+"#;
+
+/// A DNS record that has to exist for the asset canister to be served from a custom domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsRecord {
+    /// The record type, e.g. CNAME.
+    pub record_type: &'static str,
+    /// The fully qualified name of the record.
+    pub name: String,
+    /// The content of the record.
+    pub value: String,
+}
+
+/// Writes the `/.well-known/ic-domains` asset, listing one domain per line, into `dir`.
+/// The directory has to be synchronized along with the other asset directories.
+pub fn write_custom_domains(dir: &Path, domains: &[String]) -> Result<(), WriteCustomDomainsError> {
+    for domain in domains {
+        validate_custom_domain(domain)?;
+    }
+    let well_known = dir.join(".well-known");
+    dfx_core::fs::create_dir_all(&well_known)?;
+    let content: String = domains.iter().map(|domain| format!("{domain}\n")).collect();
+    dfx_core::fs::write(well_known.join("ic-domains"), content)?;
+    dfx_core::fs::write(
+        dir.join(ASSETS_CONFIG_FILENAME_JSON),
+        IC_DOMAINS_ASSET_CONFIG,
+    )?;
+    Ok(())
+}
+
+/// Checks that the domain is a plain, lowercase domain name such as `www.example.com`.
+pub fn validate_custom_domain(domain: &str) -> Result<(), WriteCustomDomainsError> {
+    let invalid = || WriteCustomDomainsError::InvalidDomain(domain.to_string());
+    let labels: Vec<&str> = domain.split('.').collect();
+    if domain.len() > 253 || labels.len() < 2 {
+        return Err(invalid());
+    }
+    for label in labels {
+        let valid = !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(invalid());
+        }
+    }
+    Ok(())
+}
+
+/// The DNS records that point `domain` to the asset canister.
+/// At the apex of a zone, where CNAME records are not allowed, use an ALIAS or ANAME record instead.
+pub fn dns_records(canister_id: Principal, domain: &str) -> Vec<DnsRecord> {
+    vec![
+        DnsRecord {
+            record_type: "CNAME",
+            name: domain.to_string(),
+            value: format!("{domain}.icp1.io"),
+        },
+        DnsRecord {
+            record_type: "TXT",
+            name: format!("_canister-id.{domain}"),
+            value: canister_id.to_text(),
+        },
+        DnsRecord {
+            record_type: "CNAME",
+            name: format!("_acme-challenge.{domain}"),
+            value: format!("_acme-challenge.{domain}.icp2.io"),
+        },
+    ]
+}
+
+/// Checks that the asset canister serves a certified `/.well-known/ic-domains` that lists all of the domains.
+pub async fn check_custom_domains(
+    canister: &Canister<'_>,
+    agent: &Agent,
+    domains: &[String],
+) -> Result<(), CheckCustomDomainsError> {
+    let canister_id = *canister.canister_id_();
+    let response = http_get(canister, IC_DOMAINS_KEY)
+        .await
+        .map_err(HttpRequestFailed)?;
+    if response.status_code != 200 {
+        return Err(NotServed(response.status_code));
+    }
+
+    let header = response
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("IC-Certificate"))
+        .map(|(_, value)| value.as_str())
+        .ok_or(MissingCertificate)?;
+    let (certificate, tree) = parse_certificate_header(header)?;
+    let certificate = serde_cbor::from_slice(&certificate)
+        .map_err(|e| MalformedCertificate(format!("certificate: {e}")))?;
+    agent
+        .verify(&certificate, canister_id)
+        .map_err(InvalidCertificate)?;
+    let certified_data = lookup_value(
+        &certificate,
+        [
+            b"canister".as_slice(),
+            canister_id.as_slice(),
+            b"certified_data",
+        ],
+    )
+    .map_err(InvalidCertificate)?;
+    let tree = serde_cbor::from_slice::<HashTree<Vec<u8>>>(&tree)
+        .map_err(|e| MalformedCertificate(format!("tree: {e}")))?;
+    if tree.digest() != certified_data {
+        return Err(ContentNotCertified);
+    }
+    // When the asset does not exist, the canister serves the fallback file with a witness for its absence.
+    match tree.lookup_path([b"http_assets".as_slice(), IC_DOMAINS_KEY.as_bytes()]) {
+        LookupResult::Found(hash) if hash == Sha256::digest(&response.body).as_slice() => {}
+        _ => return Err(ContentNotCertified),
+    }
+
+    let content = String::from_utf8_lossy(&response.body);
+    let listed: Vec<&str> = content.lines().map(str::trim).collect();
+    for domain in domains {
+        if !listed.contains(&domain.as_str()) {
+            return Err(DomainNotListed(domain.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the certificate and tree from `certificate=:<base64>:, tree=:<base64>:`.
+fn parse_certificate_header(header: &str) -> Result<(Vec<u8>, Vec<u8>), CheckCustomDomainsError> {
+    let mut certificate = None;
+    let mut tree = None;
+    for field in header.split(',') {
+        let Some((name, value)) = field.trim().split_once('=') else {
+            continue;
+        };
+        let Some(value) = value.strip_prefix(':').and_then(|v| v.strip_suffix(':')) else {
+            continue;
+        };
+        match name {
+            "certificate" => certificate = Some(value),
+            "tree" => tree = Some(value),
+            _ => {}
+        }
+    }
+    let decode = |name: &str, value: Option<&str>| {
+        let value = value.ok_or_else(|| MalformedCertificate(format!("{name} is missing")))?;
+        base64::decode(value).map_err(|e| MalformedCertificate(format!("{name}: {e}")))
+    };
+    Ok((decode("certificate", certificate)?, decode("tree", tree)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_domains() {
+        assert!(validate_custom_domain("example.com").is_ok());
+        assert!(validate_custom_domain("www.my-app.example.com").is_ok());
+        for invalid in [
+            "localhost",
+            "https://example.com",
+            "example.com/",
+            "Example.com",
+            "-example.com",
+            "example..com",
+        ] {
+            assert!(validate_custom_domain(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn writes_ic_domains() {
+        let dir = tempfile::tempdir().unwrap();
+        let domains = vec!["example.com".to_string(), "www.example.com".to_string()];
+        write_custom_domains(dir.path(), &domains).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(".well-known/ic-domains")).unwrap(),
+            "example.com\nwww.example.com\n"
+        );
+
+        let logger = slog::Logger::root(slog::Discard, slog::o!());
+        let descriptors = crate::sync::gather_asset_descriptors(&[dir.path()], &logger).unwrap();
+        assert_eq!(descriptors.len(), 1);
+        assert_eq!(descriptors[0].key, IC_DOMAINS_KEY);
+    }
+
+    #[test]
+    fn parses_certificate_header() {
+        let (certificate, tree) =
+            parse_certificate_header("certificate=:AQI=:, tree=:AwQ=:").unwrap();
+        assert_eq!(certificate, vec![1, 2]);
+        assert_eq!(tree, vec![3, 4]);
+        assert!(parse_certificate_header("version=2, tree=:AwQ=:").is_err());
+    }
+}
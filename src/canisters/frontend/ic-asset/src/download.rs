@@ -0,0 +1,397 @@
+use crate::asset::config::{ASSETS_CONFIG_FILENAME_JSON, ASSETS_CONFIG_FILENAME_JSON5};
+use crate::batch_upload::plumbing::{
+    content_encoding_descriptive_suffix, CONTENT_ENCODING_IDENTITY,
+};
+use crate::canister_api::methods::asset_properties::get_assets_properties;
+use crate::canister_api::methods::get::{get, get_chunk};
+use crate::canister_api::methods::list::list_assets;
+use crate::canister_api::types::asset::AssetProperties;
+use crate::error::DownloadError;
+use crate::error::DownloadError::ListAssetsFailed;
+use crate::error::GetContentError;
+use crate::error::GetContentError::{GetFailed, LengthMismatch, Sha256Mismatch};
+use dfx_core::error::fs::{FsError, FsErrorKind};
+use ic_utils::Canister;
+use serde_bytes::ByteBuf;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use slog::{info, warn, Logger};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where encodings other than identity are written, relative to the download directory.
+/// Synchronizing the directory uploads them with their assets, instead of computing encodings.
+pub(crate) const ENCODINGS_DIR: &str = ".ic-assets-encodings";
+
+/// Reads the content of an asset encoding one chunk at a time, so that large assets
+/// never have to be held in memory at once.
+pub(crate) struct EncodingChunks<'a, 'agent> {
+    canister: &'a Canister<'agent>,
+    key: String,
+    content_encoding: String,
+    first: Option<ByteBuf>,
+    expected_sha256: Option<ByteBuf>,
+    total_length: u64,
+    read_length: u64,
+    next_index: usize,
+    hasher: Sha256,
+    sha256: Option<Vec<u8>>,
+}
+
+impl<'a, 'agent> EncodingChunks<'a, 'agent> {
+    pub(crate) async fn get(
+        canister: &'a Canister<'agent>,
+        key: &str,
+        content_encoding: &str,
+    ) -> Result<EncodingChunks<'a, 'agent>, GetContentError> {
+        let response = get(canister, key, content_encoding)
+            .await
+            .map_err(|e| GetFailed(key.to_string(), content_encoding.to_string(), e))?;
+        let total_length = u64::try_from(&response.total_length.0).unwrap_or(u64::MAX);
+        Ok(Self {
+            canister,
+            key: key.to_string(),
+            content_encoding: response.content_encoding,
+            first: Some(response.content),
+            expected_sha256: response.sha256,
+            total_length,
+            read_length: 0,
+            next_index: 0,
+            hasher: Sha256::new(),
+            sha256: None,
+        })
+    }
+
+    /// The next chunk, or None once the content has the total length listed by the canister.
+    /// Chunks can have any size, so they are read until that length is reached.
+    /// Fails if the content does not have that length or does not match the listed sha256.
+    pub(crate) async fn next(&mut self) -> Result<Option<ByteBuf>, GetContentError> {
+        if self.sha256.is_some() {
+            return Ok(None);
+        }
+        let chunk = match self.first.take() {
+            Some(first) => first,
+            None => get_chunk(
+                self.canister,
+                &self.key,
+                &self.content_encoding,
+                self.next_index,
+                self.expected_sha256.clone(),
+            )
+            .await
+            .map_err(|e| GetFailed(self.key.clone(), self.content_encoding.clone(), e))?,
+        };
+        self.next_index += 1;
+        self.read_length += chunk.len() as u64;
+        // Reading on after an empty chunk that does not end the content would never end.
+        if self.read_length > self.total_length
+            || (chunk.is_empty() && self.read_length < self.total_length)
+        {
+            return Err(LengthMismatch(
+                self.key.clone(),
+                self.content_encoding.clone(),
+                self.total_length,
+            ));
+        }
+        self.hasher.update(&chunk);
+        if self.read_length == self.total_length {
+            let sha256 = std::mem::take(&mut self.hasher).finalize().to_vec();
+            if let Some(expected) = &self.expected_sha256 {
+                if expected.as_slice() != sha256.as_slice() {
+                    return Err(Sha256Mismatch(
+                        self.key.clone(),
+                        self.content_encoding.clone(),
+                    ));
+                }
+            }
+            self.sha256 = Some(sha256);
+        }
+        Ok(Some(chunk))
+    }
+
+    /// The sha256 of the whole content, once all chunks have been read.
+    pub(crate) fn sha256(&self) -> Option<&[u8]> {
+        self.sha256.as_deref()
+    }
+}
+
+/// Downloads all assets of an asset canister into a directory, with all of their encodings.
+///
+/// The identity encoding of each asset is written to its key under `dir`, and other encodings
+/// to `.ic-assets-encodings/<content encoding>/<key>`. The properties of the assets are written
+/// to `.ic-assets.json`, so that synchronizing `dir` to another canister reproduces them.
+pub async fn download(
+    canister: &Canister<'_>,
+    dir: &Path,
+    logger: &Logger,
+) -> Result<(), DownloadError> {
+    let canister_assets = list_assets(canister).await.map_err(ListAssetsFailed)?;
+    info!(
+        logger,
+        "Fetching properties for all assets in the canister."
+    );
+    let canister_asset_properties = get_assets_properties(canister, &canister_assets).await?;
+
+    info!(logger, "Downloading assets to {}:", dir.display());
+    let mut keys: Vec<&String> = canister_assets.keys().collect();
+    keys.sort();
+    let mut downloaded = vec![];
+    for key in keys {
+        let asset = &canister_assets[key];
+        let Some(relative_path) = relative_path(key) else {
+            warn!(
+                logger,
+                "Skipping {}: the key cannot be stored as a file.", key
+            );
+            continue;
+        };
+        if !asset
+            .encodings
+            .iter()
+            .any(|encoding| encoding.content_encoding == CONTENT_ENCODING_IDENTITY)
+        {
+            warn!(
+                logger,
+                "{} has no identity encoding, so synchronizing the download will not upload it.",
+                key
+            );
+        }
+        let guessed_type = mime_guess::from_path(&relative_path).first_or_octet_stream();
+        if guessed_type.to_string() != asset.content_type {
+            warn!(
+                logger,
+                "{} has the content type {}, but synchronizing the download will upload it as {}.",
+                key,
+                asset.content_type,
+                guessed_type
+            );
+        }
+
+        for encoding in &asset.encodings {
+            let content_encoding = &encoding.content_encoding;
+            let path = if content_encoding == CONTENT_ENCODING_IDENTITY {
+                dir.join(&relative_path)
+            } else if is_valid_content_encoding(content_encoding) {
+                dir.join(ENCODINGS_DIR)
+                    .join(content_encoding)
+                    .join(&relative_path)
+            } else {
+                warn!(
+                    logger,
+                    "Skipping the {} encoding of {}: it cannot be stored as a directory.",
+                    content_encoding,
+                    key
+                );
+                continue;
+            };
+            let size = download_encoding(canister, key, content_encoding, &path).await?;
+            info!(
+                logger,
+                "  {}{} ({} bytes)",
+                key,
+                content_encoding_descriptive_suffix(content_encoding),
+                size
+            );
+        }
+        let properties = canister_asset_properties
+            .get(key)
+            .cloned()
+            .unwrap_or_default();
+        downloaded.push((relative_path, properties));
+    }
+
+    let rules = asset_config_rules(&downloaded);
+    if !rules.is_empty() {
+        dfx_core::json::save_json_file(&dir.join(ASSETS_CONFIG_FILENAME_JSON), &rules)?;
+    }
+    info!(logger, "Downloaded {} assets.", downloaded.len());
+    Ok(())
+}
+
+/// Writes the content of an asset encoding to `path` as it is read. Returns the size of the content.
+async fn download_encoding(
+    canister: &Canister<'_>,
+    key: &str,
+    content_encoding: &str,
+    path: &Path,
+) -> Result<usize, DownloadError> {
+    dfx_core::fs::create_dir_all(&dfx_core::fs::parent(path)?)?;
+    let write_failed = |e| FsError::new(FsErrorKind::WriteFileFailed(path.to_path_buf(), e));
+    let mut file = std::fs::File::create(path).map_err(write_failed)?;
+    let mut chunks = EncodingChunks::get(canister, key, content_encoding).await?;
+    let mut size = 0;
+    while let Some(chunk) = chunks.next().await? {
+        file.write_all(&chunk).map_err(write_failed)?;
+        size += chunk.len();
+    }
+    Ok(size)
+}
+
+/// The path of the asset relative to the download directory, if the key can be stored as a file.
+fn relative_path(key: &str) -> Option<PathBuf> {
+    let segments: Vec<&str> = key.strip_prefix('/')?.split('/').collect();
+    let valid = segments
+        .iter()
+        .all(|segment| !matches!(*segment, "" | "." | "..") && !segment.contains('\\'));
+    let is_config_file = matches!(
+        segments.last(),
+        Some(&ASSETS_CONFIG_FILENAME_JSON) | Some(&ASSETS_CONFIG_FILENAME_JSON5)
+    );
+    (valid && !is_config_file).then(|| segments.iter().collect())
+}
+
+fn is_valid_content_encoding(content_encoding: &str) -> bool {
+    !content_encoding.is_empty()
+        && content_encoding
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The `.ic-assets.json` rules that reproduce the properties of the downloaded assets.
+/// Dot files and directories are included explicitly, since they are ignored by default.
+fn asset_config_rules(assets: &[(PathBuf, AssetProperties)]) -> Vec<Value> {
+    let mut hidden = BTreeSet::new();
+    for (relative_path, _) in assets {
+        let mut prefix = PathBuf::new();
+        for segment in relative_path.iter() {
+            prefix.push(segment);
+            if segment.to_string_lossy().starts_with('.') {
+                hidden.insert(prefix.clone());
+            }
+        }
+    }
+    let mut rules: Vec<Value> = hidden
+        .iter()
+        .map(|path| json!({ "match": escape_glob(path), "ignore": false }))
+        .collect();
+
+    for (relative_path, properties) in assets {
+        let mut rule = Map::new();
+        if let Some(max_age) = properties.max_age {
+            rule.insert("cache".to_string(), json!({ "max_age": max_age }));
+        }
+        if let Some(headers) = &properties.headers {
+            let headers: BTreeMap<_, _> = headers.iter().collect();
+            rule.insert("headers".to_string(), json!(headers));
+        }
+        if let Some(is_aliased) = properties.is_aliased {
+            rule.insert("enable_aliasing".to_string(), json!(is_aliased));
+        }
+        if let Some(allow_raw_access) = properties.allow_raw_access {
+            rule.insert("allow_raw_access".to_string(), json!(allow_raw_access));
+        }
+        if !rule.is_empty() {
+            rule.insert("match".to_string(), json!(escape_glob(relative_path)));
+            rules.push(Value::Object(rule));
+        }
+    }
+    rules
+}
+
+/// A glob that matches exactly this path, relative to the directory of `.ic-assets.json`.
+fn escape_glob(path: &Path) -> String {
+    let path = path
+        .iter()
+        .map(|segment| segment.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '*' | '?' | '[' | ']' | '{' | '}' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::gather_asset_descriptors;
+    use std::collections::HashMap;
+
+    #[test]
+    fn keys_that_cannot_be_stored_as_files() {
+        assert_eq!(
+            relative_path("/css/main.css"),
+            Some(PathBuf::from("css/main.css"))
+        );
+        for key in [
+            "index.html",
+            "/",
+            "/a//b",
+            "/../etc/passwd",
+            "/a/./b",
+            "/a\\b",
+            "/.ic-assets.json",
+            "/sub/.ic-assets.json5",
+        ] {
+            assert_eq!(relative_path(key), None, "{key}");
+        }
+    }
+
+    #[test]
+    fn rules_reproduce_asset_properties() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = vec![
+            (
+                PathBuf::from("index.html"),
+                AssetProperties {
+                    max_age: Some(30),
+                    headers: Some(HashMap::from([(
+                        "X-Frame-Options".to_string(),
+                        "DENY".to_string(),
+                    )])),
+                    allow_raw_access: Some(false),
+                    is_aliased: Some(true),
+                },
+            ),
+            (
+                PathBuf::from(".well-known/ic-domains"),
+                AssetProperties::default(),
+            ),
+            (
+                PathBuf::from("img/logo[1].png"),
+                AssetProperties {
+                    max_age: Some(3600),
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (relative_path, _) in &assets {
+            let path = dir.path().join(relative_path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "content").unwrap();
+        }
+        dfx_core::json::save_json_file(
+            &dir.path().join(ASSETS_CONFIG_FILENAME_JSON),
+            &asset_config_rules(&assets),
+        )
+        .unwrap();
+
+        let logger = slog::Logger::root(slog::Discard, slog::o!());
+        let descriptors = gather_asset_descriptors(&[dir.path()], &logger).unwrap();
+        let configs: HashMap<_, _> = descriptors
+            .into_iter()
+            .map(|descriptor| (descriptor.key, descriptor.config))
+            .collect();
+        assert_eq!(configs.len(), 3);
+
+        let index = &configs["/index.html"];
+        assert_eq!(index.cache.as_ref().unwrap().max_age, Some(30));
+        assert_eq!(index.headers.as_ref().unwrap()["X-Frame-Options"], "DENY");
+        assert_eq!(index.allow_raw_access, Some(false));
+        assert_eq!(index.enable_aliasing, Some(true));
+        assert!(configs.contains_key("/.well-known/ic-domains"));
+        assert_eq!(
+            configs["/img/logo[1].png"].cache.as_ref().unwrap().max_age,
+            Some(3600)
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use dfx_core::error::fs::FsError;
+use ic_agent::AgentError;
+use thiserror::Error;
+
+/// Errors related to generating the `/.well-known/ic-domains` asset.
+#[derive(Error, Debug)]
+pub enum WriteCustomDomainsError {
+    /// The custom domain is not a plain domain name.
+    #[error("'{0}' is not a valid custom domain. Use the domain name only, e.g. 'example.com', without a scheme or path.")]
+    InvalidDomain(String),
+
+    /// Failed to write the generated files.
+    #[error(transparent)]
+    FsError(#[from] FsError),
+}
+
+/// Errors found when checking that an asset canister is set up for its custom domains.
+#[derive(Error, Debug)]
+pub enum CheckCustomDomainsError {
+    /// The http_request call failed.
+    #[error("Failed to request /.well-known/ic-domains: {0}")]
+    HttpRequestFailed(AgentError),
+
+    /// The asset canister did not respond with 200 OK.
+    #[error("/.well-known/ic-domains is not served (status code {0}).")]
+    NotServed(u16),
+
+    /// The response has no IC-Certificate header.
+    #[error("The response for /.well-known/ic-domains is not certified.")]
+    MissingCertificate,
+
+    /// The IC-Certificate header could not be parsed.
+    #[error("The IC-Certificate header of /.well-known/ic-domains is malformed: {0}")]
+    MalformedCertificate(String),
+
+    /// The certificate was not issued by the IC for this canister.
+    #[error("The certificate of /.well-known/ic-domains could not be verified: {0}")]
+    InvalidCertificate(AgentError),
+
+    /// The certified tree does not match the certified data of the canister, or does not cover the response.
+    #[error("The certificate of /.well-known/ic-domains does not match the content that is served. Is another asset served in its place?")]
+    ContentNotCertified,
+
+    /// The domain is not listed in /.well-known/ic-domains.
+    #[error("{0} is not listed in /.well-known/ic-domains.")]
+    DomainNotListed(String),
+}
@@ -0,0 +1,45 @@
+use crate::error::get_asset_properties::GetAssetPropertiesError;
+use dfx_core::error::fs::FsError;
+use dfx_core::error::structured_file::StructuredFileError;
+use ic_agent::AgentError;
+use thiserror::Error;
+
+/// Errors related to reading the content of an asset encoding from an asset canister.
+#[derive(Error, Debug)]
+pub enum GetContentError {
+    /// The call to get or get_chunk failed.
+    #[error("Failed to get {0} ({1}): {2}")]
+    GetFailed(String, String, AgentError),
+
+    /// The content does not have the sha256 that the canister lists for it.
+    #[error("The content of {0} ({1}) does not match its sha256.")]
+    Sha256Mismatch(String, String),
+
+    /// The chunks of the content do not add up to the total length that the canister lists for it.
+    #[error("The content of {0} ({1}) does not have its length of {2} bytes.")]
+    LengthMismatch(String, String, u64),
+}
+
+/// Errors related to downloading the contents of an asset canister.
+#[derive(Error, Debug)]
+pub enum DownloadError {
+    /// Failed to write the downloaded content.
+    #[error(transparent)]
+    FsError(#[from] FsError),
+
+    /// Failed to read the content of an asset.
+    #[error(transparent)]
+    GetContentFailed(#[from] GetContentError),
+
+    /// Failed when getting asset properties.
+    #[error(transparent)]
+    GetAssetPropertiesFailed(#[from] GetAssetPropertiesError),
+
+    /// Failed when calling the list method.
+    #[error("Failed to list assets: {0}")]
+    ListAssetsFailed(AgentError),
+
+    /// Failed to write the .ic-assets.json with the asset properties.
+    #[error("Failed to write the asset configuration: {0}")]
+    WriteAssetConfigFailed(#[from] StructuredFileError),
+}
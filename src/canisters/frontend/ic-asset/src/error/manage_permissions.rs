@@ -0,0 +1,23 @@
+use ic_agent::AgentError;
+use thiserror::Error;
+
+/// Errors related to granting, revoking and listing permissions.
+#[derive(Error, Debug)]
+pub enum ManagePermissionsError {
+    /// The asset canister does not know about scoped permissions,
+    /// and would ignore the scope and grant the permission for all assets.
+    #[error("The asset canister does not support scoped permissions. Upgrade it to a newer version first.")]
+    ScopesNotSupported,
+
+    /// Failed to grant the permission.
+    #[error("Failed to grant permission: {0}")]
+    GrantPermission(AgentError),
+
+    /// Failed to revoke the permission.
+    #[error("Failed to revoke permission: {0}")]
+    RevokePermission(AgentError),
+
+    /// Failed to list the principals with a permission.
+    #[error("Failed to list permissions: {0}")]
+    ListPermissions(AgentError),
+}
@@ -0,0 +1,38 @@
+use crate::error::create_chunk::CreateChunkError;
+use crate::error::download::GetContentError;
+use crate::error::get_asset_properties::GetAssetPropertiesError;
+use crate::error::sync::SyncError;
+use ic_agent::AgentError;
+use thiserror::Error;
+
+/// Errors related to copying the assets of one asset canister to another.
+#[derive(Error, Debug)]
+pub enum MirrorError {
+    /// Failed when committing the batch to the target canister.
+    #[error(transparent)]
+    CommitFailed(#[from] SyncError),
+
+    /// Failed when calling create_batch on the target canister.
+    #[error("Failed to create batch: {0}")]
+    CreateBatchFailed(AgentError),
+
+    /// Failed when uploading a chunk to the target canister.
+    #[error(transparent)]
+    CreateChunkFailed(#[from] CreateChunkError),
+
+    /// Failed to read the content of an asset from the source canister.
+    #[error(transparent)]
+    GetContentFailed(#[from] GetContentError),
+
+    /// Failed when getting asset properties.
+    #[error(transparent)]
+    GetAssetPropertiesFailed(#[from] GetAssetPropertiesError),
+
+    /// The source canister lists a content type that cannot be parsed.
+    #[error("Asset {0} has an invalid content type '{1}'.")]
+    InvalidContentType(String, String),
+
+    /// Failed when calling the list method.
+    #[error("Failed to list assets of {0}: {1}")]
+    ListAssetsFailed(String, AgentError),
+}
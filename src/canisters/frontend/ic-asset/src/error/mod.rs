@@ -5,13 +5,18 @@ mod compute_evidence;
 mod create_chunk;
 mod create_encoding;
 mod create_project_asset;
+mod custom_domains;
 mod downgrade_commit_batch_arguments;
+mod download;
 mod gather_asset_descriptors;
 mod get_asset_config;
 mod get_asset_properties;
 mod hash_content;
 mod load_config;
 mod load_rule;
+mod manage_permissions;
+mod mirror;
+mod plan_sync;
 mod prepare_sync_for_proposal;
 mod sync;
 mod upload;
@@ -22,13 +27,18 @@ pub use compute_evidence::ComputeEvidenceError;
 pub use create_chunk::CreateChunkError;
 pub use create_encoding::CreateEncodingError;
 pub use create_project_asset::CreateProjectAssetError;
+pub use custom_domains::{CheckCustomDomainsError, WriteCustomDomainsError};
 pub use downgrade_commit_batch_arguments::DowngradeCommitBatchArgumentsV1ToV0Error;
+pub use download::{DownloadError, GetContentError};
 pub use gather_asset_descriptors::GatherAssetDescriptorsError;
 pub use get_asset_config::GetAssetConfigError;
 pub use get_asset_properties::GetAssetPropertiesError;
 pub use hash_content::HashContentError;
 pub use load_config::AssetLoadConfigError;
 pub use load_rule::LoadRuleError;
+pub use manage_permissions::ManagePermissionsError;
+pub use mirror::MirrorError;
+pub use plan_sync::PlanSyncError;
 pub use prepare_sync_for_proposal::PrepareSyncForProposalError;
 pub use sync::SyncError;
 pub use upload::UploadError;
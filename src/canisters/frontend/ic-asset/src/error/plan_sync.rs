@@ -0,0 +1,25 @@
+use crate::error::create_project_asset::CreateProjectAssetError;
+use crate::error::gather_asset_descriptors::GatherAssetDescriptorsError;
+use crate::error::get_asset_properties::GetAssetPropertiesError;
+use ic_agent::AgentError;
+use thiserror::Error;
+
+/// Errors related to computing the changes a sync would make.
+#[derive(Error, Debug)]
+pub enum PlanSyncError {
+    /// Failed when inspecting assets to be updated.
+    #[error(transparent)]
+    ProcessProjectAsset(#[from] CreateProjectAssetError),
+
+    /// Failed when determining which assets and encodings changed.
+    #[error(transparent)]
+    GatherAssetDescriptors(#[from] GatherAssetDescriptorsError),
+
+    /// Failed when reading assets properties from the asset canister.
+    #[error(transparent)]
+    GetAssetProperties(#[from] GetAssetPropertiesError),
+
+    /// Failed to list assets in the asset canister.
+    #[error("Failed to list assets: {0}")]
+    ListAssets(AgentError),
+}
@@ -20,7 +20,7 @@
 //!     .with_agent(&agent)
 //!     .build()?;
 //! let logger = slog::Logger::root(slog::Discard, slog::o!());
-//! ic_asset::sync(&canister, &[concat!(env!("CARGO_MANIFEST_DIR"), "assets/").as_ref()], &logger).await?;
+//! ic_asset::sync(&canister, &[concat!(env!("CARGO_MANIFEST_DIR"), "assets/").as_ref()], None, &logger).await?;
 //! # Ok(())
 //! # }
 
@@ -34,12 +34,26 @@
 mod asset;
 mod batch_upload;
 mod canister_api;
+mod custom_domains;
+mod download;
 pub mod error;
 mod evidence;
+mod mirror;
+mod permissions;
+mod plan;
 mod sync;
 mod upload;
 
+pub use canister_api::types::permissions::{Permission, ScopedPermission};
+pub use custom_domains::{
+    check_custom_domains, dns_records, validate_custom_domain, write_custom_domains, DnsRecord,
+    IC_DOMAINS_KEY,
+};
+pub use download::download;
 pub use evidence::compute_evidence;
+pub use mirror::mirror;
+pub use permissions::{grant_permission, list_permissions, revoke_permission};
+pub use plan::{plan_sync, AssetChange, EncodingUpload, PropertyChange, SyncPlan};
 pub use sync::prepare_sync_for_proposal;
 pub use sync::sync;
 pub use upload::upload;
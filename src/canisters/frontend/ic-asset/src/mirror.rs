@@ -0,0 +1,183 @@
+use crate::asset::config::{AssetConfig, CacheConfig};
+use crate::batch_upload::operations::{assemble_commit_batch_arguments, AssetDeletionReason};
+use crate::batch_upload::plumbing::{
+    content_encoding_descriptive_suffix, AssetDescriptor, ChunkUploader, ProjectAsset,
+    ProjectAssetEncoding,
+};
+use crate::batch_upload::semaphores::Semaphores;
+use crate::canister_api::methods::asset_properties::get_assets_properties;
+use crate::canister_api::methods::batch::create_batch;
+use crate::canister_api::methods::list::list_assets;
+use crate::canister_api::types::asset::{AssetDetails, AssetEncodingDetails, AssetProperties};
+use crate::download::EncodingChunks;
+use crate::error::MirrorError;
+use crate::error::MirrorError::{CreateBatchFailed, InvalidContentType, ListAssetsFailed};
+use crate::sync::commit_sync_operations;
+use ic_utils::Canister;
+use mime::Mime;
+use slog::{info, Logger};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// Makes the assets of the target canister the same as those of the source canister,
+/// including all content encodings and asset properties. Assets that only the target has are deleted.
+///
+/// Content is copied one chunk at a time. Encodings that the target already has are not copied again.
+pub async fn mirror(
+    source: &Canister<'_>,
+    target: &Canister<'_>,
+    logger: &Logger,
+) -> Result<(), MirrorError> {
+    let source_assets = list_assets(source)
+        .await
+        .map_err(|e| ListAssetsFailed(source.canister_id_().to_text(), e))?;
+    let target_assets = list_assets(target)
+        .await
+        .map_err(|e| ListAssetsFailed(target.canister_id_().to_text(), e))?;
+    info!(
+        logger,
+        "Fetching properties for all assets in both canisters."
+    );
+    let source_asset_properties = get_assets_properties(source, &source_assets).await?;
+    let target_asset_properties = get_assets_properties(target, &target_assets).await?;
+
+    info!(logger, "Starting batch.");
+    let batch_id = create_batch(target).await.map_err(CreateBatchFailed)?;
+    info!(
+        logger,
+        "Copying contents of new and changed assets in batch {}:", batch_id
+    );
+    let chunk_uploader = ChunkUploader::new(target.clone(), batch_id.clone());
+    let semaphores = Semaphores::new();
+
+    let mut keys: Vec<&String> = source_assets.keys().collect();
+    keys.sort();
+    let mut project_assets = HashMap::new();
+    for key in keys {
+        let source_asset = &source_assets[key];
+        let media_type: Mime = source_asset
+            .content_type
+            .parse()
+            .map_err(|_| InvalidContentType(key.clone(), source_asset.content_type.clone()))?;
+        let target_asset = target_assets
+            .get(key)
+            .filter(|target_asset| target_asset.content_type == media_type.to_string());
+        let mut encodings = HashMap::new();
+        for encoding in &source_asset.encodings {
+            let project_asset_encoding = copy_encoding(
+                source,
+                &chunk_uploader,
+                key,
+                encoding,
+                target_asset,
+                &semaphores,
+                logger,
+            )
+            .await?;
+            encodings.insert(encoding.content_encoding.clone(), project_asset_encoding);
+        }
+        let properties = source_asset_properties
+            .get(key)
+            .cloned()
+            .unwrap_or_default();
+        project_assets.insert(
+            key.clone(),
+            ProjectAsset {
+                asset_descriptor: AssetDescriptor {
+                    source: PathBuf::new(),
+                    key: key.clone(),
+                    config: asset_config(properties),
+                    stored_encodings: BTreeMap::new(),
+                },
+                media_type,
+                encodings,
+            },
+        );
+    }
+
+    let commit_batch_args = assemble_commit_batch_arguments(
+        project_assets,
+        target_assets,
+        AssetDeletionReason::Obsolete,
+        target_asset_properties,
+        batch_id,
+    );
+    commit_sync_operations(target, commit_batch_args, logger).await?;
+    Ok(())
+}
+
+async fn copy_encoding(
+    source: &Canister<'_>,
+    chunk_uploader: &ChunkUploader<'_>,
+    key: &str,
+    encoding: &AssetEncodingDetails,
+    target_asset: Option<&AssetDetails>,
+    semaphores: &Semaphores,
+    logger: &Logger,
+) -> Result<ProjectAssetEncoding, MirrorError> {
+    let content_encoding = &encoding.content_encoding;
+    let target_sha256 = target_asset
+        .and_then(|asset| {
+            asset
+                .encodings
+                .iter()
+                .find(|details| &details.content_encoding == content_encoding)
+        })
+        .and_then(|details| details.sha256.as_ref());
+    if let (Some(sha256), Some(target_sha256)) = (&encoding.sha256, target_sha256) {
+        if sha256 == target_sha256 {
+            info!(
+                logger,
+                "  {}{} sha {} is already installed",
+                key,
+                content_encoding_descriptive_suffix(content_encoding),
+                hex::encode(sha256),
+            );
+            return Ok(ProjectAssetEncoding {
+                chunk_ids: vec![],
+                sha256: sha256.clone(),
+                already_in_place: true,
+                size: 0,
+            });
+        }
+    }
+
+    let mut chunks = EncodingChunks::get(source, key, content_encoding).await?;
+    let mut chunk_ids = vec![];
+    let mut size = 0;
+    while let Some(chunk) = chunks.next().await? {
+        size += chunk.len();
+        chunk_ids.push(chunk_uploader.create_chunk(&chunk, semaphores).await?);
+    }
+    let sha256 = chunks.sha256().unwrap_or_default().to_vec();
+    info!(
+        logger,
+        "  {}{} {} chunks ({} bytes) sha {}",
+        key,
+        content_encoding_descriptive_suffix(content_encoding),
+        chunk_ids.len(),
+        size,
+        hex::encode(&sha256),
+    );
+    Ok(ProjectAssetEncoding {
+        chunk_ids,
+        sha256,
+        already_in_place: false,
+        size,
+    })
+}
+
+/// The configuration that gives an asset these properties.
+fn asset_config(properties: AssetProperties) -> AssetConfig {
+    AssetConfig {
+        cache: properties.max_age.map(|max_age| CacheConfig {
+            max_age: Some(max_age),
+        }),
+        headers: properties
+            .headers
+            .map(|headers| headers.into_iter().collect()),
+        enable_aliasing: properties.is_aliased,
+        allow_raw_access: properties.allow_raw_access,
+        ..Default::default()
+    }
+}
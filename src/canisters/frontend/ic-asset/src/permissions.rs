@@ -0,0 +1,79 @@
+use crate::canister_api::methods::permissions;
+use crate::canister_api::types::permissions::{Permission, ScopedPermission};
+use crate::error::ManagePermissionsError;
+use crate::error::ManagePermissionsError::{
+    GrantPermission, ListPermissions, RevokePermission, ScopesNotSupported,
+};
+use candid::Principal;
+use ic_agent::{agent::RejectResponse, AgentError};
+use ic_utils::Canister;
+
+/// Grants a permission, optionally limited to the assets under the path `scope`.
+pub async fn grant_permission(
+    canister: &Canister<'_>,
+    to_principal: Principal,
+    permission: Permission,
+    scope: Option<&str>,
+) -> Result<(), ManagePermissionsError> {
+    if scope.is_some() {
+        ensure_scopes_supported(canister).await?;
+    }
+    permissions::grant_permission(
+        canister,
+        to_principal,
+        permission,
+        scope.map(str::to_string),
+    )
+    .await
+    .map_err(GrantPermission)
+}
+
+/// Revokes a permission. Without `scope`, the permission is revoked for all assets,
+/// including any scopes it was granted for.
+pub async fn revoke_permission(
+    canister: &Canister<'_>,
+    of_principal: Principal,
+    permission: Permission,
+    scope: Option<&str>,
+) -> Result<(), ManagePermissionsError> {
+    if scope.is_some() {
+        ensure_scopes_supported(canister).await?;
+    }
+    permissions::revoke_permission(
+        canister,
+        of_principal,
+        permission,
+        scope.map(str::to_string),
+    )
+    .await
+    .map_err(RevokePermission)
+}
+
+/// Lists the principals with a permission and the scopes each is limited to.
+pub async fn list_permissions(
+    canister: &Canister<'_>,
+    permission: Permission,
+) -> Result<Vec<ScopedPermission>, ManagePermissionsError> {
+    match permissions::list_scoped_permissions(canister, permission).await {
+        Err(e) if is_method_not_found(&e) => Err(ScopesNotSupported),
+        result => result.map_err(ListPermissions),
+    }
+}
+
+/// Older asset canisters ignore the scope of a grant, so they must not receive one.
+async fn ensure_scopes_supported(canister: &Canister<'_>) -> Result<(), ManagePermissionsError> {
+    list_permissions(canister, Permission::Commit)
+        .await
+        .map(|_| ())
+}
+
+fn is_method_not_found(error: &AgentError) -> bool {
+    match error {
+        AgentError::CertifiedReject(RejectResponse { reject_message, .. })
+        | AgentError::UncertifiedReject(RejectResponse { reject_message, .. }) => {
+            reject_message.contains("has no update method")
+                || reject_message.contains("method does not exist")
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,426 @@
+use crate::batch_upload::operations::assemble_batch_operations;
+use crate::batch_upload::operations::AssetDeletionReason::Obsolete;
+use crate::batch_upload::plumbing::{make_project_assets, ProjectAsset};
+use crate::canister_api::methods::asset_properties::get_assets_properties;
+use crate::canister_api::methods::list::list_assets;
+use crate::canister_api::types::asset::{AssetDetails, AssetProperties};
+use crate::canister_api::types::batch_upload::v1::BatchOperationKind;
+use crate::error::PlanSyncError;
+use crate::sync::gather_asset_descriptors;
+use ic_utils::Canister;
+use serde::Serialize;
+use serde_json::{json, Value};
+use slog::{info, Logger};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// The changes [`sync()`](crate::sync()) would make to an asset canister.
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct SyncPlan {
+    /// Assets that are not in the canister yet.
+    pub added: Vec<AssetChange>,
+    /// Assets whose content, content type or properties change.
+    pub changed: Vec<AssetChange>,
+    /// Keys of the assets that will be deleted.
+    pub deleted: Vec<String>,
+    /// Total bytes of content that will be uploaded.
+    pub upload_bytes: usize,
+}
+
+/// The changes to a single asset.
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct AssetChange {
+    /// The asset key.
+    pub key: String,
+    /// Content encodings that will be uploaded.
+    pub set_encodings: Vec<EncodingUpload>,
+    /// Content encodings that will be removed.
+    pub unset_encodings: Vec<String>,
+    /// Properties that will change.
+    pub properties: Vec<PropertyChange>,
+}
+
+/// A content encoding that will be uploaded.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct EncodingUpload {
+    /// The content encoding, e.g. `identity` or `gzip`.
+    pub encoding: String,
+    /// The size of the encoded content.
+    pub bytes: usize,
+}
+
+/// A property whose value will change.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct PropertyChange {
+    /// The property name, e.g. `max_age`.
+    pub name: String,
+    /// The value in the canister.
+    pub old: Value,
+    /// The value after the sync.
+    pub new: Value,
+}
+
+impl SyncPlan {
+    /// Whether the sync would leave the canister unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.deleted.is_empty()
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.added {
+            writeln!(f, "+ {}{}", change.key, change.describe())?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {}{}", change.key, change.describe())?;
+        }
+        for key in &self.deleted {
+            writeln!(f, "- {}", key)?;
+        }
+        write!(
+            f,
+            "{} added, {} changed, {} deleted, {} bytes to upload",
+            self.added.len(),
+            self.changed.len(),
+            self.deleted.len(),
+            self.upload_bytes
+        )
+    }
+}
+
+impl AssetChange {
+    fn describe(&self) -> String {
+        let mut parts = vec![];
+        parts.extend(
+            self.set_encodings
+                .iter()
+                .map(|e| format!("{} {} bytes", e.encoding, e.bytes)),
+        );
+        parts.extend(self.unset_encodings.iter().map(|e| format!("unset {}", e)));
+        parts.extend(
+            self.properties
+                .iter()
+                .map(|p| format!("{}: {} -> {}", p.name, p.old, p.new)),
+        );
+        if parts.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", parts.join(", "))
+        }
+    }
+}
+
+/// Compute the changes [`sync()`](crate::sync()) would make to the asset canister, without uploading or committing anything.
+pub async fn plan_sync(
+    canister: &Canister<'_>,
+    dirs: &[&Path],
+    logger: &Logger,
+) -> Result<SyncPlan, PlanSyncError> {
+    let asset_descriptors = gather_asset_descriptors(dirs, logger)?;
+
+    let canister_assets = list_assets(canister)
+        .await
+        .map_err(PlanSyncError::ListAssets)?;
+    info!(
+        logger,
+        "Fetching properties for all assets in the canister."
+    );
+    let canister_asset_properties = get_assets_properties(canister, &canister_assets).await?;
+
+    let project_assets =
+        make_project_assets(None, asset_descriptors, &canister_assets, logger).await?;
+
+    let operations = assemble_batch_operations(
+        &project_assets,
+        canister_assets.clone(),
+        Obsolete,
+        canister_asset_properties.clone(),
+    );
+
+    Ok(plan_from_operations(
+        operations,
+        &project_assets,
+        &canister_assets,
+        &canister_asset_properties,
+    ))
+}
+
+fn plan_from_operations(
+    operations: Vec<BatchOperationKind>,
+    project_assets: &HashMap<String, ProjectAsset>,
+    canister_assets: &HashMap<String, AssetDetails>,
+    canister_asset_properties: &HashMap<String, AssetProperties>,
+) -> SyncPlan {
+    let mut changes: BTreeMap<String, AssetChange> = BTreeMap::new();
+    let mut created = vec![];
+    let mut deleted = vec![];
+    let mut upload_bytes = 0;
+
+    for operation in operations {
+        match operation {
+            BatchOperationKind::CreateAsset(args) => {
+                let asset_change = change(&mut changes, &args.key);
+                // Recreated with a different content type.
+                if let Some(canister_asset) = canister_assets.get(&args.key) {
+                    asset_change.properties.push(PropertyChange {
+                        name: "content_type".to_string(),
+                        old: json!(canister_asset.content_type),
+                        new: json!(args.content_type),
+                    });
+                }
+                created.push(args.key);
+            }
+            BatchOperationKind::SetAssetContent(args) => {
+                let bytes = project_assets
+                    .get(&args.key)
+                    .and_then(|asset| asset.encodings.get(&args.content_encoding))
+                    .map_or(0, |encoding| encoding.size);
+                upload_bytes += bytes;
+                change(&mut changes, &args.key)
+                    .set_encodings
+                    .push(EncodingUpload {
+                        encoding: args.content_encoding,
+                        bytes,
+                    });
+            }
+            BatchOperationKind::UnsetAssetContent(args) => {
+                change(&mut changes, &args.key)
+                    .unset_encodings
+                    .push(args.content_encoding);
+            }
+            BatchOperationKind::DeleteAsset(args) => deleted.push(args.key),
+            BatchOperationKind::SetAssetProperties(args) => {
+                let old = canister_asset_properties.get(&args.key);
+                let properties = &mut change(&mut changes, &args.key).properties;
+                if let Some(new) = args.max_age {
+                    properties.push(PropertyChange {
+                        name: "max_age".to_string(),
+                        old: json!(old.and_then(|p| p.max_age)),
+                        new: json!(new),
+                    });
+                }
+                if let Some(new) = args.headers {
+                    let new: Option<BTreeMap<_, _>> = new.map(|h| h.into_iter().collect());
+                    let old: Option<BTreeMap<_, _>> = old
+                        .and_then(|p| p.headers.clone())
+                        .map(|h| h.into_iter().collect());
+                    properties.push(PropertyChange {
+                        name: "headers".to_string(),
+                        old: json!(old),
+                        new: json!(new),
+                    });
+                }
+                if let Some(new) = args.is_aliased {
+                    properties.push(PropertyChange {
+                        name: "enable_aliasing".to_string(),
+                        old: json!(old.and_then(|p| p.is_aliased)),
+                        new: json!(new),
+                    });
+                }
+                if let Some(new) = args.allow_raw_access {
+                    properties.push(PropertyChange {
+                        name: "allow_raw_access".to_string(),
+                        old: json!(old.and_then(|p| p.allow_raw_access)),
+                        new: json!(new),
+                    });
+                }
+            }
+            BatchOperationKind::Clear(_) => {}
+        }
+    }
+
+    let mut plan = SyncPlan {
+        upload_bytes,
+        ..Default::default()
+    };
+    for (key, mut asset_change) in changes {
+        asset_change
+            .set_encodings
+            .sort_by(|a, b| a.encoding.cmp(&b.encoding));
+        asset_change.unset_encodings.sort();
+        if created.contains(&key) && !canister_assets.contains_key(&key) {
+            plan.added.push(asset_change);
+        } else {
+            plan.changed.push(asset_change);
+        }
+    }
+    // Assets that are deleted only to be recreated are listed as changed.
+    deleted.retain(|key| !created.contains(key));
+    deleted.sort();
+    plan.deleted = deleted;
+    plan
+}
+
+fn change<'a>(changes: &'a mut BTreeMap<String, AssetChange>, key: &str) -> &'a mut AssetChange {
+    changes
+        .entry(key.to_string())
+        .or_insert_with(|| AssetChange {
+            key: key.to_string(),
+            ..Default::default()
+        })
+}
+
+#[cfg(test)]
+mod test_plan_from_operations {
+    use super::*;
+    use crate::asset::config::AssetConfig;
+    use crate::batch_upload::plumbing::{AssetDescriptor, ProjectAssetEncoding};
+    use crate::canister_api::types::asset::{AssetEncodingDetails, SetAssetPropertiesArguments};
+    use crate::canister_api::types::batch_upload::common::{
+        CreateAssetArguments, DeleteAssetArguments, SetAssetContentArguments,
+        UnsetAssetContentArguments,
+    };
+    use std::path::PathBuf;
+
+    fn project_asset(key: &str, encodings: &[(&str, usize)]) -> ProjectAsset {
+        ProjectAsset {
+            asset_descriptor: AssetDescriptor {
+                source: PathBuf::new(),
+                key: key.to_string(),
+                config: AssetConfig::default(),
+                stored_encodings: BTreeMap::new(),
+            },
+            media_type: mime::TEXT_PLAIN,
+            encodings: encodings
+                .iter()
+                .map(|(encoding, size)| {
+                    (
+                        encoding.to_string(),
+                        ProjectAssetEncoding {
+                            chunk_ids: vec![],
+                            sha256: vec![],
+                            already_in_place: false,
+                            size: *size,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn canister_asset(key: &str, content_type: &str) -> AssetDetails {
+        AssetDetails {
+            key: key.to_string(),
+            content_type: content_type.to_string(),
+            encodings: vec![AssetEncodingDetails {
+                content_encoding: "gzip".to_string(),
+                sha256: None,
+            }],
+        }
+    }
+
+    fn set_content(key: &str, encoding: &str) -> BatchOperationKind {
+        BatchOperationKind::SetAssetContent(SetAssetContentArguments {
+            key: key.to_string(),
+            content_encoding: encoding.to_string(),
+            chunk_ids: vec![],
+            sha256: None,
+        })
+    }
+
+    fn create(key: &str) -> BatchOperationKind {
+        BatchOperationKind::CreateAsset(CreateAssetArguments {
+            key: key.to_string(),
+            content_type: "text/plain".to_string(),
+            max_age: None,
+            headers: None,
+            enable_aliasing: None,
+            allow_raw_access: None,
+        })
+    }
+
+    fn delete(key: &str) -> BatchOperationKind {
+        BatchOperationKind::DeleteAsset(DeleteAssetArguments {
+            key: key.to_string(),
+        })
+    }
+
+    #[test]
+    fn classifies_added_changed_and_deleted_assets() {
+        let project_assets = HashMap::from([
+            (
+                "/new.txt".to_string(),
+                project_asset("/new.txt", &[("identity", 10)]),
+            ),
+            (
+                "/changed.txt".to_string(),
+                project_asset("/changed.txt", &[("identity", 20)]),
+            ),
+            (
+                "/retyped.txt".to_string(),
+                project_asset("/retyped.txt", &[("identity", 5)]),
+            ),
+        ]);
+        let canister_assets = HashMap::from([
+            (
+                "/changed.txt".to_string(),
+                canister_asset("/changed.txt", "text/plain"),
+            ),
+            (
+                "/retyped.txt".to_string(),
+                canister_asset("/retyped.txt", "text/html"),
+            ),
+            (
+                "/old.txt".to_string(),
+                canister_asset("/old.txt", "text/plain"),
+            ),
+        ]);
+        let canister_asset_properties = HashMap::from([(
+            "/changed.txt".to_string(),
+            AssetProperties {
+                max_age: Some(60),
+                headers: None,
+                is_aliased: None,
+                allow_raw_access: None,
+            },
+        )]);
+        let operations = vec![
+            delete("/old.txt"),
+            delete("/retyped.txt"),
+            create("/new.txt"),
+            create("/retyped.txt"),
+            BatchOperationKind::UnsetAssetContent(UnsetAssetContentArguments {
+                key: "/changed.txt".to_string(),
+                content_encoding: "gzip".to_string(),
+            }),
+            set_content("/new.txt", "identity"),
+            set_content("/changed.txt", "identity"),
+            set_content("/retyped.txt", "identity"),
+            BatchOperationKind::SetAssetProperties(SetAssetPropertiesArguments {
+                key: "/changed.txt".to_string(),
+                max_age: Some(Some(3600)),
+                headers: None,
+                is_aliased: None,
+                allow_raw_access: None,
+            }),
+        ];
+
+        let plan = plan_from_operations(
+            operations,
+            &project_assets,
+            &canister_assets,
+            &canister_asset_properties,
+        );
+
+        assert_eq!(plan.upload_bytes, 35);
+        assert_eq!(plan.deleted, vec!["/old.txt".to_string()]);
+        assert_eq!(plan.added.len(), 1);
+        assert_eq!(plan.added[0].key, "/new.txt");
+        let changed: Vec<_> = plan.changed.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(changed, vec!["/changed.txt", "/retyped.txt"]);
+        assert_eq!(
+            plan.changed[0].describe(),
+            " (identity 20 bytes, unset gzip, max_age: 60 -> 3600)"
+        );
+        assert_eq!(
+            plan.changed[1].describe(),
+            " (identity 5 bytes, content_type: \"text/html\" -> \"text/plain\")"
+        );
+        assert_eq!(
+            plan.to_string().lines().last().unwrap(),
+            "1 added, 2 changed, 1 deleted, 35 bytes to upload"
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use crate::asset::config::{
     AssetConfig, AssetSourceDirectoryConfiguration, ASSETS_CONFIG_FILENAME_JSON,
 };
-use crate::batch_upload::operations::BATCH_UPLOAD_API_VERSION;
-use crate::batch_upload::plumbing::ChunkUploader;
+use crate::batch_upload::operations::{BATCH_UPLOAD_API_VERSION, SHARED_CHUNKS_API_VERSION};
+use crate::batch_upload::plumbing::{ChunkUploader, CONTENT_ENCODING_IDENTITY};
+use crate::batch_upload::progress::UploadProgress;
 use crate::batch_upload::{
     self,
     operations::AssetDeletionReason,
@@ -20,6 +21,7 @@ use crate::canister_api::types::batch_upload::v1::BatchOperationKind;
 use crate::canister_api::types::batch_upload::{
     common::ComputeEvidenceArguments, v1::CommitBatchArguments,
 };
+use crate::download::ENCODINGS_DIR;
 use crate::error::CompatibilityError::DowngradeV1TOV0Failed;
 use crate::error::GatherAssetDescriptorsError;
 use crate::error::GatherAssetDescriptorsError::{
@@ -29,19 +31,45 @@ use crate::error::PrepareSyncForProposalError;
 use crate::error::SyncError;
 use crate::error::SyncError::CommitBatchFailed;
 use crate::error::UploadContentError;
-use crate::error::UploadContentError::{CreateBatchFailed, ListAssetsFailed};
+use crate::error::UploadContentError::{
+    CreateBatchFailed, CreateProjectAssetError, ListAssetsFailed,
+};
 use candid::Nat;
 use ic_agent::AgentError;
 use ic_utils::Canister;
 use slog::{debug, info, trace, warn, Logger};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 /// Sets the contents of the asset canister to the contents of a directory, including deleting old assets.
+/// If `progress_file` holds the progress of an earlier attempt, chunks that are still
+/// in the canister are not uploaded again.
 pub async fn upload_content_and_assemble_sync_operations(
     canister: &Canister<'_>,
     dirs: &[&Path],
+    progress_file: Option<&Path>,
+    logger: &Logger,
+) -> Result<CommitBatchArguments, UploadContentError> {
+    let resume = progress_file.is_some_and(Path::exists);
+    match upload_content(canister, dirs, progress_file, resume, logger).await {
+        // The batch of the earlier attempt may have expired, been committed or been proposed.
+        Err(CreateProjectAssetError(e)) if resume => {
+            warn!(
+                logger,
+                "Failed to continue the earlier upload: {}. Uploading everything again.", e
+            );
+            upload_content(canister, dirs, progress_file, false, logger).await
+        }
+        result => result,
+    }
+}
+
+async fn upload_content(
+    canister: &Canister<'_>,
+    dirs: &[&Path],
+    progress_file: Option<&Path>,
+    resume: bool,
     logger: &Logger,
 ) -> Result<CommitBatchArguments, UploadContentError> {
     let asset_descriptors = gather_asset_descriptors(dirs, logger)?;
@@ -53,9 +81,7 @@ pub async fn upload_content_and_assemble_sync_operations(
     );
     let canister_asset_properties = get_assets_properties(canister, &canister_assets).await?;
 
-    info!(logger, "Starting batch.");
-
-    let batch_id = create_batch(canister).await.map_err(CreateBatchFailed)?;
+    let (batch_id, progress) = start_batch(canister, progress_file, resume, logger).await?;
 
     info!(
         logger,
@@ -63,6 +89,14 @@ pub async fn upload_content_and_assemble_sync_operations(
     );
 
     let chunk_uploader = ChunkUploader::new(canister.clone(), batch_id.clone());
+    let chunk_uploader = match progress {
+        Some(progress) => chunk_uploader.with_shared_chunks(
+            progress,
+            progress_file.map(Path::to_path_buf),
+            logger,
+        ),
+        None => chunk_uploader,
+    };
 
     let project_assets = make_project_assets(
         Some(&chunk_uploader),
@@ -99,14 +133,66 @@ pub async fn upload_content_and_assemble_sync_operations(
     Ok(commit_batch_args)
 }
 
+/// Continues the batch of an earlier attempt if `resume` is set and there is one,
+/// and otherwise creates a new batch.
+async fn start_batch(
+    canister: &Canister<'_>,
+    progress_file: Option<&Path>,
+    resume: bool,
+    logger: &Logger,
+) -> Result<(Nat, Option<UploadProgress>), UploadContentError> {
+    if api_version(canister).await < SHARED_CHUNKS_API_VERSION {
+        info!(logger, "Starting batch.");
+        let batch_id = create_batch(canister).await.map_err(CreateBatchFailed)?;
+        return Ok((batch_id, None));
+    }
+
+    let canister_id = *canister.canister_id_();
+    let progress = progress_file
+        .filter(|_| resume)
+        .and_then(|path| UploadProgress::load(path, canister_id));
+    if let Some(progress) = progress {
+        let batch_id = progress.batch_id();
+        info!(
+            logger,
+            "Continuing batch {} with {} chunks that were already uploaded.",
+            batch_id,
+            progress.chunks.len()
+        );
+        return Ok((batch_id, Some(progress)));
+    }
+
+    info!(logger, "Starting batch.");
+    let batch_id = create_batch(canister).await.map_err(CreateBatchFailed)?;
+    Ok((
+        batch_id.clone(),
+        UploadProgress::new(canister_id, &batch_id),
+    ))
+}
+
 /// Sets the contents of the asset canister to the contents of a directory, including deleting old assets.
+/// If `progress_file` is given, an attempt that fails can be retried without uploading everything again.
 pub async fn sync(
     canister: &Canister<'_>,
     dirs: &[&Path],
+    progress_file: Option<&Path>,
     logger: &Logger,
 ) -> Result<(), SyncError> {
     let commit_batch_args =
-        upload_content_and_assemble_sync_operations(canister, dirs, logger).await?;
+        upload_content_and_assemble_sync_operations(canister, dirs, progress_file, logger).await?;
+    // Committing uses up the chunks, so a failed commit cannot continue the batch.
+    if let Some(progress_file) = progress_file {
+        UploadProgress::remove(progress_file);
+    }
+    commit_sync_operations(canister, commit_batch_args, logger).await
+}
+
+/// Commits the operations of a batch, in several calls if necessary.
+pub(crate) async fn commit_sync_operations(
+    canister: &Canister<'_>,
+    commit_batch_args: CommitBatchArguments,
+    logger: &Logger,
+) -> Result<(), SyncError> {
     let canister_api_version = api_version(canister).await;
     debug!(logger, "Canister API version: {canister_api_version}. ic-asset API version: {BATCH_UPLOAD_API_VERSION}");
     info!(logger, "Committing batch.");
@@ -147,7 +233,7 @@ async fn commit_in_stages(
 
     // Seen to work at 800 ({"SetAssetContent": 932, "Delete": 47, "CreateAsset": 58})
     // so 500 shouldn't exceed per-message instruction limit
-    for operations in other_operations.chunks(500) {
+    for operations in commit_stages(other_operations, 500) {
         info!(
             logger,
             "Committing batch with {} operations.",
@@ -157,7 +243,7 @@ async fn commit_in_stages(
             canister,
             CommitBatchArguments {
                 batch_id: Nat::from(0_u8),
-                operations: operations.into(),
+                operations,
             },
         )
         .await?
@@ -174,13 +260,64 @@ async fn commit_in_stages(
     .await
 }
 
+/// Splits operations into stages of at least `stage_size` operations, except for the last one.
+/// The canister releases a chunk after the last operation of a commit that uses it,
+/// so the operations that use the same chunk have to be committed in the same stage.
+fn commit_stages(
+    operations: Vec<BatchOperationKind>,
+    stage_size: usize,
+) -> Vec<Vec<BatchOperationKind>> {
+    fn chunk_ids(operation: &BatchOperationKind) -> &[Nat] {
+        match operation {
+            BatchOperationKind::SetAssetContent(arg) => &arg.chunk_ids,
+            _ => &[],
+        }
+    }
+
+    let mut stage_ends = vec![];
+    {
+        let mut last_use = HashMap::new();
+        for (index, operation) in operations.iter().enumerate() {
+            for chunk_id in chunk_ids(operation) {
+                last_use.insert(chunk_id, index);
+            }
+        }
+        let mut stage_start = 0;
+        let mut used_until = 0;
+        for (index, operation) in operations.iter().enumerate() {
+            for chunk_id in chunk_ids(operation) {
+                used_until = used_until.max(last_use[chunk_id]);
+            }
+            if index + 1 - stage_start >= stage_size && used_until <= index {
+                stage_start = index + 1;
+                stage_ends.push(stage_start);
+            }
+        }
+    }
+
+    let mut operations = operations.into_iter();
+    let mut stages = vec![];
+    let mut stage_start = 0;
+    for stage_end in stage_ends {
+        stages.push(operations.by_ref().take(stage_end - stage_start).collect());
+        stage_start = stage_end;
+    }
+    let rest: Vec<_> = operations.collect();
+    if !rest.is_empty() {
+        stages.push(rest);
+    }
+    stages
+}
+
 /// Stage changes and propose the batch for commit.
 pub async fn prepare_sync_for_proposal(
     canister: &Canister<'_>,
     dirs: &[&Path],
+    progress_file: Option<&Path>,
     logger: &Logger,
 ) -> Result<(), PrepareSyncForProposalError> {
-    let arg = upload_content_and_assemble_sync_operations(canister, dirs, logger).await?;
+    let arg =
+        upload_content_and_assemble_sync_operations(canister, dirs, progress_file, logger).await?;
     let arg = sort_batch_operations(arg);
     let batch_id = arg.batch_id.clone();
 
@@ -188,6 +325,10 @@ pub async fn prepare_sync_for_proposal(
     propose_commit_batch(canister, arg)
         .await
         .map_err(PrepareSyncForProposalError::ProposeCommitBatch)?;
+    // A proposed batch cannot take more chunks.
+    if let Some(progress_file) = progress_file {
+        UploadProgress::remove(progress_file);
+    }
 
     let compute_evidence_arg = ComputeEvidenceArguments {
         batch_id: batch_id.clone(),
@@ -226,6 +367,22 @@ fn include_entry(entry: &walkdir::DirEntry, config: &AssetConfig) -> bool {
     }
 }
 
+/// The encodings of an asset that `download` wrote to the encodings directory of `dir`.
+fn stored_encodings(dir: &Path, relative: &Path) -> BTreeMap<String, PathBuf> {
+    let Ok(entries) = dfx_core::fs::read_dir(&dir.join(ENCODINGS_DIR)) else {
+        return BTreeMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let content_encoding = entry.file_name().into_string().ok()?;
+            let path = entry.path().join(relative);
+            (content_encoding != CONTENT_ENCODING_IDENTITY && path.is_file())
+                .then_some((content_encoding, path))
+        })
+        .collect()
+}
+
 pub(crate) fn gather_asset_descriptors(
     dirs: &[&Path],
     logger: &Logger,
@@ -239,7 +396,9 @@ pub(crate) fn gather_asset_descriptors(
         let entries = WalkDir::new(&dir)
             .into_iter()
             .filter_entry(|entry| {
-                if let Ok(canonical_path) = &dfx_core::fs::canonicalize(entry.path()) {
+                if entry.path() == dir.join(ENCODINGS_DIR) {
+                    false
+                } else if let Ok(canonical_path) = &dfx_core::fs::canonicalize(entry.path()) {
                     let config = configuration
                         .get_asset_config(canonical_path)
                         .unwrap_or_default();
@@ -259,11 +418,13 @@ pub(crate) fn gather_asset_descriptors(
             let relative = source.strip_prefix(&dir).expect("cannot strip prefix");
             let key = String::from("/") + relative.to_string_lossy().as_ref();
             let config = configuration.get_asset_config(&source)?;
+            let stored_encodings = stored_encodings(&dir, relative);
 
             asset_descriptors_interim.push(AssetDescriptor {
                 source,
                 key,
                 config,
+                stored_encodings,
             })
         }
 
@@ -278,6 +439,24 @@ pub(crate) fn gather_asset_descriptors(
             asset_descriptors.insert(asset_descriptor.key.clone(), asset_descriptor);
         }
 
+        let mut unprotected_pages: Vec<_> = asset_descriptors_interim
+            .iter()
+            .filter(|asset_descriptor| {
+                mime_guess::from_path(&asset_descriptor.source).first() == Some(mime::TEXT_HTML)
+                    && asset_descriptor.config.lacks_security_policy()
+            })
+            .map(|asset_descriptor| asset_descriptor.key.as_str())
+            .collect();
+        if !unprotected_pages.is_empty() {
+            unprotected_pages.sort();
+            warn!(
+                logger,
+                "HTML assets without a Content-Security-Policy in {path}: {keys}. Set \"security_policy\" to \"standard\" or \"hardened\" in .ic-assets.json, or to \"disabled\" to silence this warning.",
+                path = dir.display(),
+                keys = unprotected_pages.join(", ")
+            );
+        }
+
         for (config_path, rules) in configuration.get_unused_configs() {
             warn!(
                 logger,
@@ -301,7 +480,7 @@ mod test_gathering_asset_descriptors_with_tempdir {
 
     use super::AssetDescriptor;
     use std::{
-        collections::HashMap,
+        collections::{BTreeMap, HashMap},
         fs,
         path::{Path, PathBuf},
     };
@@ -322,6 +501,7 @@ mod test_gathering_asset_descriptors_with_tempdir {
                 source: assets_dir.join(&relative_path),
                 key: format!("/{}", relative_path.to_str().unwrap()),
                 config: Default::default(),
+                stored_encodings: BTreeMap::new(),
             }
         }
         fn with_headers(mut self, headers: HashMap<&str, &str>) -> Self {
@@ -460,6 +640,52 @@ mod test_gathering_asset_descriptors_with_tempdir {
         assert_eq!(asset_descriptors, expected_asset_descriptors);
     }
 
+    #[test]
+    /// test gathering the encodings that `download` stored next to the assets
+    fn gather_stored_encodings() {
+        let files = HashMap::from([
+            (
+                Path::new(".ic-assets.json").to_path_buf(),
+                r#"[
+                {"match": ".*", "ignore": false}
+            ]"#
+                .to_string(),
+            ),
+            (Path::new("page.js").to_path_buf(), "page".to_string()),
+            (
+                Path::new(".ic-assets-encodings/gzip/page.js").to_path_buf(),
+                "gzipped page".to_string(),
+            ),
+            (
+                Path::new(".ic-assets-encodings/br/gone.js").to_path_buf(),
+                "compressed asset that no longer exists".to_string(),
+            ),
+        ]);
+        let assets_temp_dir = create_temporary_assets_directory(files);
+        let assets_dir = assets_temp_dir.path().canonicalize().unwrap();
+        let asset_descriptors = gather_asset_descriptors(&[&assets_dir]);
+
+        assert!(!asset_descriptors
+            .iter()
+            .any(|descriptor| descriptor.key.starts_with("/.ic-assets-encodings")));
+        let page = asset_descriptors
+            .iter()
+            .find(|descriptor| descriptor.key == "/page.js")
+            .unwrap();
+        assert_eq!(
+            page.stored_encodings,
+            BTreeMap::from([(
+                "gzip".to_string(),
+                assets_dir.join(".ic-assets-encodings/gzip/page.js")
+            )])
+        );
+        let file = asset_descriptors
+            .iter()
+            .find(|descriptor| descriptor.key == "/file")
+            .unwrap();
+        assert!(file.stored_encodings.is_empty());
+    }
+
     #[test]
     /// test gathering all non-dot files, from non-dot dirs
     fn gather_all_nondot_files_from_nondot_dirs() {
@@ -857,3 +1083,62 @@ mod test_gathering_asset_descriptors_with_tempdir {
         assert_eq!(dbg!(asset_descriptors), expected_asset_descriptors);
     }
 }
+
+#[cfg(test)]
+mod test_commit_stages {
+    use super::commit_stages;
+    use crate::canister_api::types::batch_upload::common::{
+        DeleteAssetArguments, SetAssetContentArguments,
+    };
+    use crate::canister_api::types::batch_upload::v1::BatchOperationKind;
+    use candid::Nat;
+
+    fn delete(key: &str) -> BatchOperationKind {
+        BatchOperationKind::DeleteAsset(DeleteAssetArguments {
+            key: key.to_string(),
+        })
+    }
+
+    fn set_content(key: &str, chunk_id: u8) -> BatchOperationKind {
+        BatchOperationKind::SetAssetContent(SetAssetContentArguments {
+            key: key.to_string(),
+            content_encoding: "identity".to_string(),
+            chunk_ids: vec![Nat::from(chunk_id)],
+            sha256: None,
+        })
+    }
+
+    fn stage_lengths(operations: Vec<BatchOperationKind>, stage_size: usize) -> Vec<usize> {
+        commit_stages(operations, stage_size)
+            .iter()
+            .map(Vec::len)
+            .collect()
+    }
+
+    #[test]
+    fn splits_into_stages_of_the_given_size() {
+        let operations = vec![
+            delete("/a"),
+            delete("/b"),
+            delete("/c"),
+            delete("/d"),
+            delete("/e"),
+        ];
+        assert_eq!(stage_lengths(operations, 2), vec![2, 2, 1]);
+        assert_eq!(stage_lengths(vec![], 2), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn keeps_the_uses_of_a_chunk_in_one_stage() {
+        let operations = vec![
+            set_content("/a", 1),
+            set_content("/b", 2),
+            set_content("/c", 3),
+            set_content("/copy-of-b", 2),
+            delete("/d"),
+            set_content("/e", 4),
+            set_content("/copy-of-e", 4),
+        ];
+        assert_eq!(stage_lengths(operations, 2), vec![4, 3]);
+    }
+}
@@ -16,7 +16,7 @@ use crate::error::UploadError;
 use crate::error::UploadError::{CommitBatchFailed, CreateBatchFailed, ListAssetsFailed};
 use ic_utils::Canister;
 use slog::{info, Logger};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 /// Upload the specified files
@@ -31,6 +31,7 @@ pub async fn upload(
             source: x.1.clone(),
             key: x.0.clone(),
             config: AssetConfig::default(),
+            stored_encodings: BTreeMap::new(),
         })
         .collect();
 
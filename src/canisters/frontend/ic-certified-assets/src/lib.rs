@@ -35,7 +35,7 @@ thread_local! {
 #[query]
 #[candid_method(query)]
 fn api_version() -> u16 {
-    1
+    2
 }
 
 #[update(guard = "is_manager_or_controller")]
@@ -48,8 +48,17 @@ fn authorize(other: Principal) {
 #[candid_method(update)]
 fn grant_permission(arg: GrantPermissionArguments) {
     STATE.with(|s| {
-        s.borrow_mut()
-            .grant_permission(arg.to_principal, &arg.permission)
+        let mut s = s.borrow_mut();
+        match &arg.scope {
+            Some(scope) => {
+                if let Err(msg) =
+                    s.grant_scoped_permission(arg.to_principal, &arg.permission, scope)
+                {
+                    trap(&msg);
+                }
+            }
+            None => s.grant_permission(arg.to_principal, &arg.permission),
+        }
     })
 }
 
@@ -57,8 +66,10 @@ fn grant_permission(arg: GrantPermissionArguments) {
 #[candid_method(update)]
 async fn validate_grant_permission(arg: GrantPermissionArguments) -> Result<String, String> {
     Ok(format!(
-        "grant {} permission to principal {}",
-        arg.permission, arg.to_principal
+        "grant {} permission to principal {}{}",
+        arg.permission,
+        arg.to_principal,
+        describe_scope(&arg.scope)
     ))
 }
 
@@ -89,8 +100,17 @@ async fn revoke_permission(arg: RevokePermissionArguments) {
     match check_access_result {
         Err(e) => trap(&e),
         Ok(_) => STATE.with(|s| {
-            s.borrow_mut()
-                .revoke_permission(arg.of_principal, &arg.permission)
+            let mut s = s.borrow_mut();
+            match &arg.scope {
+                Some(scope) => {
+                    if let Err(msg) =
+                        s.revoke_scoped_permission(arg.of_principal, &arg.permission, scope)
+                    {
+                        trap(&msg);
+                    }
+                }
+                None => s.revoke_permission(arg.of_principal, &arg.permission),
+            }
         }),
     }
 }
@@ -99,11 +119,20 @@ async fn revoke_permission(arg: RevokePermissionArguments) {
 #[candid_method(update)]
 async fn validate_revoke_permission(arg: RevokePermissionArguments) -> Result<String, String> {
     Ok(format!(
-        "revoke {} permission from principal {}",
-        arg.permission, arg.of_principal
+        "revoke {} permission from principal {}{}",
+        arg.permission,
+        arg.of_principal,
+        describe_scope(&arg.scope)
     ))
 }
 
+fn describe_scope(scope: &Option<String>) -> String {
+    scope
+        .as_ref()
+        .map(|scope| format!(" for {}", scope))
+        .unwrap_or_default()
+}
+
 #[update(manual_reply = true)]
 #[candid_method(update)]
 fn list_authorized() -> ManualReply<Vec<Principal>> {
@@ -116,6 +145,12 @@ fn list_permitted(arg: ListPermittedArguments) -> ManualReply<Vec<Principal>> {
     STATE.with(|s| ManualReply::one(s.borrow().list_permitted(&arg.permission)))
 }
 
+#[update]
+#[candid_method(update)]
+fn list_scoped_permissions(arg: ListPermittedArguments) -> Vec<ScopedPermission> {
+    STATE.with(|s| s.borrow().list_scoped_permissions(&arg.permission))
+}
+
 #[update(guard = "is_controller")]
 #[candid_method(update)]
 async fn take_ownership() {
@@ -141,6 +176,7 @@ fn retrieve(key: AssetKey) -> RcBytes {
 #[update(guard = "can_commit")]
 #[candid_method(update)]
 fn store(arg: StoreArg) {
+    check_commit_scope(&arg.key);
     STATE.with(move |s| {
         if let Err(msg) = s.borrow_mut().store(arg, time()) {
             trap(&msg);
@@ -170,6 +206,7 @@ fn create_chunk(arg: CreateChunkArg) -> CreateChunkResponse {
 #[update(guard = "can_commit")]
 #[candid_method(update)]
 fn create_asset(arg: CreateAssetArguments) {
+    check_commit_scope(&arg.key);
     STATE.with(|s| {
         if let Err(msg) = s.borrow_mut().create_asset(arg) {
             trap(&msg);
@@ -181,6 +218,7 @@ fn create_asset(arg: CreateAssetArguments) {
 #[update(guard = "can_commit")]
 #[candid_method(update)]
 fn set_asset_content(arg: SetAssetContentArguments) {
+    check_commit_scope(&arg.key);
     STATE.with(|s| {
         if let Err(msg) = s.borrow_mut().set_asset_content(arg, time()) {
             trap(&msg);
@@ -192,6 +230,7 @@ fn set_asset_content(arg: SetAssetContentArguments) {
 #[update(guard = "can_commit")]
 #[candid_method(update)]
 fn unset_asset_content(arg: UnsetAssetContentArguments) {
+    check_commit_scope(&arg.key);
     STATE.with(|s| {
         if let Err(msg) = s.borrow_mut().unset_asset_content(arg) {
             trap(&msg);
@@ -203,13 +242,14 @@ fn unset_asset_content(arg: UnsetAssetContentArguments) {
 #[update(guard = "can_commit")]
 #[candid_method(update)]
 fn delete_asset(arg: DeleteAssetArguments) {
+    check_commit_scope(&arg.key);
     STATE.with(|s| {
         s.borrow_mut().delete_asset(arg);
         set_certified_data(&s.borrow().root_hash());
     });
 }
 
-#[update(guard = "can_commit")]
+#[update(guard = "can_commit_everything")]
 #[candid_method(update)]
 fn clear() {
     STATE.with(|s| {
@@ -222,6 +262,12 @@ fn clear() {
 #[candid_method(update)]
 fn commit_batch(arg: CommitBatchArguments) {
     STATE.with(|s| {
+        if let Err(msg) =
+            s.borrow()
+                .check_operations_scope(&caller(), &Permission::Commit, &arg.operations)
+        {
+            trap(&msg);
+        }
         if let Err(msg) = s.borrow_mut().commit_batch(arg, time()) {
             trap(&msg);
         }
@@ -233,6 +279,12 @@ fn commit_batch(arg: CommitBatchArguments) {
 #[candid_method(update)]
 fn propose_commit_batch(arg: CommitBatchArguments) {
     STATE.with(|s| {
+        if let Err(msg) =
+            s.borrow()
+                .check_operations_scope(&caller(), &Permission::Prepare, &arg.operations)
+        {
+            trap(&msg);
+        }
         if let Err(msg) = s.borrow_mut().propose_commit_batch(arg) {
             trap(&msg);
         }
@@ -242,9 +294,17 @@ fn propose_commit_batch(arg: CommitBatchArguments) {
 #[update(guard = "can_prepare")]
 #[candid_method(update)]
 fn compute_evidence(arg: ComputeEvidenceArguments) -> Option<ByteBuf> {
-    STATE.with(|s| match s.borrow_mut().compute_evidence(arg) {
-        Err(msg) => trap(&msg),
-        Ok(maybe_evidence) => maybe_evidence,
+    STATE.with(|s| {
+        if let Err(msg) =
+            s.borrow()
+                .check_proposed_batch_scope(&caller(), &Permission::Prepare, &arg.batch_id)
+        {
+            trap(&msg);
+        }
+        match s.borrow_mut().compute_evidence(arg) {
+            Err(msg) => trap(&msg),
+            Ok(maybe_evidence) => maybe_evidence,
+        }
     })
 }
 
@@ -252,6 +312,12 @@ fn compute_evidence(arg: ComputeEvidenceArguments) -> Option<ByteBuf> {
 #[candid_method(update)]
 fn commit_proposed_batch(arg: CommitProposedBatchArguments) {
     STATE.with(|s| {
+        if let Err(msg) =
+            s.borrow()
+                .check_proposed_batch_scope(&caller(), &Permission::Commit, &arg.batch_id)
+        {
+            trap(&msg);
+        }
         if let Err(msg) = s.borrow_mut().commit_proposed_batch(arg, time()) {
             trap(&msg);
         }
@@ -265,7 +331,7 @@ fn validate_commit_proposed_batch(arg: CommitProposedBatchArguments) -> Result<S
     STATE.with(|s| s.borrow_mut().validate_commit_proposed_batch(arg))
 }
 
-#[update(guard = "can_prepare")]
+#[update(guard = "can_prepare_everything")]
 #[candid_method(update)]
 fn delete_batch(arg: DeleteBatchArguments) {
     STATE.with(|s| {
@@ -344,6 +410,7 @@ fn get_asset_properties(key: AssetKey) -> AssetProperties {
 #[update(guard = "can_commit")]
 #[candid_method(update)]
 fn set_asset_properties(arg: SetAssetPropertiesArguments) {
+    check_commit_scope(&arg.key);
     STATE.with(|s| {
         if let Err(msg) = s.borrow_mut().set_asset_properties(arg) {
             trap(&msg);
@@ -357,7 +424,7 @@ fn get_configuration() -> ConfigurationResponse {
     STATE.with(|s| s.borrow().get_configuration())
 }
 
-#[update(guard = "can_commit")]
+#[update(guard = "can_commit_everything")]
 #[candid_method(update)]
 fn configure(arg: ConfigureArguments) {
     STATE.with(|s| s.borrow_mut().configure(arg))
@@ -372,7 +439,7 @@ fn validate_configure(arg: ConfigureArguments) -> Result<String, String> {
 fn can(permission: Permission) -> Result<(), String> {
     STATE.with(|s| {
         s.borrow()
-            .can(&caller(), &permission)
+            .can_in_some_scope(&caller(), &permission)
             .then_some(())
             .ok_or_else(|| format!("Caller does not have {} permission", permission))
     })
@@ -386,6 +453,41 @@ fn can_prepare() -> Result<(), String> {
     can(Permission::Prepare)
 }
 
+/// Like `can`, but a permission limited to some scopes is not enough.
+fn can_everything(permission: Permission) -> Result<(), String> {
+    STATE.with(|s| {
+        s.borrow()
+            .can(&caller(), &permission)
+            .then_some(())
+            .ok_or_else(|| {
+                format!(
+                    "Caller does not have {} permission for all assets",
+                    permission
+                )
+            })
+    })
+}
+
+fn can_commit_everything() -> Result<(), String> {
+    can_everything(Permission::Commit)
+}
+
+fn can_prepare_everything() -> Result<(), String> {
+    can_everything(Permission::Prepare)
+}
+
+/// Traps unless the caller has Commit permission for the key, either for all assets or in a scope.
+fn check_commit_scope(key: &str) {
+    STATE.with(|s| {
+        if let Err(msg) = s
+            .borrow()
+            .check_scope(&caller(), &Permission::Commit, [key])
+        {
+            trap(&msg);
+        }
+    })
+}
+
 fn has_permission_or_is_controller(permission: &Permission) -> Result<(), String> {
     let caller = caller();
     let has_permission = STATE.with(|s| s.borrow().has_permission(&caller, permission));
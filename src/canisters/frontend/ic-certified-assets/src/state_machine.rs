@@ -30,7 +30,7 @@ use num_traits::ToPrimitive;
 use serde::Serialize;
 use serde_bytes::ByteBuf;
 use sha2::Digest;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryInto;
 
 /// The amount of time a batch is kept alive. Modifying the batch
@@ -220,15 +220,22 @@ pub struct State {
     commit_principals: BTreeSet<Principal>,
     prepare_principals: BTreeSet<Principal>,
     manage_permissions_principals: BTreeSet<Principal>,
+    // path prefixes that limit the permissions of principals not in the lists above
+    commit_scopes: PermissionScopes,
+    prepare_scopes: PermissionScopes,
 
     asset_hashes: CertifiedResponses,
 }
 
+type PermissionScopes = BTreeMap<Principal, BTreeSet<String>>;
+
 #[derive(Clone, Debug, CandidType, Deserialize)]
 pub struct StableStatePermissions {
     commit: BTreeSet<Principal>,
     prepare: BTreeSet<Principal>,
     manage_permissions: BTreeSet<Principal>,
+    commit_scopes: Option<PermissionScopes>,
+    prepare_scopes: Option<PermissionScopes>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -332,6 +339,8 @@ impl State {
         *self.get_mut_permission_list(&Permission::Commit) = commit.into_iter().collect();
         *self.get_mut_permission_list(&Permission::ManagePermissions) =
             manage_permissions.into_iter().collect();
+        self.commit_scopes.clear();
+        self.prepare_scopes.clear();
     }
 
     pub fn grant_permission(&mut self, principal: Principal, permission: &Permission) {
@@ -339,19 +348,81 @@ impl State {
         permitted.insert(principal);
     }
 
+    pub fn grant_scoped_permission(
+        &mut self,
+        principal: Principal,
+        permission: &Permission,
+        scope: &str,
+    ) -> Result<(), String> {
+        let scope = normalize_scope(scope)?;
+        let scopes = self
+            .get_mut_permission_scopes(permission)
+            .ok_or_else(|| format!("{} permission cannot be scoped", permission))?;
+        scopes.entry(principal).or_default().insert(scope);
+        Ok(())
+    }
+
+    /// Revokes the permission, including all of its scopes.
     pub fn revoke_permission(&mut self, principal: Principal, permission: &Permission) {
         let permitted = self.get_mut_permission_list(permission);
         permitted.remove(&principal);
+        if let Some(scopes) = self.get_mut_permission_scopes(permission) {
+            scopes.remove(&principal);
+        }
+    }
+
+    pub fn revoke_scoped_permission(
+        &mut self,
+        principal: Principal,
+        permission: &Permission,
+        scope: &str,
+    ) -> Result<(), String> {
+        let scope = normalize_scope(scope)?;
+        if let Some(scopes) = self.get_mut_permission_scopes(permission) {
+            if let Some(principal_scopes) = scopes.get_mut(&principal) {
+                principal_scopes.remove(&scope);
+                if principal_scopes.is_empty() {
+                    scopes.remove(&principal);
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn list_permitted(&self, permission: &Permission) -> &BTreeSet<Principal> {
         self.get_permission_list(permission)
     }
 
+    /// Lists the principals with the permission, whether scoped or not.
+    pub fn list_scoped_permissions(&self, permission: &Permission) -> Vec<ScopedPermission> {
+        let unscoped = self.get_permission_list(permission);
+        let mut permissions: Vec<_> = unscoped
+            .iter()
+            .map(|principal| ScopedPermission {
+                principal: *principal,
+                scopes: vec![],
+            })
+            .collect();
+        if let Some(scopes) = self.get_permission_scopes(permission) {
+            permissions.extend(
+                scopes
+                    .iter()
+                    .filter(|(principal, _)| !unscoped.contains(principal))
+                    .map(|(principal, scopes)| ScopedPermission {
+                        principal: *principal,
+                        scopes: scopes.iter().cloned().collect(),
+                    }),
+            );
+        }
+        permissions
+    }
+
     pub fn take_ownership(&mut self, controller: Principal) {
         self.commit_principals.clear();
         self.prepare_principals.clear();
         self.manage_permissions_principals.clear();
+        self.commit_scopes.clear();
+        self.prepare_scopes.clear();
         self.commit_principals.insert(controller);
     }
 
@@ -381,6 +452,18 @@ impl State {
         &mut self,
         arg: SetAssetContentArguments,
         now: u64,
+    ) -> Result<(), String> {
+        self.set_asset_content_with_chunk_uses(arg, now, &mut HashMap::new())
+    }
+
+    /// Sets the content of an asset.  `chunk_uses` counts how many more operations of the
+    /// current commit use each chunk: a chunk is taken out of the chunk table by its last use,
+    /// so that its content is only held by the encodings made from it.
+    fn set_asset_content_with_chunk_uses(
+        &mut self,
+        arg: SetAssetContentArguments,
+        now: u64,
+        chunk_uses: &mut HashMap<ChunkId, usize>,
     ) -> Result<(), String> {
         if arg.chunk_ids.is_empty() {
             return Err("encoding must have at least one chunk".to_string());
@@ -396,8 +479,14 @@ impl State {
 
         let mut content_chunks = vec![];
         for chunk_id in arg.chunk_ids.iter() {
-            let chunk = self.chunks.remove(chunk_id).expect("chunk not found");
-            content_chunks.push(chunk.content);
+            let uses = chunk_uses.entry(chunk_id.clone()).or_insert(1);
+            *uses = uses.saturating_sub(1);
+            let content = if *uses == 0 {
+                self.chunks.remove(chunk_id).map(|chunk| chunk.content)
+            } else {
+                self.chunks.get(chunk_id).map(|chunk| chunk.content.clone())
+            };
+            content_chunks.push(content.ok_or_else(|| "chunk not found".to_string())?);
         }
 
         let sha256: [u8; 32] = match arg.sha256 {
@@ -487,6 +576,91 @@ impl State {
                 && self.has_permission(principal, &Permission::Commit))
     }
 
+    /// Like `can`, but also true if the permission is limited to some scopes.
+    pub fn can_in_some_scope(&self, principal: &Principal, permission: &Permission) -> bool {
+        self.can(principal, permission) || !self.scopes_of(principal, permission).is_empty()
+    }
+
+    /// Checks that the principal has the permission for every key.
+    pub fn check_scope<'a>(
+        &self,
+        principal: &Principal,
+        permission: &Permission,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), String> {
+        if self.can(principal, permission) {
+            return Ok(());
+        }
+        let scopes = self.scopes_of(principal, permission);
+        for key in keys {
+            if !scopes.iter().any(|scope| scope_contains(scope, key)) {
+                return Err(format!(
+                    "Caller does not have {} permission for {}",
+                    permission, key
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the principal has the permission for every asset touched by the operations.
+    pub fn check_operations_scope(
+        &self,
+        principal: &Principal,
+        permission: &Permission,
+        operations: &[BatchOperation],
+    ) -> Result<(), String> {
+        if self.can(principal, permission) {
+            return Ok(());
+        }
+        let mut keys = vec![];
+        for op in operations {
+            match op {
+                BatchOperation::CreateAsset(arg) => keys.push(arg.key.as_str()),
+                BatchOperation::SetAssetContent(arg) => keys.push(arg.key.as_str()),
+                BatchOperation::UnsetAssetContent(arg) => keys.push(arg.key.as_str()),
+                BatchOperation::DeleteAsset(arg) => keys.push(arg.key.as_str()),
+                BatchOperation::SetAssetProperties(arg) => keys.push(arg.key.as_str()),
+                BatchOperation::Clear(_) => {
+                    return Err(format!(
+                        "Caller does not have {} permission for all assets, which is required to clear them",
+                        permission
+                    ))
+                }
+            }
+        }
+        self.check_scope(principal, permission, keys)
+    }
+
+    /// Checks that the principal has the permission for the operations proposed in a batch.
+    pub fn check_proposed_batch_scope(
+        &self,
+        principal: &Principal,
+        permission: &Permission,
+        batch_id: &BatchId,
+    ) -> Result<(), String> {
+        match self
+            .batches
+            .get(batch_id)
+            .and_then(|batch| batch.commit_batch_arguments.as_ref())
+        {
+            Some(args) => self.check_operations_scope(principal, permission, &args.operations),
+            None => Ok(()),
+        }
+    }
+
+    /// The scopes of a scoped permission. Commit permission implies Prepare permission.
+    fn scopes_of(&self, principal: &Principal, permission: &Permission) -> BTreeSet<&String> {
+        let mut scopes: BTreeSet<&String> = BTreeSet::new();
+        if let Some(permission_scopes) = self.get_permission_scopes(permission) {
+            scopes.extend(permission_scopes.get(principal).into_iter().flatten());
+        }
+        if *permission == Permission::Prepare {
+            scopes.extend(self.commit_scopes.get(principal).into_iter().flatten());
+        }
+        scopes
+    }
+
     fn get_permission_list(&self, permission: &Permission) -> &BTreeSet<Principal> {
         match permission {
             Permission::Commit => &self.commit_principals,
@@ -503,6 +677,25 @@ impl State {
         }
     }
 
+    fn get_permission_scopes(&self, permission: &Permission) -> Option<&PermissionScopes> {
+        match permission {
+            Permission::Commit => Some(&self.commit_scopes),
+            Permission::Prepare => Some(&self.prepare_scopes),
+            Permission::ManagePermissions => None,
+        }
+    }
+
+    fn get_mut_permission_scopes(
+        &mut self,
+        permission: &Permission,
+    ) -> Option<&mut PermissionScopes> {
+        match permission {
+            Permission::Commit => Some(&mut self.commit_scopes),
+            Permission::Prepare => Some(&mut self.prepare_scopes),
+            Permission::ManagePermissions => None,
+        }
+    }
+
     pub fn retrieve(&self, key: &AssetKey) -> Result<RcBytes, String> {
         let asset = self.get_asset(key)?;
 
@@ -623,10 +816,22 @@ impl State {
 
     pub fn commit_batch(&mut self, arg: CommitBatchArguments, now: u64) -> Result<(), String> {
         let batch_id = arg.batch_id;
+        // Identical content under several keys is uploaded once, so a chunk can be used by
+        // several operations.  It is released by the last of them.
+        let mut chunk_uses: HashMap<ChunkId, usize> = HashMap::new();
+        for op in arg.operations.iter() {
+            if let BatchOperation::SetAssetContent(arg) = op {
+                for chunk_id in arg.chunk_ids.iter() {
+                    *chunk_uses.entry(chunk_id.clone()).or_default() += 1;
+                }
+            }
+        }
         for op in arg.operations {
             match op {
                 BatchOperation::CreateAsset(arg) => self.create_asset(arg)?,
-                BatchOperation::SetAssetContent(arg) => self.set_asset_content(arg, now)?,
+                BatchOperation::SetAssetContent(arg) => {
+                    self.set_asset_content_with_chunk_uses(arg, now, &mut chunk_uses)?
+                }
                 BatchOperation::UnsetAssetContent(arg) => self.unset_asset_content(arg)?,
                 BatchOperation::DeleteAsset(arg) => self.delete_asset(arg),
                 BatchOperation::Clear(_) => self.clear(),
@@ -634,6 +839,7 @@ impl State {
             }
         }
         self.batches.remove(&batch_id);
+        self.chunks.retain(|_, c| c.batch_id != batch_id);
         self.certify_404_if_required();
         Ok(())
     }
@@ -1061,6 +1267,8 @@ impl From<State> for StableState {
             commit: state.commit_principals,
             prepare: state.prepare_principals,
             manage_permissions: state.manage_permissions_principals,
+            commit_scopes: Some(state.commit_scopes),
+            prepare_scopes: Some(state.prepare_scopes),
         };
         Self {
             authorized: vec![],
@@ -1074,24 +1282,35 @@ impl From<State> for StableState {
 
 impl From<StableState> for State {
     fn from(stable_state: StableState) -> Self {
-        let (commit_principals, prepare_principals, manage_permissions_principals) =
-            if let Some(permissions) = stable_state.permissions {
-                (
-                    permissions.commit,
-                    permissions.prepare,
-                    permissions.manage_permissions,
-                )
-            } else {
-                (
-                    stable_state.authorized.into_iter().collect(),
-                    BTreeSet::new(),
-                    BTreeSet::new(),
-                )
-            };
+        let (
+            commit_principals,
+            prepare_principals,
+            manage_permissions_principals,
+            commit_scopes,
+            prepare_scopes,
+        ) = if let Some(permissions) = stable_state.permissions {
+            (
+                permissions.commit,
+                permissions.prepare,
+                permissions.manage_permissions,
+                permissions.commit_scopes.unwrap_or_default(),
+                permissions.prepare_scopes.unwrap_or_default(),
+            )
+        } else {
+            (
+                stable_state.authorized.into_iter().collect(),
+                BTreeSet::new(),
+                BTreeSet::new(),
+                BTreeMap::new(),
+                BTreeMap::new(),
+            )
+        };
         let mut state = Self {
             commit_principals,
             prepare_principals,
             manage_permissions_principals,
+            commit_scopes,
+            prepare_scopes,
             assets: stable_state.stable_assets,
             next_batch_id: stable_state
                 .next_batch_id
@@ -1116,6 +1335,24 @@ impl From<StableState> for State {
     }
 }
 
+/// Scopes are path prefixes such as `/blog/`. A trailing `*` or `**` is ignored.
+fn normalize_scope(scope: &str) -> Result<String, String> {
+    let scope = scope.trim_end_matches('*');
+    if !scope.starts_with('/') {
+        return Err(format!("scope '{}' must start with '/'", scope));
+    }
+    Ok(scope.to_string())
+}
+
+/// Whether the key is the scope itself, with or without its trailing slash, or lies in the directory it names.
+fn scope_contains(scope: &str, key: &str) -> bool {
+    key == scope
+        || key == scope.trim_end_matches('/')
+        || key
+            .strip_prefix(scope)
+            .is_some_and(|rest| scope.ends_with('/') || rest.starts_with('/'))
+}
+
 fn build_headers(
     custom_headers: Option<impl Iterator<Item = (impl Into<String>, impl Into<String>)>>,
     max_age: &Option<u64>,
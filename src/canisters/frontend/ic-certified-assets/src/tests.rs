@@ -3860,3 +3860,303 @@ mod enforce_limits {
         );
     }
 }
+
+mod scoped_permissions {
+    use super::*;
+    use crate::types::{ClearArguments, Permission, ScopedPermission};
+
+    fn other_principal() -> Principal {
+        Principal::from_text("rrkah-fqaaa-aaaaa-aaaaq-cai").unwrap()
+    }
+
+    fn delete(key: &str) -> BatchOperation {
+        BatchOperation::DeleteAsset(DeleteAssetArguments {
+            key: key.to_string(),
+        })
+    }
+
+    #[test]
+    fn scoped_commit_is_limited_to_its_paths() {
+        let mut state = State::default();
+        let marketing = some_principal();
+        state
+            .grant_scoped_permission(marketing, &Permission::Commit, "/blog/**")
+            .unwrap();
+
+        assert!(!state.can(&marketing, &Permission::Commit));
+        assert!(state.can_in_some_scope(&marketing, &Permission::Commit));
+        assert!(state.can_in_some_scope(&marketing, &Permission::Prepare));
+        assert!(!state.can_in_some_scope(&other_principal(), &Permission::Commit));
+
+        assert!(state
+            .check_scope(&marketing, &Permission::Commit, ["/blog/post.html"])
+            .is_ok());
+        assert!(state
+            .check_scope(&marketing, &Permission::Commit, ["/blog"])
+            .is_ok());
+        assert!(state
+            .check_scope(&marketing, &Permission::Commit, ["/blogger.html"])
+            .is_err());
+        assert!(state
+            .check_operations_scope(
+                &marketing,
+                &Permission::Commit,
+                &[delete("/blog/a.html"), delete("/index.html")]
+            )
+            .is_err());
+        assert!(state
+            .check_operations_scope(
+                &marketing,
+                &Permission::Commit,
+                &[BatchOperation::Clear(ClearArguments {})]
+            )
+            .is_err());
+
+        state.grant_permission(other_principal(), &Permission::Commit);
+        assert!(state
+            .check_operations_scope(
+                &other_principal(),
+                &Permission::Commit,
+                &[BatchOperation::Clear(ClearArguments {})]
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn grant_revoke_and_list_scopes() {
+        let mut state = State::default();
+        let marketing = some_principal();
+        state
+            .grant_scoped_permission(marketing, &Permission::Commit, "/blog/")
+            .unwrap();
+        state
+            .grant_scoped_permission(marketing, &Permission::Commit, "/news/")
+            .unwrap();
+        state.grant_permission(other_principal(), &Permission::Commit);
+        assert!(state
+            .grant_scoped_permission(marketing, &Permission::ManagePermissions, "/blog/")
+            .is_err());
+        assert!(state
+            .grant_scoped_permission(marketing, &Permission::Commit, "blog")
+            .is_err());
+
+        assert_eq!(
+            state.list_scoped_permissions(&Permission::Commit),
+            vec![
+                ScopedPermission {
+                    principal: other_principal(),
+                    scopes: vec![],
+                },
+                ScopedPermission {
+                    principal: marketing,
+                    scopes: vec!["/blog/".to_string(), "/news/".to_string()],
+                },
+            ]
+        );
+        assert!(!state
+            .list_permitted(&Permission::Commit)
+            .contains(&marketing));
+
+        state
+            .revoke_scoped_permission(marketing, &Permission::Commit, "/news/")
+            .unwrap();
+        assert!(state
+            .check_scope(&marketing, &Permission::Commit, ["/news/a.html"])
+            .is_err());
+        state.revoke_permission(marketing, &Permission::Commit);
+        assert!(!state.can_in_some_scope(&marketing, &Permission::Commit));
+    }
+
+    #[test]
+    fn scoped_prepare_cannot_act_on_batches_proposed_outside_its_scope() {
+        let mut state = State::default();
+        let time_now = 100_000_000_000;
+        let marketing = some_principal();
+        state
+            .grant_scoped_permission(marketing, &Permission::Prepare, "/blog/")
+            .unwrap();
+        state.grant_permission(other_principal(), &Permission::Prepare);
+
+        let batch_id = state.create_batch(time_now).unwrap();
+        assert!(state
+            .check_proposed_batch_scope(&marketing, &Permission::Prepare, &batch_id)
+            .is_ok());
+        state
+            .propose_commit_batch(CommitBatchArguments {
+                batch_id: batch_id.clone(),
+                operations: vec![delete("/index.html")],
+            })
+            .unwrap();
+
+        assert_eq!(
+            state.check_proposed_batch_scope(&marketing, &Permission::Prepare, &batch_id),
+            Err("Caller does not have Prepare permission for /index.html".to_string())
+        );
+        assert!(state
+            .check_proposed_batch_scope(&other_principal(), &Permission::Prepare, &batch_id)
+            .is_ok());
+    }
+
+    #[test]
+    fn scopes_survive_upgrade() {
+        let mut state = State::default();
+        state
+            .grant_scoped_permission(some_principal(), &Permission::Prepare, "/blog/")
+            .unwrap();
+        let stable_state: StableState = state.into();
+        let state: State = stable_state.into();
+        assert!(state
+            .check_scope(&some_principal(), &Permission::Prepare, ["/blog/a.html"])
+            .is_ok());
+    }
+}
+
+mod shared_chunks {
+    use super::*;
+    use crate::types::ConfigureArguments;
+
+    const BODY: &[u8] = b"same image";
+
+    #[test]
+    fn chunk_can_be_used_by_several_assets() {
+        let mut state = State::default();
+        let time_now = 100_000_000_000;
+        state.configure(ConfigureArguments {
+            max_batches: None,
+            max_chunks: Some(Some(1)),
+            max_bytes: None,
+        });
+
+        let batch_id = state.create_batch(time_now).unwrap();
+        let chunk_id = state
+            .create_chunk(
+                CreateChunkArg {
+                    batch_id: batch_id.clone(),
+                    content: ByteBuf::from(BODY.to_vec()),
+                },
+                time_now,
+            )
+            .unwrap();
+        let mut operations = vec![];
+        for key in ["/a.png", "/b/a.png"] {
+            operations.push(BatchOperation::CreateAsset(CreateAssetArguments {
+                key: key.to_string(),
+                content_type: "image/png".to_string(),
+                max_age: None,
+                headers: None,
+                enable_aliasing: None,
+                allow_raw_access: None,
+            }));
+            operations.push(BatchOperation::SetAssetContent(SetAssetContentArguments {
+                key: key.to_string(),
+                content_encoding: "identity".to_string(),
+                chunk_ids: vec![chunk_id.clone()],
+                sha256: None,
+            }));
+        }
+        state
+            .commit_batch(
+                CommitBatchArguments {
+                    batch_id,
+                    operations,
+                },
+                time_now,
+            )
+            .unwrap();
+
+        assert_eq!(
+            state.retrieve(&"/a.png".to_string()).unwrap().as_ref(),
+            BODY
+        );
+        assert_eq!(
+            state.retrieve(&"/b/a.png".to_string()).unwrap().as_ref(),
+            BODY
+        );
+
+        // the chunk was released with its batch, so it does not count against the limit
+        let batch_id = state.create_batch(time_now).unwrap();
+        assert!(state
+            .create_chunk(
+                CreateChunkArg {
+                    batch_id,
+                    content: ByteBuf::new(),
+                },
+                time_now,
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn chunk_is_released_by_its_last_use() {
+        let mut state = State::default();
+        let time_now = 100_000_000_000;
+        state.configure(ConfigureArguments {
+            max_batches: None,
+            max_chunks: Some(Some(1)),
+            max_bytes: None,
+        });
+
+        create_assets(
+            &mut state,
+            time_now,
+            vec![
+                AssetBuilder::new("/a.png", "image/png"),
+                AssetBuilder::new("/b.png", "image/png"),
+            ],
+        );
+        let batch_id = state.create_batch(time_now).unwrap();
+        let chunk_id = state
+            .create_chunk(
+                CreateChunkArg {
+                    batch_id: batch_id.clone(),
+                    content: ByteBuf::from(BODY.to_vec()),
+                },
+                time_now,
+            )
+            .unwrap();
+        let set_content = |key: &str| {
+            BatchOperation::SetAssetContent(SetAssetContentArguments {
+                key: key.to_string(),
+                content_encoding: "identity".to_string(),
+                chunk_ids: vec![chunk_id.clone()],
+                sha256: None,
+            })
+        };
+        // a staged commit leaves the batch open
+        state
+            .commit_batch(
+                CommitBatchArguments {
+                    batch_id: Nat::from(0_u8),
+                    operations: vec![set_content("/a.png"), set_content("/b.png")],
+                },
+                time_now,
+            )
+            .unwrap();
+
+        assert_eq!(
+            state.retrieve(&"/b.png".to_string()).unwrap().as_ref(),
+            BODY
+        );
+        // the chunk no longer counts against the limit although its batch is still open
+        assert!(state
+            .create_chunk(
+                CreateChunkArg {
+                    batch_id,
+                    content: ByteBuf::from(BODY.to_vec()),
+                },
+                time_now,
+            )
+            .is_ok());
+        // and cannot be used by later commits
+        assert_eq!(
+            state.commit_batch(
+                CommitBatchArguments {
+                    batch_id: Nat::from(0_u8),
+                    operations: vec![set_content("/a.png")],
+                },
+                time_now,
+            ),
+            Err("chunk not found".to_string())
+        );
+    }
+}
@@ -172,12 +172,16 @@ impl std::fmt::Display for Permission {
 pub struct GrantPermissionArguments {
     pub to_principal: Principal,
     pub permission: Permission,
+    /// Limits the permission to the assets under this path, e.g. `/blog/`.
+    pub scope: Option<String>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
 pub struct RevokePermissionArguments {
     pub of_principal: Principal,
     pub permission: Permission,
+    /// Revokes only this scope. Without a scope, the permission is revoked entirely.
+    pub scope: Option<String>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -185,6 +189,13 @@ pub struct ListPermittedArguments {
     pub permission: Permission,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct ScopedPermission {
+    pub principal: Principal,
+    /// The path prefixes the principal is limited to. Empty if the permission is not scoped.
+    pub scopes: Vec<String>,
+}
+
 /// The argument to `init` and `post_upgrade` needs to have the same argument type by definition.
 /// `AssetCanisterArgs` is there so that the two functions can take different argument types.
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -0,0 +1,11 @@
+use ic_utils::Canister;
+use slog::Logger;
+
+pub(crate) async fn mirror(
+    source: &Canister<'_>,
+    target: &Canister<'_>,
+    logger: &Logger,
+) -> anyhow::Result<()> {
+    ic_asset::mirror(source, target, logger).await?;
+    Ok(())
+}
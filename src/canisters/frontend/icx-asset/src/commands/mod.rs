@@ -1,3 +1,5 @@
+pub mod download;
 pub mod list;
+pub mod mirror;
 pub mod sync;
 pub mod upload;
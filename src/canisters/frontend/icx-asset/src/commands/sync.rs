@@ -9,6 +9,15 @@ pub(crate) async fn sync(
     logger: &Logger,
 ) -> anyhow::Result<()> {
     let dirs: Vec<&Path> = o.directory.iter().map(|d| d.as_path()).collect();
-    ic_asset::sync(canister, &dirs, logger).await?;
+    if o.dry_run {
+        let plan = ic_asset::plan_sync(canister, &dirs, logger).await?;
+        if o.json {
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+        } else {
+            println!("{}", plan);
+        }
+    } else {
+        ic_asset::sync(canister, &dirs, None, logger).await?;
+    }
     Ok(())
 }
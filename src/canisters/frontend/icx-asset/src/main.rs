@@ -1,6 +1,8 @@
 mod commands;
 mod support;
+use crate::commands::download::download;
 use crate::commands::list::list;
+use crate::commands::mirror::mirror;
 use crate::commands::sync::sync;
 use crate::commands::upload::upload;
 use anstyle::{AnsiColor, Style};
@@ -41,10 +43,16 @@ struct Opts {
 
 #[derive(Parser)]
 enum SubCommand {
+    /// Download all assets, with all encodings and properties, into a directory.
+    Download(DownloadOpts),
+
     /// List keys from the asset canister.
     #[command(name = "ls")]
     List(ListOpts),
 
+    /// Copy all assets of one asset canister to another.
+    Mirror(MirrorOpts),
+
     /// Synchronize a directory to the asset canister
     Sync(SyncOpts),
 
@@ -52,12 +60,31 @@ enum SubCommand {
     Upload(UploadOpts),
 }
 
+#[derive(Parser)]
+struct DownloadOpts {
+    /// The canister ID.
+    canister_id: String,
+
+    /// The directory to download to.
+    directory: PathBuf,
+}
+
 #[derive(Parser)]
 struct ListOpts {
     /// The canister ID.
     canister_id: String,
 }
 
+#[derive(Parser)]
+struct MirrorOpts {
+    /// The ID of the canister to copy the assets from.
+    source_canister_id: String,
+
+    /// The ID of the canister to copy the assets to.
+    /// Its assets that the source canister does not have are deleted.
+    target_canister_id: String,
+}
+
 #[derive(Parser)]
 struct SyncOpts {
     /// The canister ID.
@@ -65,6 +92,14 @@ struct SyncOpts {
 
     /// The directories to synchronize
     directory: Vec<PathBuf>,
+
+    /// List the changes that would be made, without uploading or committing anything.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Print the changes as JSON.
+    #[arg(long, requires("dry_run"))]
+    json: bool,
 }
 
 #[derive(Parser)]
@@ -122,6 +157,13 @@ async fn main() -> anyhow::Result<()> {
     }
 
     match &opts.subcommand {
+        SubCommand::Download(o) => {
+            let canister = ic_utils::Canister::builder()
+                .with_agent(&agent)
+                .with_canister_id(Principal::from_text(&o.canister_id)?)
+                .build()?;
+            download(&canister, o, &logger).await?;
+        }
         SubCommand::List(o) => {
             let canister = ic_utils::Canister::builder()
                 .with_agent(&agent)
@@ -129,6 +171,17 @@ async fn main() -> anyhow::Result<()> {
                 .build()?;
             list(&canister, &logger).await?;
         }
+        SubCommand::Mirror(o) => {
+            let source = ic_utils::Canister::builder()
+                .with_agent(&agent)
+                .with_canister_id(Principal::from_text(&o.source_canister_id)?)
+                .build()?;
+            let target = ic_utils::Canister::builder()
+                .with_agent(&agent)
+                .with_canister_id(Principal::from_text(&o.target_canister_id)?)
+                .build()?;
+            mirror(&source, &target, &logger).await?;
+        }
         SubCommand::Sync(o) => {
             let canister = ic_utils::Canister::builder()
                 .with_agent(&agent)
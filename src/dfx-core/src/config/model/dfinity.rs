@@ -4,6 +4,7 @@ use crate::config::directories::get_user_dfx_config_dir;
 use crate::config::model::bitcoin_adapter::BitcoinAdapterLogLevel;
 use crate::config::model::canister_http_adapter::HttpAdapterLogLevel;
 use crate::config::model::extension_canister_type::apply_extension_canister_types;
+use crate::config::model::network_overrides::apply_network_overrides;
 use crate::error::config::{GetOutputEnvFileError, GetTempPathError};
 use crate::error::dfx_config::AddDependenciesError::CanisterCircularDependency;
 use crate::error::dfx_config::GetCanisterNamesWithDependenciesError::AddDependenciesFailed;
@@ -73,6 +74,7 @@ const EMPTY_CONFIG_DEFAULTS: ConfigDefaults = ConfigDefaults {
 const EMPTY_CONFIG_DEFAULTS_BUILD: ConfigDefaultsBuild = ConfigDefaultsBuild {
     packtool: None,
     args: None,
+    packages: BTreeMap::new(),
 };
 
 /// # Remote Canister Configuration
@@ -247,6 +249,12 @@ pub struct ConfigCanistersCanister {
     /// This field defines an additional argument to pass to the Motoko compiler when building the canister.
     pub args: Option<String>,
 
+    /// # Motoko Compiler Options
+    /// Options for the Motoko compiler when building the canister.
+    /// Unlike `args`, these are checked when dfx.json is loaded.
+    #[serde(default)]
+    pub moc: ConfigMotokoOptions,
+
     /// # Resource Allocation Settings
     /// Defines initial values for resource allocation settings.
     #[serde(default)]
@@ -278,6 +286,22 @@ pub struct ConfigCanistersCanister {
     #[serde(default)]
     pub post_install: SerdeVec<String>,
 
+    /// # Deploy Hooks
+    /// Commands that run before and after the code of this canister is installed.
+    #[serde(default)]
+    pub hooks: ConfigHooks,
+
+    /// # Tags
+    /// Labels that select this canister with the `--tag` option of dfx commands.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// # Environment Variables
+    /// Environment variables passed to the build commands and post-install commands of this canister.
+    /// Variables defined here take precedence over the project-level `env`.
+    #[serde(default)]
+    pub env: BTreeMap<String, ConfigEnvValue>,
+
     /// # Path to Canister Entry Point
     /// Entry point for e.g. Motoko Compiler.
     pub main: Option<PathBuf>,
@@ -330,12 +354,269 @@ pub struct ConfigCanistersCanister {
     /// The Candid initialization argument file for installing the canister.
     /// If the `--argument` or `--argument-file` argument is also provided, this `init_arg_file` field will be ignored.
     pub init_arg_file: Option<String>,
+
+    /// # Per-Network Overrides
+    /// Settings that replace the ones above on the named networks.
+    /// Objects such as `initialization_values` are merged key by key, other values are replaced.
+    #[serde(default)]
+    pub networks: BTreeMap<String, ConfigCanistersCanisterOverride>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+/// # Canister Network Override
+/// The subset of the canister configuration that can be set per network.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigCanistersCanisterOverride {
+    /// # Canister-Specific Build Argument
+    pub args: Option<String>,
+
+    /// # Build Commands
+    /// Replaces `build` of custom and asset canisters.
+    pub build: Option<SerdeVec<String>>,
+
+    /// # Dependencies
+    pub dependencies: Option<Vec<String>>,
+
+    /// # Environment Variables
+    pub env: Option<BTreeMap<String, ConfigEnvValue>>,
+
+    /// # Init Arg
+    pub init_arg: Option<String>,
+
+    /// # Init Arg File
+    pub init_arg_file: Option<String>,
+
+    /// # Resource Allocation Settings
+    pub initialization_values: Option<InitializationValues>,
+
+    /// # Post-Install Commands
+    pub post_install: Option<SerdeVec<String>>,
+
+    /// # Specified Canister ID
+    #[schemars(with = "Option<String>")]
+    pub specified_id: Option<Principal>,
+}
+
+/// # Deploy Hooks
+/// Commands that receive a JSON description of the deployment on stdin:
+/// the network, the principal of the identity, the canister IDs, and for a canister
+/// its old and new module hashes and the install mode.
+/// A command that exits with a non-zero status aborts the deployment.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigHooks {
+    /// # Pre-Deploy Hooks
+    /// Commands to run before installing code.
+    #[serde(default)]
+    pub pre_deploy: SerdeVec<String>,
+
+    /// # Post-Deploy Hooks
+    /// Commands to run after the code is installed.
+    #[serde(default)]
+    pub post_deploy: SerdeVec<String>,
+
+    /// # Pre-Upgrade Check Hooks
+    /// Commands to run before upgrading a canister, after the compatibility checks of dfx.
+    #[serde(default)]
+    pub pre_upgrade_check: SerdeVec<String>,
+
+    /// # On-Failure Hooks
+    /// Commands to run when the deployment fails. The error is in the `error` field of their input.
+    #[serde(default)]
+    pub on_failure: SerdeVec<String>,
+}
+
+/// # Motoko Compiler Options
+#[derive(Clone, Debug, Default, Serialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct ConfigMotokoOptions {
+    #[serde(flatten)]
+    pub options: MotokoOptions,
+
+    /// # Per-Profile Overrides
+    /// Options that replace the ones above when building with the `Debug` or `Release` profile.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<Profile, MotokoOptions>,
+}
+
+// deny_unknown_fields has no effect on flattened fields, so they are spelled out here.
+impl<'de> Deserialize<'de> for ConfigMotokoOptions {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Fields {
+            warnings: Option<MotokoWarnings>,
+            gc: Option<MotokoGc>,
+            stable_regions: Option<bool>,
+            public_metadata: Option<Vec<MotokoMetadataSection>>,
+            strip_debug: Option<bool>,
+            #[serde(default)]
+            profiles: BTreeMap<Profile, MotokoOptions>,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        Ok(Self {
+            options: MotokoOptions {
+                warnings: fields.warnings,
+                gc: fields.gc,
+                stable_regions: fields.stable_regions,
+                public_metadata: fields.public_metadata,
+                strip_debug: fields.strip_debug,
+            },
+            profiles: fields.profiles,
+        })
+    }
+}
+
+impl ConfigMotokoOptions {
+    /// The options for a build with the given profile.
+    pub fn for_profile(&self, profile: Profile) -> MotokoOptions {
+        let mut options = self.options.clone();
+        if let Some(overrides) = self.profiles.get(&profile) {
+            let overrides = overrides.clone();
+            options.warnings = overrides.warnings.or(options.warnings);
+            options.gc = overrides.gc.or(options.gc);
+            options.stable_regions = overrides.stable_regions.or(options.stable_regions);
+            options.public_metadata = overrides.public_metadata.or(options.public_metadata);
+            options.strip_debug = overrides.strip_debug.or(options.strip_debug);
+        }
+        options
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MotokoOptions {
+    /// # Warnings
+    /// `show` displays the warnings of the compiler, `hide` hides them, and `error` fails the build on them (`-Werror`).
+    /// Defaults to `show`.
+    pub warnings: Option<MotokoWarnings>,
+
+    /// # Garbage Collector
+    /// The garbage collector of the canister. Defaults to the default of the compiler.
+    pub gc: Option<MotokoGc>,
+
+    /// # Stable Regions
+    /// Whether to enable the stable regions API (`--stable-regions`).
+    pub stable_regions: Option<bool>,
+
+    /// # Public Metadata
+    /// Metadata sections that the compiler makes public, in addition to the Candid sections that are public by `metadata`.
+    pub public_metadata: Option<Vec<MotokoMetadataSection>>,
+
+    /// # Strip Debug Code
+    /// Whether to leave out `debug` blocks and `Debug.print` output.
+    /// Defaults to true with the `Release` profile and to false otherwise.
+    pub strip_debug: Option<bool>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MotokoWarnings {
+    Show,
+    Hide,
+    Error,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MotokoGc {
+    Incremental,
+    Copying,
+    Compacting,
+    Generational,
+}
+
+impl MotokoGc {
+    /// The flag of moc that selects the garbage collector.
+    pub fn moc_flag(&self) -> &'static str {
+        match self {
+            MotokoGc::Incremental => "--incremental-gc",
+            MotokoGc::Copying => "--copying-gc",
+            MotokoGc::Compacting => "--compacting-gc",
+            MotokoGc::Generational => "--generational-gc",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum MotokoMetadataSection {
+    #[serde(rename = "candid:service")]
+    CandidService,
+    #[serde(rename = "candid:args")]
+    CandidArgs,
+    #[serde(rename = "motoko:stable-types")]
+    StableTypes,
+    #[serde(rename = "motoko:compiler")]
+    Compiler,
+}
+
+impl MotokoMetadataSection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MotokoMetadataSection::CandidService => "candid:service",
+            MotokoMetadataSection::CandidArgs => "candid:args",
+            MotokoMetadataSection::StableTypes => "motoko:stable-types",
+            MotokoMetadataSection::Compiler => "motoko:compiler",
+        }
+    }
+}
+
+/// # Environment Variable
+/// Either the value of the variable, or an object that can override the value per network
+/// and mark the variable as secret.
+/// Values may refer to other variables as `${NAME}`. These are resolved from the environment of dfx,
+/// or else from the `.env.<network>` file in the project root.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ConfigEnvValue {
+    Value(String),
+    Detailed(ConfigEnvValueDetails),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigEnvValueDetails {
+    /// # Value
+    /// The value on networks that have no entry in `networks`.
+    /// If absent, the variable is only set on the networks listed there.
+    pub value: Option<String>,
+
+    /// # Per-Network Values
+    /// Values that replace `value` on the named networks.
+    #[serde(default)]
+    pub networks: BTreeMap<String, String>,
+
+    /// # Secret
+    /// Secret variables are never written to `output_env_file` and never printed.
+    #[serde(default)]
+    pub secret: bool,
+}
+
+impl ConfigEnvValue {
+    /// The unresolved value of the variable on the given network, if it is set there.
+    pub fn value_for_network(&self, network: &str) -> Option<&str> {
+        match self {
+            ConfigEnvValue::Value(value) => Some(value),
+            ConfigEnvValue::Detailed(details) => details
+                .networks
+                .get(network)
+                .or(details.value.as_ref())
+                .map(String::as_str),
+        }
+    }
+
+    pub fn is_secret(&self) -> bool {
+        matches!(self, ConfigEnvValue::Detailed(details) if details.secret)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum CanisterTypeProperties {
@@ -365,6 +646,12 @@ pub enum CanisterTypeProperties {
         /// # NPM workspace
         /// The workspace in package.json that this canister is in, if it is not in the root workspace.
         workspace: Option<String>,
+
+        /// # Custom Domains
+        /// Domains that this canister is served from, in addition to its canister URL.
+        /// They are listed in the generated and certified asset /.well-known/ic-domains.
+        #[schemars(default)]
+        custom_domains: Vec<String>,
     },
     /// # Custom-Specific Properties
     Custom {
@@ -383,6 +670,16 @@ pub enum CanisterTypeProperties {
         /// No build commands are allowed if the `wasm` field is a URL.
         #[schemars(default)]
         build: SerdeVec<String>,
+
+        /// # WASM Hash
+        /// SHA256 hash of the WASM module, if the `wasm` field is a URL.
+        /// The download is rejected if it does not match.
+        wasm_hash: Option<String>,
+
+        /// # Candid Hash
+        /// SHA256 hash of the candid file, if the `candid` field is a URL.
+        /// The download is rejected if it does not match.
+        candid_hash: Option<String>,
     },
     /// # Motoko-Specific Properties
     Motoko,
@@ -599,6 +896,32 @@ pub struct ConfigDefaultsBuild {
 
     /// Arguments for packtool.
     pub args: Option<String>,
+
+    /// # Motoko Packages
+    /// Motoko packages that canisters import with `mo:<name>`, resolved by dfx.
+    /// Downloaded packages are cached, and the hashes of their archives are recorded in dfx.lock.json.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub packages: BTreeMap<String, MotokoPackage>,
+}
+
+/// # Motoko Package
+/// A Motoko package, from a local directory or from a .tar.gz archive.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MotokoPackage {
+    /// # Archive URL
+    /// URL of a .tar.gz archive of the package.
+    pub url: Option<String>,
+
+    /// # Archive Hash
+    /// SHA256 hash of the archive. If it is not set, the hash of the first download is recorded in dfx.lock.json.
+    pub sha256: Option<String>,
+
+    /// # Source Directory
+    /// Without `url`, the directory of the package's sources, relative to the project root.
+    /// With `url`, the directory of the sources inside the archive, below its top-level directory if it has only one.
+    /// Defaults to `src` if the archive has such a directory.
+    pub path: Option<PathBuf>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -643,10 +966,34 @@ pub struct ConfigDefaultsReplica {
     /// Defaults to 'application'.
     pub subnet_type: Option<ReplicaSubnetType>,
 
+    /// # Replica Clock
+    /// Runs the replica on a clock that `dfx network time` can set, advance and freeze.
+    /// Requires libfaketime.
+    pub time: Option<ConfigReplicaTime>,
+
     /// Run replica with the provided log level. Default is 'error'. Debug prints still get displayed
     pub log_level: Option<ReplicaLogLevel>,
 }
 
+/// # Replica Clock Configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigReplicaTime {
+    /// # Start Time
+    /// The time, in RFC 3339 format, that the clock shows when the network state is created.
+    /// Defaults to the current time.
+    pub start: Option<String>,
+
+    /// # Frozen
+    /// Start with the clock stopped. Defaults to false.
+    #[serde(default)]
+    pub frozen: bool,
+
+    /// # libfaketime Path
+    /// Path to the libfaketime shared library.
+    /// By default, dfx looks for it where package managers install it.
+    pub libfaketime: Option<PathBuf>,
+}
+
 /// Configuration for icx-proxy.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ConfigDefaultsProxy {
@@ -723,6 +1070,10 @@ pub struct ConfigNetworkProvider {
     #[serde(default = "NetworkType::persistent")]
     pub r#type: NetworkType,
     pub playground: Option<PlaygroundConfig>,
+
+    /// # Default Identity
+    /// The identity to use for this network when neither `--identity` nor `DFX_IDENTITY` is set.
+    pub identity: Option<String>,
 }
 
 /// # Local Replica Configuration
@@ -743,6 +1094,10 @@ pub struct ConfigLocalProvider {
     pub replica: Option<ConfigDefaultsReplica>,
     pub playground: Option<PlaygroundConfig>,
     pub proxy: Option<ConfigDefaultsProxy>,
+
+    /// # Default Identity
+    /// The identity to use for this network when neither `--identity` nor `DFX_IDENTITY` is set.
+    pub identity: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
@@ -752,7 +1107,19 @@ pub enum ConfigNetwork {
     ConfigLocalProvider(ConfigLocalProvider),
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema)]
+impl ConfigNetwork {
+    /// The identity configured as the default for this network, if any.
+    pub fn get_identity(&self) -> Option<&str> {
+        match self {
+            ConfigNetwork::ConfigNetworkProvider(provider) => provider.identity.as_deref(),
+            ConfigNetwork::ConfigLocalProvider(provider) => provider.identity.as_deref(),
+        }
+    }
+}
+
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
 pub enum Profile {
     // debug is for development only
     Debug,
@@ -795,6 +1162,21 @@ pub struct ConfigInterface {
 
     /// If set, environment variables will be output to this file (without overwriting any user-defined variables, if the file already exists).
     pub output_env_file: Option<PathBuf>,
+
+    /// # Environment Variables
+    /// Environment variables passed to the build commands and post-install commands of every canister.
+    #[serde(default)]
+    pub env: BTreeMap<String, ConfigEnvValue>,
+
+    /// # Deploy Hooks
+    /// Commands that `dfx deploy` runs before and after deploying the canisters.
+    #[serde(default)]
+    pub hooks: ConfigHooks,
+
+    /// # Canister Groups
+    /// Named lists of canisters that dfx commands select with the `--group` option.
+    #[serde(default)]
+    pub groups: BTreeMap<String, Vec<String>>,
 }
 
 pub type TopLevelConfigNetworks = BTreeMap<String, ConfigNetwork>;
@@ -1106,8 +1488,20 @@ impl Config {
             .map_err(|e| LoadDfxConfigError::DeserializeValueFailed(Box::new(path.clone()), e))?;
         let effective_json = apply_extension_canister_types(json.clone(), extension_manager)?;
 
-        let config = serde_json::from_value(effective_json)
+        let config: ConfigInterface = serde_json::from_value(effective_json.clone())
             .map_err(|e| LoadDfxConfigError::DeserializeValueFailed(Box::new(path.clone()), e))?;
+        for (name, canister) in config.canisters.iter().flatten() {
+            let has_moc = effective_json
+                .get("canisters")
+                .and_then(|canisters| canisters.get(name))
+                .and_then(|canister| canister.get("moc"))
+                .is_some();
+            if has_moc && !matches!(canister.type_specific, CanisterTypeProperties::Motoko) {
+                return Err(LoadDfxConfigError::MotokoOptionsOfNonMotokoCanister(
+                    name.clone(),
+                ));
+            }
+        }
         Ok(Config { path, json, config })
     }
 
@@ -1125,6 +1519,26 @@ impl Config {
         Ok(Config::from_slice(path, content.as_bytes(), None).unwrap())
     }
 
+    /// The configuration as it applies to `network`, with the canisters' per-network overrides merged in.
+    /// The JSON of the returned configuration is unchanged, so edits to it do not persist the overrides.
+    pub fn for_network(
+        &self,
+        network: &str,
+        extension_manager: Option<&ExtensionManager>,
+    ) -> Result<Config, LoadDfxConfigError> {
+        let mut json = self.json.clone();
+        apply_network_overrides(&mut json, network);
+        let effective_json = apply_extension_canister_types(json, extension_manager)?;
+        let config = serde_json::from_value(effective_json).map_err(|e| {
+            LoadDfxConfigError::DeserializeValueFailed(Box::new(self.path.clone()), e)
+        })?;
+        Ok(Config {
+            path: self.path.clone(),
+            json: self.json.clone(),
+            config,
+        })
+    }
+
     pub fn get_path(&self) -> &PathBuf {
         &self.path
     }
@@ -1216,6 +1630,9 @@ impl<'de> Visitor<'de> for PropertiesVisitor {
         let mut r#type = None;
         let mut id = None;
         let mut workspace = None;
+        let mut wasm_hash = None;
+        let mut candid_hash = None;
+        let mut custom_domains = None;
         while let Some(key) = map.next_key::<String>()? {
             match &*key {
                 "package" => package = Some(map.next_value()?),
@@ -1226,6 +1643,9 @@ impl<'de> Visitor<'de> for PropertiesVisitor {
                 "type" => r#type = Some(map.next_value::<String>()?),
                 "id" => id = Some(map.next_value()?),
                 "workspace" => workspace = Some(map.next_value()?),
+                "wasm_hash" => wasm_hash = Some(map.next_value()?),
+                "candid_hash" => candid_hash = Some(map.next_value()?),
+                "custom_domains" => custom_domains = Some(map.next_value()?),
                 _ => continue,
             }
         }
@@ -1239,11 +1659,14 @@ impl<'de> Visitor<'de> for PropertiesVisitor {
                 source: source.ok_or_else(|| missing_field("source"))?,
                 build: build.unwrap_or_default(),
                 workspace,
+                custom_domains: custom_domains.unwrap_or_default(),
             },
             Some("custom") => CanisterTypeProperties::Custom {
                 build: build.unwrap_or_default(),
                 candid: candid.ok_or_else(|| missing_field("candid"))?,
                 wasm: wasm.ok_or_else(|| missing_field("wasm"))?,
+                wasm_hash,
+                candid_hash,
             },
             Some("pull") => CanisterTypeProperties::Pull {
                 id: id.ok_or_else(|| missing_field("id"))?,
@@ -1450,10 +1873,39 @@ mod tests {
                 providers: vec![String::from("https://1.2.3.4:5000")],
                 r#type: NetworkType::Ephemeral,
                 playground: None,
+                identity: None,
             })
         );
     }
 
+    #[test]
+    fn network_can_declare_default_identity() {
+        let config = Config::from_str(
+            r#"{
+            "networks": {
+                "ic": {
+                    "identity": "prod-deployer"
+                },
+                "staging": {
+                    "providers": [ "https://1.2.3.4:5000" ],
+                    "identity": "staging-deployer"
+                }
+            }
+        }"#,
+        )
+        .unwrap();
+
+        let get_identity = |name| {
+            config
+                .get_config()
+                .get_network(name)
+                .and_then(ConfigNetwork::get_identity)
+        };
+        assert_eq!(get_identity("ic"), Some("prod-deployer"));
+        assert_eq!(get_identity("staging"), Some("staging-deployer"));
+        assert_eq!(get_identity("local"), None);
+    }
+
     #[test]
     fn get_correct_initialization_values() {
         let config = Config::from_str(
@@ -1502,4 +1954,124 @@ mod tests {
         assert_eq!(None, compute_allocation);
         assert_eq!(None, memory_allocation);
     }
+
+    #[test]
+    fn canister_config_for_network() {
+        let config = Config::from_str(
+            r#"{
+            "canisters": {
+                "backend": {
+                    "type": "custom",
+                    "wasm": "main.wasm",
+                    "candid": "main.did",
+                    "init_arg": "(1)",
+                    "initialization_values": {
+                        "compute_allocation": 5,
+                        "freezing_threshold": "30days"
+                    },
+                    "networks": {
+                        "staging": {
+                            "init_arg": "(2)",
+                            "initialization_values": {
+                                "freezing_threshold": "1day"
+                            }
+                        }
+                    }
+                }
+            }
+        }"#,
+        )
+        .unwrap();
+
+        let staging = config.for_network("staging", None).unwrap();
+        let backend = &staging.get_config().canisters.as_ref().unwrap()["backend"];
+        assert_eq!(backend.init_arg.as_deref(), Some("(2)"));
+        assert_eq!(
+            backend.initialization_values.freezing_threshold,
+            Some(Duration::from_secs(24 * 60 * 60))
+        );
+        assert!(backend.initialization_values.compute_allocation.is_some());
+        assert_eq!(staging.get_json(), config.get_json());
+
+        let ic = config.for_network("ic", None).unwrap();
+        let backend = &ic.get_config().canisters.as_ref().unwrap()["backend"];
+        assert_eq!(backend.init_arg.as_deref(), Some("(1)"));
+
+        let unknown_override = r#"{
+            "canisters": { "backend": { "networks": { "ic": { "main": "other.mo" } } } }
+        }"#;
+        assert!(Config::from_slice(PathBuf::from("-"), unknown_override.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn motoko_options_for_profile() {
+        let config = Config::from_str(
+            r#"{
+            "canisters": {
+                "backend": {
+                    "main": "main.mo",
+                    "moc": {
+                        "warnings": "error",
+                        "gc": "incremental",
+                        "public_metadata": ["motoko:stable-types"],
+                        "profiles": {
+                            "Release": {
+                                "warnings": "hide",
+                                "strip_debug": false
+                            }
+                        }
+                    }
+                }
+            }
+        }"#,
+        )
+        .unwrap();
+
+        let moc = &config.get_config().canisters.as_ref().unwrap()["backend"].moc;
+        let debug = moc.for_profile(Profile::Debug);
+        assert_eq!(debug.warnings, Some(MotokoWarnings::Error));
+        assert_eq!(debug.strip_debug, None);
+        let release = moc.for_profile(Profile::Release);
+        assert_eq!(release.warnings, Some(MotokoWarnings::Hide));
+        assert_eq!(release.gc, Some(MotokoGc::Incremental));
+        assert_eq!(
+            release.public_metadata,
+            Some(vec![MotokoMetadataSection::StableTypes])
+        );
+        assert_eq!(release.strip_debug, Some(false));
+
+        let unknown_gc = r#"{ "canisters": { "backend": { "moc": { "gc": "none" } } } }"#;
+        assert!(Config::from_slice(PathBuf::from("-"), unknown_gc.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn motoko_options_are_checked() {
+        let misspelled = r#"{ "canisters": { "backend": { "moc": { "warning": "error" } } } }"#;
+        assert!(Config::from_slice(PathBuf::from("-"), misspelled.as_bytes(), None).is_err());
+
+        let misspelled_in_profile = r#"{
+            "canisters": { "backend": { "moc": { "profiles": { "Release": { "stripdebug": true } } } } }
+        }"#;
+        assert!(
+            Config::from_slice(PathBuf::from("-"), misspelled_in_profile.as_bytes(), None).is_err()
+        );
+
+        let rust = r#"{
+            "canisters": {
+                "backend": {
+                    "type": "rust",
+                    "candid": "backend.did",
+                    "package": "backend",
+                    "moc": { "warnings": "error" }
+                }
+            }
+        }"#;
+        assert!(matches!(
+            Config::from_slice(PathBuf::from("-"), rust.as_bytes(), None),
+            Err(LoadDfxConfigError::MotokoOptionsOfNonMotokoCanister(name)) if name == "backend"
+        ));
+
+        let motoko = r#"{ "canisters": { "backend": { "main": "main.mo", "moc": {} } } }"#;
+        assert!(Config::from_slice(PathBuf::from("-"), motoko.as_bytes(), None).is_ok());
+    }
 }
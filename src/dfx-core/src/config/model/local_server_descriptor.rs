@@ -128,6 +128,21 @@ impl LocalServerDescriptor {
         self.data_directory.join("ic-canister-http-socket-path")
     }
 
+    /// The state of the replica's clock, changed by `dfx network time`
+    pub fn replica_clock_path(&self) -> PathBuf {
+        self.data_directory.join("replica-clock.json")
+    }
+
+    /// The timestamp file libfaketime reads the replica's clock from
+    pub fn replica_faketime_path(&self) -> PathBuf {
+        self.data_directory.join("replica-faketime")
+    }
+
+    /// HTTPS outcalls answered by `dfx start --canister-http-mock`, one JSON object per line
+    pub fn canister_http_mock_log_path(&self) -> PathBuf {
+        self.data_directory.join("canister-http-mock-log.jsonl")
+    }
+
     /// The replica configuration directory doesn't actually contain replica configuration.
     /// It contains two files:
     ///   - replica-1.port  contains the listening port of the running replica process
@@ -166,6 +181,20 @@ impl LocalServerDescriptor {
     pub fn effective_config_path(&self) -> PathBuf {
         self.data_directory.join("replica-effective-config.json")
     }
+
+    /// Named snapshots of this network's state, saved with `dfx network snapshot save`.
+    /// Kept outside of the data directory so that `dfx start --clean` does not remove them:
+    ///     <project directory>/.dfx/snapshots/local
+    ///     <shared data directory>/snapshots/local
+    pub fn snapshots_dir(&self) -> PathBuf {
+        let network_dir_name = self.data_directory.file_name().unwrap_or_default();
+        self.data_directory
+            .parent()
+            .and_then(Path::parent)
+            .unwrap_or(&self.data_directory)
+            .join("snapshots")
+            .join(network_dir_name)
+    }
 }
 
 impl LocalServerDescriptor {
@@ -192,6 +221,17 @@ impl LocalServerDescriptor {
         Self { bitcoin, ..self }
     }
 
+    pub fn with_canister_http_enabled(self) -> LocalServerDescriptor {
+        let canister_http = ConfigDefaultsCanisterHttp {
+            enabled: true,
+            ..self.canister_http
+        };
+        Self {
+            canister_http,
+            ..self
+        }
+    }
+
     pub fn with_bitcoin_nodes(self, nodes: Vec<SocketAddr>) -> LocalServerDescriptor {
         let bitcoin = ConfigDefaultsBitcoin {
             nodes: Some(nodes),
@@ -0,0 +1,76 @@
+use serde_json::{Map, Value};
+
+/// Merges `canisters.<name>.networks.<network>` into the definition of each canister.
+/// Objects are merged key by key, and any other value replaces the one in the definition.
+pub fn apply_network_overrides(json: &mut Value, network: &str) {
+    let Some(canisters) = json.get_mut("canisters").and_then(Value::as_object_mut) else {
+        return;
+    };
+    for canister in canisters.values_mut() {
+        let Some(canister) = canister.as_object_mut() else {
+            continue;
+        };
+        let overrides = canister
+            .get("networks")
+            .and_then(|networks| networks.get(network))
+            .and_then(Value::as_object)
+            .cloned();
+        if let Some(overrides) = overrides {
+            merge(canister, overrides);
+        }
+    }
+}
+
+fn merge(target: &mut Map<String, Value>, overrides: Map<String, Value>) {
+    for (key, value) in overrides {
+        match (target.get_mut(&key), value) {
+            (Some(Value::Object(target)), Value::Object(value)) => merge(target, value),
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merges_overrides_of_the_network() {
+        let mut config = json!({
+            "canisters": {
+                "backend": {
+                    "init_arg": "(\"default\")",
+                    "dependencies": ["a", "b"],
+                    "initialization_values": { "compute_allocation": 1, "freezing_threshold": "30d" },
+                    "networks": {
+                        "staging": {
+                            "init_arg": "(\"test\")",
+                            "dependencies": ["a"],
+                            "initialization_values": { "freezing_threshold": "1d" }
+                        }
+                    }
+                },
+                "frontend": { "type": "assets" }
+            }
+        });
+        let unchanged = config.clone();
+        apply_network_overrides(&mut config, "ic");
+        assert_eq!(config, unchanged);
+
+        apply_network_overrides(&mut config, "staging");
+        let backend = &config["canisters"]["backend"];
+        assert_eq!(backend["init_arg"], "(\"test\")");
+        assert_eq!(backend["dependencies"], json!(["a"]));
+        assert_eq!(
+            backend["initialization_values"],
+            json!({ "compute_allocation": 1, "freezing_threshold": "1d" })
+        );
+        assert_eq!(
+            config["canisters"]["frontend"],
+            unchanged["canisters"]["frontend"]
+        );
+    }
+}
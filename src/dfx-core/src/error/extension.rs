@@ -32,6 +32,18 @@ pub enum ListInstalledExtensionsError {
     ExtensionsDirectoryIsNotReadable(#[from] crate::error::fs::FsError),
 }
 
+#[derive(Error, Debug)]
+pub enum ListProjectTemplatesError {
+    #[error(transparent)]
+    ListInstalledExtensions(#[from] ListInstalledExtensionsError),
+
+    #[error("Failed to load the manifest of extension '{0}'")]
+    LoadExtensionManifest(String, #[source] LoadExtensionManifestError),
+
+    #[error("Extensions '{1}' and '{2}' both provide a project template named '{0}'")]
+    DuplicateProjectTemplate(String, String, String),
+}
+
 #[derive(Error, Debug)]
 pub enum ConvertExtensionSubcommandIntoClapArgError {
     #[error("Extension's subcommand argument '{0}' is missing description.")]
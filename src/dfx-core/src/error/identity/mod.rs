@@ -23,6 +23,7 @@ pub mod require_identity_exists;
 pub mod save_identity_configuration;
 pub mod save_pem;
 pub mod use_identity_by_name;
+pub mod use_network_identity;
 pub mod validate_pem_file;
 pub mod write_default_identity;
 pub mod write_pem_to_file;
@@ -0,0 +1,8 @@
+use crate::error::identity::require_identity_exists::RequireIdentityExistsError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum UseNetworkIdentityError {
+    #[error("The identity configured for network '{0}' must exist")]
+    NetworkIdentityMustExist(String, #[source] RequireIdentityExistsError),
+}
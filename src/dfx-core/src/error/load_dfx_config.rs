@@ -19,4 +19,7 @@ pub enum LoadDfxConfigError {
 
     #[error("Failed to determine current working dir")]
     DetermineCurrentWorkingDirFailed(#[source] std::io::Error),
+
+    #[error("Canister '{0}' has Motoko compiler options (moc), but it is not a Motoko canister")]
+    MotokoOptionsOfNonMotokoCanister(String),
 }
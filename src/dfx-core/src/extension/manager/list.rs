@@ -1,6 +1,10 @@
 use super::ExtensionManager;
-use crate::error::extension::{ConvertExtensionIntoClapCommandError, ListInstalledExtensionsError};
+use crate::error::extension::{
+    ConvertExtensionIntoClapCommandError, ListInstalledExtensionsError, ListProjectTemplatesError,
+};
+use crate::extension::manifest::{ExtensionManifest, ExtensionProjectTemplate};
 use crate::extension::Extension;
+use std::collections::BTreeMap;
 
 impl ExtensionManager {
     pub fn list_installed_extensions(
@@ -35,4 +39,33 @@ impl ExtensionManager {
         }
         Ok(extensions)
     }
+
+    /// The project templates provided by installed extensions, by name.
+    /// The paths of the templates are absolute.
+    pub fn list_project_templates(
+        &self,
+    ) -> Result<BTreeMap<String, ExtensionProjectTemplate>, ListProjectTemplatesError> {
+        let mut templates = BTreeMap::new();
+        let mut providers: BTreeMap<String, String> = BTreeMap::new();
+        for extension in self.list_installed_extensions()? {
+            let manifest = ExtensionManifest::load(&extension.name, &self.dir).map_err(|e| {
+                ListProjectTemplatesError::LoadExtensionManifest(extension.name.clone(), e)
+            })?;
+            for (name, mut template) in manifest.project_templates.unwrap_or_default() {
+                if let Some(other) = providers.get(&name) {
+                    return Err(ListProjectTemplatesError::DuplicateProjectTemplate(
+                        name,
+                        other.clone(),
+                        extension.name,
+                    ));
+                }
+                template.path = self
+                    .get_extension_directory(&extension.name)
+                    .join(&template.path);
+                providers.insert(name.clone(), extension.name.clone());
+                templates.insert(name, template);
+            }
+        }
+        Ok(templates)
+    }
 }
@@ -29,6 +29,7 @@ pub struct ExtensionManifest {
     pub subcommands: Option<ExtensionSubcommandsOpts>,
     pub dependencies: Option<HashMap<String, String>>,
     pub canister_type: Option<ExtensionCanisterType>,
+    pub project_templates: Option<BTreeMap<String, ExtensionProjectTemplate>>,
 }
 
 impl ExtensionManifest {
@@ -79,6 +80,17 @@ pub struct ExtensionCanisterType {
     pub defaults: BTreeMap<String, Value>,
 }
 
+/// A template for `dfx new --template <name>`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExtensionProjectTemplate {
+    /// The directory with the template manifest, relative to the extension directory.
+    pub path: PathBuf,
+
+    /// A one-line description of the template.
+    pub description: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Default)]
 pub struct ExtensionSubcommandsOpts(BTreeMap<SubcmdName, ExtensionSubcommandOpts>);
 
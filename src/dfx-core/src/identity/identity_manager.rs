@@ -50,6 +50,7 @@ use crate::error::identity::save_identity_configuration::SaveIdentityConfigurati
 use crate::error::identity::save_identity_configuration::SaveIdentityConfigurationError::EnsureIdentityConfigurationDirExistsFailed;
 use crate::error::identity::use_identity_by_name::UseIdentityByNameError;
 use crate::error::identity::use_identity_by_name::UseIdentityByNameError::WriteDefaultIdentityFailed;
+use crate::error::identity::use_network_identity::UseNetworkIdentityError;
 use crate::error::identity::write_default_identity::WriteDefaultIdentityError;
 use crate::error::identity::write_default_identity::WriteDefaultIdentityError::SaveIdentityManagerConfigurationFailed;
 use crate::error::structured_file::StructuredFileError;
@@ -194,6 +195,22 @@ pub enum IdentityCreationParameters {
     },
 }
 
+/// Where the selected identity came from, in order of precedence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdentitySelection {
+    /// `--identity <name>` or the `DFX_IDENTITY` environment variable.
+    Override,
+
+    /// The `identity` field of a network definition in dfx.json or networks.json.
+    Network {
+        network: String,
+        config_path: PathBuf,
+    },
+
+    /// The identity selected with `dfx identity use`.
+    Default,
+}
+
 #[derive(Clone, Debug)]
 pub struct IdentityManager {
     identity_json_path: PathBuf,
@@ -201,6 +218,7 @@ pub struct IdentityManager {
     configuration: Configuration,
     selected_identity: String,
     selected_identity_principal: Option<Principal>,
+    selection: IdentitySelection,
 }
 
 impl IdentityManager {
@@ -224,6 +242,11 @@ impl IdentityManager {
         let selected_identity = identity_override
             .clone()
             .unwrap_or_else(|| configuration.default.clone());
+        let selection = if identity_override.is_some() {
+            IdentitySelection::Override
+        } else {
+            IdentitySelection::Default
+        };
         let file_locations = IdentityFileLocations::new(identity_root_path);
 
         let mgr = IdentityManager {
@@ -232,6 +255,7 @@ impl IdentityManager {
             configuration,
             selected_identity,
             selected_identity_principal: None,
+            selection,
         };
 
         if let Some(identity) = identity_override {
@@ -246,6 +270,40 @@ impl IdentityManager {
         self.selected_identity_principal
     }
 
+    /// Select the identity a network definition declares as its default.
+    ///
+    /// Does nothing if the identity was overridden with `--identity` or `DFX_IDENTITY`.
+    pub fn use_network_identity(
+        &mut self,
+        log: &Logger,
+        network: &str,
+        identity: &str,
+        config_path: &Path,
+    ) -> Result<(), UseNetworkIdentityError> {
+        if self.selection == IdentitySelection::Override {
+            return Ok(());
+        }
+        self.require_identity_exists(log, identity).map_err(|e| {
+            UseNetworkIdentityError::NetworkIdentityMustExist(network.to_string(), e)
+        })?;
+        debug!(
+            log,
+            "Using identity '{identity}' configured for network '{network}' in {}",
+            config_path.display()
+        );
+        self.selected_identity = identity.to_string();
+        self.selection = IdentitySelection::Network {
+            network: network.to_string(),
+            config_path: config_path.to_path_buf(),
+        };
+        Ok(())
+    }
+
+    /// Return where the currently selected identity was chosen from
+    pub fn get_identity_selection(&self) -> &IdentitySelection {
+        &self.selection
+    }
+
     /// Create an Identity instance for use with an Agent
     pub fn instantiate_selected_identity(
         &mut self,
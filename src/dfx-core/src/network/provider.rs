@@ -244,6 +244,7 @@ fn create_shared_network_descriptor(
                 replica: None,
                 playground: None,
                 proxy: None,
+                identity: None,
             }))
         }
         (network_name, None) => {
@@ -930,6 +931,7 @@ mod tests {
             &ConfigDefaultsReplica {
                 subnet_type: Some(VerifiedApplication),
                 port: Some(17001),
+                time: None,
                 log_level: Some(ReplicaLogLevel::Trace)
             }
         );
@@ -977,6 +979,7 @@ mod tests {
             &ConfigDefaultsReplica {
                 subnet_type: Some(System),
                 port: None,
+                time: None,
                 log_level: None
             }
         );
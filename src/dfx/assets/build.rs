@@ -344,6 +344,18 @@ fn define_replica_rev(replica_rev: &str) {
     println!("cargo:rustc-env=DFX_ASSET_REPLICA_REV={}", replica_rev);
 }
 
+/// Generates the server side of the protocol the replica uses to talk to the HTTPS outcalls adapter.
+fn compile_https_outcalls_proto() {
+    let proto = "assets/proto/https_outcalls_service/v1/proto.proto";
+    println!("cargo:rerun-if-changed={}", proto);
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("unable to find a vendored protoc");
+    std::env::set_var("PROTOC", protoc);
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&[proto], &["assets/proto"])
+        .expect("unable to compile the HTTPS outcalls proto");
+}
+
 fn main() {
     let sources: Sources = toml::from_str(
         &fs::read_to_string("assets/dfx-asset-sources.toml")
@@ -353,4 +365,5 @@ fn main() {
     define_replica_rev(&sources.replica_rev);
     add_assets(sources);
     define_dfx_version();
+    compile_https_outcalls_proto();
 }
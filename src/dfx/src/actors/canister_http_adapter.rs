@@ -5,6 +5,7 @@ use crate::actors::shutdown::{wait_for_child_or_receiver, ChildOrReceiver};
 use crate::actors::shutdown_controller::signals::outbound::Shutdown;
 use crate::actors::shutdown_controller::signals::ShutdownSubscribe;
 use crate::actors::shutdown_controller::ShutdownController;
+use crate::lib::canister_http_mock::{self, MockOutcalls};
 use crate::lib::error::{DfxError, DfxResult};
 use actix::{
     Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, Context, Handler, Recipient,
@@ -12,8 +13,9 @@ use actix::{
 };
 use anyhow::bail;
 use crossbeam::channel::{unbounded, Receiver, Sender};
-use slog::{debug, info, Logger};
+use slog::{debug, error, info, Logger};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
@@ -38,11 +40,15 @@ pub struct Config {
     pub shutdown_controller: Addr<ShutdownController>,
     pub pid_file_path: PathBuf,
 
+    /// Answer outcalls from fixtures instead of running the adapter process.
+    pub mock: Option<Arc<MockOutcalls>>,
+
     pub logger: Option<Logger>,
 }
 
 /// An actor for the ic-https-outcalls-adapter process.  Publishes information about
 /// the process starting or restarting, so that other processes can reconnect.
+/// With mocked outcalls, serves the adapter socket itself instead.
 pub struct CanisterHttpAdapter {
     config: Config,
 
@@ -187,6 +193,19 @@ fn canister_http_adapter_start_thread(
     addr: Addr<CanisterHttpAdapter>,
     receiver: Receiver<()>,
 ) -> DfxResult<std::thread::JoinHandle<()>> {
+    if let (Some(mock), Some(socket_path)) = (config.mock.clone(), config.socket_path.clone()) {
+        return std::thread::Builder::new()
+            .name("canister-http-adapter-actor".to_owned())
+            .spawn(move || {
+                debug!(logger, "Serving mocked HTTPS outcalls...");
+                let ready = || addr.do_send(signals::CanisterHttpAdapterReady {});
+                if let Err(e) = canister_http_mock::serve(mock, &socket_path, receiver, ready) {
+                    error!(logger, "{:#}", e);
+                }
+            })
+            .map_err(DfxError::from);
+    }
+
     let thread_handler = move || {
         let adapter_path = config.adapter_path.as_os_str();
         let mut cmd = std::process::Command::new(adapter_path);
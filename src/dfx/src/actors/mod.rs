@@ -6,8 +6,10 @@ use crate::actors::icx_proxy::signals::PortReadySubscribe;
 use crate::actors::icx_proxy::{IcxProxy, IcxProxyConfig};
 use crate::actors::replica::{BitcoinIntegrationConfig, Replica};
 use crate::actors::shutdown_controller::ShutdownController;
+use crate::lib::canister_http_mock::MockOutcalls;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::network::time::ReplicaTimeControl;
 use crate::lib::replica_config::ReplicaConfig;
 use actix::{Actor, Addr, Recipient};
 use anyhow::Context;
@@ -15,6 +17,7 @@ use dfx_core::config::model::local_server_descriptor::LocalServerDescriptor;
 use fn_error_context::context;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 pub mod btc_adapter;
 pub mod canister_http_adapter;
@@ -61,6 +64,7 @@ pub fn start_canister_http_adapter_actor(
     socket_path: Option<PathBuf>,
     shutdown_controller: Addr<ShutdownController>,
     pid_file_path: PathBuf,
+    mock: Option<Arc<MockOutcalls>>,
 ) -> DfxResult<Recipient<CanisterHttpAdapterReadySubscribe>> {
     let adapter_path = env
         .get_cache()
@@ -74,6 +78,7 @@ pub fn start_canister_http_adapter_actor(
 
         shutdown_controller,
         pid_file_path,
+        mock,
         logger: Some(env.get_logger().clone()),
     };
     Ok(CanisterHttpAdapter::new(actor_config).start().recipient())
@@ -123,6 +128,7 @@ pub fn start_replica_actor(
     env: &dyn Environment,
     replica_config: ReplicaConfig,
     local_server_descriptor: &LocalServerDescriptor,
+    time_control: Option<ReplicaTimeControl>,
     shutdown_controller: Addr<ShutdownController>,
     btc_adapter_ready_subscribe: Option<Recipient<BtcAdapterReadySubscribe>>,
     canister_http_adapter_ready_subscribe: Option<Recipient<CanisterHttpAdapterReadySubscribe>>,
@@ -149,6 +155,7 @@ pub fn start_replica_actor(
         shutdown_controller,
         logger: Some(env.get_logger().clone()),
         replica_pid_path,
+        time_control,
         btc_adapter_ready_subscribe,
         canister_http_adapter_ready_subscribe,
     };
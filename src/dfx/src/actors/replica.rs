@@ -11,6 +11,7 @@ use crate::actors::shutdown_controller::ShutdownController;
 use crate::lib::error::{DfxError, DfxResult};
 use crate::lib::integrations::bitcoin::initialize_bitcoin_canister;
 use crate::lib::integrations::create_integrations_agent;
+use crate::lib::network::time::ReplicaTimeControl;
 use crate::lib::replica_config::ReplicaConfig;
 use actix::{
     Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, Context, Handler, Recipient,
@@ -48,6 +49,7 @@ pub struct Config {
     pub bitcoin_integration_config: Option<BitcoinIntegrationConfig>,
     pub replica_path: PathBuf,
     pub replica_pid_path: PathBuf,
+    pub time_control: Option<ReplicaTimeControl>,
     pub shutdown_controller: Addr<ShutdownController>,
     pub logger: Option<Logger>,
     pub btc_adapter_ready_subscribe: Option<Recipient<BtcAdapterReadySubscribe>>,
@@ -165,6 +167,7 @@ impl Replica {
                 replica_path,
                 replica_pid_path,
                 artificial_delay,
+                self.config.time_control.clone(),
                 addr,
                 receiver,
             ),
@@ -297,6 +300,7 @@ fn replica_start_thread(
     replica_path: PathBuf,
     replica_pid_path: PathBuf,
     artificial_delay: u32,
+    time_control: Option<ReplicaTimeControl>,
     addr: Addr<Replica>,
     receiver: Receiver<()>,
 ) -> DfxResult<std::thread::JoinHandle<()>> {
@@ -371,6 +375,11 @@ fn replica_start_thread(
         // at https://gitlab.com/dfinity-lab/core/ic/-/blob/master/ic-os/guestos/rootfs/etc/systemd/system/ic-replica.service
         cmd.env("RUST_MIN_STACK", "8192000");
 
+        // ic-starter passes its environment on to the replica.
+        if let Some(time_control) = &time_control {
+            cmd.envs(time_control.env_vars());
+        }
+
         cmd.stdout(std::process::Stdio::inherit());
         cmd.stderr(std::process::Stdio::inherit());
 
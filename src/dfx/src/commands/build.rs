@@ -6,7 +6,9 @@ use crate::lib::error::DfxResult;
 use crate::lib::models::canister::CanisterPool;
 use crate::lib::network::network_opt::NetworkOpt;
 use crate::lib::operations::canister::add_canisters_with_ids;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use clap::Parser;
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 use tokio::runtime::Runtime;
 
@@ -15,12 +17,16 @@ use tokio::runtime::Runtime;
 pub struct CanisterBuildOpts {
     /// Specifies the name of the canister to build.
     /// You must specify either a canister name or the --all option.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister_name: Option<String>,
 
     /// Builds all canisters configured in the dfx.json file.
-    #[arg(long, conflicts_with("canister_name"))]
+    #[arg(long, conflicts_with_all(["canister_name", "group", "tag"]))]
     all: bool,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     /// Build canisters without creating them. This can be used to check that canisters build ok.
     #[arg(long)]
     check: bool,
@@ -48,10 +54,24 @@ pub fn exec(env1: &dyn Environment, opts: CanisterBuildOpts) -> DfxResult {
 
     let build_mode_check = opts.check;
 
-    // Option can be None in which case --all was specified
-    let required_canisters = config
-        .get_config()
-        .get_canister_names_with_dependencies(opts.canister_name.as_deref())?;
+    let selected_canisters = opts.canister_group.select(config.get_config())?;
+    let required_canisters = match &selected_canisters {
+        Some(selected_canisters) => {
+            let mut names = BTreeSet::new();
+            for canister in selected_canisters {
+                names.extend(
+                    config
+                        .get_config()
+                        .get_canister_names_with_dependencies(Some(canister))?,
+                );
+            }
+            names.into_iter().collect()
+        }
+        // Option can be None in which case --all was specified
+        None => config
+            .get_config()
+            .get_canister_names_with_dependencies(opts.canister_name.as_deref())?,
+    };
     let canisters_to_load = add_canisters_with_ids(&required_canisters, &env, &config);
 
     // let canisters_to_build = required_canisters
@@ -96,6 +116,8 @@ pub fn exec(env1: &dyn Environment, opts: CanisterBuildOpts) -> DfxResult {
             .with_build_mode_check(build_mode_check)
             .with_canisters_to_build(if let Some(canister) = opts.canister_name {
                 vec![canister] // hacky
+            } else if let Some(selected_canisters) = selected_canisters {
+                selected_canisters
             } else {
                 config
                     .get_config()
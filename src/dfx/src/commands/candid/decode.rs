@@ -0,0 +1,69 @@
+use crate::commands::candid::MethodTypeOpts;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::util::print_idl_blob;
+use anyhow::Context;
+use candid::types::Function;
+use clap::Parser;
+use std::path::Path;
+
+/// Decodes a Candid message, such as the argument or the reply of a call.
+#[derive(Parser)]
+pub struct CandidDecodeOpts {
+    /// Specifies the message in hex, or the path of a file with the message in binary or in hex.
+    message: String,
+
+    #[command(flatten)]
+    method_type: MethodTypeOpts,
+
+    /// Specifies the format for displaying the values.
+    #[arg(long, value_parser = ["idl", "pp", "json"])]
+    output: Option<String>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: CandidDecodeOpts) -> DfxResult {
+    let blob = read_message(&opts.message)?;
+    let method_type = opts.method_type.load(env).await?.map(|(type_env, types)| {
+        (
+            type_env,
+            Function {
+                args: vec![],
+                rets: types,
+                modes: vec![],
+            },
+        )
+    });
+    print_idl_blob(&blob, opts.output.as_deref(), &method_type)
+}
+
+fn read_message(message: &str) -> DfxResult<Vec<u8>> {
+    let path = Path::new(message);
+    if !path.is_file() {
+        return decode_hex(message);
+    }
+    let content = dfx_core::fs::read(path)?;
+    if content.starts_with(b"DIDL") {
+        return Ok(content);
+    }
+    let text = String::from_utf8(content)
+        .with_context(|| format!("{} is neither a Candid message nor hex.", path.display()))?;
+    decode_hex(&text)
+}
+
+fn decode_hex(text: &str) -> DfxResult<Vec<u8>> {
+    let hex: String = text.split_whitespace().collect();
+    let hex = hex.strip_prefix("0x").unwrap_or(&hex);
+    hex::decode(hex).context("The message is not valid hex, nor the path of a file.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_hex() {
+        assert_eq!(decode_hex("4449444c0000").unwrap(), b"DIDL\0\0");
+        assert_eq!(decode_hex("0x4449 444c\n0000\n").unwrap(), b"DIDL\0\0");
+        assert!(decode_hex("DIDL").is_err());
+    }
+}
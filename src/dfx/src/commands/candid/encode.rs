@@ -0,0 +1,58 @@
+use crate::commands::candid::MethodTypeOpts;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::util::clap::parsers::file_or_stdin_parser;
+use crate::util::{arguments_from_file, blob_from_arguments};
+use candid::types::Function;
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Encodes Candid values into a message, such as the argument of a call.
+#[derive(Parser)]
+pub struct CandidEncodeOpts {
+    /// Specifies the values to encode, in Candid.
+    #[arg(conflicts_with("argument_file"))]
+    argument: Option<String>,
+
+    /// Specifies the file from which to read the values to encode.
+    #[arg(long, value_parser = file_or_stdin_parser)]
+    argument_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    method_type: MethodTypeOpts,
+
+    /// Writes the message to a file, in binary, instead of displaying it in hex.
+    #[arg(long)]
+    output_file: Option<PathBuf>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: CandidEncodeOpts) -> DfxResult {
+    let argument = match &opts.argument_file {
+        Some(path) => Some(arguments_from_file(path)?),
+        None => opts.argument,
+    };
+    let method_type = opts.method_type.load(env).await?.map(|(type_env, types)| {
+        (
+            type_env,
+            Function {
+                args: types,
+                rets: vec![],
+                modes: vec![],
+            },
+        )
+    });
+    let blob = blob_from_arguments(
+        Some(env),
+        argument.as_deref(),
+        None,
+        None,
+        &method_type,
+        false,
+        false,
+    )?;
+    match &opts.output_file {
+        Some(path) => dfx_core::fs::write(path, blob)?,
+        None => println!("{}", hex::encode(blob)),
+    }
+    Ok(())
+}
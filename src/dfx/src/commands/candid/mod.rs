@@ -0,0 +1,103 @@
+use crate::lib::agent::create_anonymous_agent_environment;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::network::network_opt::NetworkOpt;
+use crate::lib::operations::canister::get_canister_id_and_candid_path;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::{get_candid_type, get_candid_type_of_canister};
+use anyhow::{bail, Context};
+use candid::types::{Type, TypeEnv};
+use candid_parser::utils::CandidSource;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use tokio::runtime::Runtime;
+
+mod decode;
+mod encode;
+
+/// Encodes and decodes Candid messages, with the types of the methods of canisters.
+#[derive(Parser)]
+pub struct CandidOpts {
+    #[command(flatten)]
+    network: NetworkOpt,
+
+    #[command(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Subcommand)]
+enum SubCommand {
+    Decode(decode::CandidDecodeOpts),
+    Encode(encode::CandidEncodeOpts),
+}
+
+pub fn exec(env: &dyn Environment, opts: CandidOpts) -> DfxResult {
+    // Reading the interface of a canister needs no identity.
+    let agent_env = create_anonymous_agent_environment(env, opts.network.to_network_name())?;
+    let runtime = Runtime::new().expect("Unable to create a runtime");
+    runtime.block_on(async {
+        match opts.subcmd {
+            SubCommand::Decode(v) => decode::exec(&agent_env, v).await,
+            SubCommand::Encode(v) => encode::exec(&agent_env, v).await,
+        }
+    })
+}
+
+/// Options for finding the types of the arguments or the results of a method.
+#[derive(Args)]
+pub struct MethodTypeOpts {
+    /// Specifies the name or id of the canister whose interface has the method.
+    /// The interface is read from the candid:service metadata of the canister,
+    /// or else from the Candid file of the project.
+    #[arg(long, requires("method"), conflicts_with("candid"))]
+    canister: Option<String>,
+
+    /// Specifies a Candid file with the interface that has the method.
+    #[arg(long, requires("method"))]
+    candid: Option<PathBuf>,
+
+    /// Specifies the method whose types to use.
+    #[arg(long)]
+    method: Option<String>,
+
+    /// Uses the types of the method's arguments. This is the default.
+    #[arg(long, requires("method"), conflicts_with("rets"))]
+    args: bool,
+
+    /// Uses the types of the method's results.
+    #[arg(long, requires("method"))]
+    rets: bool,
+}
+
+impl MethodTypeOpts {
+    /// Returns the types of the method's arguments or results, if a method is specified.
+    pub async fn load(&self, env: &dyn Environment) -> DfxResult<Option<(TypeEnv, Vec<Type>)>> {
+        let Some(method) = &self.method else {
+            return Ok(None);
+        };
+        let method_type = if let Some(path) = &self.candid {
+            get_candid_type(CandidSource::File(path), method)
+        } else if let Some(canister) = &self.canister {
+            let (canister_id, local_candid_path) = get_canister_id_and_candid_path(env, canister)?;
+            if fetch_root_key_if_needed(env).await.is_ok() {
+                get_candid_type_of_canister(
+                    env.get_agent(),
+                    canister_id,
+                    local_candid_path.as_deref(),
+                    method,
+                )
+                .await
+            } else {
+                // The network is not running, but the project may have the interface.
+                local_candid_path
+                    .and_then(|path| get_candid_type(CandidSource::File(&path), method))
+            }
+        } else {
+            bail!("Specify the interface that has method {method} with --canister or --candid.");
+        };
+        let (type_env, func) =
+            method_type.with_context(|| format!("Cannot find the type of method {method}."))?;
+        let types = if self.rets { func.rets } else { func.args };
+        Ok(Some((type_env, types)))
+    }
+}
@@ -0,0 +1,310 @@
+use crate::commands::new::{create_file, replace_variables, write_template_file};
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::project::import::get_canisters_json_object;
+use crate::util::assets;
+use crate::util::clap::parsers::project_name_parser;
+use anyhow::{bail, Context};
+use clap::Parser;
+use dfx_core::config::model::dfinity::BUILTIN_CANISTER_TYPES;
+use dfx_core::extension::manifest::ExtensionManifest;
+use serde_json::{json, Value};
+use slog::{info, warn, Logger};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Adds a canister to the project in the current directory.
+/// The source of Motoko, Rust and assets canisters is generated from the templates of dfx new.
+#[derive(Parser)]
+pub struct CanisterAddOpts {
+    /// Specifies the name of the canister to add.
+    #[arg(value_parser = project_name_parser)]
+    canister_name: String,
+
+    /// Specifies the type of the canister: motoko, rust, assets, custom,
+    /// or a canister type defined by an installed extension.
+    #[arg(long, default_value = "motoko")]
+    r#type: String,
+
+    /// Adds a dependency on another canister of the project. Can be specified more than once.
+    #[arg(long = "dependency", value_name = "CANISTER")]
+    dependencies: Vec<String>,
+
+    /// Specifies the directory to generate the declarations of the canister in.
+    #[arg(long, value_name = "DIR")]
+    declarations: Option<PathBuf>,
+
+    /// Displays the files that would be created without changing the project.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn exec(env: &dyn Environment, opts: CanisterAddOpts) -> DfxResult {
+    let log = env.get_logger();
+    let config = env.get_config_or_anyhow()?;
+    let mut config = config.as_ref().clone();
+    let project_root = config.get_project_root().to_path_buf();
+    let name = &opts.canister_name;
+
+    let canisters = get_canisters_json_object(&mut config)?;
+    if canisters.contains_key(name) {
+        bail!("Canister '{name}' already exists in dfx.json.");
+    }
+    for dependency in &opts.dependencies {
+        if !canisters.contains_key(dependency) {
+            bail!("Canister '{dependency}' does not exist in dfx.json.");
+        }
+    }
+
+    let variables = BTreeMap::from([
+        ("backend_name".to_string(), name.clone()),
+        ("backend_name_ident".to_string(), name.replace('-', "_")),
+        ("frontend_name".to_string(), name.clone()),
+        ("frontend_name_ident".to_string(), name.replace('-', "_")),
+    ]);
+
+    let mut definition = match opts.r#type.as_str() {
+        "motoko" => add_from_template(
+            log,
+            assets::new_project_motoko_files()?,
+            &project_root,
+            name,
+            opts.dry_run,
+            &variables,
+        )?,
+        "rust" => add_from_template(
+            log,
+            assets::new_project_rust_files()?,
+            &project_root,
+            name,
+            opts.dry_run,
+            &variables,
+        )?,
+        "assets" => add_from_template(
+            log,
+            assets::new_project_assets_files()?,
+            &project_root,
+            name,
+            opts.dry_run,
+            &variables,
+        )?,
+        "custom" => {
+            info!(
+                log,
+                "Set the build command of canister '{}' in dfx.json.", name
+            );
+            json!({
+                "type": "custom",
+                "build": [],
+                "candid": format!("src/{name}/{name}.did"),
+                "wasm": format!("src/{name}/{name}.wasm"),
+            })
+        }
+        extension => {
+            let extension_dir = &env.get_extension_manager().dir;
+            if BUILTIN_CANISTER_TYPES.contains(&extension)
+                || !ExtensionManifest::exists(extension, extension_dir)
+            {
+                bail!("Canister type '{extension}' is not supported by dfx canister add, and no extension with that name is installed.");
+            }
+            if ExtensionManifest::load(extension, extension_dir)?
+                .canister_type
+                .is_none()
+            {
+                bail!("Extension '{extension}' does not define a canister type.");
+            }
+            info!(
+                log,
+                "Set the fields that the '{}' canister type needs on canister '{}' in dfx.json.",
+                extension,
+                name
+            );
+            json!({ "type": extension })
+        }
+    };
+
+    let fields = definition
+        .as_object_mut()
+        .context("The template does not define the canister as an object.")?;
+    // The templates of dfx new make the frontend depend on the backend.
+    fields.remove("dependencies");
+    if !opts.dependencies.is_empty() {
+        fields.insert("dependencies".to_string(), json!(opts.dependencies));
+    }
+    if let Some(declarations) = &opts.declarations {
+        fields.insert(
+            "declarations".to_string(),
+            json!({ "output": declarations }),
+        );
+    }
+
+    let canisters = get_canisters_json_object(&mut config)?;
+    canisters.insert(name.clone(), definition);
+    if !opts.dry_run {
+        config.save()?;
+    }
+    info!(log, "Added canister '{}' to dfx.json.", name);
+    Ok(())
+}
+
+/// Writes the source files of the canister from a template of dfx new,
+/// and returns the definition of the canister from the template's dfx.json-patch.
+fn add_from_template<R: Read>(
+    log: &Logger,
+    mut archive: tar::Archive<R>,
+    project_root: &Path,
+    name: &str,
+    dry_run: bool,
+    variables: &BTreeMap<String, String>,
+) -> DfxResult<Value> {
+    let source_dir = project_root.join("src").join(name);
+    if source_dir.exists() {
+        bail!("{} already exists.", source_dir.display());
+    }
+
+    let mut definition = None;
+    for entry in archive.entries()? {
+        let mut file = entry?;
+        if file.header().entry_type().is_dir() {
+            continue;
+        }
+        let path = file.header().path()?.into_owned();
+        let mut v = Vec::new();
+        file.read_to_end(&mut v)?;
+
+        if path == Path::new("dfx.json-patch") {
+            let patch = replace_variables(String::from_utf8(v)?, variables);
+            let patch: json_patch::Patch =
+                serde_json::from_str(&patch).context("Failed to parse dfx.json-patch.")?;
+            let mut dfx_json = json!({ "canisters": {} });
+            json_patch::patch(&mut dfx_json, &patch).context("Failed to apply dfx.json-patch.")?;
+            definition = dfx_json.pointer(&format!("/canisters/{name}")).cloned();
+        } else if path == Path::new("Cargo.toml") {
+            let manifest = replace_variables(String::from_utf8(v)?, variables);
+            add_to_cargo_workspace(log, project_root, &manifest, name, dry_run)?;
+        } else if path.starts_with("src") {
+            write_template_file(log, &path, v, project_root, dry_run, variables)?;
+        }
+        // Files for the project as a whole, such as the README, are left alone.
+    }
+    definition.context("The template does not define the canister in dfx.json.")
+}
+
+/// Adds the package of a Rust canister to the members of the cargo workspace,
+/// creating the workspace if the project has none.
+fn add_to_cargo_workspace(
+    log: &Logger,
+    project_root: &Path,
+    template_manifest: &str,
+    name: &str,
+    dry_run: bool,
+) -> DfxResult {
+    let path = project_root.join("Cargo.toml");
+    if !path.exists() {
+        return create_file(log, &path, template_manifest.as_bytes(), dry_run);
+    }
+    let manifest = dfx_core::fs::read_to_string(&path)?;
+    let member = format!("src/{name}");
+    match insert_workspace_member(&manifest, &member)
+        .with_context(|| format!("Failed to add {} to the cargo workspace.", member))?
+    {
+        Some(updated) => {
+            if !dry_run {
+                dfx_core::fs::write(&path, updated)?;
+            }
+            info!(log, "Added {} to the cargo workspace.", member);
+        }
+        None => warn!(
+            log,
+            "{} has no [workspace]. Add \"{}\" to its members yourself.",
+            path.display(),
+            member
+        ),
+    }
+    Ok(())
+}
+
+/// Adds a member to the `members` array of the `[workspace]` table of a Cargo.toml,
+/// keeping its formatting. Returns None if the Cargo.toml has no workspace.
+fn insert_workspace_member(manifest: &str, member: &str) -> DfxResult<Option<String>> {
+    let mut document: toml_edit::Document =
+        manifest.parse().context("Failed to parse Cargo.toml.")?;
+    let Some(workspace) = document
+        .get_mut("workspace")
+        .and_then(|workspace| workspace.as_table_like_mut())
+    else {
+        return Ok(None);
+    };
+    let members = workspace
+        .entry("members")
+        .or_insert(toml_edit::value(toml_edit::Array::new()));
+    let Some(members) = members.as_array_mut() else {
+        bail!("The members of the cargo workspace are not an array.");
+    };
+    if members
+        .iter()
+        .all(|existing| existing.as_str() != Some(member))
+    {
+        let mut value = toml_edit::Value::from(member);
+        // Members that are on lines of their own stay that way.
+        if let Some(last) = members.iter_mut().last() {
+            let decor = last.decor().clone();
+            if decor
+                .prefix()
+                .and_then(|prefix| prefix.as_str())
+                .is_some_and(|prefix| prefix.contains('\n'))
+            {
+                last.decor_mut().set_suffix("");
+                *value.decor_mut() = decor;
+            }
+        }
+        members.push_formatted(value);
+    }
+    Ok(Some(document.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserts_workspace_members() {
+        let manifest = "[workspace]\nmembers = [\n    \"src/a\"\n]\nresolver = \"2\"\n";
+        assert_eq!(
+            insert_workspace_member(manifest, "src/b").unwrap().unwrap(),
+            "[workspace]\nmembers = [\n    \"src/a\",\n    \"src/b\"\n]\nresolver = \"2\"\n"
+        );
+        assert_eq!(
+            insert_workspace_member(manifest, "src/a").unwrap().unwrap(),
+            manifest
+        );
+        assert_eq!(
+            insert_workspace_member(
+                "# members\n[workspace]\nmembers = [\"src/a\", \"src/c\"] # sorted\n",
+                "src/b"
+            )
+            .unwrap()
+            .unwrap(),
+            "# members\n[workspace]\nmembers = [\"src/a\", \"src/c\", \"src/b\"] # sorted\n"
+        );
+        assert_eq!(
+            insert_workspace_member("[workspace]\nmembers = []\n", "src/b")
+                .unwrap()
+                .unwrap(),
+            "[workspace]\nmembers = [\"src/b\"]\n"
+        );
+        assert_eq!(
+            insert_workspace_member("[workspace]\nresolver = \"2\"\n", "src/b")
+                .unwrap()
+                .unwrap(),
+            "[workspace]\nresolver = \"2\"\nmembers = [\"src/b\"]\n"
+        );
+        assert_eq!(
+            insert_workspace_member("[package]\nname = \"a\"\n", "src/b").unwrap(),
+            None
+        );
+        assert!(insert_workspace_member("[workspace]\nmembers = \"src/a\"\n", "src/b").is_err());
+        assert!(insert_workspace_member("[workspace\n", "src/b").is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use super::update_settings::controller_to_principal;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use anyhow::{bail, Context};
+use candid::Principal;
+use clap::{ArgAction, Parser, ValueEnum};
+use ic_asset::{Permission, ScopedPermission};
+use slog::info;
+
+/// Grants, revokes or lists the permissions to change the assets of an asset canister.
+/// Without --grant or --revoke, lists the principals that have each permission.
+#[derive(Parser)]
+pub struct AssetPermissionsOpts {
+    /// Specifies the name or id of the asset canister.
+    canister: String,
+
+    /// Grants the permission to this identity name or principal.
+    /// Can be specified more than once.
+    #[arg(long, action = ArgAction::Append)]
+    grant: Vec<String>,
+
+    /// Revokes the permission from this identity name or principal.
+    /// Can be specified more than once.
+    #[arg(long, action = ArgAction::Append)]
+    revoke: Vec<String>,
+
+    /// The permission to grant, revoke or list.
+    /// Defaults to commit when granting or revoking, and to all permissions when listing.
+    #[arg(long, value_enum)]
+    permission: Option<PermissionArg>,
+
+    /// Limits the granted permission to the assets under this path, e.g. "/blog/".
+    /// When revoking, revokes only this scope.
+    #[arg(long)]
+    scope: Option<String>,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+enum PermissionArg {
+    Commit,
+    Prepare,
+    ManagePermissions,
+}
+
+impl From<PermissionArg> for Permission {
+    fn from(permission: PermissionArg) -> Self {
+        match permission {
+            PermissionArg::Commit => Permission::Commit,
+            PermissionArg::Prepare => Permission::Prepare,
+            PermissionArg::ManagePermissions => Permission::ManagePermissions,
+        }
+    }
+}
+
+pub async fn exec(env: &dyn Environment, opts: AssetPermissionsOpts) -> DfxResult {
+    let log = env.get_logger();
+    let canister_id_store = env.get_canister_id_store()?;
+    let canister_id =
+        Principal::from_text(&opts.canister).or_else(|_| canister_id_store.get(&opts.canister))?;
+
+    fetch_root_key_if_needed(env).await?;
+    let canister = ic_utils::Canister::builder()
+        .with_agent(env.get_agent())
+        .with_canister_id(canister_id)
+        .build()
+        .context("Failed to build asset canister caller.")?;
+
+    if opts.grant.is_empty() && opts.revoke.is_empty() {
+        if opts.scope.is_some() {
+            bail!("--scope can only be used with --grant or --revoke.");
+        }
+        let permissions = match opts.permission {
+            Some(permission) => vec![permission.into()],
+            None => vec![
+                Permission::Commit,
+                Permission::Prepare,
+                Permission::ManagePermissions,
+            ],
+        };
+        for permission in permissions {
+            let permitted = ic_asset::list_permissions(&canister, permission).await?;
+            println!("{permission}:");
+            for ScopedPermission { principal, scopes } in permitted {
+                if scopes.is_empty() {
+                    println!("  {principal}");
+                } else {
+                    println!("  {principal} (scopes: {})", scopes.join(", "));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let permission: Permission = opts.permission.unwrap_or(PermissionArg::Commit).into();
+    let scope = opts.scope.as_deref();
+    let scope_description = scope.map(|s| format!(" for {s}")).unwrap_or_default();
+    for principal in &opts.grant {
+        let principal = controller_to_principal(env, principal)?;
+        ic_asset::grant_permission(&canister, principal, permission, scope).await?;
+        info!(
+            log,
+            "Granted {permission} permission{scope_description} to {principal}."
+        );
+    }
+    for principal in &opts.revoke {
+        let principal = controller_to_principal(env, principal)?;
+        ic_asset::revoke_permission(&canister, principal, permission, scope).await?;
+        info!(
+            log,
+            "Revoked {permission} permission{scope_description} from {principal}."
+        );
+    }
+    Ok(())
+}
@@ -13,6 +13,7 @@ use candid_parser::utils::CandidSource;
 use clap::Parser;
 use dfx_core::canister::build_wallet_canister;
 use dfx_core::identity::CallSender;
+use ic_agent::Agent;
 use ic_utils::canister::Argument;
 use ic_utils::interfaces::management_canister::builders::{CanisterInstall, CanisterSettings};
 use ic_utils::interfaces::management_canister::MgmtMethod;
@@ -135,6 +136,89 @@ async fn request_id_via_wallet_call(
         .map_err(|err| anyhow!("Agent error {}", err))
 }
 
+/// A call of a canister method, sent by the selected identity or through a wallet.
+pub struct MethodCall<'a> {
+    pub canister_id: Principal,
+    pub effective_canister_id: Principal,
+    pub method_name: &'a str,
+    pub arg_value: Vec<u8>,
+    pub is_query: bool,
+    pub verify_query_signatures: bool,
+    /// Deducted from the wallet. Only a wallet can send cycles.
+    pub cycles: u128,
+}
+
+impl MethodCall<'_> {
+    /// Sends the call and waits for the reply.
+    pub async fn call_and_wait(
+        self,
+        agent: &Agent,
+        call_sender: &CallSender,
+    ) -> DfxResult<Vec<u8>> {
+        let blob = match call_sender {
+            CallSender::SelectedId if self.is_query => {
+                let query_builder = agent
+                    .query(&self.canister_id, self.method_name)
+                    .with_effective_canister_id(self.effective_canister_id)
+                    .with_arg(self.arg_value);
+                if self.verify_query_signatures {
+                    query_builder.call().await.context("Failed query call.")?
+                } else {
+                    query_builder
+                        .call_without_verification()
+                        .await
+                        .context("Failed query call.")?
+                }
+            }
+            CallSender::SelectedId => agent
+                .update(&self.canister_id, self.method_name)
+                .with_effective_canister_id(self.effective_canister_id)
+                .with_arg(self.arg_value)
+                .call_and_wait()
+                .await
+                .context("Failed update call.")?,
+            CallSender::Wallet(wallet_id) => {
+                let wallet = build_wallet_canister(*wallet_id, agent).await?;
+                do_wallet_call(
+                    &wallet,
+                    &CallIn {
+                        canister: self.canister_id,
+                        method_name: self.method_name.to_string(),
+                        args: self.arg_value,
+                        cycles: self.cycles,
+                    },
+                )
+                .await
+                .context(if self.is_query {
+                    "Failed wallet call."
+                } else {
+                    "Failed to do wallet call."
+                })?
+            }
+        };
+        Ok(blob)
+    }
+}
+
+/// Returns the effective canister id of a call, which for the management canister
+/// is the canister that the method manages.
+pub fn get_effective_canister_id_of_call(
+    canister_id: Principal,
+    method_name: &str,
+    arg_value: &[u8],
+) -> DfxResult<CanisterId> {
+    if canister_id != CanisterId::management_canister() {
+        return Ok(canister_id);
+    }
+    let management_method = MgmtMethod::from_str(method_name).map_err(|_| {
+        anyhow!(
+            "Attempted to call an unsupported management canister method: {}",
+            method_name
+        )
+    })?;
+    get_effective_canister_id(&management_method, arg_value)
+}
+
 // TODO: move to ic_utils? SDKTG-302
 pub fn get_effective_canister_id(
     method_name: &MgmtMethod,
@@ -360,38 +444,7 @@ To figure out the id of your wallet, run 'dfx identity get-wallet (--network ic)
         To figure out the id of your wallet, run 'dfx identity get-wallet (--network ic)'.".to_string())).context("Function caller is not a canister.");
     }
 
-    if is_query {
-        let blob = match call_sender {
-            CallSender::SelectedId => {
-                let query_builder = agent
-                    .query(&canister_id, method_name)
-                    .with_effective_canister_id(effective_canister_id)
-                    .with_arg(arg_value);
-                match disable_verify_query_signatures {
-                    true => query_builder
-                        .call_without_verification()
-                        .await
-                        .context("Failed query call.")?,
-                    false => query_builder.call().await.context("Failed query call.")?,
-                }
-            }
-            CallSender::Wallet(wallet_id) => {
-                let wallet = build_wallet_canister(*wallet_id, agent).await?;
-                do_wallet_call(
-                    &wallet,
-                    &CallIn {
-                        canister: canister_id,
-                        method_name: method_name.to_string(),
-                        args: arg_value,
-                        cycles,
-                    },
-                )
-                .await
-                .context("Failed wallet call.")?
-            }
-        };
-        print_idl_blob(&blob, output_type, &method_type)?;
-    } else if opts.r#async {
+    if opts.r#async {
         let request_id = match call_sender {
             CallSender::SelectedId => agent
                 .update(&canister_id, method_name)
@@ -413,29 +466,17 @@ To figure out the id of your wallet, run 'dfx identity get-wallet (--network ic)
         eprint!("Request ID: ");
         println!("0x{}", String::from(request_id));
     } else {
-        let blob = match call_sender {
-            CallSender::SelectedId => agent
-                .update(&canister_id, method_name)
-                .with_effective_canister_id(effective_canister_id)
-                .with_arg(arg_value)
-                .call_and_wait()
-                .await
-                .context("Failed update call.")?,
-            CallSender::Wallet(wallet_id) => {
-                let wallet = build_wallet_canister(*wallet_id, agent).await?;
-                do_wallet_call(
-                    &wallet,
-                    &CallIn {
-                        canister: canister_id,
-                        method_name: method_name.to_string(),
-                        args: arg_value,
-                        cycles,
-                    },
-                )
-                .await
-                .context("Failed to do wallet call.")?
-            }
-        };
+        let blob = MethodCall {
+            canister_id,
+            effective_canister_id,
+            method_name,
+            arg_value,
+            is_query,
+            verify_query_signatures: !disable_verify_query_signatures,
+            cycles,
+        }
+        .call_and_wait(agent, call_sender)
+        .await?;
         print_idl_blob(&blob, output_type, &method_type)?;
     }
 
@@ -7,6 +7,7 @@ use crate::lib::ic_attributes::{
 };
 use crate::lib::operations::canister::create_canister;
 use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use crate::util::clap::parsers::{
     compute_allocation_parser, freezing_threshold_parser, memory_allocation_parser,
     reserved_cycles_limit_parser, wasm_memory_limit_parser,
@@ -27,12 +28,20 @@ use slog::info;
 #[derive(Parser)]
 pub struct CanisterCreateOpts {
     /// Specifies the canister name. Either this or the --all flag are required.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister_name: Option<String>,
 
     /// Creates all canisters configured in dfx.json.
-    #[arg(long, required_unless_present("canister_name"))]
+    #[arg(
+        long,
+        required_unless_present_any(["canister_name", "group", "tag"]),
+        conflicts_with_all(["group", "tag"])
+    )]
     all: bool,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     /// Specifies the initial cycle balance to deposit into the newly created canister.
     /// The specified amount needs to take the canister create fee into account.
     /// This amount is deducted from the wallet's cycle balance.
@@ -44,7 +53,7 @@ pub struct CanisterCreateOpts {
     /// This option only works with non-mainnet replica.
     /// This option implies the --no-wallet flag.
     /// This option takes precedence over the specified_id field in dfx.json.
-    #[arg(long, value_name = "PRINCIPAL", conflicts_with = "all")]
+    #[arg(long, value_name = "PRINCIPAL", conflicts_with_all = ["all", "group", "tag"])]
     specified_id: Option<CanisterId>,
 
     /// Specifies the identity name or the principal of the new controller.
@@ -96,7 +105,7 @@ pub struct CanisterCreateOpts {
 
     /// Transaction timestamp, in nanoseconds, for use in controlling transaction deduplication, default is system time.
     /// https://internetcomputer.org/docs/current/developer-docs/integrations/icrc-1/#transaction-deduplication-
-    #[arg(long, conflicts_with = "all")]
+    #[arg(long, conflicts_with_all = ["all", "group", "tag"])]
     created_at_time: Option<u64>,
 
     /// Subaccount of the selected identity to spend cycles from.
@@ -216,85 +225,77 @@ pub async fn exec(
         )
         .await?;
         Ok(())
-    } else if opts.all {
-        // Create all canisters.
-        if let Some(canisters) = &config_interface.canisters {
-            for canister_name in canisters.keys() {
-                if pull_canisters_in_config.contains_key(canister_name) {
-                    continue;
-                }
-                let canister_is_remote =
-                    config_interface.is_remote_canister(canister_name, &network.name)?;
-                if canister_is_remote {
-                    info!(
-                        env.get_logger(),
-                        "Skipping canister '{canister_name}' because it is remote for network '{}'",
-                        &network.name,
-                    );
-
-                    continue;
-                }
-                let specified_id = config_interface.get_specified_id(canister_name)?;
-                let compute_allocation = get_compute_allocation(
-                    opts.compute_allocation,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| {
-                    format!("Failed to read compute allocation of {canister_name}.")
-                })?;
-                let memory_allocation = get_memory_allocation(
-                    opts.memory_allocation,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| format!("Failed to read memory allocation of {canister_name}."))?;
-                let freezing_threshold = get_freezing_threshold(
-                    opts.freezing_threshold,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| {
-                    format!("Failed to read freezing threshold of {canister_name}.")
-                })?;
-                let reserved_cycles_limit = get_reserved_cycles_limit(
-                    opts.reserved_cycles_limit,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| {
-                    format!("Failed to read reserved cycles limit of {canister_name}.")
-                })?;
-                let wasm_memory_limit = get_wasm_memory_limit(
-                    opts.wasm_memory_limit,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| format!("Failed to read WASM memory limit of {canister_name}."))?;
-                create_canister(
-                    env,
-                    canister_name,
-                    with_cycles,
-                    specified_id,
-                    call_sender,
-                    opts.no_wallet,
-                    opts.from_subaccount,
-                    CanisterSettings {
-                        controllers: controllers.clone(),
-                        compute_allocation,
-                        memory_allocation,
-                        freezing_threshold,
-                        reserved_cycles_limit,
-                        wasm_memory_limit,
-                    },
-                    opts.created_at_time,
-                    &mut subnet_selection,
-                )
-                .await?;
+    } else if opts.all || opts.canister_group.is_present() {
+        // Create all canisters, or those selected by --group or --tag.
+        for canister_name in &opts.canister_group.select_or_all(config_interface)? {
+            if pull_canisters_in_config.contains_key(canister_name) {
+                continue;
             }
-            if !pull_canisters_in_config.is_empty() {
-                info!(env.get_logger(), "There are pull dependencies defined in dfx.json. Please deploy them using `dfx deps deploy`.");
+            let canister_is_remote =
+                config_interface.is_remote_canister(canister_name, &network.name)?;
+            if canister_is_remote {
+                info!(
+                    env.get_logger(),
+                    "Skipping canister '{canister_name}' because it is remote for network '{}'",
+                    &network.name,
+                );
+
+                continue;
             }
+            let specified_id = config_interface.get_specified_id(canister_name)?;
+            let compute_allocation = get_compute_allocation(
+                opts.compute_allocation,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to read compute allocation of {canister_name}."))?;
+            let memory_allocation = get_memory_allocation(
+                opts.memory_allocation,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to read memory allocation of {canister_name}."))?;
+            let freezing_threshold = get_freezing_threshold(
+                opts.freezing_threshold,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to read freezing threshold of {canister_name}."))?;
+            let reserved_cycles_limit = get_reserved_cycles_limit(
+                opts.reserved_cycles_limit,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to read reserved cycles limit of {canister_name}."))?;
+            let wasm_memory_limit = get_wasm_memory_limit(
+                opts.wasm_memory_limit,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to read WASM memory limit of {canister_name}."))?;
+            create_canister(
+                env,
+                canister_name,
+                with_cycles,
+                specified_id,
+                call_sender,
+                opts.no_wallet,
+                opts.from_subaccount,
+                CanisterSettings {
+                    controllers: controllers.clone(),
+                    compute_allocation,
+                    memory_allocation,
+                    freezing_threshold,
+                    reserved_cycles_limit,
+                    wasm_memory_limit,
+                },
+                opts.created_at_time,
+                &mut subnet_selection,
+            )
+            .await?;
+        }
+        if !pull_canisters_in_config.is_empty() {
+            info!(env.get_logger(), "There are pull dependencies defined in dfx.json. Please deploy them using `dfx deps deploy`.");
         }
         Ok(())
     } else {
@@ -0,0 +1,75 @@
+use crate::lib::canister_info::assets::AssetsCanisterInfo;
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use anyhow::{bail, Context};
+use clap::Parser;
+use slog::info;
+
+/// Checks that an asset canister can be served from the custom domains listed in dfx.json.
+/// The canister has to serve a certified /.well-known/ic-domains that lists each domain.
+#[derive(Parser)]
+pub struct CustomDomainsOpts {
+    /// Specifies the name of the asset canister.
+    canister: String,
+
+    /// Prints the DNS records that have to be added for each domain, without contacting the network.
+    #[arg(long)]
+    dns_records: bool,
+}
+
+pub async fn exec(env: &dyn Environment, opts: CustomDomainsOpts) -> DfxResult {
+    let log = env.get_logger();
+    let config = env.get_config_or_anyhow()?;
+    let canister_id = env.get_canister_id_store()?.get(&opts.canister)?;
+    let canister_info = CanisterInfo::load(&config, &opts.canister, Some(canister_id))?;
+    if !canister_info.is_assets() {
+        bail!("Canister {} is not an asset canister.", opts.canister);
+    }
+    let assets_canister_info = canister_info.as_info::<AssetsCanisterInfo>()?;
+    let domains = assets_canister_info.get_custom_domains();
+    if domains.is_empty() {
+        bail!(
+            "Canister {} has no custom_domains in dfx.json.",
+            opts.canister
+        );
+    }
+
+    if opts.dns_records {
+        for domain in domains {
+            println!("{domain}:");
+            for record in ic_asset::dns_records(canister_id, domain) {
+                println!(
+                    "  {:<6}{}  {}",
+                    record.record_type, record.name, record.value
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    fetch_root_key_if_needed(env).await?;
+    let agent = env.get_agent();
+    let canister = ic_utils::Canister::builder()
+        .with_agent(agent)
+        .with_canister_id(canister_id)
+        .build()
+        .context("Failed to build asset canister caller.")?;
+    ic_asset::check_custom_domains(&canister, agent, domains)
+        .await
+        .with_context(|| {
+            format!(
+                "Canister {} is not ready for its custom domains.",
+                opts.canister
+            )
+        })?;
+    info!(
+        log,
+        "{} serves a certified {} that lists {}.",
+        opts.canister,
+        ic_asset::IC_DOMAINS_KEY,
+        domains.join(", ")
+    );
+    Ok(())
+}
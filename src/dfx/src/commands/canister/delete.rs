@@ -12,6 +12,7 @@ use crate::lib::operations::cycles_ledger::{
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::assets::wallet_wasm;
 use crate::util::blob_from_arguments;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use crate::util::clap::parsers::icrc_subaccount_parser;
 use anyhow::{bail, Context};
 use candid::Principal;
@@ -42,12 +43,20 @@ const WITHDRAWAL_COST: u128 = 10_606_030_000; // 5% higher than a value observed
 pub struct CanisterDeleteOpts {
     /// Specifies the name of the canister to delete.
     /// You must specify either a canister name/id or the --all flag.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister: Option<String>,
 
     /// Deletes all of the canisters configured in the dfx.json file.
-    #[arg(long, required_unless_present("canister"))]
+    #[arg(
+        long,
+        required_unless_present_any(["canister", "group", "tag"]),
+        conflicts_with_all(["group", "tag"])
+    )]
     all: bool,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     /// Do not withdrawal cycles, just delete the canister.
     #[arg(long)]
     no_withdrawal: bool,
@@ -338,22 +347,20 @@ pub async fn exec(
             opts.to_subaccount,
         )
         .await
-    } else if opts.all {
-        if let Some(canisters) = &config.get_config().canisters {
-            for canister in canisters.keys() {
-                delete_canister(
-                    env,
-                    canister,
-                    call_sender,
-                    opts.no_withdrawal,
-                    opts.yes,
-                    opts.withdraw_cycles_to_canister.clone(),
-                    opts.withdraw_cycles_to_dank,
-                    opts.withdraw_cycles_to_dank_principal.clone(),
-                    opts.to_subaccount,
-                )
-                .await?;
-            }
+    } else if opts.all || opts.canister_group.is_present() {
+        for canister in &opts.canister_group.select_or_all(config.get_config())? {
+            delete_canister(
+                env,
+                canister,
+                call_sender,
+                opts.no_withdrawal,
+                opts.yes,
+                opts.withdraw_cycles_to_canister.clone(),
+                opts.withdraw_cycles_to_dank,
+                opts.withdraw_cycles_to_dank_principal.clone(),
+                opts.to_subaccount,
+            )
+            .await?;
         }
         Ok(())
     } else {
@@ -6,6 +6,7 @@ use crate::lib::operations::canister;
 use crate::lib::operations::cycles_ledger::cycles_ledger_enabled;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::{environment::Environment, operations::cycles_ledger};
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use crate::util::clap::parsers::{cycle_amount_parser, icrc_subaccount_parser};
 use anyhow::{bail, Context};
 use candid::Principal;
@@ -24,12 +25,20 @@ pub struct DepositCyclesOpts {
 
     /// Specifies the name or id of the canister to receive the cycles deposit.
     /// You must specify either a canister name/id or the --all option.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister: Option<String>,
 
     /// Deposit cycles to all of the canisters configured in the dfx.json file.
-    #[arg(long, required_unless_present("canister"))]
+    #[arg(
+        long,
+        required_unless_present_any(["canister", "group", "tag"]),
+        conflicts_with_all(["group", "tag"])
+    )]
     all: bool,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     /// Use cycles from this subaccount.
     #[arg(long, value_parser = icrc_subaccount_parser)]
     from_subaccount: Option<Subaccount>,
@@ -147,22 +156,20 @@ pub async fn exec(
             opts.from_subaccount,
         )
         .await
-    } else if opts.all {
+    } else if opts.all || opts.canister_group.is_present() {
         let config = env.get_config_or_anyhow()?;
 
-        if let Some(canisters) = &config.get_config().canisters {
-            for canister in canisters.keys() {
-                deposit_cycles(
-                    env,
-                    canister,
-                    call_sender,
-                    cycles,
-                    created_at_time,
-                    opts.from_subaccount,
-                )
-                .await
-                .with_context(|| format!("Failed to deposit cycles into {}.", canister))?;
-            }
+        for canister in &opts.canister_group.select_or_all(config.get_config())? {
+            deposit_cycles(
+                env,
+                canister,
+                call_sender,
+                cycles,
+                created_at_time,
+                opts.from_subaccount,
+            )
+            .await
+            .with_context(|| format!("Failed to deposit cycles into {}.", canister))?;
         }
         Ok(())
     } else {
@@ -6,6 +6,7 @@ use crate::lib::operations::canister::install_canister::install_canister;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::blob_from_arguments;
 use crate::util::clap::argument_from_cli::ArgumentFromCliLongOpt;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use dfx_core::canister::{install_canister_wasm, install_mode_to_prompt};
 use dfx_core::identity::CallSender;
 
@@ -21,17 +22,22 @@ use std::str::FromStr;
 #[derive(Parser, Clone)]
 pub struct CanisterInstallOpts {
     /// Specifies the canister to deploy. You must specify either canister name/id or the --all option.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister: Option<String>,
 
     /// Deploys all canisters configured in the project dfx.json files.
     #[arg(
         long,
-        required_unless_present("canister"),
+        required_unless_present_any(["canister", "group", "tag"]),
+        conflicts_with_all(["group", "tag"]),
         conflicts_with("argument"),
         conflicts_with("argument_file")
     )]
     all: bool,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     /// Specifies not to wait for the result of the call to be returned by polling the replica. Instead return a response ID.
     #[arg(long)]
     async_call: bool,
@@ -50,7 +56,7 @@ pub struct CanisterInstallOpts {
     argument_from_cli: ArgumentFromCliLongOpt,
 
     /// Specifies a particular WASM file to install, bypassing the dfx.json project settings.
-    #[arg(long, conflicts_with("all"))]
+    #[arg(long, conflicts_with_all(["all", "group", "tag"]))]
     wasm: Option<PathBuf>,
 
     /// Output environment variables to a file in dotenv format (without overwriting any user-defined variables, if the file already exists).
@@ -93,6 +99,11 @@ pub async fn exec(
     if mode == Some(InstallMode::Reinstall) && (opts.canister.is_none() || opts.all) {
         bail!("The --mode=reinstall is only valid when specifying a single canister, because reinstallation destroys all data in the canister.");
     }
+    if opts.canister_group.is_present()
+        && opts.argument_from_cli.get_argument_and_type()?.0.is_some()
+    {
+        bail!("The --argument and --argument-file options are only valid when specifying a single canister.");
+    }
 
     if let Some(canister) = opts.canister.as_deref() {
         let (argument_from_cli, argument_type) = opts.argument_from_cli.get_argument_and_type()?;
@@ -168,6 +179,7 @@ pub async fn exec(
                     None,
                     opts.no_asset_upgrade,
                     opts.always_assist,
+                    false,
                 )
                 .await
                 .map_err(Into::into)
@@ -188,53 +200,53 @@ pub async fn exec(
                     env_file.as_deref(),
                     opts.no_asset_upgrade,
                     opts.always_assist,
+                    false,
                 )
                 .await
                 .map_err(Into::into)
             }
         }
-    } else if opts.all {
-        // Install all canisters.
+    } else if opts.all || opts.canister_group.is_present() {
+        // Install all canisters, or those selected by --group or --tag.
         let config = env.get_config_or_anyhow()?;
         let config_interface = config.get_config();
         let env_file = config.get_output_env_file(opts.output_env_file)?;
         let pull_canisters_in_config = get_pull_canisters_in_config(env)?;
-        if let Some(canisters) = &config.get_config().canisters {
-            for canister in canisters.keys() {
-                if pull_canisters_in_config.contains_key(canister) {
-                    continue;
-                }
-                if config_interface.is_remote_canister(canister, &network.name)? {
-                    info!(
-                        env.get_logger(),
-                        "Skipping canister '{}' because it is remote for network '{}'",
-                        canister,
-                        &network.name,
-                    );
-                    continue;
-                }
-
-                let canister_id = canister_id_store.get(canister)?;
-                let canister_info = CanisterInfo::load(&config, canister, Some(canister_id))?;
-                install_canister(
-                    env,
-                    &mut canister_id_store,
-                    canister_id,
-                    &canister_info,
-                    None,
-                    None,
-                    None,
-                    mode,
-                    call_sender,
-                    opts.upgrade_unchanged,
-                    None,
-                    opts.yes,
-                    env_file.as_deref(),
-                    opts.no_asset_upgrade,
-                    opts.always_assist,
-                )
-                .await?;
+        for canister in &opts.canister_group.select_or_all(config.get_config())? {
+            if pull_canisters_in_config.contains_key(canister) {
+                continue;
             }
+            if config_interface.is_remote_canister(canister, &network.name)? {
+                info!(
+                    env.get_logger(),
+                    "Skipping canister '{}' because it is remote for network '{}'",
+                    canister,
+                    &network.name,
+                );
+                continue;
+            }
+
+            let canister_id = canister_id_store.get(canister)?;
+            let canister_info = CanisterInfo::load(&config, canister, Some(canister_id))?;
+            install_canister(
+                env,
+                &mut canister_id_store,
+                canister_id,
+                &canister_info,
+                None,
+                None,
+                None,
+                mode,
+                call_sender,
+                opts.upgrade_unchanged,
+                None,
+                opts.yes,
+                env_file.as_deref(),
+                opts.no_asset_upgrade,
+                opts.always_assist,
+                false,
+            )
+            .await?;
         }
         if !pull_canisters_in_config.is_empty() {
             info!(env.get_logger(), "There are pull dependencies defined in dfx.json. Please deploy them using `dfx deps deploy`.");
@@ -7,8 +7,11 @@ use clap::{Parser, Subcommand};
 use dfx_core::identity::CallSender;
 use tokio::runtime::Runtime;
 
+mod add;
+mod asset_permissions;
 mod call;
 mod create;
+mod custom_domains;
 mod delete;
 mod deposit_cycles;
 mod id;
@@ -16,7 +19,9 @@ mod info;
 mod install;
 mod logs;
 mod metadata;
+mod repl;
 mod request_status;
+mod run_script;
 mod send;
 mod sign;
 mod start;
@@ -43,15 +48,20 @@ pub struct CanisterOpts {
 
 #[derive(Subcommand)]
 pub enum SubCommand {
+    Add(add::CanisterAddOpts),
+    AssetPermissions(asset_permissions::AssetPermissionsOpts),
     Call(call::CanisterCallOpts),
     Create(create::CanisterCreateOpts),
+    CustomDomains(custom_domains::CustomDomainsOpts),
     Delete(delete::CanisterDeleteOpts),
     DepositCycles(deposit_cycles::DepositCyclesOpts),
     Id(id::CanisterIdOpts),
     Info(info::InfoOpts),
     Install(install::CanisterInstallOpts),
     Metadata(metadata::CanisterMetadataOpts),
+    Repl(repl::CanisterReplOpts),
     RequestStatus(request_status::RequestStatusOpts),
+    RunScript(run_script::CanisterRunScriptOpts),
     Send(send::CanisterSendOpts),
     Sign(sign::CanisterSignOpts),
     Start(start::CanisterStartOpts),
@@ -64,7 +74,7 @@ pub enum SubCommand {
 
 pub fn exec(env: &dyn Environment, opts: CanisterOpts) -> DfxResult {
     let agent_env;
-    let env = if matches!(&opts.subcmd, SubCommand::Id(_)) {
+    let env = if matches!(&opts.subcmd, SubCommand::Add(_) | SubCommand::Id(_)) {
         env
     } else {
         agent_env = create_agent_environment(env, opts.network.to_network_name())?;
@@ -76,15 +86,20 @@ pub fn exec(env: &dyn Environment, opts: CanisterOpts) -> DfxResult {
         let call_sender = CallSender::from(&opts.wallet)
             .map_err(|e| anyhow!("Failed to determine call sender: {}", e))?;
         match opts.subcmd {
+            SubCommand::Add(v) => add::exec(env, v),
+            SubCommand::AssetPermissions(v) => asset_permissions::exec(env, v).await,
             SubCommand::Call(v) => call::exec(env, v, &call_sender).await,
             SubCommand::Create(v) => create::exec(env, v, &call_sender).await,
+            SubCommand::CustomDomains(v) => custom_domains::exec(env, v).await,
             SubCommand::Delete(v) => delete::exec(env, v, &call_sender).await,
             SubCommand::DepositCycles(v) => deposit_cycles::exec(env, v, &call_sender).await,
             SubCommand::Id(v) => id::exec(env, v).await,
             SubCommand::Install(v) => install::exec(env, v, &call_sender).await,
             SubCommand::Info(v) => info::exec(env, v).await,
             SubCommand::Metadata(v) => metadata::exec(env, v).await,
+            SubCommand::Repl(v) => repl::exec(env, v, &call_sender).await,
             SubCommand::RequestStatus(v) => request_status::exec(env, v).await,
+            SubCommand::RunScript(v) => run_script::exec(env, v, &call_sender).await,
             SubCommand::Send(v) => send::exec(env, v, &call_sender).await,
             SubCommand::Sign(v) => sign::exec(env, v, &call_sender).await,
             SubCommand::Start(v) => start::exec(env, v, &call_sender).await,
@@ -0,0 +1,792 @@
+use crate::commands::canister::call::{get_effective_canister_id_of_call, MethodCall};
+use crate::lib::environment::{AgentEnvironment, Environment};
+use crate::lib::error::DfxResult;
+use crate::lib::operations::canister::get_canister_id_and_candid_path;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::{blob_from_arguments, decode_idl_blob, fetch_remote_did_file};
+use anyhow::{anyhow, bail, Context};
+use candid::types::{Function, Type, TypeEnv, TypeInner};
+use candid::{IDLArgs, Principal};
+use candid_parser::utils::CandidSource;
+use clap::Parser;
+use dfx_core::identity::CallSender;
+use dfx_core::util::expiry_duration;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{BasicHistory, Completion, Input};
+use slog::{info, warn};
+use std::collections::BTreeMap;
+use std::io::{stdin, BufRead, IsTerminal};
+use std::path::PathBuf;
+
+const DOT_COMMANDS: [&str; 7] = [
+    ".exit",
+    ".help",
+    ".identity",
+    ".methods",
+    ".use",
+    ".vars",
+    ".wallet",
+];
+
+const HELP: &str = r#"<method> [<argument>]            Calls a method of the selected canister, e.g. greet ("world")
+<canister>.<method> [<argument>] Calls a method of another canister
+query|update <method> ...        Calls the method as a query or an update, instead of as its interface says
+let <name> = <method> ...        Stores the result in a variable
+$<name>, $<name>.<index>         The result stored in a variable, or one of its values. $_ is the last result.
+.use <canister>                  Selects the canister to call
+.methods                         Lists the methods of the selected canister
+.identity [<name>]               Shows or switches the identity that sends calls
+.wallet [<id>|none]              Shows or switches the wallet that calls are proxied through
+.vars                            Lists the variables
+.exit                            Ends the session"#;
+
+/// Starts an interactive session for calling the methods of canisters.
+/// Methods and their arguments are completed with Tab, from the Candid interface of the canister.
+#[derive(Parser)]
+pub struct CanisterReplOpts {
+    /// Specifies the names or ids of the canisters to call. The first one is selected.
+    canisters: Vec<String>,
+
+    /// Runs the commands in a .repl file, one per line, instead of reading them from the terminal.
+    /// The session ends at the first command that fails.
+    #[arg(long)]
+    script: Option<PathBuf>,
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: CanisterReplOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    fetch_root_key_if_needed(env).await?;
+    let mut session = Session {
+        env,
+        identity_env: None,
+        call_sender: *call_sender,
+        canisters: vec![],
+        current: None,
+        variables: BTreeMap::new(),
+    };
+    for canister in opts.canisters.iter().rev() {
+        session.use_canister(canister).await?;
+    }
+
+    if let Some(script) = &opts.script {
+        let content = dfx_core::fs::read_to_string(script)?;
+        for (number, line) in content.lines().enumerate() {
+            let proceed = session
+                .run_line(line)
+                .await
+                .with_context(|| format!("{}:{}: {}", script.display(), number + 1, line.trim()))?;
+            if !proceed {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
+    if !stdin().is_terminal() {
+        // Commands piped into the session run like a script.
+        for (number, line) in stdin().lock().lines().enumerate() {
+            let line = line.context("Failed to read from stdin.")?;
+            let proceed = session
+                .run_line(&line)
+                .await
+                .with_context(|| format!("line {}: {}", number + 1, line.trim()))?;
+            if !proceed {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
+    eprintln!("Type .help for the commands, and press Tab to complete methods and arguments.");
+    let theme = ColorfulTheme::default();
+    let mut history = BasicHistory::new().max_entries(1000).no_duplicates(true);
+    loop {
+        let completion = session.completion();
+        let line: String = Input::with_theme(&theme)
+            .with_prompt(session.prompt())
+            .allow_empty(true)
+            .completion_with(&completion)
+            .history_with(&mut history)
+            .interact_text()?;
+        match session.run_line(&line).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => eprintln!("Error: {err:#}"),
+        }
+    }
+    Ok(())
+}
+
+struct Session<'a> {
+    env: &'a dyn Environment,
+    /// The environment of the identity selected with .identity, if any.
+    identity_env: Option<AgentEnvironment<'a>>,
+    call_sender: CallSender,
+    canisters: Vec<ReplCanister>,
+    current: Option<usize>,
+    variables: BTreeMap<String, IDLArgs>,
+}
+
+struct ReplCanister {
+    name: String,
+    canister_id: Principal,
+    interface: Option<Interface>,
+}
+
+/// The methods of a canister, from its Candid interface.
+struct Interface {
+    env: TypeEnv,
+    methods: Vec<(String, Function)>,
+}
+
+impl Interface {
+    fn load(candid: CandidSource) -> Option<Self> {
+        let (env, actor) = candid.load().ok()?;
+        let methods = env
+            .as_service(&actor?)
+            .ok()?
+            .iter()
+            .map(|(name, ty)| Some((name.clone(), env.as_func(ty).ok()?.clone())))
+            .collect::<Option<_>>()?;
+        Some(Self { env, methods })
+    }
+
+    fn method(&self, name: &str) -> Option<&Function> {
+        self.methods
+            .iter()
+            .find(|(method, _)| method == name)
+            .map(|(_, func)| func)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum CallMode {
+    Query,
+    Update,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ReplCommand {
+    Call {
+        variable: Option<String>,
+        canister: Option<String>,
+        method: String,
+        mode: Option<CallMode>,
+        argument: Option<String>,
+    },
+    Use(String),
+    Methods,
+    Identity(Option<String>),
+    Wallet(Option<String>),
+    Vars,
+    Help,
+    Exit,
+    Empty,
+}
+
+impl<'a> Session<'a> {
+    fn env(&self) -> &dyn Environment {
+        match &self.identity_env {
+            Some(identity_env) => identity_env,
+            None => self.env,
+        }
+    }
+
+    fn prompt(&self) -> String {
+        match self.current {
+            Some(index) => self.canisters[index].name.clone(),
+            None => "dfx".to_string(),
+        }
+    }
+
+    /// Runs one line of the session. Returns false when the session ends.
+    async fn run_line(&mut self, line: &str) -> DfxResult<bool> {
+        match parse_line(line).map_err(|err| anyhow!(err))? {
+            ReplCommand::Call {
+                variable,
+                canister,
+                method,
+                mode,
+                argument,
+            } => {
+                let result = self
+                    .call(canister.as_deref(), &method, mode, argument.as_deref())
+                    .await?;
+                println!("{result}");
+                if let Some(variable) = variable {
+                    self.variables.insert(variable, result.clone());
+                }
+                self.variables.insert("_".to_string(), result);
+            }
+            ReplCommand::Use(canister) => self.use_canister(&canister).await?,
+            ReplCommand::Methods => {
+                let index = self.current_canister()?;
+                let canister = &self.canisters[index];
+                let Some(interface) = &canister.interface else {
+                    bail!("The Candid interface of {} is not known.", canister.name);
+                };
+                for (name, func) in &interface.methods {
+                    println!("{name} : {func}");
+                }
+            }
+            ReplCommand::Identity(None) => {
+                let env = self.env();
+                println!(
+                    "{} ({})",
+                    env.get_selected_identity()
+                        .map_or("anonymous", |name| name.as_str()),
+                    env.get_selected_identity_principal()
+                        .unwrap_or_else(Principal::anonymous)
+                );
+            }
+            ReplCommand::Identity(Some(name)) => {
+                let identity_env = AgentEnvironment::new(
+                    self.env,
+                    self.env.get_network_descriptor().clone(),
+                    expiry_duration(),
+                    Some(name.as_str()),
+                )?;
+                fetch_root_key_if_needed(&identity_env).await?;
+                self.identity_env = Some(identity_env);
+                info!(
+                    self.env.get_logger(),
+                    "Calls are sent by identity {}.", name
+                );
+            }
+            ReplCommand::Wallet(None) => match self.call_sender {
+                CallSender::SelectedId => println!("none"),
+                CallSender::Wallet(wallet) => println!("{wallet}"),
+            },
+            ReplCommand::Wallet(Some(wallet)) => {
+                self.call_sender = if wallet == "none" {
+                    CallSender::SelectedId
+                } else {
+                    CallSender::from(&Some(wallet))
+                        .map_err(|e| anyhow!("Failed to determine call sender: {}", e))?
+                };
+            }
+            ReplCommand::Vars => {
+                for (name, value) in &self.variables {
+                    println!("${name} = {value}");
+                }
+            }
+            ReplCommand::Help => println!("{HELP}"),
+            ReplCommand::Exit => return Ok(false),
+            ReplCommand::Empty => {}
+        }
+        Ok(true)
+    }
+
+    fn current_canister(&self) -> DfxResult<usize> {
+        self.current
+            .context("No canister is selected. Select one with .use <canister>.")
+    }
+
+    /// Selects a canister, loading its interface the first time it is used.
+    async fn use_canister(&mut self, canister: &str) -> DfxResult {
+        let index = self.canister_index(canister).await?;
+        self.current = Some(index);
+        Ok(())
+    }
+
+    async fn canister_index(&mut self, canister: &str) -> DfxResult<usize> {
+        if let Some(index) = self.canisters.iter().position(|c| c.name == canister) {
+            return Ok(index);
+        }
+        let env = self.env();
+        let (canister_id, local_candid_path) = get_canister_id_and_candid_path(env, canister)?;
+        let interface = match fetch_remote_did_file(env.get_agent(), canister_id).await {
+            Some(did) => Interface::load(CandidSource::Text(&did)),
+            None => local_candid_path
+                .filter(|path| path.exists())
+                .and_then(|path| Interface::load(CandidSource::File(&path))),
+        };
+        if interface.is_none() {
+            warn!(
+                env.get_logger(),
+                "Cannot fetch the Candid interface of {}, sending arguments with inferred types.",
+                canister
+            );
+        }
+        self.canisters.push(ReplCanister {
+            name: canister.to_string(),
+            canister_id,
+            interface,
+        });
+        Ok(self.canisters.len() - 1)
+    }
+
+    async fn call(
+        &mut self,
+        canister: Option<&str>,
+        method_name: &str,
+        mode: Option<CallMode>,
+        argument: Option<&str>,
+    ) -> DfxResult<IDLArgs> {
+        let index = match canister {
+            Some(canister) => self.canister_index(canister).await?,
+            None => self.current_canister()?,
+        };
+        let canister = &self.canisters[index];
+        let method_type = match &canister.interface {
+            Some(interface) => {
+                let func = interface.method(method_name).with_context(|| {
+                    format!("Canister {} has no method {}.", canister.name, method_name)
+                })?;
+                Some((interface.env.clone(), func.clone()))
+            }
+            None => None,
+        };
+        let argument = argument
+            .map(|argument| substitute_variables(argument, &self.variables))
+            .transpose()
+            .map_err(|err| anyhow!(err))?;
+
+        let env = self.env();
+        let arg_value = blob_from_arguments(
+            Some(env),
+            argument.as_deref(),
+            None,
+            None,
+            &method_type,
+            false,
+            false,
+        )?;
+        let is_query_method = method_type.as_ref().map(|(_, func)| func.is_query());
+        let is_query = match (mode, is_query_method) {
+            (Some(CallMode::Query), Some(false)) => {
+                bail!("{method_name} is an update method, not a query method.")
+            }
+            (Some(mode), _) => mode == CallMode::Query,
+            (None, is_query_method) => is_query_method.unwrap_or(false),
+        };
+        let effective_canister_id =
+            get_effective_canister_id_of_call(canister.canister_id, method_name, &arg_value)?;
+
+        let blob = MethodCall {
+            canister_id: canister.canister_id,
+            effective_canister_id,
+            method_name,
+            arg_value,
+            is_query,
+            verify_query_signatures: true,
+            cycles: 0,
+        }
+        .call_and_wait(env.get_agent(), &self.call_sender)
+        .await?;
+        decode_idl_blob(&blob, &method_type)
+    }
+
+    fn completion(&self) -> ReplCompletion {
+        let mut targets = vec![];
+        for (index, canister) in self.canisters.iter().enumerate() {
+            let Some(interface) = &canister.interface else {
+                continue;
+            };
+            for (method, func) in &interface.methods {
+                let target = if Some(index) == self.current {
+                    method.clone()
+                } else {
+                    format!("{}.{}", canister.name, method)
+                };
+                targets.push((target, argument_skeleton(&interface.env, &func.args)));
+            }
+        }
+        ReplCompletion {
+            targets,
+            variables: self.variables.keys().cloned().collect(),
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Result<ReplCommand, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(ReplCommand::Empty);
+    }
+    if line.starts_with('.') {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap();
+        let argument = words.next().map(str::to_string);
+        if words.next().is_some() {
+            return Err(format!("Too many arguments for {command}."));
+        }
+        return match (command, argument) {
+            (".use", Some(canister)) => Ok(ReplCommand::Use(canister)),
+            (".use", None) => Err(".use needs the name or id of a canister.".to_string()),
+            (".identity", identity) => Ok(ReplCommand::Identity(identity)),
+            (".wallet", wallet) => Ok(ReplCommand::Wallet(wallet)),
+            (".methods", None) => Ok(ReplCommand::Methods),
+            (".vars", None) => Ok(ReplCommand::Vars),
+            (".help", None) => Ok(ReplCommand::Help),
+            (".exit" | ".quit", None) => Ok(ReplCommand::Exit),
+            _ => Err(format!(
+                "Unknown command {line}. Type .help for the commands."
+            )),
+        };
+    }
+
+    let (variable, call) = match line.strip_prefix("let ") {
+        Some(rest) => {
+            let (variable, call) = rest
+                .split_once('=')
+                .ok_or_else(|| "Expected let <name> = <method> ...".to_string())?;
+            let variable = variable.trim();
+            if !is_identifier(variable) {
+                return Err(format!("{variable:?} is not a valid variable name."));
+            }
+            (Some(variable.to_string()), call.trim())
+        }
+        None => (None, line),
+    };
+    let (mode, call) = if let Some(rest) = call.strip_prefix("query ") {
+        (Some(CallMode::Query), rest.trim_start())
+    } else if let Some(rest) = call.strip_prefix("update ") {
+        (Some(CallMode::Update), rest.trim_start())
+    } else {
+        (None, call)
+    };
+    let target_end = call
+        .find(|c: char| c.is_whitespace() || c == '(')
+        .unwrap_or(call.len());
+    let (target, argument) = call.split_at(target_end);
+    if target.is_empty() {
+        return Err("Expected the name of a method.".to_string());
+    }
+    let (canister, method) = match target.rsplit_once('.') {
+        Some((canister, method)) => (Some(canister.to_string()), method.to_string()),
+        None => (None, target.to_string()),
+    };
+    let argument = argument.trim();
+    Ok(ReplCommand::Call {
+        variable,
+        canister,
+        method,
+        mode,
+        argument: (!argument.is_empty()).then(|| argument.to_string()),
+    })
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replaces `$name` with the value stored in a variable, and `$name.index` with one of its values.
+/// `$$` is a `$`, and text literals are left as they are.
+pub fn substitute_variables(
+    argument: &str,
+    variables: &BTreeMap<String, IDLArgs>,
+) -> Result<String, String> {
+    let mut substituted = String::new();
+    let mut rest = argument;
+    while let Some(special) = rest.find(['$', '"']) {
+        substituted.push_str(&rest[..special]);
+        rest = &rest[special..];
+        if rest.starts_with('"') {
+            let literal_len = text_literal_len(rest);
+            substituted.push_str(&rest[..literal_len]);
+            rest = &rest[literal_len..];
+            continue;
+        }
+        rest = &rest[1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            substituted.push('$');
+            rest = after;
+            continue;
+        }
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..name_len];
+        rest = &rest[name_len..];
+        let value = variables
+            .get(name)
+            .ok_or_else(|| format!("There is no variable ${name}."))?;
+        let index_len = rest
+            .strip_prefix('.')
+            .map(|index| {
+                index
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(index.len())
+            })
+            .unwrap_or(0);
+        let value = if index_len > 0 {
+            let index = &rest[1..=index_len];
+            let index: usize = index
+                .parse()
+                .map_err(|_| format!("${name} has no value {index}."))?;
+            rest = &rest[index_len + 1..];
+            value
+                .args
+                .get(index)
+                .ok_or_else(|| format!("${name} has no value {index}."))?
+        } else {
+            match value.args.as_slice() {
+                [value] => value,
+                values => {
+                    return Err(format!(
+                        "${name} has {} values. Use ${name}.0 for the first one.",
+                        values.len()
+                    ))
+                }
+            }
+        };
+        substituted.push_str(&value.to_string());
+    }
+    substituted.push_str(rest);
+    Ok(substituted)
+}
+
+/// The length of the text literal at the start of `s`, including its quotes,
+/// or the length of `s` if the literal is not closed.
+fn text_literal_len(s: &str) -> usize {
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return i + 1,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// An argument with a placeholder value of each type, to be edited after completing a method.
+fn argument_skeleton(env: &TypeEnv, args: &[Type]) -> String {
+    let values: Vec<String> = args.iter().map(|ty| value_skeleton(env, ty, 0)).collect();
+    format!("({})", values.join(", "))
+}
+
+fn value_skeleton(env: &TypeEnv, ty: &Type, depth: usize) -> String {
+    // Recursive types are cut short.
+    if depth > 4 {
+        return "null".to_string();
+    }
+    match ty.as_ref() {
+        TypeInner::Var(id) => env
+            .find_type(id)
+            .map(|ty| value_skeleton(env, ty, depth + 1))
+            .unwrap_or_else(|_| "null".to_string()),
+        TypeInner::Bool => "false".to_string(),
+        TypeInner::Nat
+        | TypeInner::Int
+        | TypeInner::Nat8
+        | TypeInner::Nat16
+        | TypeInner::Nat32
+        | TypeInner::Nat64
+        | TypeInner::Int8
+        | TypeInner::Int16
+        | TypeInner::Int32
+        | TypeInner::Int64 => "0".to_string(),
+        TypeInner::Float32 | TypeInner::Float64 => "0.0".to_string(),
+        TypeInner::Text => "\"\"".to_string(),
+        TypeInner::Principal => "principal \"aaaaa-aa\"".to_string(),
+        TypeInner::Vec(ty) if ty.as_ref() == &TypeInner::Nat8 => "blob \"\"".to_string(),
+        TypeInner::Vec(_) => "vec {}".to_string(),
+        TypeInner::Record(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{} = {};",
+                        field.id,
+                        value_skeleton(env, &field.ty, depth + 1)
+                    )
+                })
+                .collect();
+            format!("record {{ {} }}", fields.join(" "))
+        }
+        TypeInner::Variant(fields) => match fields.first() {
+            Some(field) if field.ty.as_ref() == &TypeInner::Null => {
+                format!("variant {{ {} }}", field.id)
+            }
+            Some(field) => format!(
+                "variant {{ {} = {} }}",
+                field.id,
+                value_skeleton(env, &field.ty, depth + 1)
+            ),
+            None => "null".to_string(),
+        },
+        _ => "null".to_string(),
+    }
+}
+
+/// Completes dot commands, methods with a skeleton of their argument, and variables.
+struct ReplCompletion {
+    /// The methods that can be called, with the skeleton of their argument.
+    targets: Vec<(String, String)>,
+    variables: Vec<String>,
+}
+
+impl Completion for ReplCompletion {
+    fn get(&self, input: &str) -> Option<String> {
+        if let Some(dollar) = input.rfind('$') {
+            let partial = &input[dollar + 1..];
+            if partial
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                let completed = complete(partial, self.variables.iter().map(String::as_str))?;
+                return Some(format!("{}{}", &input[..=dollar], completed));
+            }
+        }
+        if input.starts_with('.') && !input.contains(' ') {
+            return complete(input, DOT_COMMANDS.into_iter());
+        }
+
+        // Complete the method of a call, after `let <name> =` and `query`/`update`.
+        let mut prefix_len = 0;
+        if let Some(rest) = input.strip_prefix("let ") {
+            let (_, call) = rest.split_once('=')?;
+            prefix_len = input.len() - call.trim_start().len();
+        }
+        for keyword in ["query ", "update "] {
+            if input[prefix_len..].starts_with(keyword) {
+                prefix_len = input.len() - input[prefix_len + keyword.len()..].trim_start().len();
+            }
+        }
+        let (prefix, call) = input.split_at(prefix_len);
+        match call.split_once(' ') {
+            None => {
+                let completed = complete(call, self.targets.iter().map(|(t, _)| t.as_str()))?;
+                match self.targets.iter().find(|(target, _)| *target == completed) {
+                    Some((_, skeleton)) if completed.len() > call.len() => {
+                        Some(format!("{prefix}{completed} {skeleton}"))
+                    }
+                    _ => Some(format!("{prefix}{completed}")),
+                }
+            }
+            Some((target, "")) => {
+                let (_, skeleton) = self.targets.iter().find(|(t, _)| t == target)?;
+                Some(format!("{prefix}{target} {skeleton}"))
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+/// The candidate that starts with `partial`, or the longest common prefix if there are several.
+fn complete<'a>(partial: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let mut matching = candidates.filter(|candidate| candidate.starts_with(partial));
+    let mut completed = matching.next()?.to_string();
+    for candidate in matching {
+        let common = completed
+            .chars()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        completed = completed.chars().take(common).collect();
+    }
+    Some(completed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lines() {
+        assert_eq!(
+            parse_line(r#"let greeting = query greet ("world")"#),
+            Ok(ReplCommand::Call {
+                variable: Some("greeting".to_string()),
+                canister: None,
+                method: "greet".to_string(),
+                mode: Some(CallMode::Query),
+                argument: Some(r#"("world")"#.to_string()),
+            })
+        );
+        assert_eq!(
+            parse_line("ledger.account_balance($account)"),
+            Ok(ReplCommand::Call {
+                variable: None,
+                canister: Some("ledger".to_string()),
+                method: "account_balance".to_string(),
+                mode: None,
+                argument: Some("($account)".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_line(".identity alice"),
+            Ok(ReplCommand::Identity(Some("alice".to_string())))
+        );
+        assert_eq!(parse_line("  # comment"), Ok(ReplCommand::Empty));
+        assert!(parse_line(".nope").is_err());
+        assert!(parse_line("let 1x = read").is_err());
+    }
+
+    #[test]
+    fn substitutes_variables() {
+        let variables = BTreeMap::from([
+            (
+                "count".to_string(),
+                candid_parser::parse_idl_args("(5 : nat)").unwrap(),
+            ),
+            (
+                "pair".to_string(),
+                candid_parser::parse_idl_args(r#"("a", "b")"#).unwrap(),
+            ),
+        ]);
+        assert_eq!(
+            substitute_variables("($count, $pair.1)", &variables).unwrap(),
+            r#"(5 : nat, "b")"#
+        );
+        assert!(substitute_variables("($pair)", &variables).is_err());
+        assert!(substitute_variables("($other)", &variables).is_err());
+        assert!(substitute_variables("($pair.2)", &variables).is_err());
+        assert!(substitute_variables("($pair.99999999999999999999999)", &variables).is_err());
+
+        assert_eq!(
+            substitute_variables(r#"("costs $5", $count)"#, &variables).unwrap(),
+            r#"("costs $5", 5 : nat)"#
+        );
+        assert_eq!(
+            substitute_variables(r#"("say \"$count\"", $count)"#, &variables).unwrap(),
+            r#"("say \"$count\"", 5 : nat)"#
+        );
+        assert_eq!(
+            substitute_variables("(record { $$count = $count })", &variables).unwrap(),
+            "(record { $count = 5 : nat })"
+        );
+        assert_eq!(
+            substitute_variables(r#"("unclosed $count"#, &variables).unwrap(),
+            r#"("unclosed $count"#
+        );
+    }
+
+    #[test]
+    fn completes_methods_and_variables() {
+        let interface = Interface::load(CandidSource::Text(
+            r#"service : {
+                greet : (text, record { tags : vec text }) -> (text) query;
+                get_balance : () -> (nat);
+            }"#,
+        ))
+        .unwrap();
+        let completion = ReplCompletion {
+            targets: interface
+                .methods
+                .iter()
+                .map(|(name, func)| (name.clone(), argument_skeleton(&interface.env, &func.args)))
+                .collect(),
+            variables: vec!["balance".to_string(), "_".to_string()],
+        };
+        assert_eq!(
+            completion.get("gr").unwrap(),
+            r#"greet ("", record { tags = vec {}; })"#
+        );
+        assert_eq!(
+            completion.get("let b = get_b").unwrap(),
+            "let b = get_balance ()"
+        );
+        assert_eq!(completion.get("greet ($ba").unwrap(), "greet ($balance");
+        assert_eq!(completion.get(".me").unwrap(), ".methods");
+        assert_eq!(completion.get("x"), None);
+    }
+}
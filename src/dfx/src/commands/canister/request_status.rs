@@ -1,8 +1,9 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::{DfxError, DfxResult};
+use crate::lib::operations::canister::get_canister_id_and_candid_path;
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::util::clap::parsers;
-use crate::util::print_idl_blob;
+use crate::util::{get_candid_type_of_canister, print_idl_blob};
 use anyhow::Context;
 use backoff::backoff::Backoff;
 use backoff::ExponentialBackoff;
@@ -28,8 +29,13 @@ pub struct RequestStatusOpts {
     /// specify the wallet canister id.
     canister: String,
 
+    /// Specifies the method that was called, to decode the reply with the method's return types
+    /// from the Candid interface of the canister.
+    #[arg(long)]
+    method: Option<String>,
+
     /// Specifies the format for displaying the method's return result.
-    #[arg(long, value_parser = ["idl", "raw", "pp"])]
+    #[arg(long, value_parser = ["idl", "raw", "pp", "json"])]
     output: Option<String>,
 }
 
@@ -87,7 +93,23 @@ pub async fn exec(env: &dyn Environment, opts: RequestStatusOpts) -> DfxResult {
     .await
     .map_err(DfxError::from)?;
 
+    let method_type = match &opts.method {
+        Some(method) => {
+            let (_, local_candid_path) = get_canister_id_and_candid_path(env, callee_canister)?;
+            let method_type = get_candid_type_of_canister(
+                agent,
+                canister_id,
+                local_candid_path.as_deref(),
+                method,
+            )
+            .await
+            .with_context(|| format!("Cannot find the type of method {method}."))?;
+            Some(method_type)
+        }
+        None => None,
+    };
+
     let output_type = opts.output.as_deref();
-    print_idl_blob(&blob, output_type, &None)?;
+    print_idl_blob(&blob, output_type, &method_type)?;
     Ok(())
 }
@@ -0,0 +1,568 @@
+use crate::commands::canister::call::{get_effective_canister_id_of_call, MethodCall};
+use crate::commands::canister::repl::substitute_variables;
+use crate::lib::environment::{AgentEnvironment, Environment};
+use crate::lib::error::DfxResult;
+use crate::lib::operations::canister::get_canister_id_and_candid_path;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::{
+    blob_from_arguments, decode_idl_blob, fetch_remote_did_file, get_candid_type, print_idl_blob,
+};
+use anyhow::{anyhow, bail, Context};
+use candid::types::{Function, TypeEnv};
+use candid::{IDLArgs, Principal};
+use candid_parser::utils::CandidSource;
+use clap::{Parser, ValueEnum};
+use dfx_core::identity::CallSender;
+use dfx_core::util::expiry_duration;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use slog::{error, info};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Runs a script of canister calls, and checks their replies.
+/// The script is a JSON file with a list of steps, each of them a call with a Candid argument.
+#[derive(Parser)]
+pub struct CanisterRunScriptOpts {
+    /// Specifies the path of the script.
+    script: PathBuf,
+
+    /// Writes a report of the steps to a file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// Specifies the format of the report.
+    #[arg(long, value_enum, default_value = "junit", requires("report"))]
+    report_format: ReportFormat,
+
+    /// Stops at the first step that fails, instead of running the rest of the script.
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Specifies the format for displaying the replies of the calls, as with dfx canister call.
+    #[arg(long, value_parser = ["idl", "raw", "pp", "json"])]
+    output: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ReportFormat {
+    Junit,
+    Json,
+}
+
+/// A script of canister calls.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    /// The canister called by the steps that do not specify one.
+    canister: Option<String>,
+
+    /// The identity that sends the calls of the steps that do not specify one.
+    identity: Option<String>,
+
+    /// The wallet that proxies the calls of the steps that do not specify one.
+    wallet: Option<String>,
+
+    steps: Vec<Step>,
+}
+
+/// A call of a script, and what its reply should be.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Step {
+    /// Names the step in the output and the report. Defaults to <canister>.<method>.
+    name: Option<String>,
+
+    canister: Option<String>,
+
+    method: String,
+
+    /// The Candid argument of the call. Variables are substituted as `$name` or `$name.index`.
+    args: Option<String>,
+
+    identity: Option<String>,
+
+    wallet: Option<String>,
+
+    /// Calls the method as a query (true) or as an update (false), instead of as its interface says.
+    query: Option<bool>,
+
+    /// Cycles sent with the call. Only a wallet can send cycles.
+    with_cycles: Option<u128>,
+
+    /// The reply that the call must return, in Candid.
+    expect: Option<String>,
+
+    /// A regular expression that the reply must match, in Candid.
+    expect_pattern: Option<String>,
+
+    /// A regular expression that the error of a call that must fail must match.
+    expect_reject: Option<String>,
+
+    /// Stores the reply in a variable.
+    capture: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    script: PathBuf,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    steps: Vec<StepReport>,
+}
+
+#[derive(Serialize)]
+struct StepReport {
+    name: String,
+    canister: String,
+    method: String,
+    passed: bool,
+    /// Whether the step did not run because an earlier step failed with `--fail-fast`.
+    skipped: bool,
+    /// The duration of the step in seconds.
+    time: f64,
+    reply: Option<String>,
+    failure: Option<String>,
+}
+
+pub async fn exec(
+    env: &dyn Environment,
+    opts: CanisterRunScriptOpts,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let log = env.get_logger();
+    fetch_root_key_if_needed(env).await?;
+    let script: Script = dfx_core::json::load_json_file(&opts.script)?;
+
+    let mut runner = Runner {
+        env,
+        call_sender: *call_sender,
+        identity_envs: BTreeMap::new(),
+        candid: BTreeMap::new(),
+        variables: BTreeMap::new(),
+    };
+    let mut report = Report {
+        script: opts.script.clone(),
+        passed: 0,
+        failed: 0,
+        skipped: 0,
+        steps: vec![],
+    };
+    for (index, step) in script.steps.iter().enumerate() {
+        let canister = step.canister.as_ref().or(script.canister.as_ref());
+        if opts.fail_fast && report.failed > 0 {
+            let canister = canister.cloned().unwrap_or_default();
+            report.skipped += 1;
+            report.steps.push(StepReport {
+                name: step_name(step, &canister),
+                canister,
+                method: step.method.clone(),
+                passed: false,
+                skipped: true,
+                time: 0.0,
+                reply: None,
+                failure: None,
+            });
+            continue;
+        }
+        let canister =
+            canister.with_context(|| format!("Step {} does not specify a canister.", index + 1))?;
+        let name = step_name(step, canister);
+
+        let start = Instant::now();
+        let result = runner
+            .run_step(&script, step, canister, opts.output.as_deref())
+            .await;
+        let time = start.elapsed().as_secs_f64();
+        let (reply, failure) = match result {
+            Ok(reply) => {
+                info!(log, "ok     {}", name);
+                (reply, None)
+            }
+            Err(err) => {
+                error!(log, "FAILED {}: {:#}", name, err);
+                (None, Some(format!("{err:#}")))
+            }
+        };
+        if failure.is_none() {
+            report.passed += 1;
+        } else {
+            report.failed += 1;
+        }
+        report.steps.push(StepReport {
+            name,
+            canister: canister.clone(),
+            method: step.method.clone(),
+            passed: failure.is_none(),
+            skipped: false,
+            time,
+            reply,
+            failure,
+        });
+    }
+
+    if let Some(path) = &opts.report {
+        let content = match opts.report_format {
+            ReportFormat::Junit => junit_report(&report),
+            ReportFormat::Json => serde_json::to_string_pretty(&report)?,
+        };
+        dfx_core::fs::write(path, content)?;
+    }
+
+    if report.failed > 0 {
+        bail!("{} of {} steps failed.", report.failed, script.steps.len());
+    }
+    info!(log, "All {} steps passed.", report.passed);
+    Ok(())
+}
+
+struct Runner<'a> {
+    env: &'a dyn Environment,
+    /// The sender of the steps that do not specify a wallet.
+    call_sender: CallSender,
+    /// The environments of the identities that steps send calls as.
+    identity_envs: BTreeMap<String, AgentEnvironment<'a>>,
+    /// The id and the Candid interface of each canister that was called.
+    candid: BTreeMap<String, (Principal, Option<String>)>,
+    variables: BTreeMap<String, IDLArgs>,
+}
+
+impl<'a> Runner<'a> {
+    /// Runs a step, and returns its reply, if it has one.
+    async fn run_step(
+        &mut self,
+        script: &Script,
+        step: &Step,
+        canister: &str,
+        output_type: Option<&str>,
+    ) -> DfxResult<Option<String>> {
+        let identity = step.identity.as_ref().or(script.identity.as_ref());
+        if let Some(identity) = identity {
+            if !self.identity_envs.contains_key(identity) {
+                let identity_env = AgentEnvironment::new(
+                    self.env,
+                    self.env.get_network_descriptor().clone(),
+                    expiry_duration(),
+                    Some(identity.as_str()),
+                )?;
+                fetch_root_key_if_needed(&identity_env).await?;
+                self.identity_envs.insert(identity.clone(), identity_env);
+            }
+        }
+        let env: &dyn Environment = match identity {
+            Some(identity) => &self.identity_envs[identity],
+            None => self.env,
+        };
+        let call_sender = match step.wallet.as_ref().or(script.wallet.as_ref()) {
+            Some(wallet) => CallSender::from(&Some(wallet.clone()))
+                .map_err(|e| anyhow!("Failed to determine call sender: {}", e))?,
+            None => self.call_sender,
+        };
+
+        if !self.candid.contains_key(canister) {
+            let (canister_id, local_candid_path) = get_canister_id_and_candid_path(env, canister)?;
+            let did = match fetch_remote_did_file(env.get_agent(), canister_id).await {
+                Some(did) => Some(did),
+                None => local_candid_path
+                    .filter(|path| path.exists())
+                    .map(|path| dfx_core::fs::read_to_string(&path))
+                    .transpose()?,
+            };
+            self.candid.insert(canister.to_string(), (canister_id, did));
+        }
+        let (canister_id, did) = &self.candid[canister];
+        let method_name = step.method.as_str();
+        let method_type = match did {
+            Some(did) => Some(
+                get_candid_type(CandidSource::Text(did), method_name).with_context(|| {
+                    format!("Canister {} has no method {}.", canister, method_name)
+                })?,
+            ),
+            None => None,
+        };
+
+        let argument = step
+            .args
+            .as_deref()
+            .map(|args| substitute_variables(args, &self.variables))
+            .transpose()
+            .map_err(|err| anyhow!(err))?;
+        let arg_value = blob_from_arguments(
+            Some(env),
+            argument.as_deref(),
+            None,
+            None,
+            &method_type,
+            false,
+            false,
+        )?;
+        let is_query_method = method_type.as_ref().map(|(_, func)| func.is_query());
+        let is_query = match (step.query, is_query_method) {
+            (Some(true), Some(false)) => {
+                bail!("{method_name} is an update method, not a query method.")
+            }
+            (Some(query), _) => query,
+            (None, is_query_method) => is_query_method.unwrap_or(false),
+        };
+        let cycles = step.with_cycles.unwrap_or(0);
+        if call_sender == CallSender::SelectedId && cycles != 0 {
+            bail!("It is only possible to send cycles from a canister. Set the wallet of the step to send cycles with.");
+        }
+
+        let result = MethodCall {
+            canister_id: *canister_id,
+            effective_canister_id: get_effective_canister_id_of_call(
+                *canister_id,
+                method_name,
+                &arg_value,
+            )?,
+            method_name,
+            arg_value,
+            is_query,
+            verify_query_signatures: true,
+            cycles,
+        }
+        .call_and_wait(env.get_agent(), &call_sender)
+        .await;
+
+        let blob = match (result, &step.expect_reject) {
+            (Ok(_), Some(_)) => bail!("The call succeeded, but it should have been rejected."),
+            (Err(err), Some(pattern)) => {
+                let message = format!("{err:#}");
+                let regex = Regex::new(pattern)
+                    .with_context(|| format!("Invalid expect_reject pattern {pattern:?}."))?;
+                if !regex.is_match(&message) {
+                    bail!("The call was rejected with \"{message}\", which does not match {pattern:?}.");
+                }
+                return Ok(None);
+            }
+            (result, None) => result?,
+        };
+        print_idl_blob(&blob, output_type, &method_type)?;
+        let reply = decode_idl_blob(&blob, &method_type)?;
+
+        if let Some(expected) = &step.expect {
+            let expected =
+                substitute_variables(expected, &self.variables).map_err(|err| anyhow!(err))?;
+            check_reply(&reply, &expected, &method_type)?;
+        }
+        if let Some(pattern) = &step.expect_pattern {
+            let regex = Regex::new(pattern)
+                .with_context(|| format!("Invalid expect_pattern {pattern:?}."))?;
+            if !regex.is_match(&reply.to_string()) {
+                bail!("The reply {reply} does not match {pattern:?}.");
+            }
+        }
+        let text = reply.to_string();
+        if let Some(variable) = &step.capture {
+            self.variables.insert(variable.clone(), reply);
+        }
+        Ok(Some(text))
+    }
+}
+
+/// Checks that a reply is the expected Candid value, with the types of the method's results if they are known.
+fn check_reply(
+    reply: &IDLArgs,
+    expected: &str,
+    method_type: &Option<(TypeEnv, Function)>,
+) -> DfxResult {
+    let expected = candid_parser::parse_idl_args(expected)
+        .with_context(|| format!("Invalid expected reply {expected:?}."))?;
+    let expected = match method_type {
+        Some((env, func)) => expected
+            .annotate_types(true, env, &func.rets)
+            .context("The expected reply does not have the types of the method's results.")?,
+        None => expected,
+    };
+    if expected.to_string() != reply.to_string() {
+        bail!("Expected {expected}, but the reply is {reply}.");
+    }
+    Ok(())
+}
+
+fn step_name(step: &Step, canister: &str) -> String {
+    step.name
+        .clone()
+        .unwrap_or_else(|| format!("{}.{}", canister, step.method))
+}
+
+fn junit_report(report: &Report) -> String {
+    let time: f64 = report.steps.iter().map(|step| step.time).sum();
+    let tests = report.steps.len();
+    let name = escape_xml(&report.script.display().to_string());
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites tests=\"{tests}\" failures=\"{}\" skipped=\"{}\" time=\"{time:.3}\">",
+        report.failed, report.skipped
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{name}\" tests=\"{tests}\" failures=\"{}\" skipped=\"{}\" time=\"{time:.3}\">",
+        report.failed, report.skipped
+    );
+    for step in &report.steps {
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape_xml(&step.name),
+            escape_xml(&format!("{}.{}", step.canister, step.method)),
+            step.time
+        );
+        match &step.failure {
+            Some(failure) => {
+                let _ = writeln!(
+                    xml,
+                    ">\n      <failure message=\"{}\"/>\n    </testcase>",
+                    escape_xml(failure)
+                );
+            }
+            None if step.skipped => xml.push_str(">\n      <skipped/>\n    </testcase>\n"),
+            None => xml.push_str("/>\n"),
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_replies_with_the_result_types() {
+        let method_type = get_candid_type(
+            CandidSource::Text(
+                "service : { get : () -> (record { count : nat; owner : opt text }) query }",
+            ),
+            "get",
+        );
+        let reply = candid_parser::parse_idl_args(r#"(record { count = 3; owner = opt "me" })"#)
+            .unwrap()
+            .annotate_types(
+                true,
+                &method_type.as_ref().unwrap().0,
+                &method_type.as_ref().unwrap().1.rets,
+            )
+            .unwrap();
+        check_reply(
+            &reply,
+            r#"(record { owner = opt "me"; count = 3 })"#,
+            &method_type,
+        )
+        .unwrap();
+        assert!(check_reply(
+            &reply,
+            r#"(record { owner = null; count = 3 })"#,
+            &method_type
+        )
+        .is_err());
+        assert!(check_reply(&reply, "(3)", &method_type).is_err());
+    }
+
+    #[test]
+    fn keeps_dollar_signs_in_text_arguments() {
+        let variables = BTreeMap::from([(
+            "price".to_string(),
+            candid_parser::parse_idl_args("(5 : nat)").unwrap(),
+        )]);
+        let method_type = get_candid_type(
+            CandidSource::Text("service : { quote : (text, nat) -> (text) }"),
+            "quote",
+        );
+        let argument = substitute_variables(r#"("costs $price", $price)"#, &variables).unwrap();
+        assert_eq!(argument, r#"("costs $price", 5 : nat)"#);
+        blob_from_arguments(
+            None,
+            Some(&argument),
+            None,
+            None,
+            &method_type,
+            false,
+            false,
+        )
+        .unwrap();
+
+        let reply = candid_parser::parse_idl_args(r#"("costs $5")"#).unwrap();
+        let expected = substitute_variables(r#"("costs $5")"#, &variables).unwrap();
+        check_reply(&reply, &expected, &method_type).unwrap();
+    }
+
+    #[test]
+    fn writes_junit_reports() {
+        let report = Report {
+            script: PathBuf::from("smoke.json"),
+            passed: 1,
+            failed: 1,
+            skipped: 1,
+            steps: vec![
+                StepReport {
+                    name: "read".to_string(),
+                    canister: "counter".to_string(),
+                    method: "read".to_string(),
+                    passed: true,
+                    skipped: false,
+                    time: 0.5,
+                    reply: Some("(1 : nat)".to_string()),
+                    failure: None,
+                },
+                StepReport {
+                    name: "write <2>".to_string(),
+                    canister: "counter".to_string(),
+                    method: "write".to_string(),
+                    passed: false,
+                    skipped: false,
+                    time: 0.25,
+                    reply: None,
+                    failure: Some("Expected \"a\" & \"b\".".to_string()),
+                },
+                StepReport {
+                    name: "counter.read".to_string(),
+                    canister: "counter".to_string(),
+                    method: "read".to_string(),
+                    passed: false,
+                    skipped: true,
+                    time: 0.0,
+                    reply: None,
+                    failure: None,
+                },
+            ],
+        };
+        assert_eq!(
+            junit_report(&report),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="3" failures="1" skipped="1" time="0.750">
+  <testsuite name="smoke.json" tests="3" failures="1" skipped="1" time="0.750">
+    <testcase name="read" classname="counter.read" time="0.500"/>
+    <testcase name="write &lt;2&gt;" classname="counter.write" time="0.250">
+      <failure message="Expected &quot;a&quot; &amp; &quot;b&quot;."/>
+    </testcase>
+    <testcase name="counter.read" classname="counter.read" time="0.000">
+      <skipped/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::operations::canister;
 use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use candid::Principal;
 use clap::Parser;
 use dfx_core::identity::CallSender;
@@ -11,11 +12,19 @@ use slog::info;
 #[derive(Parser)]
 pub struct CanisterStartOpts {
     /// Specifies the name or id of the canister to start. You must specify either a canister name/id or the --all flag.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister: Option<String>,
 
     /// Starts all of the canisters configured in the dfx.json file.
-    #[arg(long, required_unless_present("canister"))]
+    #[arg(
+        long,
+        required_unless_present_any(["canister", "group", "tag"]),
+        conflicts_with_all(["group", "tag"])
+    )]
     all: bool,
+
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
 }
 
 async fn start_canister(
@@ -49,12 +58,10 @@ pub async fn exec(
 
     if let Some(canister) = opts.canister.as_deref() {
         start_canister(env, canister, call_sender).await
-    } else if opts.all {
+    } else if opts.all || opts.canister_group.is_present() {
         let config = env.get_config_or_anyhow()?;
-        if let Some(canisters) = &config.get_config().canisters {
-            for canister in canisters.keys() {
-                start_canister(env, canister, call_sender).await?;
-            }
+        for canister in &opts.canister_group.select_or_all(config.get_config())? {
+            start_canister(env, canister, call_sender).await?;
         }
         Ok(())
     } else {
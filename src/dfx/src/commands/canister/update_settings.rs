@@ -7,6 +7,7 @@ use crate::lib::ic_attributes::{
 };
 use crate::lib::operations::canister::{get_canister_status, update_settings};
 use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use crate::util::clap::parsers::{
     compute_allocation_parser, freezing_threshold_parser, memory_allocation_parser,
     reserved_cycles_limit_parser, wasm_memory_limit_parser,
@@ -25,12 +26,20 @@ use ic_agent::identity::Identity;
 #[derive(Parser, Debug)]
 pub struct UpdateSettingsOpts {
     /// Specifies the canister name or id to update. You must specify either canister name/id or the --all option.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister: Option<String>,
 
     /// Updates the settings of all canisters configured in the project dfx.json files.
-    #[arg(long, required_unless_present("canister"))]
+    #[arg(
+        long,
+        required_unless_present_any(["canister", "group", "tag"]),
+        conflicts_with_all(["group", "tag"])
+    )]
     all: bool,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     /// Specifies the identity name or the principal of the new controller.
     /// Can be specified more than once, indicating the canister will have multiple controllers.
     /// If any controllers are set with this parameter, any other controllers will be removed.
@@ -187,87 +196,79 @@ pub async fn exec(
         };
         update_settings(env, canister_id, settings, call_sender).await?;
         display_controller_update(&opts, canister_name_or_id);
-    } else if opts.all {
-        // Update all canister settings.
+    } else if opts.all || opts.canister_group.is_present() {
+        // Update the settings of all canisters, or those selected by --group or --tag.
         let config = env.get_config_or_anyhow()?;
         let config_interface = config.get_config();
-        if let Some(canisters) = &config_interface.canisters {
-            for canister_name in canisters.keys() {
-                let mut controllers = controllers.clone();
-                let canister_id = canister_id_store.get(canister_name)?;
-                let compute_allocation = get_compute_allocation(
-                    opts.compute_allocation,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| {
-                    format!("Failed to get compute allocation for {canister_name}.")
-                })?;
-                let memory_allocation = get_memory_allocation(
-                    opts.memory_allocation,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| format!("Failed to get memory allocation for {canister_name}."))?;
-                let freezing_threshold = get_freezing_threshold(
-                    opts.freezing_threshold,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| {
-                    format!("Failed to get freezing threshold for {canister_name}.")
-                })?;
-                let reserved_cycles_limit = get_reserved_cycles_limit(
-                    opts.reserved_cycles_limit,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| {
-                    format!("Failed to get reserved cycles limit for {canister_name}.")
-                })?;
-                let wasm_memory_limit = get_wasm_memory_limit(
-                    opts.wasm_memory_limit,
-                    Some(config_interface),
-                    Some(canister_name),
-                )
-                .with_context(|| format!("Failed to get WASM memory limit for {canister_name}."))?;
-                if let Some(added) = &opts.add_controller {
-                    let status = get_canister_status(env, canister_id, call_sender).await?;
-                    let mut existing_controllers = status.settings.controllers;
-                    for s in added {
-                        existing_controllers.push(controller_to_principal(env, s)?);
-                    }
-                    controllers = Some(existing_controllers);
+        for canister_name in &opts.canister_group.select_or_all(config_interface)? {
+            let mut controllers = controllers.clone();
+            let canister_id = canister_id_store.get(canister_name)?;
+            let compute_allocation = get_compute_allocation(
+                opts.compute_allocation,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to get compute allocation for {canister_name}."))?;
+            let memory_allocation = get_memory_allocation(
+                opts.memory_allocation,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to get memory allocation for {canister_name}."))?;
+            let freezing_threshold = get_freezing_threshold(
+                opts.freezing_threshold,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to get freezing threshold for {canister_name}."))?;
+            let reserved_cycles_limit = get_reserved_cycles_limit(
+                opts.reserved_cycles_limit,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to get reserved cycles limit for {canister_name}."))?;
+            let wasm_memory_limit = get_wasm_memory_limit(
+                opts.wasm_memory_limit,
+                Some(config_interface),
+                Some(canister_name),
+            )
+            .with_context(|| format!("Failed to get WASM memory limit for {canister_name}."))?;
+            if let Some(added) = &opts.add_controller {
+                let status = get_canister_status(env, canister_id, call_sender).await?;
+                let mut existing_controllers = status.settings.controllers;
+                for s in added {
+                    existing_controllers.push(controller_to_principal(env, s)?);
                 }
-                if let Some(removed) = &opts.remove_controller {
-                    let controllers = if opts.add_controller.is_some() {
-                        controllers.as_mut().unwrap()
-                    } else {
-                        let status = get_canister_status(env, canister_id, call_sender).await?;
-                        controllers.get_or_insert(status.settings.controllers)
-                    };
-                    let removed = removed
-                        .iter()
-                        .map(|r| controller_to_principal(env, r))
-                        .collect::<DfxResult<Vec<_>>>()
-                        .context("Failed to determine all controllers to remove.")?;
-                    for s in removed {
-                        if let Some(idx) = controllers.iter().position(|x| *x == s) {
-                            controllers.swap_remove(idx);
-                        }
+                controllers = Some(existing_controllers);
+            }
+            if let Some(removed) = &opts.remove_controller {
+                let controllers = if opts.add_controller.is_some() {
+                    controllers.as_mut().unwrap()
+                } else {
+                    let status = get_canister_status(env, canister_id, call_sender).await?;
+                    controllers.get_or_insert(status.settings.controllers)
+                };
+                let removed = removed
+                    .iter()
+                    .map(|r| controller_to_principal(env, r))
+                    .collect::<DfxResult<Vec<_>>>()
+                    .context("Failed to determine all controllers to remove.")?;
+                for s in removed {
+                    if let Some(idx) = controllers.iter().position(|x| *x == s) {
+                        controllers.swap_remove(idx);
                     }
                 }
-                let settings = CanisterSettings {
-                    controllers,
-                    compute_allocation,
-                    memory_allocation,
-                    freezing_threshold,
-                    reserved_cycles_limit,
-                    wasm_memory_limit,
-                };
-                update_settings(env, canister_id, settings, call_sender).await?;
-                display_controller_update(&opts, canister_name);
             }
+            let settings = CanisterSettings {
+                controllers,
+                compute_allocation,
+                memory_allocation,
+                freezing_threshold,
+                reserved_cycles_limit,
+                wasm_memory_limit,
+            };
+            update_settings(env, canister_id, settings, call_sender).await?;
+            display_controller_update(&opts, canister_name);
         }
     } else {
         bail!("Cannot find canister name.")
@@ -296,7 +297,10 @@ fn user_is_removing_themselves_as_controller(
 }
 
 #[context("Failed to convert controller '{}' to a principal", controller)]
-fn controller_to_principal(env: &dyn Environment, controller: &str) -> DfxResult<CanisterId> {
+pub(crate) fn controller_to_principal(
+    env: &dyn Environment,
+    controller: &str,
+) -> DfxResult<CanisterId> {
     match CanisterId::from_text(controller) {
         Ok(principal) => Ok(principal),
         Err(_) => {
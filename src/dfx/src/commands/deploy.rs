@@ -4,11 +4,14 @@ use crate::lib::error::DfxResult;
 use crate::lib::network::network_opt::NetworkOpt;
 use crate::lib::operations::canister::deploy_canisters::deploy_canisters;
 use crate::lib::operations::canister::deploy_canisters::DeployMode::{
-    ComputeEvidence, ForceReinstallSingleCanister, NormalDeploy, PrepareForProposal,
+    ApplyPlan, ComputeEvidence, DryRun, ForceReinstallSingleCanister, NormalDeploy, Plan,
+    PrepareForProposal,
 };
+use crate::lib::operations::canister::deploy_plan::{load_deploy_plan, verify_plan_target};
 use crate::lib::root_key::fetch_root_key_if_needed;
 use crate::lib::{environment::Environment, named_canister};
 use crate::util::clap::argument_from_cli::ArgumentFromCliLongOpt;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use crate::util::clap::parsers::{cycle_amount_parser, icrc_subaccount_parser};
 use crate::util::clap::subnet_selection_opt::SubnetSelectionOpt;
 use anyhow::{anyhow, bail, Context};
@@ -36,8 +39,12 @@ const MAINNET_CANDID_INTERFACE_PRINCIPAL: &str = "a4gq6-oaaaa-aaaab-qaa4q-cai";
 pub struct DeployOpts {
     /// Specifies the name of the canister you want to deploy.
     /// If you don’t specify a canister name, all canisters defined in the dfx.json file are deployed.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister_name: Option<String>,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     #[command(flatten)]
     argument_from_cli: ArgumentFromCliLongOpt,
 
@@ -100,6 +107,42 @@ pub struct DeployOpts {
     #[arg(long, conflicts_with("by_proposal"))]
     compute_evidence: bool,
 
+    /// List the changes that would be made to asset canisters, without uploading or committing anything.
+    #[arg(long, group = "preview", conflicts_with_all(["by_proposal", "compute_evidence", "mode"]))]
+    dry_run: bool,
+
+    /// Show which canisters would be created, installed, upgraded, reinstalled or skipped,
+    /// and the results of the interface compatibility checks, without changing anything.
+    #[arg(long, group = "preview", conflicts_with_all(["by_proposal", "compute_evidence"]))]
+    plan: bool,
+
+    /// Specifies the format for displaying the output of --dry-run or --plan.
+    #[arg(long, requires("preview"), value_parser = ["text", "json"])]
+    output: Option<String>,
+
+    /// Deploy exactly what a plan made with `--plan --output json` describes.
+    /// Fails if the network or the project changed since the plan was made.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all([
+            "canister_name",
+            "group",
+            "tag",
+            "argument",
+            "argument_file",
+            "mode",
+            "upgrade_unchanged",
+            "with_cycles",
+            "no_asset_upgrade",
+            "by_proposal",
+            "compute_evidence",
+            "preview",
+            "always_assist",
+        ])
+    )]
+    apply_plan: Option<PathBuf>,
+
     /// Transaction timestamp, in nanoseconds, for use in controlling transaction deduplication, default is system time.
     /// https://internetcomputer.org/docs/current/developer-docs/integrations/icrc-1/#transaction-deduplication-
     #[arg(long, requires = "canister_name")]
@@ -126,8 +169,22 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
     let env = create_agent_environment(env, opts.network.to_network_name())?;
     let runtime = Runtime::new().expect("Unable to create a runtime");
 
-    let canister_name = opts.canister_name.as_deref();
-    let (argument_from_cli, argument_type) = opts.argument_from_cli.get_argument_and_type()?;
+    let plan = opts
+        .apply_plan
+        .as_deref()
+        .map(load_deploy_plan)
+        .transpose()?;
+    if let Some(plan) = &plan {
+        verify_plan_target(&env, plan)?;
+    }
+    let canister_name = match &plan {
+        Some(plan) => plan.canister.as_deref(),
+        None => opts.canister_name.as_deref(),
+    };
+    let (argument_from_cli, argument_type) = match &plan {
+        Some(plan) => (plan.argument.clone(), plan.argument_type.clone()),
+        None => opts.argument_from_cli.get_argument_and_type()?,
+    };
     if argument_from_cli.is_some() && canister_name.is_none() {
         bail!("The init argument can only be set when deploying a single canister.");
     }
@@ -139,6 +196,10 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
         .map_err(|err| anyhow!(err))
         .context("Failed to parse InstallMode.")?;
     let config = env.get_config_or_anyhow()?;
+    let selected_canisters = match &plan {
+        Some(plan) => plan.selected_canisters.clone(),
+        None => opts.canister_group.select(config.get_config())?,
+    };
     let env_file = config.get_output_env_file(opts.output_env_file)?;
     let mut subnet_selection =
         runtime.block_on(opts.subnet_selection.into_subnet_selection_type(&env))?;
@@ -154,7 +215,14 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
                 bail!("The '{}' canister is remote for network '{}' and cannot be force-reinstalled from here",
                     canister_name, &network.name);
             }
-            ForceReinstallSingleCanister(canister_name.to_string())
+            if opts.plan {
+                Plan {
+                    json: opts.output.as_deref() == Some("json"),
+                    force_reinstall: true,
+                }
+            } else {
+                ForceReinstallSingleCanister(canister_name.to_string())
+            }
         }
         (Some(InstallMode::Reinstall), None) => {
             bail!("The --mode=reinstall is only valid when deploying a single canister, because reinstallation destroys all data in the canister.");
@@ -174,6 +242,14 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
         (None, Some(canister_name)) if opts.compute_evidence => {
             ComputeEvidence(canister_name.to_string())
         }
+        (None, _) if plan.is_some() => ApplyPlan(plan.clone().unwrap()),
+        (None, _) if opts.plan => Plan {
+            json: opts.output.as_deref() == Some("json"),
+            force_reinstall: false,
+        },
+        (None, _) if opts.dry_run => DryRun {
+            json: opts.output.as_deref() == Some("json"),
+        },
         (None, _) => NormalDeploy,
     };
 
@@ -185,6 +261,7 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
     runtime.block_on(deploy_canisters(
         &env,
         canister_name,
+        selected_canisters.as_deref(),
         argument_from_cli.as_deref(),
         argument_type.as_deref(),
         &deploy_mode,
@@ -202,7 +279,10 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
         opts.always_assist,
     ))?;
 
-    if matches!(deploy_mode, NormalDeploy | ForceReinstallSingleCanister(_)) {
+    if matches!(
+        deploy_mode,
+        NormalDeploy | ForceReinstallSingleCanister(_) | ApplyPlan(_)
+    ) {
         display_urls(&env)?;
     }
     Ok(())
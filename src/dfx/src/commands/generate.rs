@@ -4,7 +4,9 @@ use crate::lib::builders::BuildConfig;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::models::canister::CanisterPool;
+use crate::util::clap::canister_group_opt::CanisterGroupOpt;
 use clap::Parser;
+use std::collections::BTreeSet;
 use tokio::runtime::Runtime;
 
 /// Generate type declarations for canisters from the code in your project
@@ -12,8 +14,12 @@ use tokio::runtime::Runtime;
 pub struct GenerateOpts {
     /// Specifies the name of the canister to generate type information for.
     /// If you do not specify a canister name, generates types for all canisters.
+    #[arg(conflicts_with_all(["group", "tag"]))]
     canister_name: Option<String>,
 
+    #[command(flatten)]
+    canister_group: CanisterGroupOpt,
+
     // Deprecated/hidden because it had/has no effect.
     // Cannot use 'hide' on a flattened  object - inlined the flattened network specifier
     #[arg(long, global = true, hide = true)]
@@ -31,10 +37,23 @@ pub fn exec(env1: &dyn Environment, opts: GenerateOpts) -> DfxResult {
     // already.
     DiskBasedCache::install(&env.get_cache().version_str())?;
 
-    // Option can be None which means generate types for all canisters
-    let canisters_to_load = config
-        .get_config()
-        .get_canister_names_with_dependencies(opts.canister_name.as_deref())?;
+    let canisters_to_load = match opts.canister_group.select(config.get_config())? {
+        Some(selected_canisters) => {
+            let mut names = BTreeSet::new();
+            for canister in &selected_canisters {
+                names.extend(
+                    config
+                        .get_config()
+                        .get_canister_names_with_dependencies(Some(canister))?,
+                );
+            }
+            names.into_iter().collect()
+        }
+        // Option can be None which means generate types for all canisters
+        None => config
+            .get_config()
+            .get_canister_names_with_dependencies(opts.canister_name.as_deref())?,
+    };
     let canisters_to_generate = canisters_to_load.clone().into_iter().collect();
 
     let canister_pool_load = CanisterPool::load(&env, false, &canisters_to_load)?;
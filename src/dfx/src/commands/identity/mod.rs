@@ -51,12 +51,12 @@ pub fn exec(env: &dyn Environment, opts: IdentityOpts) -> DfxResult {
         SubCommand::GetWallet(v) => get_wallet::exec(env, v, opts.network),
         SubCommand::List(v) => list::exec(env, v),
         SubCommand::New(v) => new::exec(env, v),
-        SubCommand::GetPrincipal(v) => principal::exec(env, v),
+        SubCommand::GetPrincipal(v) => principal::exec(env, v, opts.network),
         SubCommand::Import(v) => import::exec(env, v),
         SubCommand::Remove(v) => remove::exec(env, v),
         SubCommand::Rename(v) => rename::exec(env, v),
         SubCommand::SetWallet(v) => set_wallet::exec(env, v, opts.network),
         SubCommand::Use(v) => r#use::exec(env, v),
-        SubCommand::Whoami(v) => whoami::exec(env, v),
+        SubCommand::Whoami(v) => whoami::exec(env, v, opts.network),
     }
 }
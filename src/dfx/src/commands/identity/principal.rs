@@ -1,5 +1,7 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::identity::use_network_identity;
+use crate::lib::network::network_opt::NetworkOpt;
 use anyhow::anyhow;
 use clap::Parser;
 use ic_agent::identity::Identity;
@@ -8,10 +10,13 @@ use ic_agent::identity::Identity;
 #[derive(Parser)]
 pub struct GetPrincipalOpts {}
 
-pub fn exec(env: &dyn Environment, _opts: GetPrincipalOpts) -> DfxResult {
-    let identity = env
-        .new_identity_manager()?
-        .instantiate_selected_identity(env.get_logger())?;
+pub fn exec(env: &dyn Environment, _opts: GetPrincipalOpts, network: NetworkOpt) -> DfxResult {
+    let mut mgr = env.new_identity_manager()?;
+    let network = network
+        .to_network_name()
+        .unwrap_or_else(|| "local".to_string());
+    use_network_identity(env, &mut mgr, &network)?;
+    let identity = mgr.instantiate_selected_identity(env.get_logger())?;
     let principal_id = identity
         .as_ref()
         .sender()
@@ -1,13 +1,39 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::identity::use_network_identity;
+use crate::lib::network::network_opt::NetworkOpt;
 use clap::Parser;
+use dfx_core::identity::identity_manager::IdentitySelection;
+use slog::{debug, info};
 
 /// Shows the name of the current identity.
+/// If the identity is configured for the network, also reports where it is configured.
+/// With -v, also reports when it was selected with --identity or DFX_IDENTITY.
 #[derive(Parser)]
 pub struct WhoAmIOpts {}
 
-pub fn exec(env: &dyn Environment, _opts: WhoAmIOpts) -> DfxResult {
-    let mgr = env.new_identity_manager()?;
+pub fn exec(env: &dyn Environment, _opts: WhoAmIOpts, network: NetworkOpt) -> DfxResult {
+    let mut mgr = env.new_identity_manager()?;
+    let network = network
+        .to_network_name()
+        .unwrap_or_else(|| "local".to_string());
+    use_network_identity(env, &mut mgr, &network)?;
+    match mgr.get_identity_selection() {
+        IdentitySelection::Override => debug!(
+            env.get_logger(),
+            "Identity selected with --identity or DFX_IDENTITY."
+        ),
+        IdentitySelection::Network {
+            network,
+            config_path,
+        } => info!(
+            env.get_logger(),
+            "Identity configured for network '{}' in {}.",
+            network,
+            config_path.display()
+        ),
+        IdentitySelection::Default => {}
+    }
     let identity = mgr.get_selected_identity_name();
     println!("{}", identity);
     Ok(())
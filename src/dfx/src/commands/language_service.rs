@@ -1,5 +1,5 @@
 use crate::error_invalid_data;
-use crate::lib::builders::BuildConfig;
+use crate::lib::builders::{resolve_motoko_packages, BuildConfig};
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::package_arguments::{self, PackageArguments};
@@ -47,7 +47,12 @@ pub fn exec(env: &dyn Environment, opts: LanguageServiceOpts) -> DfxResult {
             .get_build()
             .get_packtool();
 
-        let mut package_arguments = package_arguments::load(env.get_cache().as_ref(), packtool)?;
+        let packages = resolve_motoko_packages(
+            config.get_project_root(),
+            &config.get_config().get_defaults().get_build().packages,
+        )?;
+        let mut package_arguments =
+            package_arguments::load(env.get_cache().as_ref(), packtool, &packages)?;
 
         // Include actor alias flags
         let canister_names = config
@@ -7,6 +7,7 @@ use clap::Subcommand;
 mod beta;
 mod build;
 mod cache;
+mod candid;
 mod canister;
 mod completion;
 mod cycles;
@@ -20,6 +21,8 @@ mod identity;
 mod info;
 mod language_service;
 mod ledger;
+mod motoko;
+mod network;
 mod new;
 mod ping;
 mod quickstart;
@@ -37,6 +40,7 @@ pub enum DfxCommand {
     Beta(beta::BetaOpts),
     Build(build::CanisterBuildOpts),
     Cache(cache::CacheOpts),
+    Candid(candid::CandidOpts),
     Canister(canister::CanisterOpts),
     Completion(CompletionOpts),
     Cycles(cycles::CyclesOpts),
@@ -51,6 +55,8 @@ pub enum DfxCommand {
     #[command(name = "_language-service")]
     LanguageServices(language_service::LanguageServiceOpts),
     Ledger(ledger::LedgerOpts),
+    Motoko(motoko::MotokoOpts),
+    Network(network::NetworkOpts),
     New(new::NewOpts),
     Ping(ping::PingOpts),
     Quickstart(quickstart::QuickstartOpts),
@@ -70,6 +76,7 @@ pub fn exec(env: &dyn Environment, cmd: DfxCommand) -> DfxResult {
         DfxCommand::Beta(v) => beta::exec(env, v),
         DfxCommand::Build(v) => build::exec(env, v),
         DfxCommand::Cache(v) => cache::exec(env, v),
+        DfxCommand::Candid(v) => candid::exec(env, v),
         DfxCommand::Canister(v) => canister::exec(env, v),
         DfxCommand::Completion(v) => completion::exec(env, v),
         DfxCommand::Cycles(v) => cycles::exec(env, v),
@@ -83,6 +90,8 @@ pub fn exec(env: &dyn Environment, cmd: DfxCommand) -> DfxResult {
         DfxCommand::Info(v) => info::exec(env, v),
         DfxCommand::LanguageServices(v) => language_service::exec(env, v),
         DfxCommand::Ledger(v) => ledger::exec(env, v),
+        DfxCommand::Motoko(v) => motoko::exec(env, v),
+        DfxCommand::Network(v) => network::exec(env, v),
         DfxCommand::New(v) => new::exec(env, v),
         DfxCommand::Ping(v) => ping::exec(env, v),
         DfxCommand::Quickstart(v) => quickstart::exec(env, v),
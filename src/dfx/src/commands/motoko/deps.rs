@@ -0,0 +1,194 @@
+use crate::lib::builders::{
+    fetch_motoko_packages, get_motoko_lib_file, resolve_motoko_packages, ResolvedMotokoPackage,
+};
+use crate::lib::canister_info::motoko::MotokoCanisterInfo;
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::models::canister::Import;
+use anyhow::Context;
+use clap::Parser;
+use dfx_core::config::cache::Cache;
+use serde::Serialize;
+use slog::warn;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use tokio::runtime::Runtime;
+
+/// Downloads the Motoko packages declared in dfx.json, and displays the packages
+/// that the Motoko canisters and packages import.
+#[derive(Parser)]
+pub struct MotokoDepsOpts {
+    /// Displays the packages as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct Deps {
+    canisters: BTreeMap<String, BTreeSet<String>>,
+    packages: BTreeMap<String, PackageDeps>,
+}
+
+#[derive(Serialize)]
+struct PackageDeps {
+    source: String,
+    sha256: Option<String>,
+    path: PathBuf,
+    imports: BTreeSet<String>,
+}
+
+pub fn exec(env: &dyn Environment, opts: MotokoDepsOpts) -> DfxResult {
+    let log = env.get_logger();
+    let config = env.get_config_or_anyhow()?;
+    let project_root = config.get_project_root();
+    let build = config.get_config().get_defaults().get_build();
+    if build.get_packtool().is_some() {
+        warn!(
+            log,
+            "The packages of the packtool in dfx.json are not resolved by dfx and are not displayed."
+        );
+    }
+
+    let runtime = Runtime::new().expect("Unable to create a runtime");
+    runtime.block_on(fetch_motoko_packages(project_root, &build.packages))?;
+    let mut packages = resolve_motoko_packages(project_root, &build.packages)?;
+    if !packages.iter().any(|package| package.name == "base") {
+        packages.push(ResolvedMotokoPackage {
+            name: "base".to_string(),
+            source: format!("dfx {}", env.get_version()),
+            sha256: None,
+            path: env.get_cache().get_binary_command_path("base")?,
+        });
+    }
+
+    let cache = env.get_cache();
+    let mut walker = ImportWalker {
+        cache: cache.as_ref(),
+        packages: &packages,
+        visited: BTreeSet::new(),
+        imports: BTreeMap::new(),
+    };
+    let mut canisters = BTreeMap::new();
+    let canister_names = config
+        .get_config()
+        .canisters
+        .as_ref()
+        .map(|canisters| canisters.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    for name in canister_names {
+        let info = CanisterInfo::load(&config, &name, None)?;
+        if !info.is_motoko() {
+            continue;
+        }
+        let main_path = info
+            .as_info::<MotokoCanisterInfo>()?
+            .get_main_path()
+            .to_path_buf();
+        let owner = Owner::Canister(name.clone());
+        walker.walk(&main_path, &owner)?;
+        canisters.insert(name, walker.imports.remove(&owner).unwrap_or_default());
+    }
+
+    let packages = packages
+        .into_iter()
+        .filter(|package| {
+            build.packages.contains_key(&package.name)
+                || canisters
+                    .values()
+                    .any(|imports| imports.contains(&package.name))
+        })
+        .map(|package| {
+            let imports = walker
+                .imports
+                .remove(&Owner::Package(package.name.clone()))
+                .unwrap_or_default();
+            (
+                package.name,
+                PackageDeps {
+                    source: package.source,
+                    sha256: package.sha256,
+                    path: package.path,
+                    imports,
+                },
+            )
+        })
+        .collect();
+    let deps = Deps {
+        canisters,
+        packages,
+    };
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&deps)?);
+        return Ok(());
+    }
+    for (name, imports) in &deps.canisters {
+        println!("canister {name}");
+        for import in imports {
+            println!("  {import}");
+        }
+    }
+    for (name, package) in &deps.packages {
+        match &package.sha256 {
+            Some(sha256) => println!("package {name} ({}, sha256 {sha256})", package.source),
+            None => println!("package {name} ({})", package.source),
+        }
+        for import in &package.imports {
+            println!("  {import}");
+        }
+    }
+    Ok(())
+}
+
+/// A canister or a package, whose files import packages.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Owner {
+    Canister(String),
+    Package(String),
+}
+
+/// Follows the imports of Motoko files with `moc --print-deps`,
+/// into the files of the packages that dfx resolves.
+struct ImportWalker<'a> {
+    cache: &'a dyn Cache,
+    packages: &'a [ResolvedMotokoPackage],
+    visited: BTreeSet<PathBuf>,
+    imports: BTreeMap<Owner, BTreeSet<String>>,
+}
+
+impl ImportWalker<'_> {
+    fn walk(&mut self, file: &Path, owner: &Owner) -> DfxResult {
+        if !self.visited.insert(file.to_path_buf()) {
+            return Ok(());
+        }
+        let mut command = self.cache.get_binary_command("moc")?;
+        let command = command.arg("--print-deps").arg(file);
+        let output = command
+            .output()
+            .with_context(|| format!("Error executing {:#?}", command))?;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            match Import::try_from(line).context("Failed to create MotokoImport.")? {
+                Import::FullPath(path) => self.walk(&path, owner)?,
+                // The primitives of the compiler are not a package.
+                Import::Lib(import) if import == "prim" || import == "⛔" => {}
+                Import::Lib(import) => {
+                    let package = import.split('/').next().unwrap_or(&import).to_string();
+                    // Imports within a package are not dependencies.
+                    if *owner != Owner::Package(package.clone()) {
+                        self.imports
+                            .entry(owner.clone())
+                            .or_default()
+                            .insert(package.clone());
+                    }
+                    if let Some(lib_file) = get_motoko_lib_file(self.packages, &import) {
+                        self.walk(&lib_file, &Owner::Package(package))?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
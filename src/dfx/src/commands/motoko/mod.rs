@@ -0,0 +1,24 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use clap::Parser;
+
+mod deps;
+
+/// Manages the Motoko packages of the project.
+#[derive(Parser)]
+#[command(name = "motoko")]
+pub struct MotokoOpts {
+    #[command(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser)]
+pub enum SubCommand {
+    Deps(deps::MotokoDepsOpts),
+}
+
+pub fn exec(env: &dyn Environment, opts: MotokoOpts) -> DfxResult {
+    match opts.subcmd {
+        SubCommand::Deps(v) => deps::exec(env, v),
+    }
+}
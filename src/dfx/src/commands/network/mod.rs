@@ -0,0 +1,31 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::network::network_opt::NetworkOpt;
+use clap::Parser;
+
+mod snapshot;
+mod time;
+
+/// Manages the state and clock of local networks.
+#[derive(Parser)]
+#[command(name = "network")]
+pub struct NetworkOpts {
+    #[command(flatten)]
+    network: NetworkOpt,
+
+    #[command(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser)]
+enum SubCommand {
+    Snapshot(snapshot::SnapshotOpts),
+    Time(time::TimeOpts),
+}
+
+pub fn exec(env: &dyn Environment, opts: NetworkOpts) -> DfxResult {
+    match opts.subcmd {
+        SubCommand::Snapshot(v) => snapshot::exec(env, v, opts.network),
+        SubCommand::Time(v) => time::exec(env, v, opts.network),
+    }
+}
@@ -0,0 +1,24 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::network::snapshot::list_snapshots;
+use clap::Parser;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+
+/// Lists the saved snapshots of a local network.
+#[derive(Parser)]
+pub struct SnapshotListOpts {}
+
+pub fn exec(
+    _env: &dyn Environment,
+    _opts: SnapshotListOpts,
+    network_descriptor: &NetworkDescriptor,
+) -> DfxResult {
+    let local_server_descriptor = network_descriptor.local_server_descriptor()?;
+    for (name, metadata) in list_snapshots(local_server_descriptor)? {
+        println!(
+            "{} (created {}, dfx {})",
+            name, metadata.created, metadata.dfx_version
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::network::network_opt::NetworkOpt;
+use clap::Parser;
+use dfx_core::network::provider::{create_network_descriptor, LocalBindDetermination};
+
+mod list;
+mod restore;
+mod save;
+
+/// Saves and restores named snapshots of a local network's state.
+/// A snapshot holds the replica state, the canister id store and pulled dependencies.
+#[derive(Parser)]
+#[command(name = "snapshot")]
+pub struct SnapshotOpts {
+    #[command(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser)]
+enum SubCommand {
+    List(list::SnapshotListOpts),
+    Restore(restore::SnapshotRestoreOpts),
+    Save(save::SnapshotSaveOpts),
+}
+
+pub fn exec(env: &dyn Environment, opts: SnapshotOpts, network: NetworkOpt) -> DfxResult {
+    let network_descriptor = create_network_descriptor(
+        env.get_config()?,
+        env.get_networks_config(),
+        network.to_network_name(),
+        None,
+        LocalBindDetermination::AsConfigured,
+    )?;
+    match opts.subcmd {
+        SubCommand::List(v) => list::exec(env, v, &network_descriptor),
+        SubCommand::Restore(v) => restore::exec(env, v, &network_descriptor),
+        SubCommand::Save(v) => save::exec(env, v, &network_descriptor),
+    }
+}
@@ -0,0 +1,35 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::network::snapshot::restore_snapshot;
+use clap::Parser;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+use slog::{info, warn};
+
+/// Replaces the state of a stopped local network with a saved snapshot.
+#[derive(Parser)]
+pub struct SnapshotRestoreOpts {
+    /// The name of the snapshot.
+    name: String,
+}
+
+pub fn exec(
+    env: &dyn Environment,
+    opts: SnapshotRestoreOpts,
+    network_descriptor: &NetworkDescriptor,
+) -> DfxResult {
+    let config = env.get_config()?;
+    let metadata = restore_snapshot(network_descriptor, config.as_deref(), &opts.name)?;
+    if metadata.dfx_version != env.get_version().to_string() {
+        warn!(
+            env.get_logger(),
+            "Snapshot '{}' was saved with dfx {}. The replica may not be able to load its state.",
+            opts.name,
+            metadata.dfx_version
+        );
+    }
+    info!(
+        env.get_logger(),
+        "Restored snapshot '{}' of network '{}'.", opts.name, network_descriptor.name
+    );
+    Ok(())
+}
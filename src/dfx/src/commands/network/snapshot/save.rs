@@ -0,0 +1,28 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::network::snapshot::save_snapshot;
+use clap::Parser;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+use slog::info;
+
+/// Saves the state of a stopped local network under a name.
+/// Replaces any existing snapshot with the same name.
+#[derive(Parser)]
+pub struct SnapshotSaveOpts {
+    /// The name of the snapshot.
+    name: String,
+}
+
+pub fn exec(
+    env: &dyn Environment,
+    opts: SnapshotSaveOpts,
+    network_descriptor: &NetworkDescriptor,
+) -> DfxResult {
+    let config = env.get_config()?;
+    save_snapshot(network_descriptor, config.as_deref(), &opts.name)?;
+    info!(
+        env.get_logger(),
+        "Saved snapshot '{}' of network '{}'.", opts.name, network_descriptor.name
+    );
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use crate::lib::error::DfxResult;
+use crate::lib::network::time::{update_replica_clock, ReplicaClock};
+use anyhow::Context;
+use clap::Parser;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+
+/// Moves the replica's clock forward.
+#[derive(Parser)]
+pub struct TimeAdvanceOpts {
+    /// How far to move the clock, e.g. 30s, 2h or 30days.
+    duration: String,
+}
+
+pub fn exec(
+    opts: TimeAdvanceOpts,
+    network_descriptor: &NetworkDescriptor,
+) -> DfxResult<ReplicaClock> {
+    let duration = humantime::parse_duration(&opts.duration)
+        .with_context(|| format!("Invalid duration '{}'.", opts.duration))?;
+    update_replica_clock(network_descriptor.local_server_descriptor()?, |clock| {
+        clock.advance(duration)
+    })
+}
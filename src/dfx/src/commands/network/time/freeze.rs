@@ -0,0 +1,17 @@
+use crate::lib::error::DfxResult;
+use crate::lib::network::time::{update_replica_clock, ReplicaClock};
+use clap::Parser;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+
+/// Stops the replica's clock. It moves only with `set` and `advance` until unfrozen.
+#[derive(Parser)]
+pub struct TimeFreezeOpts {}
+
+pub fn exec(
+    _opts: TimeFreezeOpts,
+    network_descriptor: &NetworkDescriptor,
+) -> DfxResult<ReplicaClock> {
+    update_replica_clock(network_descriptor.local_server_descriptor()?, |clock| {
+        clock.freeze()
+    })
+}
@@ -0,0 +1,51 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::network::network_opt::NetworkOpt;
+use crate::lib::network::time::format_time;
+use clap::Parser;
+use dfx_core::network::provider::{create_network_descriptor, LocalBindDetermination};
+
+mod advance;
+mod freeze;
+mod set;
+mod unfreeze;
+
+/// Controls the clock of a local replica.
+/// The network must configure 'replica.time', which runs the replica with libfaketime.
+#[derive(Parser)]
+#[command(name = "time")]
+pub struct TimeOpts {
+    #[command(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser)]
+enum SubCommand {
+    Advance(advance::TimeAdvanceOpts),
+    Freeze(freeze::TimeFreezeOpts),
+    Set(set::TimeSetOpts),
+    Unfreeze(unfreeze::TimeUnfreezeOpts),
+}
+
+pub fn exec(env: &dyn Environment, opts: TimeOpts, network: NetworkOpt) -> DfxResult {
+    let network_descriptor = create_network_descriptor(
+        env.get_config()?,
+        env.get_networks_config(),
+        network.to_network_name(),
+        None,
+        LocalBindDetermination::AsConfigured,
+    )?;
+    let clock = match opts.subcmd {
+        SubCommand::Advance(v) => advance::exec(v, &network_descriptor),
+        SubCommand::Freeze(v) => freeze::exec(v, &network_descriptor),
+        SubCommand::Set(v) => set::exec(v, &network_descriptor),
+        SubCommand::Unfreeze(v) => unfreeze::exec(v, &network_descriptor),
+    }?;
+    let frozen = if clock.frozen_at.is_some() {
+        " (frozen)"
+    } else {
+        ""
+    };
+    println!("{}{}", format_time(clock.now()?), frozen);
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use crate::lib::error::DfxResult;
+use crate::lib::network::time::{parse_time, update_replica_clock, ReplicaClock};
+use clap::Parser;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+
+/// Sets the replica's clock to a time in the future.
+#[derive(Parser)]
+pub struct TimeSetOpts {
+    /// The time in RFC 3339 format, e.g. 2030-01-31T12:00:00Z.
+    time: String,
+}
+
+pub fn exec(opts: TimeSetOpts, network_descriptor: &NetworkDescriptor) -> DfxResult<ReplicaClock> {
+    let time = parse_time(&opts.time)?;
+    update_replica_clock(network_descriptor.local_server_descriptor()?, |clock| {
+        clock.set(time)
+    })
+}
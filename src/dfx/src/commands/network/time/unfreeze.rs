@@ -0,0 +1,17 @@
+use crate::lib::error::DfxResult;
+use crate::lib::network::time::{update_replica_clock, ReplicaClock};
+use clap::Parser;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+
+/// Lets a frozen replica clock run again from the time it shows.
+#[derive(Parser)]
+pub struct TimeUnfreezeOpts {}
+
+pub fn exec(
+    _opts: TimeUnfreezeOpts,
+    network_descriptor: &NetworkDescriptor,
+) -> DfxResult<ReplicaClock> {
+    update_replica_clock(network_descriptor.local_server_descriptor()?, |clock| {
+        clock.unfreeze()
+    })
+}
@@ -4,11 +4,13 @@ use crate::lib::error::{DfxError, DfxResult};
 use crate::lib::info::replica_rev;
 use crate::lib::manifest::{get_latest_version, is_upgrade_necessary};
 use crate::lib::program;
+use crate::lib::project::templates::{parse_template_variable, ProjectTemplate};
 use crate::util::assets;
 use crate::util::clap::parsers::project_name_parser;
 use anyhow::{anyhow, bail, ensure, Context};
 use clap::{Parser, ValueEnum};
 use console::{style, Style};
+use dfx_core::cli::ask_for_consent;
 use dfx_core::json::{load_json_file, save_json_file};
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{FuzzySelect, MultiSelect};
@@ -19,10 +21,11 @@ use slog::{info, warn, Logger};
 use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
 use std::io::{self, IsTerminal, Read};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::time::Duration;
 use tar::Archive;
+use walkdir::WalkDir;
 
 // const DRY_RUN: &str = "dry_run";
 // const PROJECT_NAME: &str = "project_name";
@@ -68,6 +71,21 @@ pub struct NewOpts {
 
     #[arg(long, value_enum)]
     extras: Vec<Extra>,
+
+    /// Creates the project from a template instead of the built-in ones: a directory, a .tar.gz
+    /// archive, a git URL (append #<ref> for a branch or tag), or the name of a template that an
+    /// extension provides.
+    #[arg(long, conflicts_with_all = ["type", "frontend", "no_frontend", "extras"])]
+    template: Option<String>,
+
+    /// Sets a variable of the template. Can be specified more than once.
+    #[arg(long = "var", value_name = "NAME=VALUE", requires("template"), value_parser = parse_template_variable)]
+    vars: Vec<(String, String)>,
+
+    /// Skips the confirmation to use a template that is downloaded from a URL or cloned from a
+    /// git repository. Such templates can run commands on this machine.
+    #[arg(long, short, requires("template"))]
+    yes: bool,
 }
 
 #[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
@@ -211,7 +229,10 @@ fn patch_file(_log: &Logger, patch_path: &Path, patch_content: &[u8], dry_run: b
             .map_err(|e| anyhow!("Failed to parse {}: {e}", patch_path.display()))?;
         let to_patch = patch_path.with_extension("");
         let existing_content = dfx_core::fs::read_to_string(&to_patch)?;
-        let patched_content = apply_patch::apply_to(&patch, &existing_content)
+        // An earlier overlay may have moved the lines that the patch applies to.
+        let patched_content = apply_patch::Settings::new()
+            .ignore_line_numbers()
+            .apply_to(&patch, &existing_content)
             .with_context(|| format!("Failed to patch {}", to_patch.display()))?;
         dfx_core::fs::write(&to_patch, patched_content)?;
     }
@@ -281,40 +302,161 @@ fn write_files_from_entries<R: Sized + Read>(
         let mut v = Vec::new();
         file.read_to_end(&mut v).map_err(DfxError::from)?;
 
-        let v = match String::from_utf8(v) {
-            Err(err) => err.into_bytes(),
-            Ok(mut s) => {
-                // Perform replacements.
-                variables.iter().for_each(|(name, value)| {
-                    let pattern = "{".to_owned() + name + "}";
-                    s = s.replace(pattern.as_str(), value);
-                });
-                s.into_bytes()
+        write_template_file(log, &file.header().path()?, v, root, dry_run, variables)?;
+    }
+
+    Ok(())
+}
+
+#[context("Failed to copy template files from {}.", dir.to_string_lossy())]
+fn write_files_from_directory(
+    log: &Logger,
+    dir: &Path,
+    root: &Path,
+    dry_run: bool,
+    variables: &BTreeMap<String, String>,
+) -> DfxResult {
+    // Sorted, so that a file is created before the .patch or .json-patch files that patch it.
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let relative_path = entry.path().strip_prefix(dir)?;
+        let v = dfx_core::fs::read(entry.path())?;
+        write_template_file(log, relative_path, v, root, dry_run, variables)?;
+    }
+    Ok(())
+}
+
+/// Creates or patches a file of the new project, replacing `{name}` in the content
+/// and `__name__` in the path with the value of each variable.
+pub fn write_template_file(
+    log: &Logger,
+    relative_path: &Path,
+    v: Vec<u8>,
+    root: &Path,
+    dry_run: bool,
+    variables: &BTreeMap<String, String>,
+) -> DfxResult {
+    let v = match String::from_utf8(v) {
+        Err(err) => err.into_bytes(),
+        Ok(s) => replace_variables(s, variables).into_bytes(),
+    };
+
+    let p = root.join(replace_path_variables(relative_path, variables)?);
+    if p.extension() == Some("json-patch".as_ref()) {
+        json_patch_file(log, &p, &v, dry_run)?;
+    } else if p.extension() == Some("patch".as_ref()) {
+        patch_file(log, &p, &v, dry_run)?;
+    } else {
+        create_file(log, p.as_path(), &v, dry_run)?;
+    }
+    Ok(())
+}
+
+/// Replaces `__name__` in a path with the value of each variable.
+/// The values cannot add directories to the path, and the path cannot leave the project.
+fn replace_path_variables(path: &Path, variables: &BTreeMap<String, String>) -> DfxResult<PathBuf> {
+    let mut p = path
+        .to_str()
+        .ok_or_else(|| anyhow!("Non unicode path {}.", path.display()))?
+        .to_string();
+    for (name, value) in variables {
+        let pattern = format!("__{name}__");
+        if p.contains(&pattern) {
+            if value.contains(['/', '\\']) {
+                bail!("The value of '{name}' cannot be used in the path {p}, because it contains a path separator.");
             }
-        };
+            p = p.replace(&pattern, value);
+        }
+    }
+    let p = PathBuf::from(p);
+    if p.components().any(|component| {
+        matches!(
+            component,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_)
+        )
+    }) {
+        bail!("The path {} is outside of the project.", p.display());
+    }
+    Ok(p)
+}
 
-        // Perform path replacements.
-        let mut p = root
-            .join(file.header().path()?)
-            .to_str()
-            .expect("Non unicode project name path.")
-            .to_string();
-
-        variables.iter().for_each(|(name, value)| {
-            let pattern = "__".to_owned() + name + "__";
-            p = p.replace(pattern.as_str(), value);
-        });
-
-        let p = PathBuf::from(p);
-        if p.extension() == Some("json-patch".as_ref()) {
-            json_patch_file(log, &p, &v, dry_run)?;
-        } else if p.extension() == Some("patch".as_ref()) {
-            patch_file(log, &p, &v, dry_run)?;
-        } else {
-            create_file(log, p.as_path(), &v, dry_run)?;
+/// Replaces `{name}` with the value of each variable.
+pub fn replace_variables(mut s: String, variables: &BTreeMap<String, String>) -> String {
+    variables.iter().for_each(|(name, value)| {
+        let pattern = "{".to_owned() + name + "}";
+        s = s.replace(pattern.as_str(), value);
+    });
+    s
+}
+
+/// Creates the project from a template given with --template.
+#[context("Failed to create project from template '{}'.", template)]
+fn create_from_template(
+    env: &dyn Environment,
+    template: &str,
+    values: &[(String, String)],
+    project_name: &Path,
+    dry_run: bool,
+    yes: bool,
+    variables: &BTreeMap<String, String>,
+) -> DfxResult {
+    let log = env.get_logger();
+    let template_name = template;
+    let template = ProjectTemplate::fetch(env, template_name)?;
+    if let Some(description) = &template.manifest.description {
+        info!(log, "Using template: {}", description);
+    }
+    if template.remote && !dry_run && !yes {
+        let mut message = format!("The template {template_name} is not from this machine.");
+        if !template.manifest.post_create.is_empty() {
+            message.push_str(" It runs these commands in the new project:");
+            for command in &template.manifest.post_create {
+                message.push_str(&format!("\n  {command}"));
+            }
         }
+        ask_for_consent(&message)?;
+    }
+    let mut variables = variables.clone();
+    variables.extend(
+        template
+            .manifest
+            .resolve_variables(values, io::stdin().is_terminal())?,
+    );
+    for files in &template.manifest.files {
+        write_files_from_directory(
+            log,
+            &template.root.join(files),
+            project_name,
+            dry_run,
+            &variables,
+        )?;
     }
 
+    if !dry_run {
+        // Values are quoted, so that each is passed to the commands as it is.
+        let quoted_variables = variables
+            .iter()
+            .map(|(name, value)| (name.clone(), shell_words::quote(value).into_owned()))
+            .collect();
+        for command in &template.manifest.post_create {
+            let command = replace_variables(command.clone(), &quoted_variables);
+            info!(log, "Running '{}'...", command);
+            let status = Command::new("sh")
+                .args(["-c", &command])
+                .current_dir(project_name)
+                .stdin(Stdio::inherit())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .status()
+                .with_context(|| format!("Failed to run '{command}'."))?;
+            if !status.success() {
+                bail!("'{command}' failed with {status}.");
+            }
+        }
+    }
     Ok(())
 }
 
@@ -472,7 +614,11 @@ pub fn exec(env: &dyn Environment, mut opts: NewOpts) -> DfxResult {
 
     let r#type = if let Some(r#type) = opts.r#type {
         r#type
-    } else if opts.frontend.is_none() && opts.extras.is_empty() && io::stdout().is_terminal() {
+    } else if opts.template.is_none()
+        && opts.frontend.is_none()
+        && opts.extras.is_empty()
+        && io::stdout().is_terminal()
+    {
         opts = get_opts_interactively(opts)?;
         opts.r#type.unwrap()
     } else {
@@ -546,73 +692,87 @@ pub fn exec(env: &dyn Environment, mut opts: NewOpts) -> DfxResult {
         ("ic_commit".to_string(), replica_rev().to_string()),
     ]);
 
-    write_files_from_entries(
-        log,
-        &mut assets::new_project_base_files().context("Failed to get base project archive.")?,
-        project_name,
-        dry_run,
-        &variables,
-    )?;
-
-    let frontend = if opts.no_frontend {
-        FrontendType::None
-    } else {
-        opts.frontend.unwrap_or(FrontendType::Vanilla)
-    };
-
-    if r#type == Azle || frontend.has_js() {
-        write_files_from_entries(
-            log,
-            &mut assets::new_project_js_files().context("Failed to get JS config archive.")?,
+    if let Some(template) = &opts.template {
+        create_from_template(
+            env,
+            template,
+            &opts.vars,
             project_name,
             dry_run,
+            opts.yes,
             &variables,
         )?;
-    }
-
-    // Default to start with motoko
-    let mut new_project_files = match r#type {
-        Rust => assets::new_project_rust_files().context("Failed to get rust archive.")?,
-        Motoko => assets::new_project_motoko_files().context("Failed to get motoko archive.")?,
-        Azle => assets::new_project_azle_files().context("Failed to get azle archive.")?,
-        Kybra => assets::new_project_kybra_files().context("Failed to get kybra archive.")?,
-    };
-    write_files_from_entries(
-        log,
-        &mut new_project_files,
-        project_name,
-        dry_run,
-        &variables,
-    )?;
-
-    if opts.extras.contains(&Extra::InternetIdentity) {
+    } else {
         write_files_from_entries(
             log,
-            &mut assets::new_project_internet_identity_files()?,
+            &mut assets::new_project_base_files().context("Failed to get base project archive.")?,
             project_name,
             dry_run,
             &variables,
         )?;
-    }
-    if opts.extras.contains(&Extra::Bitcoin) {
+
+        let frontend = if opts.no_frontend {
+            FrontendType::None
+        } else {
+            opts.frontend.unwrap_or(FrontendType::Vanilla)
+        };
+
+        if r#type == Azle || frontend.has_js() {
+            write_files_from_entries(
+                log,
+                &mut assets::new_project_js_files().context("Failed to get JS config archive.")?,
+                project_name,
+                dry_run,
+                &variables,
+            )?;
+        }
+
+        // Default to start with motoko
+        let mut new_project_files = match r#type {
+            Rust => assets::new_project_rust_files().context("Failed to get rust archive.")?,
+            Motoko => {
+                assets::new_project_motoko_files().context("Failed to get motoko archive.")?
+            }
+            Azle => assets::new_project_azle_files().context("Failed to get azle archive.")?,
+            Kybra => assets::new_project_kybra_files().context("Failed to get kybra archive.")?,
+        };
         write_files_from_entries(
             log,
-            &mut assets::new_project_bitcoin_files()?,
+            &mut new_project_files,
             project_name,
             dry_run,
             &variables,
         )?;
-    }
-    if frontend != FrontendType::None {
-        scaffold_frontend_code(
-            env,
-            dry_run,
-            project_name,
-            frontend,
-            &opts.extras,
-            &opts.agent_version,
-            &variables,
-        )?;
+
+        if opts.extras.contains(&Extra::InternetIdentity) {
+            write_files_from_entries(
+                log,
+                &mut assets::new_project_internet_identity_files()?,
+                project_name,
+                dry_run,
+                &variables,
+            )?;
+        }
+        if opts.extras.contains(&Extra::Bitcoin) {
+            write_files_from_entries(
+                log,
+                &mut assets::new_project_bitcoin_files()?,
+                project_name,
+                dry_run,
+                &variables,
+            )?;
+        }
+        if frontend != FrontendType::None {
+            scaffold_frontend_code(
+                env,
+                dry_run,
+                project_name,
+                frontend,
+                &opts.extras,
+                &opts.agent_version,
+                &variables,
+            )?;
+        }
     }
 
     if !dry_run {
@@ -774,4 +934,27 @@ mod tests {
         assert!(project_name_parser("不好").is_err());
         assert!(project_name_parser("a:b").is_err());
     }
+
+    #[test]
+    fn path_variables_stay_in_the_project() {
+        let variables = BTreeMap::from([
+            ("project_name".to_string(), "hello".to_string()),
+            ("dot".to_string(), ".".to_string()),
+            ("dir".to_string(), "a/b".to_string()),
+            ("up".to_string(), "..".to_string()),
+        ]);
+        assert_eq!(
+            replace_path_variables(Path::new("src/__project_name__/main.mo"), &variables).unwrap(),
+            PathBuf::from("src/hello/main.mo")
+        );
+        assert_eq!(
+            replace_path_variables(Path::new("__dot__gitignore"), &variables).unwrap(),
+            PathBuf::from(".gitignore")
+        );
+        assert!(replace_path_variables(Path::new("src/__dir__/main.mo"), &variables).is_err());
+        assert!(replace_path_variables(Path::new("__up__/main.mo"), &variables).is_err());
+        assert!(replace_path_variables(Path::new("__dot____dot__/main.mo"), &variables).is_err());
+        // values that are not used in the path do not matter
+        assert!(replace_path_variables(Path::new("dfx.json"), &variables).is_ok());
+    }
 }
@@ -6,11 +6,15 @@ use crate::actors::{
 };
 use crate::config::dfx_version_str;
 use crate::error_invalid_argument;
+use crate::lib::canister_http_mock::MockOutcalls;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::info::replica_rev;
 use crate::lib::integrations::status::wait_for_integrations_initialized;
 use crate::lib::network::id::write_network_id;
+use crate::lib::network::local_network_is_running;
+use crate::lib::network::snapshot::{save_snapshot, validate_snapshot_name};
+use crate::lib::network::time::prepare_time_control;
 use crate::lib::replica::status::ping_and_wait;
 use crate::lib::replica_config::ReplicaConfig;
 use crate::util::get_reusable_socket_addr;
@@ -34,8 +38,8 @@ use std::io::Read;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use sysinfo::{Pid, System, SystemExt};
 use tokio::runtime::Runtime;
 
 /// Starts the local replica and a web server for the current project.
@@ -65,6 +69,11 @@ pub struct StartOpts {
     #[arg(long)]
     enable_canister_http: bool,
 
+    /// Answer HTTPS outcalls of canisters from the fixtures in this file instead of the internet.
+    /// Implies canister http requests are enabled.
+    #[arg(long, value_name = "FILE")]
+    canister_http_mock: Option<PathBuf>,
+
     /// The delay (in milliseconds) an update call should take. Lower values may be expedient in CI.
     #[arg(long, default_value_t = 600)]
     artificial_delay: u32,
@@ -80,6 +89,12 @@ pub struct StartOpts {
     /// A list of domains that can be served. These are used for canister resolution [default: localhost]
     #[arg(long)]
     domain: Vec<String>,
+
+    /// Save the network state as a named snapshot once the replica stops.
+    /// `dfx stop` waits until the snapshot is saved.
+    /// Restore it later with `dfx network snapshot restore <name>`.
+    #[arg(long, value_name = "NAME")]
+    save_state: Option<String>,
 }
 
 // The frontend webserver is brought up by the bg process; thus, the fg process
@@ -143,11 +158,16 @@ pub fn exec(
         bitcoin_node,
         enable_bitcoin,
         enable_canister_http,
+        canister_http_mock,
         artificial_delay,
         use_old_metering,
         domain,
+        save_state,
     }: StartOpts,
 ) -> DfxResult {
+    if let Some(name) = &save_state {
+        validate_snapshot_name(name)?;
+    }
     if !background {
         info!(
             env.get_logger(),
@@ -178,6 +198,7 @@ pub fn exec(
         enable_bitcoin,
         bitcoin_node,
         enable_canister_http,
+        canister_http_mock.is_some(),
         domain,
     )?;
 
@@ -239,6 +260,24 @@ pub fn exec(
 
     let replica_port_path = empty_writable_path(local_server_descriptor.replica_port_path())?;
 
+    let replica_time_control = local_server_descriptor
+        .replica
+        .time
+        .as_ref()
+        .map(|time| prepare_time_control(local_server_descriptor, time))
+        .transpose()?;
+
+    let canister_http_mock = canister_http_mock
+        .map(|fixtures_path| {
+            MockOutcalls::load(
+                &fixtures_path,
+                local_server_descriptor.canister_http_mock_log_path(),
+                env.get_logger().clone(),
+            )
+            .map(Arc::new)
+        })
+        .transpose()?;
+
     if background {
         send_background()?;
         return Runtime::new()
@@ -333,6 +372,7 @@ pub fn exec(
     save_json_file(&previous_config_path, &effective_config)
         .context("Failed to write replica configuration")?;
 
+    let snapshot_network_descriptor = network_descriptor.clone();
     let network_descriptor = network_descriptor.clone();
 
     let system = actix::System::new();
@@ -359,6 +399,7 @@ pub fn exec(
                         canister_http_adapter_config.get_socket_path(),
                         shutdown_controller.clone(),
                         canister_http_adapter_pid_file_path,
+                        canister_http_mock,
                     )
                 })
                 .transpose()?;
@@ -367,6 +408,7 @@ pub fn exec(
                 env,
                 replica_config,
                 local_server_descriptor,
+                replica_time_control,
                 shutdown_controller.clone(),
                 btc_adapter_ready_subscribe,
                 canister_http_adapter_ready_subscribe,
@@ -400,6 +442,18 @@ pub fn exec(
         let _ = std::fs::remove_file(canister_http_socket_path);
     }
 
+    if let Some(name) = save_state {
+        // All child processes have exited, so the replica state is consistent.
+        // This process is done with the network as well.
+        let _ = std::fs::remove_file(&pid_file_path);
+        save_snapshot(
+            &snapshot_network_descriptor,
+            env.get_config()?.as_deref(),
+            &name,
+        )?;
+        info!(env.get_logger(), "Saved snapshot '{}'.", name);
+    }
+
     Ok(())
 }
 
@@ -436,6 +490,7 @@ pub fn apply_command_line_parameters(
     enable_bitcoin: bool,
     bitcoin_nodes: Vec<SocketAddr>,
     enable_canister_http: bool,
+    canister_http_mock: bool,
     domain: Vec<String>,
 ) -> DfxResult<NetworkDescriptor> {
     if enable_canister_http {
@@ -469,6 +524,10 @@ pub fn apply_command_line_parameters(
         local_server_descriptor = local_server_descriptor.with_bitcoin_nodes(bitcoin_nodes)
     }
 
+    if canister_http_mock {
+        local_server_descriptor = local_server_descriptor.with_canister_http_enabled();
+    }
+
     if !domain.is_empty() {
         local_server_descriptor = local_server_descriptor.with_proxy_domains(domain)
     }
@@ -548,20 +607,8 @@ fn frontend_address(
 fn check_previous_process_running(
     local_server_descriptor: &LocalServerDescriptor,
 ) -> DfxResult<()> {
-    for pid_path in local_server_descriptor.dfx_pid_paths() {
-        if pid_path.exists() {
-            // Read and verify it's not running. If it is just return.
-            if let Ok(s) = std::fs::read_to_string(&pid_path) {
-                if let Ok(pid) = s.parse::<Pid>() {
-                    // If we find the pid in the file, we tell the user and don't start!
-                    let mut system = System::new();
-                    system.refresh_processes();
-                    if let Some(_process) = system.process(pid) {
-                        bail!("dfx is already running.");
-                    }
-                }
-            }
-        }
+    if local_network_is_running(local_server_descriptor) {
+        bail!("dfx is already running.");
     }
     Ok(())
 }
@@ -1,5 +1,6 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
+use crate::lib::network::snapshot::snapshot_in_progress;
 use anyhow::bail;
 use clap::Parser;
 use dfx_core::network::provider::{create_network_descriptor, LocalBindDetermination};
@@ -31,8 +32,14 @@ fn descendant_pids(system: &System, proc: &Process) -> Vec<Pid> {
     processes.iter().map(|proc| proc.pid()).collect()
 }
 
-fn wait_until_all_exited(mut system: System, mut pids: Vec<Pid>) -> DfxResult {
+/// Waits for the processes to exit, for as long as `dfx start --save-state` is saving a snapshot.
+fn wait_until_all_exited(
+    mut system: System,
+    mut pids: Vec<Pid>,
+    snapshot_in_progress: impl Fn() -> bool,
+) -> DfxResult {
     let mut retries = 0;
+    let mut waiting_for_snapshot = false;
 
     loop {
         system.refresh_processes();
@@ -42,6 +49,14 @@ fn wait_until_all_exited(mut system: System, mut pids: Vec<Pid>) -> DfxResult {
         if pids.is_empty() {
             return Ok(());
         }
+        if snapshot_in_progress() {
+            if !waiting_for_snapshot {
+                eprintln!("Waiting for the network snapshot to be saved.");
+                waiting_for_snapshot = true;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+            continue;
+        }
         if retries >= 30 {
             let remaining = pids
                 .iter()
@@ -64,11 +79,9 @@ pub fn exec(env: &dyn Environment, _opts: StopOpts) -> DfxResult {
         LocalBindDetermination::AsConfigured,
     )?;
 
+    let local_server_descriptor = network_descriptor.local_server_descriptor()?;
     let mut found = false;
-    for pid_file_path in network_descriptor
-        .local_server_descriptor()?
-        .dfx_pid_paths()
-    {
+    for pid_file_path in local_server_descriptor.dfx_pid_paths() {
         if pid_file_path.exists() {
             // Read and verify it's not running. If it is just return.
             if let Ok(s) = std::fs::read_to_string(&pid_file_path) {
@@ -84,7 +97,9 @@ pub fn exec(env: &dyn Environment, _opts: StopOpts) -> DfxResult {
                         vec![]
                     };
 
-                    wait_until_all_exited(system, descendant_pids)?;
+                    wait_until_all_exited(system, descendant_pids, || {
+                        snapshot_in_progress(local_server_descriptor)
+                    })?;
                 }
             }
             // We ignore errors here because there is no effect for the user. We're just being nice.
@@ -134,6 +134,7 @@ impl CanisterBuilder for AssetsBuilder {
         )?;
 
         let assets_canister_info = info.as_info::<AssetsCanisterInfo>()?;
+        assets_canister_info.write_custom_domains()?;
         assets_canister_info.assert_source_paths()?;
 
         Ok(())
@@ -0,0 +1,135 @@
+//! Environment variables configured with `env` in dfx.json.
+//!
+//! Values can refer to other variables as `${NAME}`. Those are looked up in the environment of dfx,
+//! and then in the `.env.<network>` file in the project root.
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::error::DfxResult;
+use anyhow::{bail, Context};
+use dfx_core::util::network_to_pathcompat;
+use fn_error_context::context;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub struct ConfiguredEnvVar {
+    pub name: String,
+    pub value: String,
+    pub secret: bool,
+}
+
+/// The variables from dfx.json that are set for this canister on this network, with references resolved.
+#[context("Failed to resolve the environment variables of canister {}.", info.get_name())]
+pub fn resolve_configured_env(
+    info: &CanisterInfo,
+    network_name: &str,
+) -> DfxResult<Vec<ConfiguredEnvVar>> {
+    if info.get_env().is_empty() {
+        return Ok(vec![]);
+    }
+    let dotenv = load_network_dotenv(info.get_workspace_root(), network_name)?;
+    let lookup = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .or_else(|| dotenv.get(name).cloned())
+    };
+    let mut vars = vec![];
+    for (name, configured) in info.get_env() {
+        let Some(value) = configured.value_for_network(network_name) else {
+            continue;
+        };
+        let value = interpolate(value, lookup)
+            .with_context(|| format!("Failed to resolve the value of {name}."))?;
+        vars.push(ConfiguredEnvVar {
+            name: name.clone(),
+            value,
+            secret: configured.is_secret(),
+        });
+    }
+    Ok(vars)
+}
+
+/// Reads `.env.<network>` from the project root, if it exists.
+fn load_network_dotenv(
+    project_root: &Path,
+    network_name: &str,
+) -> DfxResult<BTreeMap<String, String>> {
+    let path = project_root.join(format!(".env.{}", network_to_pathcompat(network_name)));
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = dfx_core::fs::read_to_string(&path)?;
+    Ok(parse_dotenv(&content))
+}
+
+/// Parses `NAME=value` lines, as written by `output_env_file`.
+/// Blank lines and lines starting with `#` are ignored, as is a leading `export`.
+fn parse_dotenv(content: &str) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        let value = ['\'', '"']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|value| value.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+        vars.insert(name.trim().to_string(), value.to_string());
+    }
+    vars
+}
+
+/// Replaces every `${NAME}` in `value` with the value of `NAME`.
+fn interpolate(value: &str, lookup: impl Fn(&str) -> Option<String>) -> DfxResult<String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find('}') else {
+            bail!("Unterminated '${{' in '{value}'.");
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        match lookup(name) {
+            Some(resolved) => result.push_str(&resolved),
+            None => bail!(
+                "Variable '{name}' is not set in the environment or in the .env file of the network."
+            ),
+        }
+        rest = &rest[start + 2 + len + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_variables() {
+        let lookup = |name: &str| (name == "HOST").then(|| "example.com".to_string());
+        assert_eq!(
+            interpolate("https://${HOST}/api?x=$1", lookup).unwrap(),
+            "https://example.com/api?x=$1"
+        );
+        assert!(interpolate("${MISSING}", lookup).is_err());
+        assert!(interpolate("${HOST", lookup).is_err());
+    }
+
+    #[test]
+    fn parses_dotenv() {
+        let vars = parse_dotenv("# comment\nexport A=1\nB = 'two words'\n\nC=\"3\"\ninvalid\n");
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars["A"], "1");
+        assert_eq!(vars["B"], "two words");
+        assert_eq!(vars["C"], "3");
+    }
+}
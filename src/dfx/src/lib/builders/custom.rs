@@ -1,3 +1,4 @@
+use crate::lib::builders::download_cache::download_verified;
 use crate::lib::builders::{
     BuildConfig, BuildOutput, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
 };
@@ -6,7 +7,6 @@ use crate::lib::canister_info::CanisterInfo;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::models::canister::CanisterPool;
-use crate::util::download_file_to_path;
 use anyhow::{anyhow, Context};
 use candid::Principal as CanisterId;
 use console::style;
@@ -22,10 +22,14 @@ struct CustomBuilderExtra {
     dependencies: Vec<CanisterId>,
     /// Where to download the wasm from
     input_wasm_url: Option<Url>,
+    /// The expected SHA256 hash of the downloaded wasm
+    input_wasm_hash: Option<String>,
     /// Where the wasm output will be located.
     wasm: PathBuf,
     /// Where to download the candid from
     input_candid_url: Option<Url>,
+    /// The expected SHA256 hash of the downloaded candid
+    input_candid_hash: Option<String>,
     /// Where the candid output will be located.
     candid: PathBuf,
     /// A command to run to build this canister. This is optional if the canister
@@ -49,16 +53,20 @@ impl CustomBuilderExtra {
             .collect::<DfxResult<Vec<CanisterId>>>().with_context( || format!("Failed to collect dependencies (canister ids) of canister {}.", info.get_name()))?;
         let info = info.as_info::<CustomCanisterInfo>()?;
         let input_wasm_url = info.get_input_wasm_url().to_owned();
+        let input_wasm_hash = info.get_input_wasm_hash().map(String::from);
         let wasm = info.get_output_wasm_path().to_owned();
         let input_candid_url = info.get_input_candid_url().to_owned();
+        let input_candid_hash = info.get_input_candid_hash().map(String::from);
         let candid = info.get_output_idl_path().to_owned();
         let build = info.get_build_tasks().to_owned();
 
         Ok(CustomBuilderExtra {
             dependencies,
             input_wasm_url,
+            input_wasm_hash,
             wasm,
             input_candid_url,
+            input_candid_hash,
             candid,
             build,
         })
@@ -103,8 +111,10 @@ impl CanisterBuilder for CustomBuilder {
     ) -> DfxResult<BuildOutput> {
         let CustomBuilderExtra {
             input_candid_url: _,
+            input_candid_hash: _,
             candid,
             input_wasm_url: _,
+            input_wasm_hash: _,
             wasm,
             build,
             dependencies,
@@ -153,18 +163,21 @@ impl CanisterBuilder for CustomBuilder {
 pub async fn custom_download(info: &CanisterInfo, pool: &CanisterPool) -> DfxResult {
     let CustomBuilderExtra {
         input_candid_url,
+        input_candid_hash,
         candid,
         input_wasm_url,
+        input_wasm_hash,
         wasm,
         build: _,
         dependencies: _,
     } = CustomBuilderExtra::try_from(info, pool)?;
+    let project_root = info.get_workspace_root();
 
     if let Some(url) = input_wasm_url {
-        download_file_to_path(&url, &wasm).await?;
+        download_verified(&url, input_wasm_hash.as_deref(), &wasm, project_root).await?;
     }
     if let Some(url) = input_candid_url {
-        download_file_to_path(&url, &candid).await?;
+        download_verified(&url, input_candid_hash.as_deref(), &candid, project_root).await?;
     }
 
     Ok(())
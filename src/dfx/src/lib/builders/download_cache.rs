@@ -0,0 +1,193 @@
+//! Downloads of the `wasm` and `candid` URLs of custom canisters, and of Motoko package archives.
+//!
+//! Downloaded files are kept under the dfx cache by their SHA256 hash, so a file whose hash is
+//! already known (from dfx.json or from the lockfile) is not downloaded again.
+//! `dfx.lock.json` in the project root records the hash of every downloaded URL,
+//! and a later download that does not match it is rejected.
+use crate::lib::error::DfxResult;
+use crate::util::download_file;
+use anyhow::bail;
+use dfx_core::config::cache::get_cache_root;
+use dfx_core::fs::create_dir_all;
+use dfx_core::json::{load_json_file, save_json_file};
+use fn_error_context::context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+pub const LOCKFILE_NAME: &str = "dfx.lock.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// The resolved hash of each downloaded URL.
+    #[serde(default)]
+    pub downloads: BTreeMap<String, LockedDownload>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockedDownload {
+    pub sha256: String,
+}
+
+fn get_lockfile_path(project_root: &Path) -> PathBuf {
+    project_root.join(LOCKFILE_NAME)
+}
+
+#[context("Failed to load {}.", LOCKFILE_NAME)]
+fn load_lockfile(project_root: &Path) -> DfxResult<Lockfile> {
+    let path = get_lockfile_path(project_root);
+    if !path.exists() {
+        return Ok(Lockfile::default());
+    }
+    Ok(load_json_file(&path)?)
+}
+
+#[context("Failed to save {}.", LOCKFILE_NAME)]
+fn save_lockfile(project_root: &Path, lockfile: &Lockfile) -> DfxResult {
+    save_json_file(&get_lockfile_path(project_root), lockfile)?;
+    Ok(())
+}
+
+/// <cache root>/downloads/<sha256>
+fn get_cached_download_path(sha256: &str) -> DfxResult<PathBuf> {
+    Ok(get_cache_root()?.join("downloads").join(sha256))
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+fn normalize_hash(hash: &str) -> DfxResult<String> {
+    let hash = hash.trim().to_ascii_lowercase();
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid SHA256 hash '{hash}'. Expected 64 hexadecimal digits.");
+    }
+    Ok(hash)
+}
+
+/// The cached file with this hash, if there is one and it is intact.
+fn read_cached_download(sha256: &str) -> DfxResult<Option<Vec<u8>>> {
+    let path = get_cached_download_path(sha256)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = dfx_core::fs::read(&path)?;
+    Ok((sha256_hex(&content) == sha256).then_some(content))
+}
+
+fn write_cached_download(sha256: &str, content: &[u8]) -> DfxResult {
+    let path = get_cached_download_path(sha256)?;
+    create_dir_all(path.parent().unwrap())?;
+    let partial_path = path.with_extension("partial");
+    dfx_core::fs::write(&partial_path, content)?;
+    dfx_core::fs::rename(&partial_path, &path)?;
+    Ok(())
+}
+
+/// Check a download against the hash from dfx.json, or else against the hash in the lockfile.
+fn verify_download(
+    url: &Url,
+    sha256: &str,
+    configured: Option<&str>,
+    locked: Option<&str>,
+) -> DfxResult {
+    match (configured, locked) {
+        (Some(configured), _) if sha256 != configured => bail!(
+            "The file downloaded from {url} has hash {sha256}, but dfx.json expects {configured}."
+        ),
+        (None, Some(locked)) if sha256 != locked => bail!(
+            "The file at {url} changed since it was recorded in {LOCKFILE_NAME}: expected hash {locked}, got {sha256}. \
+            If the change is expected, remove its entry from {LOCKFILE_NAME}."
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// The hash of `url` from dfx.json, or else from the lockfile, if it is known.
+pub fn locked_hash(
+    url: &Url,
+    expected_hash: Option<&str>,
+    project_root: &Path,
+) -> DfxResult<Option<String>> {
+    match expected_hash {
+        Some(hash) => Ok(Some(normalize_hash(hash)?)),
+        None => Ok(load_lockfile(project_root)?
+            .downloads
+            .get(url.as_str())
+            .map(|locked| locked.sha256.clone())),
+    }
+}
+
+/// Download `url` to `to`, or copy it from the cache if its hash is known.
+/// The resolved hash is recorded in the project's lockfile, and returned.
+#[context("Failed to download {} to {}.", url, to.display())]
+pub async fn download_verified(
+    url: &Url,
+    expected_hash: Option<&str>,
+    to: &Path,
+    project_root: &Path,
+) -> DfxResult<String> {
+    let mut lockfile = load_lockfile(project_root)?;
+    let configured = expected_hash.map(normalize_hash).transpose()?;
+    let locked = lockfile
+        .downloads
+        .get(url.as_str())
+        .map(|locked| locked.sha256.clone());
+
+    let cached = match configured.as_deref().or(locked.as_deref()) {
+        Some(sha256) => read_cached_download(sha256)?,
+        None => None,
+    };
+    let (content, sha256) = match cached {
+        Some(content) => {
+            let sha256 = sha256_hex(&content);
+            (content, sha256)
+        }
+        None => {
+            let content = download_file(url).await?;
+            let sha256 = sha256_hex(&content);
+            verify_download(url, &sha256, configured.as_deref(), locked.as_deref())?;
+            write_cached_download(&sha256, &content)?;
+            (content, sha256)
+        }
+    };
+
+    create_dir_all(to.parent().unwrap())?;
+    dfx_core::fs::write(to, content)?;
+
+    if locked.as_deref() != Some(sha256.as_str()) {
+        lockfile.downloads.insert(
+            url.to_string(),
+            LockedDownload {
+                sha256: sha256.clone(),
+            },
+        );
+        save_lockfile(project_root, &lockfile)?;
+    }
+    Ok(sha256)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_hash_takes_precedence_over_lockfile() {
+        let url = Url::parse("https://example.com/ledger.wasm").unwrap();
+        let a = "a".repeat(64);
+        let b = "b".repeat(64);
+        assert!(verify_download(&url, &a, Some(&a), Some(&b)).is_ok());
+        assert!(verify_download(&url, &b, Some(&a), Some(&b)).is_err());
+        assert!(verify_download(&url, &b, None, Some(&a)).is_err());
+        assert!(verify_download(&url, &b, None, None).is_ok());
+    }
+
+    #[test]
+    fn hashes_are_normalized() {
+        assert_eq!(normalize_hash(&"AB".repeat(32)).unwrap(), "ab".repeat(32));
+        assert!(normalize_hash("abc").is_err());
+        assert!(normalize_hash(&"zz".repeat(32)).is_err());
+    }
+}
@@ -27,14 +27,22 @@ use std::process::{Command, Stdio};
 use std::sync::Arc;
 
 mod assets;
+mod config_env;
 mod custom;
+mod download_cache;
 mod motoko;
+mod motoko_packages;
 mod pull;
 mod rust;
 
 pub use custom::custom_download;
+pub use motoko_packages::{
+    fetch_motoko_packages, get_motoko_lib_file, resolve_downloaded_motoko_packages,
+    resolve_motoko_packages, ResolvedMotokoPackage,
+};
 
 use self::motoko::add_imports;
+use self::motoko_packages::{get_motoko_package, package_versions_changed_since};
 
 #[derive(Debug)]
 pub enum WasmBuildOutput {
@@ -274,6 +282,15 @@ pub trait CanisterBuilder {
         //     .collect();
 
         self.read_dependencies(pool, canister_info, cache)?;
+        // The packages are downloaded before the canisters are built.
+        let motoko_packages = if canister_info.is_motoko() {
+            resolve_motoko_packages(
+                canister_info.get_workspace_root(),
+                canister_info.get_motoko_packages(),
+            )?
+        } else {
+            vec![]
+        };
 
         // Check that one of the dependencies is newer than the target:
         if let Ok(wasm_file_metadata) = metadata(output_wasm_path) {
@@ -336,9 +353,23 @@ pub trait CanisterBuilder {
                             //     None
                             // }
                         }
-                        Import::Lib(_path) => {
-                            // Skip libs, all changes by package managers don't modify existing directories but create new ones.
-                            continue;
+                        Import::Lib(path) => {
+                            match get_motoko_package(&motoko_packages, path) {
+                                // The files of a downloaded package never change, only which
+                                // archive the package uses.
+                                Some(package) if package.sha256.is_some() => {
+                                    if package_versions_changed_since(
+                                        canister_info.get_workspace_root(),
+                                        wasm_file_time,
+                                    )? {
+                                        break;
+                                    }
+                                    continue;
+                                }
+                                Some(_) => get_motoko_lib_file(&motoko_packages, path),
+                                // Skip libs of package managers, their changes don't modify existing directories but create new ones.
+                                None => continue,
+                            }
                         }
                         Import::FullPath(full_path) => {
                             Some(full_path.clone()) // TODO: Eliminate `clone`.
@@ -502,9 +533,10 @@ pub fn execute_command(
     for (key, value) in vars {
         cmd.env(key.as_ref(), value);
     }
+    // Not printing `cmd` itself, because that would include the values of secret variables.
     let output = cmd
         .output()
-        .with_context(|| format!("Error executing custom build step {cmd:#?}"))?;
+        .with_context(|| format!("Error executing custom build step '{command}'"))?;
     if output.status.success() {
         Ok(output.stdout)
     } else {
@@ -532,15 +564,25 @@ pub fn get_and_write_environment_variables<'a>(
     dependencies: &[CanisterId],
     write_path: Option<&Path>,
 ) -> DfxResult<Vec<Env<'a>>> {
-    // should not return Err unless write_environment_variables does
     use Cow::*;
-    let mut vars = vec![
+    // Variables from dfx.json come first, so that the ones set by dfx take precedence.
+    let configured = config_env::resolve_configured_env(info, network_name)?;
+    let secrets = configured
+        .iter()
+        .filter(|var| var.secret)
+        .map(|var| var.name.clone())
+        .collect::<Vec<_>>();
+    let mut vars = configured
+        .into_iter()
+        .map(|var| (Owned(var.name), Owned(var.value.into())))
+        .collect::<Vec<Env<'a>>>();
+    vars.extend([
         (
             Borrowed("DFX_VERSION"),
             Borrowed(dfx_version_str().as_ref()),
         ),
         (Borrowed("DFX_NETWORK"), Borrowed(network_name.as_ref())),
-    ];
+    ]);
     for dep in dependencies {
         let canister = if let Some(canister) = pool.get_canister(dep) {
             canister
@@ -589,7 +631,13 @@ pub fn get_and_write_environment_variables<'a>(
     }
 
     if let Some(write_path) = write_path {
-        write_environment_variables(&vars, write_path)?;
+        // Secrets must never be written to disk.
+        let public_vars = vars
+            .iter()
+            .filter(|(var, _)| !secrets.iter().any(|secret| secret == var))
+            .cloned()
+            .collect::<Vec<_>>();
+        write_environment_variables(&public_vars, write_path)?;
     }
     Ok(vars)
 }
@@ -649,6 +697,10 @@ pub struct BuildConfig {
     pub user_specified_canisters: Option<Vec<String>>,
     /// If environment variables should be output to a `.env` file, `env_file` is set to its path.
     pub env_file: Option<PathBuf>,
+    /// Only build the modules, without the JavaScript bindings or the frontend of asset canisters.
+    /// `dfx deploy --plan` builds canisters that do not exist yet with placeholder IDs,
+    /// which must not end up in the files of the project.
+    pub modules_only: bool,
 }
 
 impl BuildConfig {
@@ -669,6 +721,7 @@ impl BuildConfig {
             lsp_root: network_root.join("lsp/"),
             user_specified_canisters: None,
             env_file: config.get_output_env_file(None)?,
+            modules_only: false,
         })
     }
 
@@ -689,6 +742,13 @@ impl BuildConfig {
     pub fn with_env_file(self, env_file: Option<PathBuf>) -> Self {
         Self { env_file, ..self }
     }
+
+    pub fn with_modules_only(self, modules_only: bool) -> Self {
+        Self {
+            modules_only,
+            ..self
+        }
+    }
 }
 
 pub struct BuilderPool {
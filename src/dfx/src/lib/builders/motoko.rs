@@ -1,5 +1,6 @@
 use crate::lib::builders::{
-    BuildConfig, BuildOutput, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
+    get_motoko_lib_file, resolve_downloaded_motoko_packages, resolve_motoko_packages, BuildConfig,
+    BuildOutput, CanisterBuilder, IdlBuildOutput, ResolvedMotokoPackage, WasmBuildOutput,
 };
 use crate::lib::canister_info::motoko::MotokoCanisterInfo;
 use crate::lib::canister_info::CanisterInfo;
@@ -12,7 +13,9 @@ use crate::util::assets::management_idl;
 use anyhow::{anyhow, Context};
 use candid::Principal as CanisterId;
 use dfx_core::config::cache::Cache;
-use dfx_core::config::model::dfinity::{MetadataVisibility, Profile};
+use dfx_core::config::model::dfinity::{
+    MetadataVisibility, MotokoGc, MotokoMetadataSection, MotokoWarnings, Profile,
+};
 use fn_error_context::context;
 use slog::{info, o, trace, warn, Logger};
 use std::collections::BTreeMap;
@@ -49,6 +52,9 @@ pub fn add_imports(
     pool: &CanisterPool,
 ) -> DfxResult<()> {
     let motoko_info = info.as_info::<MotokoCanisterInfo>()?;
+    // Packages that are not downloaded yet are not followed, the build downloads them.
+    let packages =
+        resolve_downloaded_motoko_packages(info.get_workspace_root(), motoko_info.get_packages())?;
     #[context("Failed recursive dependency detection at {}.", file.display())]
     fn add_imports_recursive(
         cache: &dyn Cache,
@@ -56,6 +62,7 @@ pub fn add_imports(
         imports: &mut ImportsTracker,
         pool: &CanisterPool,
         top: Option<&CanisterInfo>, // hackish
+        packages: &[ResolvedMotokoPackage],
     ) -> DfxResult {
         let base_path = file
             .parent()
@@ -85,7 +92,29 @@ pub fn add_imports(
             let child = Import::try_from(line).context("Failed to create MotokoImport.")?;
             match &child {
                 Import::FullPath(full_child_path) => {
-                    add_imports_recursive(cache, full_child_path.as_path(), imports, pool, None)?;
+                    add_imports_recursive(
+                        cache,
+                        full_child_path.as_path(),
+                        imports,
+                        pool,
+                        None,
+                        packages,
+                    )?;
+                }
+                Import::Lib(path) => {
+                    // Follow the imports of packages resolved by dfx, so that their changes are noticed.
+                    if let Some(lib_file) = get_motoko_lib_file(packages, path) {
+                        let lib_file = dfx_core::fs::canonicalize(&lib_file)?;
+                        add_imports_recursive(cache, &lib_file, imports, pool, None, packages)?;
+                        let lib_node_index = *imports
+                            .nodes
+                            .entry(child.clone())
+                            .or_insert_with(|| imports.graph.add_node(child.clone()));
+                        let lib_file_node_index = imports.nodes[&Import::FullPath(lib_file)];
+                        imports
+                            .graph
+                            .update_edge(lib_node_index, lib_file_node_index, ());
+                    }
                 }
                 Import::Canister(canister_name) => {
                     // duplicate code
@@ -100,6 +129,7 @@ pub fn add_imports(
                             imports,
                             pool,
                             Some(canister.get_info()),
+                            packages,
                         )?;
                     }
                 }
@@ -127,6 +157,7 @@ pub fn add_imports(
         imports,
         pool,
         Some(info),
+        &packages,
     )?;
 
     Ok(())
@@ -222,8 +253,12 @@ impl CanisterBuilder for MotokoBuilder {
 
         let cache = &self.cache;
 
+        let packages = resolve_motoko_packages(
+            canister_info.get_workspace_root(),
+            motoko_info.get_packages(),
+        )?;
         let package_arguments =
-            package_arguments::load(cache.as_ref(), motoko_info.get_packtool())?;
+            package_arguments::load(cache.as_ref(), motoko_info.get_packtool(), &packages)?;
         let mut package_arguments_map = BTreeMap::<&str, &str>::new();
         {
             // block
@@ -258,13 +293,23 @@ impl CanisterBuilder for MotokoBuilder {
             .map(|m| m.visibility)
             .unwrap_or(MetadataVisibility::Public);
 
+        let options = motoko_info.get_moc_options().for_profile(profile);
+        let strip_debug = options
+            .strip_debug
+            .unwrap_or(matches!(profile, Profile::Release));
+
         // Generate wasm
         let params = MotokoParams {
-            build_target: match profile {
-                Profile::Release => BuildTarget::Release,
-                _ => BuildTarget::Debug,
+            build_target: if strip_debug {
+                BuildTarget::Release
+            } else {
+                BuildTarget::Debug
             },
-            suppress_warning: false,
+            warnings: options.warnings.unwrap_or(MotokoWarnings::Show),
+            gc: options.gc,
+            stable_regions: options.stable_regions.unwrap_or(false),
+            public_metadata: options.public_metadata.as_deref().unwrap_or_default(),
+            suppress_warning: options.warnings == Some(MotokoWarnings::Hide),
             input: input_path,
             package_arguments: &moc_arguments,
             candid_service_metadata_visibility,
@@ -316,6 +361,10 @@ struct MotokoParams<'a> {
     package_arguments: &'a PackageArguments,
     candid_service_metadata_visibility: MetadataVisibility,
     candid_args_metadata_visibility: MetadataVisibility,
+    warnings: MotokoWarnings,
+    gc: Option<MotokoGc>,
+    stable_regions: bool,
+    public_metadata: &'a [MotokoMetadataSection],
     output: &'a Path,
     input: &'a Path,
     // The following fields are control flags for dfx and will not be used by self.to_args()
@@ -339,6 +388,24 @@ impl MotokoParams<'_> {
             // moc defaults to private metadata, if this argument is not present.
             cmd.arg("--public-metadata").arg(CANDID_ARGS);
         }
+        for section in self.public_metadata {
+            cmd.arg("--public-metadata").arg(section.name());
+        }
+        match self.warnings {
+            MotokoWarnings::Show => {}
+            MotokoWarnings::Hide => {
+                cmd.arg("--hide-warnings");
+            }
+            MotokoWarnings::Error => {
+                cmd.arg("-Werror");
+            }
+        }
+        if let Some(gc) = self.gc {
+            cmd.arg(gc.moc_flag());
+        }
+        if self.stable_regions {
+            cmd.arg("--stable-regions");
+        }
         if !self.idl_map.is_empty() {
             cmd.arg("--actor-idl").arg(self.idl_path);
             for (name, canister_id) in self.idl_map.iter() {
@@ -0,0 +1,333 @@
+//! Motoko packages declared in the `packages` of `defaults.build` in dfx.json.
+//!
+//! A package is a local directory, or a .tar.gz archive that is downloaded like the files of
+//! custom canisters, and extracted to `<cache root>/motoko-packages/<sha256>`.
+//! Once the hash of an archive is recorded in dfx.lock.json and the archive is extracted,
+//! the package resolves without the network.
+use crate::lib::builders::download_cache::{download_verified, locked_hash, LOCKFILE_NAME};
+use crate::lib::error::DfxResult;
+use anyhow::{bail, Context};
+use dfx_core::config::cache::get_cache_root;
+use dfx_core::config::model::dfinity::{MotokoPackage, CONFIG_FILE_NAME};
+use dfx_core::fs::{create_dir_all, remove_dir_all};
+use fn_error_context::context;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use url::Url;
+
+/// A Motoko package, with the directory of its sources.
+#[derive(Clone, Debug)]
+pub struct ResolvedMotokoPackage {
+    pub name: String,
+    /// The URL of the archive, or the local directory, as in dfx.json.
+    pub source: String,
+    /// The hash of the archive.
+    pub sha256: Option<String>,
+    pub path: PathBuf,
+}
+
+/// Whether dfx.json or dfx.lock.json changed after `time`. Together they choose the archives of
+/// the packages, whose extracted files keep their age when a package switches to an archive
+/// that was extracted before.
+pub fn package_versions_changed_since(project_root: &Path, time: SystemTime) -> DfxResult<bool> {
+    for file in [CONFIG_FILE_NAME, LOCKFILE_NAME] {
+        let path = project_root.join(file);
+        if path.exists() && dfx_core::fs::metadata(&path)?.modified()? > time {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// <cache root>/motoko-packages
+fn get_packages_cache_root() -> DfxResult<PathBuf> {
+    Ok(get_cache_root()?.join("motoko-packages"))
+}
+
+fn get_url(name: &str, package: &MotokoPackage) -> DfxResult<Option<Url>> {
+    package
+        .url
+        .as_deref()
+        .map(|url| {
+            Url::parse(url).with_context(|| format!("Invalid url of Motoko package '{name}'."))
+        })
+        .transpose()
+}
+
+/// Download and extract the archives of the packages that are not in the cache.
+#[context("Failed to fetch Motoko packages.")]
+pub async fn fetch_motoko_packages(
+    project_root: &Path,
+    packages: &BTreeMap<String, MotokoPackage>,
+) -> DfxResult {
+    for (name, package) in packages {
+        let Some(url) = get_url(name, package)? else {
+            continue;
+        };
+        if let Some(sha256) = locked_hash(&url, package.sha256.as_deref(), project_root)? {
+            if get_packages_cache_root()?.join(sha256).exists() {
+                continue;
+            }
+        }
+        let cache_root = get_packages_cache_root()?;
+        create_dir_all(&cache_root)?;
+        let archive = tempfile::NamedTempFile::new_in(&cache_root)
+            .context("Failed to create a temporary file.")?;
+        let sha256 = download_verified(
+            &url,
+            package.sha256.as_deref(),
+            archive.path(),
+            project_root,
+        )
+        .await?;
+        extract_archive(archive.path(), &cache_root.join(sha256))
+            .with_context(|| format!("Failed to extract Motoko package '{name}'."))?;
+    }
+    Ok(())
+}
+
+fn extract_archive(archive: &Path, to: &Path) -> DfxResult {
+    let partial = to.with_extension("partial");
+    if partial.exists() {
+        remove_dir_all(&partial)?;
+    }
+    create_dir_all(&partial)?;
+    let content = dfx_core::fs::read(archive)?;
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(content.as_slice()));
+    // Extracted files are as new as the extraction. A package that switches back to an
+    // archive extracted earlier is noticed with `package_versions_changed_since`.
+    tar.set_preserve_mtime(false);
+    tar.unpack(&partial)
+        .with_context(|| format!("Failed to unpack {}.", archive.display()))?;
+    dfx_core::fs::rename(&partial, to)?;
+    Ok(())
+}
+
+/// Resolve the packages to the directories of their sources, without the network.
+#[context("Failed to resolve Motoko packages.")]
+pub fn resolve_motoko_packages(
+    project_root: &Path,
+    packages: &BTreeMap<String, MotokoPackage>,
+) -> DfxResult<Vec<ResolvedMotokoPackage>> {
+    packages
+        .iter()
+        .map(|(name, package)| {
+            resolve_motoko_package(project_root, name, package)?.with_context(|| {
+                format!("Motoko package '{name}' is not downloaded. Run 'dfx motoko deps' or 'dfx build' to download it.")
+            })
+        })
+        .collect()
+}
+
+/// Like `resolve_motoko_packages`, but leaves out the packages that are not downloaded yet.
+#[context("Failed to resolve Motoko packages.")]
+pub fn resolve_downloaded_motoko_packages(
+    project_root: &Path,
+    packages: &BTreeMap<String, MotokoPackage>,
+) -> DfxResult<Vec<ResolvedMotokoPackage>> {
+    packages
+        .iter()
+        .filter_map(|(name, package)| {
+            resolve_motoko_package(project_root, name, package).transpose()
+        })
+        .collect()
+}
+
+/// Resolve a package to the directory of its sources, or None if it is not downloaded yet.
+fn resolve_motoko_package(
+    project_root: &Path,
+    name: &str,
+    package: &MotokoPackage,
+) -> DfxResult<Option<ResolvedMotokoPackage>> {
+    let (source, sha256, path) = match get_url(name, package)? {
+        None => {
+            let path = package
+                .path
+                .as_ref()
+                .with_context(|| format!("Motoko package '{name}' needs a url or a path."))?;
+            (path.display().to_string(), None, project_root.join(path))
+        }
+        Some(url) => {
+            let Some(sha256) =
+                locked_hash(&url, package.sha256.as_deref(), project_root)?.filter(|sha256| {
+                    get_packages_cache_root()
+                        .map(|root| root.join(sha256).is_dir())
+                        .unwrap_or(false)
+                })
+            else {
+                return Ok(None);
+            };
+            let root = get_archive_root(&get_packages_cache_root()?.join(&sha256))?;
+            let path = match &package.path {
+                Some(path) => root.join(path),
+                None if root.join("src").is_dir() => root.join("src"),
+                None => root,
+            };
+            (url.to_string(), Some(sha256), path)
+        }
+    };
+    if !path.is_dir() {
+        bail!(
+            "The directory {} of Motoko package '{}' does not exist.",
+            path.display(),
+            name
+        );
+    }
+    Ok(Some(ResolvedMotokoPackage {
+        name: name.to_string(),
+        source,
+        sha256,
+        path,
+    }))
+}
+
+/// The directory of an extracted archive, below its top-level directory if it has only one.
+fn get_archive_root(dir: &Path) -> DfxResult<PathBuf> {
+    let entries = dfx_core::fs::read_dir(dir)?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to read {}.", dir.display()))?;
+    match entries.as_slice() {
+        [entry] if entry.path().is_dir() => Ok(entry.path()),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
+
+/// The package that `mo:<import>` refers to, if it is one of the packages.
+pub fn get_motoko_package<'a>(
+    packages: &'a [ResolvedMotokoPackage],
+    import: &str,
+) -> Option<&'a ResolvedMotokoPackage> {
+    let name = import.split_once('/').map_or(import, |(name, _)| name);
+    packages.iter().find(|package| package.name == name)
+}
+
+/// The file that `mo:<import>` refers to, if it is in one of the packages.
+pub fn get_motoko_lib_file(packages: &[ResolvedMotokoPackage], import: &str) -> Option<PathBuf> {
+    let package = get_motoko_package(packages, import)?;
+    let path = import.split_once('/').map_or("lib", |(_, path)| path);
+    [
+        package.path.join(format!("{path}.mo")),
+        package.path.join(path).join("lib.mo"),
+    ]
+    .into_iter()
+    .find(|file| file.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_imports_to_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = dir.path().join("src");
+        std::fs::create_dir_all(package.join("Map")).unwrap();
+        std::fs::write(package.join("lib.mo"), "").unwrap();
+        std::fs::write(package.join("Array.mo"), "").unwrap();
+        std::fs::write(package.join("Map").join("lib.mo"), "").unwrap();
+
+        let mut packages = BTreeMap::new();
+        packages.insert(
+            "mylib".to_string(),
+            MotokoPackage {
+                url: None,
+                sha256: None,
+                path: Some(PathBuf::from("src")),
+            },
+        );
+        let resolved = resolve_motoko_packages(dir.path(), &packages).unwrap();
+        assert_eq!(resolved[0].path, package);
+
+        assert_eq!(
+            get_motoko_lib_file(&resolved, "mylib/Array"),
+            Some(package.join("Array.mo"))
+        );
+        assert_eq!(
+            get_motoko_lib_file(&resolved, "mylib/Map"),
+            Some(package.join("Map").join("lib.mo"))
+        );
+        assert_eq!(
+            get_motoko_lib_file(&resolved, "mylib"),
+            Some(package.join("lib.mo"))
+        );
+        assert_eq!(get_motoko_lib_file(&resolved, "mylib/Nope"), None);
+        assert_eq!(get_motoko_lib_file(&resolved, "base/Array"), None);
+    }
+
+    #[test]
+    fn packages_that_are_not_downloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        let mut packages = BTreeMap::new();
+        packages.insert(
+            "base".to_string(),
+            MotokoPackage {
+                url: Some("https://example.com/motoko-base.tar.gz".to_string()),
+                sha256: Some("00".repeat(32)),
+                path: None,
+            },
+        );
+        packages.insert(
+            "mylib".to_string(),
+            MotokoPackage {
+                url: None,
+                sha256: None,
+                path: Some(PathBuf::from("src")),
+            },
+        );
+        let resolved = resolve_downloaded_motoko_packages(dir.path(), &packages).unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].name, "mylib");
+        assert!(resolve_motoko_packages(dir.path(), &packages).is_err());
+
+        // Other errors are not hidden.
+        packages.insert(
+            "missing".to_string(),
+            MotokoPackage {
+                url: None,
+                sha256: None,
+                path: Some(PathBuf::from("missing")),
+            },
+        );
+        assert!(resolve_downloaded_motoko_packages(dir.path(), &packages).is_err());
+    }
+
+    #[test]
+    fn package_versions_change_with_the_project_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let before = SystemTime::now() - std::time::Duration::from_secs(60);
+        assert!(!package_versions_changed_since(dir.path(), before).unwrap());
+        std::fs::write(dir.path().join(LOCKFILE_NAME), "{}").unwrap();
+        assert!(package_versions_changed_since(dir.path(), before).unwrap());
+        let after = SystemTime::now() + std::time::Duration::from_secs(60);
+        assert!(!package_versions_changed_since(dir.path(), after).unwrap());
+    }
+
+    #[test]
+    fn extracts_archives_below_their_top_level_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            vec![],
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "motoko-base-0.11.1/src/Array.mo", &[][..])
+            .unwrap();
+        let archive = dir.path().join("base.tar.gz");
+        std::fs::write(&archive, builder.into_inner().unwrap().finish().unwrap()).unwrap();
+
+        let extracted = dir.path().join("extracted");
+        extract_archive(&archive, &extracted).unwrap();
+        let root = get_archive_root(&extracted).unwrap();
+        assert_eq!(root, extracted.join("motoko-base-0.11.1"));
+        let file = root.join("src").join("Array.mo");
+        let age = std::fs::metadata(file).unwrap().modified().unwrap();
+        assert!(age.elapsed().unwrap().as_secs() < 60 * 60);
+    }
+}
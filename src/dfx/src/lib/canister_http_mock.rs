@@ -0,0 +1,408 @@
+//! Serves HTTPS outcalls of the local replica from a fixture file instead of the internet.
+//!
+//! The replica talks to the HTTPS outcalls adapter over gRPC on a unix domain socket.
+//! In mock mode dfx listens on that socket itself, answers every request with the first
+//! matching fixture, and appends a record of each request to a log file.
+use crate::lib::error::DfxResult;
+use anyhow::{bail, Context};
+use crossbeam::channel::Receiver;
+use dfx_core::json::load_json_file;
+use fn_error_context::context;
+use proto::https_outcalls_service_server::{HttpsOutcallsService, HttpsOutcallsServiceServer};
+use proto::{HttpHeader, HttpMethod, HttpsOutcallRequest, HttpsOutcallResponse};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use slog::{debug, warn, Logger};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tonic::Status;
+
+/// # Mocked HTTPS Outcalls
+/// The fixture file passed to `dfx start --canister-http-mock`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MockFixtures {
+    /// Checked in order; the first fixture that matches a request answers it.
+    pub outcalls: Vec<MockOutcall>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct MockOutcall {
+    /// A regular expression that must match the whole request URL.
+    pub url: String,
+
+    /// `GET`, `POST` or `HEAD`. Matches any method if omitted.
+    pub method: Option<String>,
+
+    #[serde(default = "default_status")]
+    pub status: u32,
+
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Sent as-is if it is a string, and as JSON text otherwise.
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+
+    /// How long to wait before answering.
+    #[serde(default)]
+    pub latency_ms: u64,
+
+    /// Fail the outcall instead of answering it.
+    pub error: Option<MockOutcallError>,
+}
+
+fn default_status() -> u32 {
+    200
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MockOutcallError {
+    /// The host could not be reached.
+    Unavailable,
+    /// The host did not answer in time.
+    Timeout,
+    /// The response exceeded the `max_response_bytes` of the request.
+    ResponseTooLarge,
+}
+
+/// One line of the outcall log.
+#[derive(Debug, Serialize)]
+struct OutcallLogEntry<'a> {
+    time: OffsetDateTime,
+    url: &'a str,
+    method: &'a str,
+    headers: Vec<(&'a str, &'a str)>,
+    body: String,
+    /// Index of the fixture that answered the request.
+    fixture: Option<usize>,
+    status: Option<u32>,
+    error: Option<String>,
+}
+
+struct CompiledOutcall {
+    url: Regex,
+    outcall: MockOutcall,
+}
+
+pub struct MockOutcalls {
+    outcalls: Vec<CompiledOutcall>,
+    log_path: PathBuf,
+    log_lock: Mutex<()>,
+    logger: Logger,
+}
+
+impl MockOutcalls {
+    #[context("Failed to load HTTPS outcall fixtures from {}.", fixtures_path.display())]
+    pub fn load(fixtures_path: &Path, log_path: PathBuf, logger: Logger) -> DfxResult<Self> {
+        let fixtures: MockFixtures = load_json_file(fixtures_path)?;
+        Self::new(fixtures, log_path, logger)
+    }
+
+    pub fn new(fixtures: MockFixtures, log_path: PathBuf, logger: Logger) -> DfxResult<Self> {
+        let outcalls = fixtures
+            .outcalls
+            .into_iter()
+            .enumerate()
+            .map(|(index, outcall)| {
+                if let Some(method) = &outcall.method {
+                    if !["GET", "POST", "HEAD"].contains(&method.to_uppercase().as_str()) {
+                        bail!("Fixture {index}: unsupported method '{method}'.");
+                    }
+                }
+                let url = Regex::new(&format!("^(?:{})$", outcall.url))
+                    .with_context(|| format!("Fixture {index}: invalid url pattern."))?;
+                Ok(CompiledOutcall { url, outcall })
+            })
+            .collect::<DfxResult<Vec<_>>>()?;
+        // Each `dfx start` begins with an empty log.
+        dfx_core::fs::write(&log_path, "")?;
+        Ok(Self {
+            outcalls,
+            log_path,
+            log_lock: Mutex::new(()),
+            logger,
+        })
+    }
+
+    fn find(&self, url: &str, method: &str) -> Option<(usize, &MockOutcall)> {
+        self.outcalls
+            .iter()
+            .enumerate()
+            .find(|(_, c)| {
+                c.url.is_match(url)
+                    && c.outcall
+                        .method
+                        .as_ref()
+                        .map_or(true, |m| m.eq_ignore_ascii_case(method))
+            })
+            .map(|(index, c)| (index, &c.outcall))
+    }
+
+    async fn handle(&self, request: HttpsOutcallRequest) -> Result<HttpsOutcallResponse, Status> {
+        let method = method_name(request.method);
+        let found = self.find(&request.url, method);
+        if let Some((_, outcall)) = found {
+            if outcall.latency_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(outcall.latency_ms)).await;
+            }
+        }
+        let result = match found {
+            None => Err(Status::unavailable(format!(
+                "No fixture matches {} {}",
+                method, request.url
+            ))),
+            Some((_, outcall)) => respond(outcall, request.max_response_size_bytes),
+        };
+        debug!(
+            self.logger,
+            "Mocked HTTPS outcall {} {}: {:?}",
+            method,
+            request.url,
+            result.as_ref().map(|r| r.status)
+        );
+        self.log(&request, method, found.map(|(index, _)| index), &result);
+        result
+    }
+
+    fn log(
+        &self,
+        request: &HttpsOutcallRequest,
+        method: &str,
+        fixture: Option<usize>,
+        result: &Result<HttpsOutcallResponse, Status>,
+    ) {
+        let entry = OutcallLogEntry {
+            time: OffsetDateTime::now_utc(),
+            url: &request.url,
+            method,
+            headers: request
+                .headers
+                .iter()
+                .map(|h| (h.name.as_str(), h.value.as_str()))
+                .collect(),
+            body: String::from_utf8_lossy(&request.body).to_string(),
+            fixture,
+            status: result.as_ref().ok().map(|r| r.status),
+            error: result.as_ref().err().map(|e| e.message().to_string()),
+        };
+        let _guard = self.log_lock.lock().unwrap();
+        let written = serde_json::to_string(&entry)
+            .map_err(std::io::Error::from)
+            .and_then(|line| {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.log_path)?;
+                writeln!(file, "{line}")
+            });
+        if let Err(e) = written {
+            warn!(
+                self.logger,
+                "Failed to write {}: {}",
+                self.log_path.display(),
+                e
+            );
+        }
+    }
+}
+
+fn respond(
+    outcall: &MockOutcall,
+    max_response_size_bytes: u64,
+) -> Result<HttpsOutcallResponse, Status> {
+    match outcall.error {
+        Some(MockOutcallError::Unavailable) => {
+            return Err(Status::unavailable("Mocked connection failure"))
+        }
+        Some(MockOutcallError::Timeout) => return Err(Status::deadline_exceeded("Mocked timeout")),
+        Some(MockOutcallError::ResponseTooLarge) => {
+            return Err(Status::out_of_range("Mocked response size limit exceeded"))
+        }
+        None => {}
+    }
+    let content = match &outcall.body {
+        None => vec![],
+        Some(serde_json::Value::String(s)) => s.clone().into_bytes(),
+        Some(value) => value.to_string().into_bytes(),
+    };
+    let headers: Vec<_> = outcall
+        .headers
+        .iter()
+        .map(|(name, value)| HttpHeader {
+            name: name.clone(),
+            value: value.clone(),
+        })
+        .collect();
+    let size = content.len()
+        + headers
+            .iter()
+            .map(|h| h.name.len() + h.value.len())
+            .sum::<usize>();
+    if max_response_size_bytes > 0 && size as u64 > max_response_size_bytes {
+        return Err(Status::out_of_range(format!(
+            "Response size {size} exceeds the limit of {max_response_size_bytes} bytes"
+        )));
+    }
+    Ok(HttpsOutcallResponse {
+        status: outcall.status,
+        headers,
+        content,
+    })
+}
+
+/// Listen on the adapter socket until a message arrives on `stop_receiver`.
+/// `on_ready` is called once the socket accepts connections.
+pub fn serve(
+    mock: Arc<MockOutcalls>,
+    socket_path: &Path,
+    stop_receiver: Receiver<()>,
+    on_ready: impl FnOnce(),
+) -> DfxResult {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to create a runtime for the HTTPS outcalls mock.")?;
+    runtime.block_on(async move {
+        if socket_path.exists() {
+            dfx_core::fs::remove_file(socket_path)?;
+        }
+        let listener = tokio::net::UnixListener::bind(socket_path)
+            .with_context(|| format!("Failed to bind {}.", socket_path.display()))?;
+        on_ready();
+        let stop = async move {
+            let _ = tokio::task::spawn_blocking(move || stop_receiver.recv()).await;
+        };
+        tonic::transport::Server::builder()
+            .add_service(HttpsOutcallsServiceServer::from_arc(mock))
+            .serve_with_incoming_shutdown(
+                tokio_stream::wrappers::UnixListenerStream::new(listener),
+                stop,
+            )
+            .await
+            .context("HTTPS outcalls mock failed.")
+    })
+}
+
+/// The `https_outcalls_service.v1` protocol spoken between replica and adapter,
+/// generated from `assets/proto` by the build script.
+mod proto {
+    tonic::include_proto!("https_outcalls_service.v1");
+}
+
+fn method_name(method: i32) -> &'static str {
+    match HttpMethod::try_from(method) {
+        Ok(HttpMethod::Get) => "GET",
+        Ok(HttpMethod::Post) => "POST",
+        Ok(HttpMethod::Head) => "HEAD",
+        Ok(HttpMethod::Unspecified) | Err(_) => "UNSPECIFIED",
+    }
+}
+
+#[tonic::async_trait]
+impl HttpsOutcallsService for MockOutcalls {
+    async fn https_outcall(
+        &self,
+        request: tonic::Request<HttpsOutcallRequest>,
+    ) -> Result<tonic::Response<HttpsOutcallResponse>, Status> {
+        self.handle(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The log file is deleted when the returned `TempPath` is dropped.
+    fn mock(fixtures: &str) -> (MockOutcalls, tempfile::TempPath) {
+        let fixtures = serde_json::from_str(fixtures).unwrap();
+        let log_path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+        let logger = Logger::root(slog::Discard, slog::o!());
+        let mock = MockOutcalls::new(fixtures, log_path.to_path_buf(), logger).unwrap();
+        (mock, log_path)
+    }
+
+    #[test]
+    fn first_matching_fixture_wins() {
+        let (mock, _log_path) = mock(
+            r#"{
+                "outcalls": [
+                    { "url": "https://api\\.example\\.com/price\\?.*", "method": "POST", "status": 201 },
+                    { "url": "https://api\\.example\\.com/.*", "body": { "price": 42 } }
+                ]
+            }"#,
+        );
+        let (index, _) = mock
+            .find("https://api.example.com/price?coin=icp", "post")
+            .unwrap();
+        assert_eq!(index, 0);
+        let (index, outcall) = mock
+            .find("https://api.example.com/price?coin=icp", "GET")
+            .unwrap();
+        assert_eq!(index, 1);
+        let response = respond(outcall, 0).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content, br#"{"price":42}"#);
+        assert!(mock.find("https://api.example.org/", "GET").is_none());
+        assert!(mock.find("https://api.example.com", "GET").is_none());
+    }
+
+    #[test]
+    fn errors_are_injected() {
+        let (mock, _log_path) = mock(
+            r#"{
+                "outcalls": [
+                    { "url": "https://down\\.example\\.com/.*", "error": "unavailable" },
+                    { "url": "https://big\\.example\\.com/.*", "body": "0123456789" }
+                ]
+            }"#,
+        );
+        let (_, down) = mock.find("https://down.example.com/", "GET").unwrap();
+        assert_eq!(
+            respond(down, 0).unwrap_err().code(),
+            tonic::Code::Unavailable
+        );
+        let (_, big) = mock.find("https://big.example.com/", "GET").unwrap();
+        assert_eq!(respond(big, 5).unwrap_err().code(), tonic::Code::OutOfRange);
+        assert!(respond(big, 10).is_ok());
+    }
+
+    #[test]
+    fn outcalls_are_logged() {
+        let (mock, log_path) = mock(
+            r#"{ "outcalls": [ { "url": "https://api\\.example\\.com/.*", "status": 204 } ] }"#,
+        );
+        for url in ["https://api.example.com/a", "https://api.example.org/b"] {
+            let request = HttpsOutcallRequest {
+                url: url.to_string(),
+                method: HttpMethod::Post as i32,
+                body: b"ping".to_vec(),
+                ..Default::default()
+            };
+            let (fixture, result) = match mock.find(url, "POST") {
+                Some((index, outcall)) => (Some(index), respond(outcall, 0)),
+                None => (None, Err(Status::unavailable("No fixture matches"))),
+            };
+            mock.log(&request, "POST", fixture, &result);
+        }
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let entries: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["url"], "https://api.example.com/a");
+        assert_eq!(entries[0]["fixture"], 0);
+        assert_eq!(entries[0]["status"], 204);
+        assert_eq!(entries[0]["body"], "ping");
+        assert_eq!(entries[1]["fixture"], serde_json::Value::Null);
+        assert_eq!(entries[1]["error"], "No fixture matches");
+    }
+}
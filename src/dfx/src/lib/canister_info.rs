@@ -7,13 +7,15 @@ use candid::Principal as CanisterId;
 use candid::Principal;
 use core::panic;
 use dfx_core::config::model::dfinity::{
-    CanisterDeclarationsConfig, CanisterMetadataSection, CanisterTypeProperties, Config, Pullable,
-    TechStack, WasmOptLevel,
+    CanisterDeclarationsConfig, CanisterMetadataSection, CanisterTypeProperties, Config,
+    ConfigEnvValue, ConfigHooks, ConfigMotokoOptions, MotokoPackage, Pullable, TechStack,
+    WasmOptLevel,
 };
 use dfx_core::fs::canonicalize;
 use dfx_core::network::provider::get_network_context;
 use dfx_core::util;
 use fn_error_context::context;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 pub mod assets;
@@ -48,11 +50,15 @@ pub struct CanisterInfo {
     canister_id: Option<CanisterId>,
 
     packtool: Option<String>,
+    motoko_packages: BTreeMap<String, MotokoPackage>,
     args: Option<String>,
+    moc: ConfigMotokoOptions,
     type_specific: CanisterTypeProperties,
 
     dependencies: Vec<String>,
     post_install: Vec<String>,
+    hooks: ConfigHooks,
+    env: BTreeMap<String, ConfigEnvValue>,
     main: Option<PathBuf>,
     shrink: Option<bool>,
     optimize: Option<WasmOptLevel>,
@@ -146,6 +152,8 @@ impl CanisterInfo {
         };
 
         let post_install = canister_config.post_install.clone().into_vec();
+        let mut env = config.get_config().env.clone();
+        env.extend(canister_config.env.clone());
         let metadata = CanisterMetadataConfig::new(&canister_config.metadata, &network_name);
 
         let gzip = canister_config.gzip.unwrap_or(false);
@@ -163,10 +171,14 @@ impl CanisterInfo {
             output_root,
             canister_id,
             packtool: build_defaults.get_packtool(),
+            motoko_packages: build_defaults.packages.clone(),
             args,
+            moc: canister_config.moc.clone(),
             type_specific,
             dependencies,
             post_install,
+            hooks: canister_config.hooks.clone(),
+            env,
             main: canister_config.main.clone(),
             shrink: canister_config.shrink,
             optimize: canister_config.optimize,
@@ -246,14 +258,31 @@ impl CanisterInfo {
         &self.packtool
     }
 
+    pub fn get_motoko_packages(&self) -> &BTreeMap<String, MotokoPackage> {
+        &self.motoko_packages
+    }
+
     pub fn get_post_install(&self) -> &[String] {
         &self.post_install
     }
 
+    pub fn get_hooks(&self) -> &ConfigHooks {
+        &self.hooks
+    }
+
+    /// The environment variables configured in dfx.json, with the canister's own taking precedence.
+    pub fn get_env(&self) -> &BTreeMap<String, ConfigEnvValue> {
+        &self.env
+    }
+
     pub fn get_args(&self) -> &Option<String> {
         &self.args
     }
 
+    pub fn get_moc_options(&self) -> &ConfigMotokoOptions {
+        &self.moc
+    }
+
     pub fn get_shrink(&self) -> Option<bool> {
         self.shrink
     }
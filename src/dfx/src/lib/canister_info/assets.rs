@@ -13,14 +13,23 @@ pub struct AssetsCanisterInfo {
     output_idl_path: PathBuf,
     build: Vec<String>,
     workspace: Option<String>,
+    custom_domains: Vec<String>,
+    custom_domains_path: PathBuf,
+    upload_progress_path: PathBuf,
 }
 
 impl AssetsCanisterInfo {
+    /// The folders to upload, including the generated folder with /.well-known/ic-domains.
     pub fn get_source_paths(&self) -> Vec<PathBuf> {
-        self.source_paths
+        let mut source_paths: Vec<PathBuf> = self
+            .source_paths
             .iter()
             .map(|sp| self.input_root.join(sp))
-            .collect::<_>()
+            .collect::<_>();
+        if !self.custom_domains.is_empty() {
+            source_paths.push(self.custom_domains_path.clone());
+        }
+        source_paths
     }
     pub fn get_output_idl_path(&self) -> &Path {
         self.output_idl_path.as_path()
@@ -31,6 +40,26 @@ impl AssetsCanisterInfo {
     pub fn get_npm_workspace(&self) -> Option<&str> {
         self.workspace.as_deref()
     }
+    pub fn get_custom_domains(&self) -> &[String] {
+        &self.custom_domains
+    }
+    /// Where the chunks of an upload that did not complete are recorded, so that it can be resumed.
+    pub fn get_upload_progress_path(&self) -> &Path {
+        self.upload_progress_path.as_path()
+    }
+
+    /// Generates the folder that serves /.well-known/ic-domains.
+    #[context("Failed to generate /.well-known/ic-domains.")]
+    pub fn write_custom_domains(&self) -> DfxResult {
+        if self.custom_domains.is_empty() {
+            return Ok(());
+        }
+        if self.custom_domains_path.exists() {
+            dfx_core::fs::remove_dir_all(&self.custom_domains_path)?;
+        }
+        ic_asset::write_custom_domains(&self.custom_domains_path, &self.custom_domains)?;
+        Ok(())
+    }
 
     #[context("Failed to assert source paths.")]
     pub fn assert_source_paths(&self) -> DfxResult<()> {
@@ -59,24 +88,28 @@ impl CanisterInfoFactory for AssetsCanisterInfo {
     fn create(info: &CanisterInfo) -> DfxResult<Self> {
         let input_root = info.get_workspace_root().to_path_buf();
         // If there are no "source" field, we just ignore this.
-        let (source_paths, build, workspace) = if let CanisterTypeProperties::Assets {
-            source,
-            build,
-            workspace,
-        } = info.type_specific.clone()
-        {
-            (source, build.into_vec(), workspace)
-        } else {
-            bail!(
-                "Attempted to construct an assets canister from a type:{} canister config",
-                info.type_specific.name()
-            )
-        };
+        let (source_paths, build, workspace, custom_domains) =
+            if let CanisterTypeProperties::Assets {
+                source,
+                build,
+                workspace,
+                custom_domains,
+            } = info.type_specific.clone()
+            {
+                (source, build.into_vec(), workspace, custom_domains)
+            } else {
+                bail!(
+                    "Attempted to construct an assets canister from a type:{} canister config",
+                    info.type_specific.name()
+                )
+            };
 
         let output_root = info.get_output_root();
 
         let output_wasm_path = output_root.join(Path::new("assetstorage.wasm.gz"));
         let output_idl_path = output_wasm_path.with_extension("").with_extension("did");
+        let custom_domains_path = output_root.join("custom_domains");
+        let upload_progress_path = output_root.join("upload_progress.json");
 
         Ok(AssetsCanisterInfo {
             input_root,
@@ -85,6 +118,9 @@ impl CanisterInfoFactory for AssetsCanisterInfo {
             output_idl_path,
             build,
             workspace,
+            custom_domains,
+            custom_domains_path,
+            upload_progress_path,
         })
     }
 }
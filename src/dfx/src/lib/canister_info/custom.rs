@@ -12,8 +12,10 @@ enum CustomFileLocation {
 
 pub struct CustomCanisterInfo {
     input_wasm_url: Option<Url>,
+    input_wasm_hash: Option<String>,
     output_wasm_path: PathBuf,
     input_candid_url: Option<Url>,
+    input_candid_hash: Option<String>,
     output_idl_path: PathBuf,
     build: Vec<String>,
 }
@@ -22,12 +24,18 @@ impl CustomCanisterInfo {
     pub fn get_input_wasm_url(&self) -> &Option<Url> {
         &self.input_wasm_url
     }
+    pub fn get_input_wasm_hash(&self) -> Option<&str> {
+        self.input_wasm_hash.as_deref()
+    }
     pub fn get_output_wasm_path(&self) -> &Path {
         self.output_wasm_path.as_path()
     }
     pub fn get_input_candid_url(&self) -> &Option<Url> {
         &self.input_candid_url
     }
+    pub fn get_input_candid_hash(&self) -> Option<&str> {
+        self.input_candid_hash.as_deref()
+    }
     pub fn get_output_idl_path(&self) -> &Path {
         self.output_idl_path.as_path()
     }
@@ -39,19 +47,22 @@ impl CustomCanisterInfo {
 impl CanisterInfoFactory for CustomCanisterInfo {
     fn create(info: &CanisterInfo) -> DfxResult<Self> {
         let workspace_root = info.get_workspace_root();
-        let (wasm, build, candid) = if let CanisterTypeProperties::Custom {
-            wasm,
-            build,
-            candid,
-        } = info.type_specific.clone()
-        {
-            (wasm, build.into_vec(), candid)
-        } else {
-            bail!(
-                "Attempted to construct a custom canister from a type:{} canister config",
-                info.type_specific.name()
-            )
-        };
+        let (wasm, build, candid, wasm_hash, candid_hash) =
+            if let CanisterTypeProperties::Custom {
+                wasm,
+                build,
+                candid,
+                wasm_hash,
+                candid_hash,
+            } = info.type_specific.clone()
+            {
+                (wasm, build.into_vec(), candid, wasm_hash, candid_hash)
+            } else {
+                bail!(
+                    "Attempted to construct a custom canister from a type:{} canister config",
+                    info.type_specific.name()
+                )
+            };
         let (input_wasm_url, output_wasm_path) = if let Ok(input_wasm_url) = Url::parse(&wasm) {
             if !build.is_empty() {
                 bail!(
@@ -89,10 +100,25 @@ impl CanisterInfoFactory for CustomCanisterInfo {
                 (None, workspace_root.join(candid))
             };
 
+        if input_wasm_url.is_none() && wasm_hash.is_some() {
+            bail!(
+                "Canister '{}' defines wasm_hash, but its wasm field is not a URL.",
+                info.name
+            );
+        }
+        if input_candid_url.is_none() && candid_hash.is_some() {
+            bail!(
+                "Canister '{}' defines candid_hash, but its candid field is not a URL.",
+                info.name
+            );
+        }
+
         Ok(Self {
             input_wasm_url,
+            input_wasm_hash: wasm_hash,
             output_wasm_path,
             input_candid_url,
+            input_candid_hash: candid_hash,
             output_idl_path,
             build,
         })
@@ -1,7 +1,10 @@
 use crate::lib::canister_info::{CanisterInfo, CanisterInfoFactory};
 use crate::lib::error::DfxResult;
 use anyhow::{ensure, Context};
-use dfx_core::config::model::dfinity::CanisterTypeProperties;
+use dfx_core::config::model::dfinity::{
+    CanisterTypeProperties, ConfigMotokoOptions, MotokoPackage,
+};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 pub struct MotokoCanisterInfo {
@@ -15,7 +18,9 @@ pub struct MotokoCanisterInfo {
     output_assets_root: PathBuf,
 
     packtool: Option<String>,
+    packages: BTreeMap<String, MotokoPackage>,
     moc_args: Option<String>,
+    moc_options: ConfigMotokoOptions,
 }
 
 impl MotokoCanisterInfo {
@@ -43,9 +48,15 @@ impl MotokoCanisterInfo {
     pub fn get_packtool(&self) -> &Option<String> {
         &self.packtool
     }
+    pub fn get_packages(&self) -> &BTreeMap<String, MotokoPackage> {
+        &self.packages
+    }
     pub fn get_args(&self) -> &Option<String> {
         &self.moc_args
     }
+    pub fn get_moc_options(&self) -> &ConfigMotokoOptions {
+        &self.moc_options
+    }
 }
 
 impl CanisterInfoFactory for MotokoCanisterInfo {
@@ -82,7 +93,9 @@ impl CanisterInfoFactory for MotokoCanisterInfo {
             output_canister_js_path,
             output_assets_root,
             packtool: info.get_packtool().clone(),
+            packages: info.get_motoko_packages().clone(),
             moc_args: info.get_args().clone(),
+            moc_options: info.get_moc_options().clone(),
         })
     }
 }
@@ -193,7 +193,7 @@ The pulled cache may be modified manually or the same canister was pulled in dif
     Ok(())
 }
 
-fn get_deps_dir(project_root: &Path) -> PathBuf {
+pub fn get_deps_dir(project_root: &Path) -> PathBuf {
     project_root.join("deps")
 }
 
@@ -1,6 +1,7 @@
 use crate::config::cache::DiskBasedCache;
 use crate::config::dfx_version;
 use crate::lib::error::DfxResult;
+use crate::lib::identity::use_network_identity;
 use crate::lib::progress_bar::ProgressBar;
 use crate::lib::warning::{is_warning_disabled, DfxWarning::MainnetPlainTextIdentity};
 use anyhow::anyhow;
@@ -252,6 +253,8 @@ impl Environment for EnvironmentImpl {
 
 pub struct AgentEnvironment<'a> {
     backend: &'a dyn Environment,
+    /// The project configuration with the overrides for this network applied.
+    config: Option<Arc<Config>>,
     agent: Agent,
     network_descriptor: NetworkDescriptor,
     identity_manager: IdentityManager,
@@ -270,6 +273,7 @@ impl<'a> AgentEnvironment<'a> {
         let identity = if let Some(identity_name) = use_identity {
             identity_manager.instantiate_identity_from_name(identity_name, &logger)?
         } else {
+            use_network_identity(backend, &mut identity_manager, &network_descriptor.name)?;
             identity_manager.instantiate_selected_identity(&logger)?
         };
         if network_descriptor.is_ic
@@ -280,9 +284,20 @@ impl<'a> AgentEnvironment<'a> {
                 and use it in mainnet-facing commands with the `--identity` flag", identity.name());
         }
         let url = network_descriptor.first_provider()?;
+        let config = backend
+            .get_config()?
+            .map(|config| {
+                config.for_network(
+                    &network_descriptor.name,
+                    Some(backend.get_extension_manager()),
+                )
+            })
+            .transpose()?
+            .map(Arc::new);
 
         Ok(AgentEnvironment {
             backend,
+            config,
             agent: create_agent(logger, url, identity, timeout)?,
             network_descriptor: network_descriptor.clone(),
             identity_manager,
@@ -296,7 +311,7 @@ impl<'a> Environment for AgentEnvironment<'a> {
     }
 
     fn get_config(&self) -> Result<Option<Arc<Config>>, LoadDfxConfigError> {
-        self.backend.get_config()
+        Ok(self.config.clone())
     }
 
     fn get_networks_config(&self) -> Arc<NetworksConfig> {
@@ -1 +1,39 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use dfx_core::identity::identity_manager::IdentityManager;
+
 pub mod wallet;
+
+/// Select the default identity that the project's dfx.json, or failing that the shared
+/// networks.json, declares for the given network.
+/// `--identity` and `DFX_IDENTITY` still take precedence.
+pub fn use_network_identity(
+    env: &dyn Environment,
+    identity_manager: &mut IdentityManager,
+    network: &str,
+) -> DfxResult {
+    let project_identity = env.get_config()?.and_then(|config| {
+        config
+            .get_config()
+            .get_network(network)
+            .and_then(|n| n.get_identity())
+            .map(|identity| (identity.to_string(), config.get_path().clone()))
+    });
+    let identity = project_identity.or_else(|| {
+        let networks_config = env.get_networks_config();
+        networks_config
+            .get_interface()
+            .get_network(network)
+            .and_then(|n| n.get_identity())
+            .map(|identity| (identity.to_string(), networks_config.get_path().clone()))
+    });
+    if let Some((identity, config_path)) = identity {
+        identity_manager.use_network_identity(
+            env.get_logger(),
+            network,
+            &identity,
+            &config_path,
+        )?;
+    }
+    Ok(())
+}
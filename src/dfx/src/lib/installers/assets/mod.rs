@@ -27,14 +27,19 @@ pub async fn post_install_store_assets(
         .build()
         .context("Failed to build asset canister caller.")?;
 
-    ic_asset::sync(&canister, &source_paths, logger)
-        .await
-        .with_context(|| {
-            format!(
-                "Failed asset sync with canister {}.",
-                canister.canister_id_()
-            )
-        })?;
+    ic_asset::sync(
+        &canister,
+        &source_paths,
+        Some(assets_canister_info.get_upload_progress_path()),
+        logger,
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "Failed asset sync with canister {}.",
+            canister.canister_id_()
+        )
+    })?;
 
     Ok(())
 }
@@ -59,14 +64,19 @@ pub async fn prepare_assets_for_proposal(
         .build()
         .context("Failed to build asset canister caller.")?;
 
-    ic_asset::prepare_sync_for_proposal(&canister, &source_paths, logger)
-        .await
-        .with_context(|| {
-            format!(
-                "Failed asset sync with canister {}.",
-                canister.canister_id_()
-            )
-        })?;
+    ic_asset::prepare_sync_for_proposal(
+        &canister,
+        &source_paths,
+        Some(assets_canister_info.get_upload_progress_path()),
+        logger,
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "Failed asset sync with canister {}.",
+            canister.canister_id_()
+        )
+    })?;
 
     Ok(())
 }
@@ -1,5 +1,6 @@
 pub mod agent;
 pub mod builders;
+pub mod canister_http_mock;
 pub mod canister_info;
 pub mod cycles_ledger_types;
 pub mod deps;
@@ -1,6 +1,6 @@
 use crate::lib::builders::{
-    custom_download, BuildConfig, BuildOutput, BuilderPool, CanisterBuilder, IdlBuildOutput,
-    WasmBuildOutput,
+    custom_download, fetch_motoko_packages, BuildConfig, BuildOutput, BuilderPool, CanisterBuilder,
+    IdlBuildOutput, WasmBuildOutput,
 };
 use crate::lib::canister_info::CanisterInfo;
 use crate::lib::environment::Environment;
@@ -450,7 +450,7 @@ fn check_valid_subtype(compiled_idl_path: &Path, specified_idl_path: &Path) -> D
 pub enum Import {
     Canister(String),
     Ic(String),
-    Lib(String), // TODO: Unused, because package manager never update existing files (but create new dirs)
+    Lib(String), // Followed only for the Motoko packages declared in dfx.json.
     FullPath(PathBuf),
 }
 
@@ -803,6 +803,10 @@ impl CanisterPool {
 
         canister.wasm_post_process(self.get_logger(), build_output)?;
 
+        if build_config.modules_only {
+            return Ok(());
+        }
+
         build_canister_js(&build_output.canister_id, &canister.info)?;
 
         canister.postbuild(self, build_config)
@@ -986,12 +990,19 @@ impl CanisterPool {
     }
 
     async fn download(&self, build_config: &BuildConfig) -> DfxResult {
+        let mut motoko_packages_fetched = false;
         for canister in self.canisters_to_build(build_config) {
             let info = canister.get_info();
 
             if info.is_custom() {
                 custom_download(info, self).await?;
             }
+            // The Motoko packages are the same for all canisters.
+            if info.is_motoko() && !motoko_packages_fetched {
+                fetch_motoko_packages(info.get_workspace_root(), info.get_motoko_packages())
+                    .await?;
+                motoko_packages_fetched = true;
+            }
         }
         Ok(())
     }
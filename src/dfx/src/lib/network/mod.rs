@@ -1,2 +1,21 @@
+use dfx_core::config::model::local_server_descriptor::LocalServerDescriptor;
+use sysinfo::{Pid, System, SystemExt};
+
 pub mod id;
 pub mod network_opt;
+pub mod snapshot;
+pub mod time;
+
+/// Whether a `dfx start` process recorded in one of the network's pid files is still alive.
+pub fn local_network_is_running(local_server_descriptor: &LocalServerDescriptor) -> bool {
+    local_server_descriptor
+        .dfx_pid_paths()
+        .iter()
+        .filter_map(|pid_path| std::fs::read_to_string(pid_path).ok())
+        .filter_map(|s| s.parse::<Pid>().ok())
+        .any(|pid| {
+            let mut system = System::new();
+            system.refresh_processes();
+            system.process(pid).is_some()
+        })
+}
@@ -0,0 +1,371 @@
+//! Named snapshots of a local network.
+//!
+//! A snapshot is a directory under [`LocalServerDescriptor::snapshots_dir`] containing
+//! `snapshot.json` (metadata) and `state.tar`, an archive with these top-level entries:
+//!   - `network/`  replica state, network id and effective config from the network data directory
+//!   - `project/`  the project's `.dfx/<network>` directory, which holds the canister id store
+//!   - `deps/`     the project's `deps/` directory
+//!   - `pulled/`   downloaded wasm modules of the pull dependencies listed in `deps/pulled.json`
+use crate::config::dfx_version_str;
+use crate::lib::deps::{get_deps_dir, get_pulled_canister_dir, load_pulled_json};
+use crate::lib::error::DfxResult;
+use crate::lib::network::local_network_is_running;
+use anyhow::{bail, Context};
+use candid::Principal;
+use dfx_core::config::model::dfinity::Config;
+use dfx_core::config::model::local_server_descriptor::LocalServerDescriptor;
+use dfx_core::config::model::network_descriptor::NetworkDescriptor;
+use dfx_core::identity::WALLET_CONFIG_FILENAME;
+use dfx_core::json::{load_json_file, save_json_file};
+use fn_error_context::context;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use sysinfo::{Pid, System, SystemExt};
+use time::OffsetDateTime;
+
+const SNAPSHOT_METADATA: &str = "snapshot.json";
+const SNAPSHOT_ARCHIVE: &str = "state.tar";
+/// The id of the process saving a snapshot, in the directory of the snapshot being written.
+const SAVING_PID: &str = "saving.pid";
+
+/// Files in the network data directory that make up the network's state.
+/// Everything else there (pid files, ports, sockets) belongs to a running process.
+const NETWORK_STATE_FILES: &[&str] = &[
+    "state",
+    "network-id",
+    "replica-effective-config.json",
+    "replica-clock.json",
+    WALLET_CONFIG_FILENAME,
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SnapshotMetadata {
+    pub network: String,
+    pub dfx_version: String,
+    pub created: OffsetDateTime,
+}
+
+/// Where each top-level entry of a snapshot archive comes from and is restored to.
+struct SnapshotSources {
+    network: PathBuf,
+    project: Option<PathBuf>,
+    deps: Option<PathBuf>,
+    pulled: Vec<(String, PathBuf)>,
+}
+
+impl SnapshotSources {
+    fn new(
+        network_descriptor: &NetworkDescriptor,
+        local_server_descriptor: &LocalServerDescriptor,
+        config: Option<&Config>,
+    ) -> DfxResult<Self> {
+        let (project, deps) = match config {
+            Some(config) => (
+                Some(config.get_temp_path()?.join(&network_descriptor.name)),
+                Some(get_deps_dir(config.get_project_root())),
+            ),
+            None => (None, None),
+        };
+        let mut pulled = vec![];
+        if let Some(config) = config {
+            let project_root = config.get_project_root();
+            if get_deps_dir(project_root).join("pulled.json").exists() {
+                for canister_id in load_pulled_json(project_root)?.canisters.keys() {
+                    pulled.push((canister_id.to_text(), get_pulled_canister_dir(canister_id)?));
+                }
+            }
+        }
+        Ok(Self {
+            network: local_server_descriptor.data_directory.clone(),
+            project,
+            deps,
+            pulled,
+        })
+    }
+
+    /// Map a path inside the archive to where it is restored.
+    fn destination(&self, archive_path: &Path) -> Option<PathBuf> {
+        let mut components = archive_path.components();
+        let root = match components.next()?.as_os_str().to_str()? {
+            "network" => self.network.clone(),
+            "project" => self.project.clone()?,
+            "deps" => self.deps.clone()?,
+            "pulled" => {
+                let canister_id = components.next()?.as_os_str().to_str()?;
+                let canister_id = Principal::from_text(canister_id).ok()?;
+                get_pulled_canister_dir(&canister_id).ok()?
+            }
+            _ => return None,
+        };
+        let rest = components.as_path();
+        if rest
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return None;
+        }
+        Some(root.join(rest))
+    }
+}
+
+/// Snapshot names become directory names, so keep them to a safe set of characters.
+pub fn validate_snapshot_name(name: &str) -> DfxResult {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        bail!("Invalid snapshot name '{name}'. Use only letters, digits, '-', '_' and '.', and do not start with '.'.");
+    }
+    Ok(())
+}
+
+/// Whether a snapshot is being written, which `dfx start --save-state` does after the replica stopped.
+/// A partial snapshot left behind by a save that did not finish does not count.
+pub fn snapshot_in_progress(local_server_descriptor: &LocalServerDescriptor) -> bool {
+    let Ok(entries) = dfx_core::fs::read_dir(&local_server_descriptor.snapshots_dir()) else {
+        return false;
+    };
+    let mut system = System::new();
+    entries.flatten().any(|entry| {
+        entry.file_name().to_string_lossy().ends_with(".partial")
+            && std::fs::read_to_string(entry.path().join(SAVING_PID))
+                .ok()
+                .and_then(|pid| pid.trim().parse::<Pid>().ok())
+                .map_or(false, |pid| system.refresh_process(pid))
+    })
+}
+
+/// Where a path is unpacked before it replaces `path`.
+fn staging_path(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{name}.{suffix}"))
+}
+
+fn remove_path(path: &Path) -> DfxResult {
+    if path.is_dir() {
+        dfx_core::fs::remove_dir_all(path)?;
+    } else if path.exists() || path.is_symlink() {
+        dfx_core::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn require_network_stopped(local_server_descriptor: &LocalServerDescriptor) -> DfxResult {
+    if local_network_is_running(local_server_descriptor) {
+        bail!("The local network is running. Stop it with `dfx stop` first.");
+    }
+    Ok(())
+}
+
+/// Archive the state of a stopped local network under the given name,
+/// replacing any existing snapshot with that name.
+#[context("Failed to save snapshot '{}'.", name)]
+pub fn save_snapshot(
+    network_descriptor: &NetworkDescriptor,
+    config: Option<&Config>,
+    name: &str,
+) -> DfxResult<SnapshotMetadata> {
+    validate_snapshot_name(name)?;
+    let local_server_descriptor = network_descriptor.local_server_descriptor()?;
+    require_network_stopped(local_server_descriptor)?;
+    if !local_server_descriptor.state_dir().exists() {
+        bail!(
+            "There is no replica state at {}. Run `dfx start` first.",
+            local_server_descriptor.state_dir().display()
+        );
+    }
+    let sources = SnapshotSources::new(network_descriptor, local_server_descriptor, config)?;
+
+    let snapshots_dir = local_server_descriptor.snapshots_dir();
+    let partial_dir = snapshots_dir.join(format!(".{name}.partial"));
+    if partial_dir.exists() {
+        dfx_core::fs::remove_dir_all(&partial_dir)?;
+    }
+    dfx_core::fs::create_dir_all(&partial_dir)?;
+    let saving_pid = partial_dir.join(SAVING_PID);
+    dfx_core::fs::write(&saving_pid, std::process::id().to_string())?;
+
+    let archive_path = partial_dir.join(SNAPSHOT_ARCHIVE);
+    let archive = File::create(&archive_path)
+        .with_context(|| format!("Failed to create {}.", archive_path.display()))?;
+    let mut builder = tar::Builder::new(archive);
+    builder.follow_symlinks(false);
+    let mut append = |archive_name: PathBuf, path: &Path| -> DfxResult {
+        let result = if path.is_dir() {
+            builder.append_dir_all(&archive_name, path)
+        } else if path.is_file() {
+            builder.append_path_with_name(path, &archive_name)
+        } else {
+            return Ok(());
+        };
+        result.with_context(|| format!("Failed to archive {}.", path.display()))
+    };
+    for file in NETWORK_STATE_FILES {
+        append(Path::new("network").join(file), &sources.network.join(file))?;
+    }
+    if let Some(project) = &sources.project {
+        append(PathBuf::from("project"), project)?;
+    }
+    if let Some(deps) = &sources.deps {
+        append(PathBuf::from("deps"), deps)?;
+    }
+    for (canister_id, dir) in &sources.pulled {
+        append(Path::new("pulled").join(canister_id), dir)?;
+    }
+    builder
+        .into_inner()
+        .and_then(|f| f.sync_all())
+        .with_context(|| format!("Failed to write {}.", archive_path.display()))?;
+
+    let metadata = SnapshotMetadata {
+        network: network_descriptor.name.clone(),
+        dfx_version: dfx_version_str().to_string(),
+        created: OffsetDateTime::now_utc(),
+    };
+    save_json_file(&partial_dir.join(SNAPSHOT_METADATA), &metadata)?;
+    dfx_core::fs::remove_file(&saving_pid)?;
+
+    let snapshot_dir = snapshots_dir.join(name);
+    if snapshot_dir.exists() {
+        dfx_core::fs::remove_dir_all(&snapshot_dir)?;
+    }
+    dfx_core::fs::rename(&partial_dir, &snapshot_dir)?;
+    Ok(metadata)
+}
+
+/// Replace the state of a stopped local network with a previously saved snapshot.
+#[context("Failed to restore snapshot '{}'.", name)]
+pub fn restore_snapshot(
+    network_descriptor: &NetworkDescriptor,
+    config: Option<&Config>,
+    name: &str,
+) -> DfxResult<SnapshotMetadata> {
+    validate_snapshot_name(name)?;
+    let local_server_descriptor = network_descriptor.local_server_descriptor()?;
+    require_network_stopped(local_server_descriptor)?;
+    let snapshot_dir = local_server_descriptor.snapshots_dir().join(name);
+    if !snapshot_dir.join(SNAPSHOT_METADATA).exists() {
+        bail!(
+            "There is no snapshot named '{name}' for network '{}'.",
+            network_descriptor.name
+        );
+    }
+    let metadata: SnapshotMetadata = load_json_file(&snapshot_dir.join(SNAPSHOT_METADATA))?;
+    let sources = SnapshotSources::new(network_descriptor, local_server_descriptor, config)?;
+
+    // The snapshot replaces the network state and the canister id store entirely,
+    // and whichever dependencies it holds.
+    // Everything is unpacked next to where it goes first, so that a failed unpack leaves the state as it was.
+    let mut replaced: Vec<PathBuf> = NETWORK_STATE_FILES
+        .iter()
+        .map(|file| sources.network.join(file))
+        .chain(sources.project.clone())
+        .collect();
+    for destination in &replaced {
+        remove_path(&staging_path(destination, "restoring"))?;
+    }
+    let unpacked = unpack_snapshot(
+        &snapshot_dir.join(SNAPSHOT_ARCHIVE),
+        &sources,
+        &mut replaced,
+    );
+    if let Err(err) = unpacked {
+        for destination in &replaced {
+            let _ = remove_path(&staging_path(destination, "restoring"));
+        }
+        return Err(err);
+    }
+
+    for destination in &replaced {
+        let previous = staging_path(destination, "replaced");
+        remove_path(&previous)?;
+        if destination.exists() || destination.is_symlink() {
+            dfx_core::fs::rename(destination, &previous)?;
+        }
+        let staged = staging_path(destination, "restoring");
+        if staged.exists() || staged.is_symlink() {
+            dfx_core::fs::rename(&staged, destination)?;
+        }
+    }
+    for destination in &replaced {
+        remove_path(&staging_path(destination, "replaced"))?;
+    }
+    Ok(metadata)
+}
+
+/// Unpack a snapshot archive next to the paths it replaces, adding them to `replaced`.
+fn unpack_snapshot(
+    archive_path: &Path,
+    sources: &SnapshotSources,
+    replaced: &mut Vec<PathBuf>,
+) -> DfxResult {
+    let mut archive = File::open(archive_path)
+        .map(tar::Archive::new)
+        .with_context(|| format!("Failed to open {}.", archive_path.display()))?;
+    for entry in archive
+        .entries()
+        .context("Failed to read snapshot archive.")?
+    {
+        let mut entry = entry.context("Failed to read snapshot archive entry.")?;
+        let path = entry
+            .path()
+            .context("Invalid path in snapshot archive.")?
+            .to_path_buf();
+        let depth = if path.starts_with("network") || path.starts_with("pulled") {
+            2
+        } else {
+            1
+        };
+        let top: PathBuf = path.components().take(depth).collect();
+        if top.components().count() < depth {
+            continue;
+        }
+        let (Some(top_destination), Some(destination)) =
+            (sources.destination(&top), sources.destination(&path))
+        else {
+            continue;
+        };
+        if !replaced.contains(&top_destination) {
+            remove_path(&staging_path(&top_destination, "restoring"))?;
+            replaced.push(top_destination.clone());
+        }
+        let mut staged = staging_path(&top_destination, "restoring");
+        let relative = destination.strip_prefix(&top_destination)?;
+        if !relative.as_os_str().is_empty() {
+            staged.push(relative);
+        }
+        if let Some(parent) = staged.parent() {
+            dfx_core::fs::create_dir_all(parent)?;
+        }
+        entry
+            .unpack(&staged)
+            .with_context(|| format!("Failed to unpack {}.", destination.display()))?;
+    }
+    Ok(())
+}
+
+/// All snapshots saved for a local network, sorted by name.
+pub fn list_snapshots(
+    local_server_descriptor: &LocalServerDescriptor,
+) -> DfxResult<Vec<(String, SnapshotMetadata)>> {
+    let snapshots_dir = local_server_descriptor.snapshots_dir();
+    if !snapshots_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut snapshots = vec![];
+    for entry in dfx_core::fs::read_dir(&snapshots_dir)? {
+        let entry = entry
+            .with_context(|| format!("Failed to read directory {}.", snapshots_dir.display()))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let metadata_path = entry.path().join(SNAPSHOT_METADATA);
+        if name.starts_with('.') || !metadata_path.is_file() {
+            continue;
+        }
+        snapshots.push((name, load_json_file(&metadata_path)?));
+    }
+    snapshots.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(snapshots)
+}
@@ -0,0 +1,369 @@
+//! A controllable clock for the local replica.
+//!
+//! The replica runs with libfaketime preloaded, which reads the time to report from
+//! [`LocalServerDescriptor::replica_faketime_path`] on every clock read.
+//! `dfx network time` changes the clock by rewriting that file; the state it is derived from
+//! is kept in [`LocalServerDescriptor::replica_clock_path`].
+use crate::lib::error::DfxResult;
+use anyhow::{anyhow, bail, Context};
+use dfx_core::config::model::dfinity::ConfigReplicaTime;
+use dfx_core::config::model::local_server_descriptor::LocalServerDescriptor;
+use dfx_core::json::{load_json_file, save_json_file};
+use fn_error_context::context;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// Where package managers install libfaketime.
+#[cfg(target_os = "macos")]
+const LIBFAKETIME_LOCATIONS: &[&str] = &[
+    "/opt/homebrew/lib/faketime/libfaketime.1.dylib",
+    "/usr/local/lib/faketime/libfaketime.1.dylib",
+];
+#[cfg(not(target_os = "macos"))]
+const LIBFAKETIME_LOCATIONS: &[&str] = &[
+    "/usr/lib/x86_64-linux-gnu/faketime/libfaketime.so.1",
+    "/usr/lib/aarch64-linux-gnu/faketime/libfaketime.so.1",
+    "/usr/lib/faketime/libfaketime.so.1",
+    "/usr/lib64/faketime/libfaketime.so.1",
+    "/usr/local/lib/faketime/libfaketime.so.1",
+];
+
+/// What the replica process needs to run on the controllable clock.
+#[derive(Clone, Debug)]
+pub struct ReplicaTimeControl {
+    pub libfaketime: PathBuf,
+    pub faketime_path: PathBuf,
+}
+
+impl ReplicaTimeControl {
+    /// Environment variables that make a process read its clock from the faketime file.
+    pub fn env_vars(&self) -> Vec<(&'static str, OsString)> {
+        let preload = if cfg!(target_os = "macos") {
+            "DYLD_INSERT_LIBRARIES"
+        } else {
+            "LD_PRELOAD"
+        };
+        let mut vars = vec![
+            (preload, self.libfaketime.clone().into_os_string()),
+            ("FAKETIME_TIMESTAMP_FILE", self.faketime_path.clone().into()),
+            // Re-read the file on every clock read, so that changes apply immediately.
+            ("FAKETIME_NO_CACHE", "1".into()),
+            // Timeouts and consensus delays keep running on real time.
+            ("DONT_FAKE_MONOTONIC", "1".into()),
+            // libfaketime interprets absolute timestamps in the local time zone.
+            ("TZ", "UTC".into()),
+        ];
+        if cfg!(target_os = "macos") {
+            vars.push(("DYLD_FORCE_FLAT_NAMESPACE", "1".into()));
+        }
+        vars
+    }
+}
+
+/// The state of the replica's clock.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReplicaClock {
+    /// Seconds the clock is ahead of real time (negative if behind) while it runs.
+    pub offset_seconds: i64,
+
+    /// The unix time in seconds the clock is stopped at, if frozen.
+    pub frozen_at: Option<i64>,
+}
+
+impl ReplicaClock {
+    pub fn now(&self) -> DfxResult<OffsetDateTime> {
+        self.now_at(OffsetDateTime::now_utc())
+    }
+
+    fn now_at(&self, real: OffsetDateTime) -> DfxResult<OffsetDateTime> {
+        match self.frozen_at {
+            Some(frozen_at) => OffsetDateTime::from_unix_timestamp(frozen_at).with_context(|| {
+                format!("The replica's clock is frozen at an invalid time ({frozen_at}).")
+            }),
+            None => real
+                .checked_add(time::Duration::seconds(self.offset_seconds))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "The replica's clock is off by an invalid number of seconds ({}).",
+                        self.offset_seconds
+                    )
+                }),
+        }
+    }
+
+    fn set_at(&mut self, time: OffsetDateTime, real: OffsetDateTime) {
+        match self.frozen_at {
+            Some(_) => self.frozen_at = Some(time.unix_timestamp()),
+            None => self.offset_seconds = (time - real).whole_seconds(),
+        }
+    }
+
+    /// Make the clock show `time`. The replica does not accept blocks older than its state,
+    /// so the clock only moves forward.
+    pub fn set(&mut self, time: OffsetDateTime) -> DfxResult {
+        let real = OffsetDateTime::now_utc();
+        let now = self.now_at(real)?;
+        if time.unix_timestamp() < now.unix_timestamp() {
+            bail!(
+                "Cannot move the replica's clock back from {} to {}. Run `dfx start --clean` to start over.",
+                format_time(now),
+                format_time(time)
+            );
+        }
+        self.set_at(time, real);
+        Ok(())
+    }
+
+    pub fn advance(&mut self, duration: Duration) -> DfxResult {
+        let seconds = i64::try_from(duration.as_secs())
+            .ok()
+            .filter(|s| *s < 100 * 365 * 24 * 60 * 60)
+            .ok_or_else(|| anyhow!("Cannot advance the replica's clock by {duration:?}."))?;
+        let advanced = match &mut self.frozen_at {
+            Some(frozen_at) => frozen_at,
+            None => &mut self.offset_seconds,
+        };
+        *advanced = advanced
+            .checked_add(seconds)
+            .ok_or_else(|| anyhow!("Cannot advance the replica's clock by {duration:?}."))?;
+        Ok(())
+    }
+
+    pub fn freeze(&mut self) -> DfxResult {
+        if self.frozen_at.is_none() {
+            self.frozen_at = Some(self.now()?.unix_timestamp());
+        }
+        Ok(())
+    }
+
+    pub fn unfreeze(&mut self) -> DfxResult {
+        self.unfreeze_at(OffsetDateTime::now_utc())
+    }
+
+    fn unfreeze_at(&mut self, real: OffsetDateTime) -> DfxResult {
+        if let Some(frozen_at) = self.frozen_at {
+            self.offset_seconds =
+                frozen_at
+                    .checked_sub(real.unix_timestamp())
+                    .with_context(|| {
+                        format!("The replica's clock is frozen at an invalid time ({frozen_at}).")
+                    })?;
+            self.frozen_at = None;
+        }
+        Ok(())
+    }
+
+    /// The contents of the libfaketime timestamp file:
+    /// an absolute time while frozen, and an offset from real time otherwise.
+    fn faketime_spec(&self) -> DfxResult<String> {
+        match self.frozen_at {
+            Some(_) => {
+                let frozen_at = self.now_at(OffsetDateTime::now_utc())?;
+                frozen_at
+                    .format(format_description!(
+                        "[year]-[month]-[day] [hour]:[minute]:[second]"
+                    ))
+                    .with_context(|| format!("Failed to format {frozen_at} for libfaketime."))
+            }
+            None => Ok(format!("{:+}", self.offset_seconds)),
+        }
+    }
+}
+
+pub fn format_time(time: OffsetDateTime) -> String {
+    time.format(&Rfc3339).unwrap_or_else(|_| time.to_string())
+}
+
+pub fn parse_time(s: &str) -> DfxResult<OffsetDateTime> {
+    OffsetDateTime::parse(s, &Rfc3339).with_context(|| {
+        format!("Invalid time '{s}'. Expected RFC 3339, e.g. 2024-01-31T12:00:00Z.")
+    })
+}
+
+fn find_libfaketime(config: &ConfigReplicaTime) -> DfxResult<PathBuf> {
+    if let Some(path) = &config.libfaketime {
+        if !path.is_file() {
+            bail!("libfaketime not found at {}.", path.display());
+        }
+        return Ok(path.clone());
+    }
+    LIBFAKETIME_LOCATIONS
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            anyhow!(
+                "The replica clock requires libfaketime. Install it (e.g. `apt install faketime` or `brew install libfaketime`), \
+                or set 'replica.time.libfaketime' to the path of the library."
+            )
+        })
+}
+
+fn write_clock(local_server_descriptor: &LocalServerDescriptor, clock: &ReplicaClock) -> DfxResult {
+    save_json_file(&local_server_descriptor.replica_clock_path(), clock)?;
+    // Written to a temporary file first, so the replica never reads a partial timestamp.
+    let faketime_path = local_server_descriptor.replica_faketime_path();
+    let partial_path = faketime_path.with_extension("partial");
+    dfx_core::fs::write(&partial_path, clock.faketime_spec()?)?;
+    dfx_core::fs::rename(&partial_path, &faketime_path)?;
+    Ok(())
+}
+
+/// Set up the clock for a replica about to start.
+/// A network keeps its clock across restarts; a new network starts as configured.
+#[context("Failed to set up the replica clock.")]
+pub fn prepare_time_control(
+    local_server_descriptor: &LocalServerDescriptor,
+    config: &ConfigReplicaTime,
+) -> DfxResult<ReplicaTimeControl> {
+    let libfaketime = find_libfaketime(config)?;
+    let clock_path = local_server_descriptor.replica_clock_path();
+    let clock = if clock_path.exists() {
+        load_json_file(&clock_path)?
+    } else {
+        let mut clock = ReplicaClock::default();
+        if let Some(start) = &config.start {
+            clock.set_at(parse_time(start)?, OffsetDateTime::now_utc());
+        }
+        if config.frozen {
+            clock.freeze()?;
+        }
+        clock
+    };
+    write_clock(local_server_descriptor, &clock)?;
+    Ok(ReplicaTimeControl {
+        libfaketime,
+        faketime_path: local_server_descriptor.replica_faketime_path(),
+    })
+}
+
+/// Apply a change to the clock of a local network. A running replica picks it up immediately.
+pub fn update_replica_clock(
+    local_server_descriptor: &LocalServerDescriptor,
+    update: impl FnOnce(&mut ReplicaClock) -> DfxResult,
+) -> DfxResult<ReplicaClock> {
+    if local_server_descriptor.replica.time.is_none() {
+        bail!("The replica clock is not enabled for this network. Configure 'replica.time' for the network and run `dfx start --clean`.");
+    }
+    let clock_path = local_server_descriptor.replica_clock_path();
+    if !clock_path.exists() {
+        bail!("The replica clock is not set up yet. Run `dfx start` first.");
+    }
+    let mut clock: ReplicaClock = load_json_file(&clock_path)?;
+    update(&mut clock)?;
+    write_clock(local_server_descriptor, &clock)?;
+    Ok(clock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn running_clock_is_an_offset_from_real_time() {
+        let real = datetime!(2024-01-01 00:00:00 UTC);
+        let mut clock = ReplicaClock::default();
+        clock.set_at(datetime!(2030-06-01 12:00:00 UTC), real);
+        assert_eq!(
+            clock.now_at(real).unwrap(),
+            datetime!(2030-06-01 12:00:00 UTC)
+        );
+        clock.advance(Duration::from_secs(3600)).unwrap();
+        assert_eq!(
+            clock.now_at(real + time::Duration::seconds(30)).unwrap(),
+            datetime!(2030-06-01 13:00:30 UTC)
+        );
+        assert_eq!(
+            clock.faketime_spec().unwrap(),
+            format!("+{}", clock.offset_seconds)
+        );
+    }
+
+    #[test]
+    fn frozen_clock_only_moves_when_told() {
+        let mut clock = ReplicaClock {
+            offset_seconds: 0,
+            frozen_at: Some(datetime!(2030-06-01 12:00:00 UTC).unix_timestamp()),
+        };
+        clock.advance(Duration::from_secs(90)).unwrap();
+        assert_eq!(
+            clock.now_at(datetime!(2024-01-01 00:00:00 UTC)).unwrap(),
+            datetime!(2030-06-01 12:01:30 UTC)
+        );
+        assert_eq!(clock.faketime_spec().unwrap(), "2030-06-01 12:01:30");
+        assert!(clock.set(datetime!(2030-06-01 12:00:00 UTC)).is_err());
+    }
+
+    #[test]
+    fn unfrozen_clock_continues_from_the_time_it_showed() {
+        let real = datetime!(2024-01-01 00:00:00 UTC);
+        let mut clock = ReplicaClock {
+            offset_seconds: 0,
+            frozen_at: Some(datetime!(2023-12-31 23:00:00 UTC).unix_timestamp()),
+        };
+        clock.unfreeze_at(real).unwrap();
+        assert_eq!(
+            clock,
+            ReplicaClock {
+                offset_seconds: -3600,
+                frozen_at: None,
+            }
+        );
+        assert_eq!(
+            clock.now_at(real + time::Duration::seconds(5)).unwrap(),
+            datetime!(2023-12-31 23:00:05 UTC)
+        );
+        assert_eq!(clock.faketime_spec().unwrap(), "-3600");
+    }
+
+    #[test]
+    fn clock_file() {
+        let clock: ReplicaClock =
+            serde_json::from_str(r#"{ "offset_seconds": 120, "frozen_at": null }"#).unwrap();
+        assert_eq!(
+            clock.now_at(datetime!(2024-01-01 00:00:00 UTC)).unwrap(),
+            datetime!(2024-01-01 00:02:00 UTC)
+        );
+
+        let clock: ReplicaClock =
+            serde_json::from_str(r#"{ "offset_seconds": 0, "frozen_at": 1893456000 }"#).unwrap();
+        assert_eq!(clock.now().unwrap(), datetime!(2030-01-01 00:00:00 UTC));
+        assert_eq!(
+            serde_json::from_str::<ReplicaClock>(&serde_json::to_string(&clock).unwrap()).unwrap(),
+            clock
+        );
+
+        assert!(serde_json::from_str::<ReplicaClock>(r#"{ "frozen_at": 1893456000 }"#).is_err());
+    }
+
+    #[test]
+    fn out_of_range_clock_is_an_error() {
+        let real = datetime!(2024-01-01 00:00:00 UTC);
+        let mut frozen = ReplicaClock {
+            offset_seconds: 0,
+            frozen_at: Some(i64::MAX),
+        };
+        assert!(frozen.now_at(real).is_err());
+        assert!(frozen.faketime_spec().is_err());
+        assert!(frozen.advance(Duration::from_secs(1)).is_err());
+        assert!(frozen.freeze().is_ok());
+        frozen.frozen_at = Some(i64::MIN);
+        assert!(frozen.unfreeze_at(real).is_err());
+        assert_eq!(frozen.frozen_at, Some(i64::MIN));
+
+        let mut running = ReplicaClock {
+            offset_seconds: i64::MAX,
+            frozen_at: None,
+        };
+        assert!(running.now_at(real).is_err());
+        assert!(running.freeze().is_err());
+        assert!(running.advance(Duration::from_secs(1)).is_err());
+        assert_eq!(running.offset_seconds, i64::MAX);
+    }
+}
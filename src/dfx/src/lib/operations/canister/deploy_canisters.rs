@@ -7,10 +7,19 @@ use crate::lib::ic_attributes::CanisterSettings;
 use crate::lib::installers::assets::prepare_assets_for_proposal;
 use crate::lib::models::canister::{Canister, CanisterPool};
 use crate::lib::operations::canister::deploy_canisters::DeployMode::{
-    ComputeEvidence, ForceReinstallSingleCanister, NormalDeploy, PrepareForProposal,
+    ApplyPlan, ComputeEvidence, DryRun, ForceReinstallSingleCanister, NormalDeploy, Plan,
+    PrepareForProposal,
+};
+use crate::lib::operations::canister::deploy_hooks::{
+    run_failure_hooks, run_hooks, Hook, HookContext,
+};
+use crate::lib::operations::canister::deploy_plan::{
+    make_deploy_plan, verify_deploy_plan, verify_planned_modules, DeployPlan, InstallAction,
 };
 use crate::lib::operations::canister::motoko_playground::reserve_canister_with_playground;
-use crate::lib::operations::canister::{create_canister, install_canister::install_canister};
+use crate::lib::operations::canister::{
+    create_canister, install_canister::install_canister, update_settings,
+};
 use crate::util::clap::subnet_selection_opt::SubnetSelectionType;
 use anyhow::{anyhow, bail, Context};
 use candid::Principal;
@@ -26,6 +35,7 @@ use icrc_ledger_types::icrc1::account::Subaccount;
 use itertools::Itertools;
 use slog::info;
 // use core::slice::SlicePattern;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -38,6 +48,9 @@ pub enum DeployMode {
     ForceReinstallSingleCanister(String),
     PrepareForProposal(String),
     ComputeEvidence(String),
+    DryRun { json: bool },
+    Plan { json: bool, force_reinstall: bool },
+    ApplyPlan(DeployPlan),
 }
 
 #[context("Failed while trying to deploy canisters.")]
@@ -45,6 +58,7 @@ pub enum DeployMode {
 pub async fn deploy_canisters(
     env: &dyn Environment,
     some_canister: Option<&str>,
+    selected_canisters: Option<&[String]>,
     argument: Option<&str>,
     argument_type: Option<&str>,
     deploy_mode: &DeployMode,
@@ -77,12 +91,10 @@ pub async fn deploy_canisters(
         }
     }
 
-    let canisters_to_deploy = canister_with_dependencies(&config, some_canister)?;
+    let canisters_to_deploy =
+        canister_with_dependencies(&config, some_canister, selected_canisters)?;
 
-    let required_canisters = config
-        .get_config()
-        .get_canister_names_with_dependencies(some_canister)?;
-    let canisters_to_load = add_canisters_with_ids(&required_canisters, env, &config);
+    let canisters_to_load = add_canisters_with_ids(&canisters_to_deploy, env, &config);
 
     let canister_pool = CanisterPool::load(env, false, &canisters_to_load)?;
 
@@ -94,7 +106,12 @@ pub async fn deploy_canisters(
             // don't force-reinstall the dependencies too.
             vec![String::from(canister_name)]
         }
-        NormalDeploy => canisters_to_deploy
+        Plan {
+            force_reinstall: true,
+            ..
+        } => some_canister.map(String::from).into_iter().collect(),
+        ApplyPlan(plan) if plan.force_reinstall => plan.canister.clone().into_iter().collect(),
+        NormalDeploy | DryRun { .. } | Plan { .. } | ApplyPlan(_) => canisters_to_deploy
             .clone()
             .into_iter()
             .filter(|canister_name| {
@@ -145,10 +162,54 @@ pub async fn deploy_canisters(
     let canister_id_store = env.get_canister_id_store()?;
 
     if some_canister.is_some() {
+    } else if let Some(selected_canisters) = selected_canisters {
+        info!(
+            log,
+            "Deploying canisters {}.",
+            selected_canisters.join(", ")
+        );
     } else {
         info!(log, "Deploying all canisters.");
     }
-    if canisters_to_install
+    if let DryRun { .. } = deploy_mode {
+        let missing = canisters_to_install
+            .iter()
+            .filter(|canister| canister_id_store.find(canister).is_none())
+            .join(", ");
+        if !missing.is_empty() {
+            bail!("Cannot do a dry run before these canisters are created: {missing}. Run `dfx canister create` first.");
+        }
+    } else if let Plan { .. } = deploy_mode {
+        // Canisters that do not exist yet are built with placeholder IDs below.
+    } else if let ApplyPlan(plan) = deploy_mode {
+        verify_deploy_plan(
+            env,
+            plan,
+            canisters_to_install,
+            &canister_id_store,
+            &config,
+            call_sender,
+        )
+        .await?;
+        for canister in &plan.canisters {
+            if let Some(create) = &canister.create {
+                register_canisters(
+                    env,
+                    std::slice::from_ref(&canister.name),
+                    &canister_id_store,
+                    create.cycles,
+                    specified_id_from_cli,
+                    call_sender,
+                    no_wallet,
+                    from_subaccount,
+                    created_at_time,
+                    &config,
+                    subnet_selection,
+                )
+                .await?;
+            }
+        }
+    } else if canisters_to_install
         .iter()
         .any(|canister| canister_id_store.find(canister).is_none())
     {
@@ -170,49 +231,174 @@ pub async fn deploy_canisters(
         info!(env.get_logger(), "All canisters have already been created.");
     }
 
-    // hack to load deployed canister IDs (such as of Rust canisters)
-    let new_canister_pool2 = CanisterPool::load(env, false, &order)?; // with newly registered canisters
-
-    build_canisters(
-        env,
-        order_canisters.as_slice(),
-        // toplevel_canisters,
-        &config,
-        env_file.clone(),
-        &new_canister_pool2,
-    )
-    .await?;
-
-    match deploy_mode {
-        NormalDeploy | ForceReinstallSingleCanister(_) => {
-            let force_reinstall = matches!(deploy_mode, ForceReinstallSingleCanister(_));
-            install_canisters(
-                env,
-                canisters_to_install,
-                &canister_id_store,
-                &config,
-                argument,
-                argument_type,
-                force_reinstall,
-                upgrade_unchanged,
-                call_sender,
-                new_canister_pool2,
-                skip_consent,
-                env_file.as_deref(),
-                no_asset_upgrade,
-                always_assist,
-            )
-            .await?;
-            info!(log, "Deployed canisters.");
+    // The hooks of the project run when code is deployed, after the canisters are created.
+    let hooks = &config.get_config().hooks;
+    let project_root = config.get_project_root();
+    let hook_context =
+        match deploy_mode {
+            NormalDeploy | ForceReinstallSingleCanister(_) | ApplyPlan(_) => Some(
+                HookContext::new(env, &config, &env.get_canister_id_store()?),
+            ),
+            _ => None,
+        };
+
+    let result: DfxResult = async {
+        if let Some(context) = &hook_context {
+            run_hooks(env, Hook::PreDeploy, hooks, project_root, context)?;
         }
-        PrepareForProposal(canister_name) => {
-            prepare_assets_for_commit(env, &canister_id_store, &config, canister_name).await?
+
+        // hack to load deployed canister IDs (such as of Rust canisters)
+        // A plan builds the canisters that do not exist yet with placeholder IDs,
+        // so it only builds the modules and does not write the env file.
+        let plan_only = matches!(deploy_mode, Plan { .. });
+        let new_canister_pool2 = CanisterPool::load(env, plan_only, &order)?; // with newly registered canisters
+
+        build_canisters(
+            env,
+            order_canisters.as_slice(),
+            // toplevel_canisters,
+            &config,
+            if plan_only { None } else { env_file.clone() },
+            &new_canister_pool2,
+            plan_only,
+        )
+        .await?;
+
+        match deploy_mode {
+            NormalDeploy | ForceReinstallSingleCanister(_) => {
+                let force_reinstall = matches!(deploy_mode, ForceReinstallSingleCanister(_));
+                install_canisters(
+                    env,
+                    canisters_to_install,
+                    &canister_id_store,
+                    &config,
+                    argument,
+                    argument_type,
+                    force_reinstall,
+                    upgrade_unchanged,
+                    call_sender,
+                    new_canister_pool2,
+                    skip_consent,
+                    env_file.as_deref(),
+                    no_asset_upgrade,
+                    always_assist,
+                )
+                .await?;
+                if hook_context.is_some() {
+                    let context = HookContext::new(env, &config, &env.get_canister_id_store()?);
+                    run_hooks(env, Hook::PostDeploy, hooks, project_root, &context)?;
+                }
+                info!(log, "Deployed canisters.");
+            }
+            PrepareForProposal(canister_name) => {
+                prepare_assets_for_commit(env, &canister_id_store, &config, canister_name).await?
+            }
+            ComputeEvidence(canister_name) => {
+                compute_evidence(env, &canister_id_store, &config, canister_name).await?
+            }
+            Plan {
+                json,
+                force_reinstall,
+            } => {
+                let plan = make_deploy_plan(
+                    env,
+                    some_canister,
+                    selected_canisters,
+                    canisters_to_install,
+                    &canister_id_store,
+                    &config,
+                    call_sender,
+                    argument,
+                    argument_type,
+                    with_cycles,
+                    *force_reinstall,
+                    upgrade_unchanged,
+                    no_asset_upgrade,
+                )
+                .await?;
+                if *json {
+                    println!("{}", serde_json::to_string_pretty(&plan)?);
+                } else {
+                    print!("{}", plan);
+                }
+            }
+            ApplyPlan(plan) => {
+                verify_planned_modules(plan, &env.get_canister_id_store()?, &config)?;
+                install_planned_canisters(
+                    env,
+                    plan,
+                    &config,
+                    call_sender,
+                    new_canister_pool2,
+                    env_file.as_deref(),
+                )
+                .await?;
+                if hook_context.is_some() {
+                    let context = HookContext::new(env, &config, &env.get_canister_id_store()?);
+                    run_hooks(env, Hook::PostDeploy, hooks, project_root, &context)?;
+                }
+                info!(log, "Deployed canisters.");
+            }
+            DryRun { json } => {
+                plan_asset_sync(
+                    env,
+                    canisters_to_install,
+                    &canister_id_store,
+                    &config,
+                    *json,
+                )
+                .await?
+            }
         }
-        ComputeEvidence(canister_name) => {
-            compute_evidence(env, &canister_id_store, &config, canister_name).await?
+        Ok(())
+    }
+    .await;
+    match (result, &hook_context) {
+        (Err(err), Some(context)) => Err(run_failure_hooks(env, hooks, project_root, context, err)),
+        (result, _) => result,
+    }
+}
+
+#[context("Failed to plan asset changes.")]
+async fn plan_asset_sync(
+    env: &dyn Environment,
+    canisters: &[String],
+    canister_id_store: &CanisterIdStore,
+    config: &Config,
+    json: bool,
+) -> DfxResult {
+    let mut plans = BTreeMap::new();
+    for canister_name in canisters {
+        let canister_id = canister_id_store.get(canister_name)?;
+        let canister_info = CanisterInfo::load(config, canister_name, Some(canister_id))?;
+        if !canister_info.is_assets() {
+            continue;
         }
+        let assets_canister_info = canister_info.as_info::<AssetsCanisterInfo>()?;
+        let source_paths = assets_canister_info.get_source_paths();
+        let source_paths: Vec<&Path> = source_paths.iter().map(|p| p.as_path()).collect();
+
+        let canister = ic_utils::Canister::builder()
+            .with_agent(env.get_agent())
+            .with_canister_id(canister_id)
+            .build()
+            .context("Failed to build asset canister caller.")?;
+
+        let plan = ic_asset::plan_sync(&canister, &source_paths, env.get_logger())
+            .await
+            .with_context(|| format!("Failed to plan changes to canister {canister_name}."))?;
+        plans.insert(canister_name.clone(), plan);
     }
 
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plans)?);
+    } else if plans.is_empty() {
+        info!(env.get_logger(), "No asset canisters to deploy.");
+    } else {
+        for (canister_name, plan) in plans {
+            println!("{canister_name}:\n{plan}");
+        }
+    }
     Ok(())
 }
 
@@ -220,11 +406,26 @@ pub async fn deploy_canisters(
 fn canister_with_dependencies(
     config: &Config,
     some_canister: Option<&str>,
+    selected_canisters: Option<&[String]>,
 ) -> DfxResult<Vec<String>> {
-    let mut canister_names = config
-        .get_config()
-        .get_canister_names_with_dependencies(some_canister)?;
+    let mut canister_names = match selected_canisters {
+        Some(selected_canisters) => {
+            let mut canister_names = vec![];
+            for canister in selected_canisters {
+                canister_names.extend(
+                    config
+                        .get_config()
+                        .get_canister_names_with_dependencies(Some(canister))?,
+                );
+            }
+            canister_names
+        }
+        None => config
+            .get_config()
+            .get_canister_names_with_dependencies(some_canister)?,
+    };
     canister_names.sort();
+    canister_names.dedup();
     Ok(canister_names)
 }
 
@@ -258,50 +459,6 @@ async fn register_canisters(
     } else {
         info!(env.get_logger(), "Creating canisters...");
         for canister_name in &canisters_to_create {
-            let config_interface = config.get_config();
-            let compute_allocation = config_interface
-                .get_compute_allocation(canister_name)?
-                .map(|arg| {
-                    ComputeAllocation::try_from(arg)
-                        .context("Compute Allocation must be a percentage.")
-                })
-                .transpose()?;
-            let memory_allocation = config_interface
-                .get_memory_allocation(canister_name)?
-                .map(|arg| {
-                    u64::try_from(arg.get_bytes())
-                        .map_err(|e| anyhow!(e))
-                        .and_then(|n| Ok(MemoryAllocation::try_from(n)?))
-                        .context(
-                            "Memory allocation must be between 0 and 2^48 (i.e 256TB), inclusively.",
-                        )
-                })
-                .transpose()?;
-            let freezing_threshold =
-                config_interface
-                    .get_freezing_threshold(canister_name)?
-                    .map(|arg| {
-                        FreezingThreshold::try_from(arg.as_secs())
-                            .expect("Freezing threshold must be between 0 and 2^64-1, inclusively.")
-                    });
-            let reserved_cycles_limit = config_interface
-                .get_reserved_cycles_limit(canister_name)?
-                .map(|arg| {
-                    ReservedCyclesLimit::try_from(arg)
-                        .expect("Reserved cycles limit must be between 0 and 2^128-1, inclusively.")
-                });
-            let wasm_memory_limit = config_interface.get_wasm_memory_limit(canister_name)?.map(
-                |arg| {
-                    u64::try_from(arg.get_bytes())
-                        .map_err(|e| anyhow!(e))
-                        .and_then(|n| Ok(WasmMemoryLimit::try_from(n)?))
-                        .context(
-                            "WASM memory limit must be between 0 and 2^48 (i.e 256TB), inclusively.",
-                        )
-                },
-            ).transpose()?;
-
-            let controllers = None;
             create_canister(
                 env,
                 canister_name,
@@ -310,14 +467,7 @@ async fn register_canisters(
                 call_sender,
                 no_wallet,
                 from_subaccount,
-                CanisterSettings {
-                    controllers,
-                    compute_allocation,
-                    memory_allocation,
-                    freezing_threshold,
-                    reserved_cycles_limit,
-                    wasm_memory_limit,
-                },
+                configured_settings(config, canister_name)?,
                 created_at_time,
                 subnet_selection,
             )
@@ -327,6 +477,56 @@ async fn register_canisters(
     Ok(())
 }
 
+/// The settings configured for a canister in dfx.json.
+fn configured_settings(config: &Config, canister_name: &str) -> DfxResult<CanisterSettings> {
+    let config_interface = config.get_config();
+    let compute_allocation = config_interface
+        .get_compute_allocation(canister_name)?
+        .map(|arg| {
+            ComputeAllocation::try_from(arg).context("Compute Allocation must be a percentage.")
+        })
+        .transpose()?;
+    let memory_allocation = config_interface
+        .get_memory_allocation(canister_name)?
+        .map(|arg| {
+            u64::try_from(arg.get_bytes())
+                .map_err(|e| anyhow!(e))
+                .and_then(|n| Ok(MemoryAllocation::try_from(n)?))
+                .context("Memory allocation must be between 0 and 2^48 (i.e 256TB), inclusively.")
+        })
+        .transpose()?;
+    let freezing_threshold = config_interface
+        .get_freezing_threshold(canister_name)?
+        .map(|arg| {
+            FreezingThreshold::try_from(arg.as_secs())
+                .expect("Freezing threshold must be between 0 and 2^64-1, inclusively.")
+        });
+    let reserved_cycles_limit = config_interface
+        .get_reserved_cycles_limit(canister_name)?
+        .map(|arg| {
+            ReservedCyclesLimit::try_from(arg)
+                .expect("Reserved cycles limit must be between 0 and 2^128-1, inclusively.")
+        });
+    let wasm_memory_limit = config_interface
+        .get_wasm_memory_limit(canister_name)?
+        .map(|arg| {
+            u64::try_from(arg.get_bytes())
+                .map_err(|e| anyhow!(e))
+                .and_then(|n| Ok(WasmMemoryLimit::try_from(n)?))
+                .context("WASM memory limit must be between 0 and 2^48 (i.e 256TB), inclusively.")
+        })
+        .transpose()?;
+
+    Ok(CanisterSettings {
+        controllers: None,
+        compute_allocation,
+        memory_allocation,
+        freezing_threshold,
+        reserved_cycles_limit,
+        wasm_memory_limit,
+    })
+}
+
 #[context("Failed to build all canisters.")]
 async fn build_canisters(
     env: &dyn Environment,
@@ -335,6 +535,7 @@ async fn build_canisters(
     config: &Config,
     env_file: Option<PathBuf>,
     canister_pool: &CanisterPool,
+    modules_only: bool,
 ) -> DfxResult<()> {
     let log = env.get_logger();
     info!(log, "Building canisters...");
@@ -349,7 +550,8 @@ async fn build_canisters(
                     .map(|canister| canister.get_name().to_string())
                     .collect(),
             ) // hack
-            .with_env_file(env_file);
+            .with_env_file(env_file)
+            .with_modules_only(modules_only);
     canister_pool.build_or_fail(env, log, &build_config).await?;
     Ok(())
 }
@@ -404,6 +606,85 @@ async fn install_canisters(
             env_file,
             no_asset_upgrade,
             always_assist,
+            true,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[context("Failed while trying to install the planned canisters.")]
+async fn install_planned_canisters(
+    env: &dyn Environment,
+    plan: &DeployPlan,
+    config: &Config,
+    call_sender: &CallSender,
+    pool: CanisterPool,
+    env_file: Option<&Path>,
+) -> DfxResult {
+    info!(env.get_logger(), "Installing canisters...");
+
+    let mut canister_id_store = env.get_canister_id_store()?;
+
+    for canister in &plan.canisters {
+        let install_mode = match canister.action {
+            InstallAction::Install => InstallMode::Install,
+            InstallAction::Upgrade | InstallAction::Skip => InstallMode::Upgrade {
+                skip_pre_upgrade: Some(false),
+            },
+            InstallAction::Reinstall => InstallMode::Reinstall,
+        };
+
+        let canister_id = canister_id_store.get(&canister.name)?;
+        let canister_info = CanisterInfo::load(config, &canister.name, Some(canister_id))?;
+
+        if !canister.settings_changes.is_empty() {
+            info!(
+                env.get_logger(),
+                "Updating settings of canister {}...", canister.name
+            );
+            let configured = configured_settings(config, &canister.name)?;
+            let changed = |setting: &str| canister.settings_changes.contains_key(setting);
+            let settings = CanisterSettings {
+                controllers: None,
+                compute_allocation: configured
+                    .compute_allocation
+                    .filter(|_| changed("compute_allocation")),
+                memory_allocation: configured
+                    .memory_allocation
+                    .filter(|_| changed("memory_allocation")),
+                freezing_threshold: configured
+                    .freezing_threshold
+                    .filter(|_| changed("freezing_threshold")),
+                reserved_cycles_limit: configured
+                    .reserved_cycles_limit
+                    .filter(|_| changed("reserved_cycles_limit")),
+                wasm_memory_limit: configured
+                    .wasm_memory_limit
+                    .filter(|_| changed("wasm_memory_limit")),
+            };
+            update_settings(env, canister_id, settings, call_sender).await?;
+        }
+
+        // The compatibility checks were part of the reviewed plan, so there is nothing to consent to.
+        install_canister(
+            env,
+            &mut canister_id_store,
+            canister_id,
+            &canister_info,
+            None,
+            plan.argument.as_deref(),
+            plan.argument_type.as_deref(),
+            Some(install_mode),
+            call_sender,
+            plan.upgrade_unchanged,
+            Some(&pool),
+            true,
+            env_file,
+            plan.no_asset_upgrade,
+            false,
+            true,
         )
         .await?;
     }
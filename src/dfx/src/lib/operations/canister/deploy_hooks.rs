@@ -0,0 +1,226 @@
+//! The `hooks` of dfx.json, which run around deployments at the project and canister level.
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use anyhow::{anyhow, bail, Context};
+use candid::Principal;
+use dfx_core::config::model::canister_id_store::CanisterIdStore;
+use dfx_core::config::model::dfinity::{Config, ConfigHooks};
+use fn_error_context::context;
+use ic_utils::interfaces::management_canister::builders::InstallMode;
+use serde::Serialize;
+use slog::{info, warn};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Copy, Clone, Debug)]
+pub enum Hook {
+    PreDeploy,
+    PostDeploy,
+    PreUpgradeCheck,
+    OnFailure,
+}
+
+impl Hook {
+    fn name(&self) -> &'static str {
+        match self {
+            Hook::PreDeploy => "pre_deploy",
+            Hook::PostDeploy => "post_deploy",
+            Hook::PreUpgradeCheck => "pre_upgrade_check",
+            Hook::OnFailure => "on_failure",
+        }
+    }
+
+    fn commands(&self, hooks: &ConfigHooks) -> Vec<String> {
+        match self {
+            Hook::PreDeploy => hooks.pre_deploy.clone(),
+            Hook::PostDeploy => hooks.post_deploy.clone(),
+            Hook::PreUpgradeCheck => hooks.pre_upgrade_check.clone(),
+            Hook::OnFailure => hooks.on_failure.clone(),
+        }
+        .into_vec()
+    }
+}
+
+/// The JSON that hooks receive on stdin.
+#[derive(Clone, Debug, Serialize)]
+pub struct HookContext {
+    pub hook: &'static str,
+    pub network: String,
+    /// The principal of the selected identity.
+    pub identity: Option<String>,
+    /// The IDs of the canisters of the project that have been created.
+    pub canister_ids: BTreeMap<String, String>,
+    /// The canister being deployed, for the hooks of a canister and `pre_upgrade_check`.
+    pub canister: Option<String>,
+    pub canister_id: Option<String>,
+    pub old_module_hash: Option<String>,
+    pub new_module_hash: Option<String>,
+    pub install_mode: Option<&'static str>,
+    /// The error of a failed deployment, for `on_failure`.
+    pub error: Option<String>,
+}
+
+impl HookContext {
+    pub fn new(
+        env: &dyn Environment,
+        config: &Config,
+        canister_id_store: &CanisterIdStore,
+    ) -> Self {
+        let canister_ids = config
+            .get_config()
+            .canisters
+            .iter()
+            .flat_map(|canisters| canisters.keys())
+            .filter_map(|name| Some((name.clone(), canister_id_store.find(name)?.to_text())))
+            .collect();
+        HookContext {
+            hook: "",
+            network: env.get_network_descriptor().name.clone(),
+            identity: env
+                .get_selected_identity_principal()
+                .map(|principal| principal.to_text()),
+            canister_ids,
+            canister: None,
+            canister_id: None,
+            old_module_hash: None,
+            new_module_hash: None,
+            install_mode: None,
+            error: None,
+        }
+    }
+
+    pub fn with_canister(
+        mut self,
+        name: &str,
+        canister_id: Principal,
+        old_module_hash: Option<&[u8]>,
+        new_module_hash: &[u8],
+        mode: &InstallMode,
+    ) -> Self {
+        self.canister = Some(name.to_string());
+        self.canister_id = Some(canister_id.to_text());
+        self.old_module_hash = old_module_hash.map(hex::encode);
+        self.new_module_hash = Some(hex::encode(new_module_hash));
+        self.install_mode = Some(match mode {
+            InstallMode::Install => "install",
+            InstallMode::Reinstall => "reinstall",
+            InstallMode::Upgrade { .. } => "upgrade",
+        });
+        self
+    }
+}
+
+/// Runs the commands of a hook in the project root, in order.
+/// The first command that fails aborts the deployment.
+#[context("Failed to run the {} hooks.", hook.name())]
+pub fn run_hooks(
+    env: &dyn Environment,
+    hook: Hook,
+    hooks: &ConfigHooks,
+    project_root: &Path,
+    context: &HookContext,
+) -> DfxResult {
+    let commands = hook.commands(hooks);
+    if commands.is_empty() {
+        return Ok(());
+    }
+    let context = HookContext {
+        hook: hook.name(),
+        ..context.clone()
+    };
+    let input = serde_json::to_vec(&context)?;
+    for command in commands {
+        info!(
+            env.get_logger(),
+            "Running {} hook `{}`",
+            hook.name(),
+            command
+        );
+        run_hook(hook, &command, project_root, &input)?;
+    }
+    Ok(())
+}
+
+/// Runs the `on_failure` hooks of a failed deployment, and returns its error.
+/// A failing `on_failure` hook is reported without replacing the error of the deployment.
+pub fn run_failure_hooks(
+    env: &dyn Environment,
+    hooks: &ConfigHooks,
+    project_root: &Path,
+    context: &HookContext,
+    error: anyhow::Error,
+) -> anyhow::Error {
+    let context = HookContext {
+        error: Some(format!("{error:#}")),
+        ..context.clone()
+    };
+    if let Err(hook_error) = run_hooks(env, Hook::OnFailure, hooks, project_root, &context) {
+        warn!(env.get_logger(), "{:#}", hook_error);
+    }
+    error
+}
+
+fn run_hook(hook: Hook, command: &str, project_root: &Path, input: &[u8]) -> DfxResult {
+    let words = shell_words::split(command)
+        .with_context(|| format!("Error interpreting {} hook `{command}`", hook.name()))?;
+    let program = words
+        .first()
+        .ok_or_else(|| anyhow!("The {} hook `{command}` is empty", hook.name()))?;
+    let program = dfx_core::fs::canonicalize(&project_root.join(program))
+        .or_else(|_| which::which(program))
+        .map_err(|_| anyhow!("Cannot find command or file {program}"))?;
+    let mut child = Command::new(program)
+        .args(&words[1..])
+        .current_dir(project_root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Failed to run {} hook `{command}`", hook.name()))?;
+    // A hook that does not read its input closes stdin early, which is not an error.
+    let _ = child.stdin.take().expect("stdin is piped").write_all(input);
+    let status = child.wait()?;
+    if !status.success() {
+        match status.code() {
+            Some(code) => bail!(
+                "The {} hook `{command}` failed with exit code {code}, so the deployment was aborted.",
+                hook.name()
+            ),
+            None => bail!(
+                "The {} hook `{command}` was terminated by a signal, so the deployment was aborted.",
+                hook.name()
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_receive_the_context_and_fail_on_non_zero_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = br#"{"hook":"pre_deploy"}"#;
+        let output = dir.path().join("input.json");
+        run_hook(
+            Hook::PreDeploy,
+            &format!("sh -c 'cat > {}'", output.display()),
+            dir.path(),
+            input,
+        )
+        .unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), input);
+
+        let err = run_hook(Hook::PreUpgradeCheck, "sh -c 'exit 3'", dir.path(), input)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "The pre_upgrade_check hook `sh -c 'exit 3'` failed with exit code 3, so the deployment was aborted."
+        );
+    }
+}
@@ -0,0 +1,673 @@
+//! `dfx deploy --plan` and `dfx deploy --apply-plan`.
+//!
+//! A plan records everything a deploy would do, resolved against the current state of the network.
+//! Applying a plan first checks that the network and the project still match what was planned,
+//! so that exactly what was reviewed gets executed.
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::operations::canister::get_canister_status;
+use crate::lib::operations::canister::install_canister::{
+    check_candid_compatibility, check_stable_compatibility,
+};
+use crate::lib::state_tree::canister_info::read_state_tree_canister_module_hash;
+use crate::util::read_module_metadata;
+use anyhow::bail;
+use candid::Principal;
+use dfx_core::config::model::canister_id_store::CanisterIdStore;
+use dfx_core::config::model::dfinity::Config;
+use dfx_core::identity::CallSender;
+use fn_error_context::context;
+use ic_utils::interfaces::management_canister::StatusCallResult;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeployPlan {
+    /// The network the plan was made for.
+    pub network: String,
+
+    /// The principal of the identity the plan was made with.
+    pub identity: Option<Principal>,
+
+    /// The canister named on the command line, if any.
+    pub canister: Option<String>,
+
+    /// The canisters selected with --group or --tag, if any.
+    #[serde(default)]
+    pub selected_canisters: Option<Vec<String>>,
+
+    pub argument: Option<String>,
+    pub argument_type: Option<String>,
+    pub force_reinstall: bool,
+    pub upgrade_unchanged: bool,
+    pub no_asset_upgrade: bool,
+
+    /// False if the plan builds canisters with placeholder canister IDs,
+    /// because some canisters do not exist yet. The module hashes of these canisters,
+    /// and of the canisters that depend on them, are then only known after creation.
+    pub exact_module_hashes: bool,
+
+    /// In the order they will be deployed.
+    pub canisters: Vec<CanisterPlan>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CanisterPlan {
+    pub name: String,
+    pub canister_id: Option<Principal>,
+    pub create: Option<CreatePlan>,
+    pub action: InstallAction,
+    pub installed_module_hash: Option<String>,
+    pub module_hash: Option<String>,
+    pub candid_compatibility: Option<CompatibilityCheck>,
+    pub stable_compatibility: Option<CompatibilityCheck>,
+
+    /// The controllers of an existing canister.
+    #[serde(default)]
+    pub controllers: Option<Vec<Principal>>,
+
+    /// The settings of an existing canister that differ from dfx.json, and are updated.
+    #[serde(default)]
+    pub settings_changes: BTreeMap<String, SettingChange>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SettingChange {
+    pub current: String,
+    pub planned: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CreatePlan {
+    /// None if the network's default amount is used.
+    pub cycles: Option<u128>,
+    pub settings: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallAction {
+    Install,
+    Upgrade,
+    Reinstall,
+    /// The module is already installed.
+    Skip,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "result", content = "details", rename_all = "snake_case")]
+pub enum CompatibilityCheck {
+    Compatible,
+    Incompatible(String),
+    Failed(String),
+}
+
+impl From<anyhow::Result<Option<String>>> for CompatibilityCheck {
+    fn from(result: anyhow::Result<Option<String>>) -> Self {
+        match result {
+            Ok(None) => CompatibilityCheck::Compatible,
+            Ok(Some(err)) => CompatibilityCheck::Incompatible(err),
+            Err(err) => CompatibilityCheck::Failed(format!("{err:#}")),
+        }
+    }
+}
+
+impl fmt::Display for InstallAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            InstallAction::Install => "install",
+            InstallAction::Upgrade => "upgrade",
+            InstallAction::Reinstall => "reinstall",
+            InstallAction::Skip => "skip",
+        };
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for CompatibilityCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatibilityCheck::Compatible => write!(f, "compatible"),
+            CompatibilityCheck::Incompatible(err) => write!(f, "INCOMPATIBLE\n{}", indent(err)),
+            CompatibilityCheck::Failed(err) => write!(f, "check failed\n{}", indent(err)),
+        }
+    }
+}
+
+fn indent(s: &str) -> String {
+    s.trim_end()
+        .lines()
+        .map(|l| format!("      {l}"))
+        .join("\n")
+}
+
+fn short_hash(hash: &Option<String>) -> &str {
+    hash.as_deref().map_or("none", |h| &h[..h.len().min(12)])
+}
+
+impl fmt::Display for DeployPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Deploy plan for network {}:", self.network)?;
+        for canister in &self.canisters {
+            let id = canister
+                .canister_id
+                .map_or_else(|| "not created".to_string(), |id| id.to_text());
+            writeln!(f, "  {} ({}):", canister.name, id)?;
+            if let Some(create) = &canister.create {
+                let cycles = create
+                    .cycles
+                    .map_or_else(|| "the default amount of".to_string(), |c| c.to_string());
+                writeln!(f, "    create with {} cycles", cycles)?;
+                for (name, value) in &create.settings {
+                    writeln!(f, "      {}: {}", name, value)?;
+                }
+            }
+            if let Some(controllers) = &canister.controllers {
+                writeln!(
+                    f,
+                    "    controllers: {}",
+                    controllers.iter().map(|c| c.to_text()).join(", ")
+                )?;
+            }
+            if !canister.settings_changes.is_empty() {
+                writeln!(f, "    update settings")?;
+                for (name, change) in &canister.settings_changes {
+                    writeln!(
+                        f,
+                        "      {}: {} -> {}",
+                        name, change.current, change.planned
+                    )?;
+                }
+            }
+            match canister.action {
+                InstallAction::Skip => writeln!(
+                    f,
+                    "    skip: module {} is already installed",
+                    short_hash(&canister.module_hash)
+                )?,
+                action if canister.module_hash.is_none() => writeln!(
+                    f,
+                    "    {} module built after the canisters are created (installed: {})",
+                    action,
+                    short_hash(&canister.installed_module_hash)
+                )?,
+                action => writeln!(
+                    f,
+                    "    {} module {} (installed: {})",
+                    action,
+                    short_hash(&canister.module_hash),
+                    short_hash(&canister.installed_module_hash)
+                )?,
+            }
+            if let Some(check) = &canister.candid_compatibility {
+                writeln!(f, "    candid interface: {}", check)?;
+            }
+            if let Some(check) = &canister.stable_compatibility {
+                writeln!(f, "    stable types: {}", check)?;
+            }
+        }
+        if !self.exact_module_hashes {
+            writeln!(
+                f,
+                "Some canisters are not created yet, so module hashes are determined when the plan is applied."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The settings a new canister is created with, as configured in dfx.json.
+pub fn planned_settings(
+    config: &Config,
+    canister_name: &str,
+) -> DfxResult<BTreeMap<String, String>> {
+    let config_interface = config.get_config();
+    let mut settings = BTreeMap::new();
+    if let Some(compute_allocation) = config_interface.get_compute_allocation(canister_name)? {
+        settings.insert(
+            "compute_allocation".to_string(),
+            compute_allocation.to_string(),
+        );
+    }
+    if let Some(memory_allocation) = config_interface.get_memory_allocation(canister_name)? {
+        settings.insert(
+            "memory_allocation".to_string(),
+            memory_allocation.get_bytes().to_string(),
+        );
+    }
+    if let Some(freezing_threshold) = config_interface.get_freezing_threshold(canister_name)? {
+        settings.insert(
+            "freezing_threshold".to_string(),
+            freezing_threshold.as_secs().to_string(),
+        );
+    }
+    if let Some(reserved_cycles_limit) =
+        config_interface.get_reserved_cycles_limit(canister_name)?
+    {
+        settings.insert(
+            "reserved_cycles_limit".to_string(),
+            reserved_cycles_limit.to_string(),
+        );
+    }
+    if let Some(wasm_memory_limit) = config_interface.get_wasm_memory_limit(canister_name)? {
+        settings.insert(
+            "wasm_memory_limit".to_string(),
+            wasm_memory_limit.get_bytes().to_string(),
+        );
+    }
+    Ok(settings)
+}
+
+/// The settings of an existing canister, in the units of [`planned_settings`].
+fn current_settings(status: &StatusCallResult) -> BTreeMap<String, String> {
+    let settings = &status.settings;
+    let mut current = BTreeMap::from([
+        (
+            "compute_allocation".to_string(),
+            settings.compute_allocation.0.to_string(),
+        ),
+        (
+            "memory_allocation".to_string(),
+            settings.memory_allocation.0.to_string(),
+        ),
+        (
+            "freezing_threshold".to_string(),
+            settings.freezing_threshold.0.to_string(),
+        ),
+    ]);
+    if let Some(reserved_cycles_limit) = &settings.reserved_cycles_limit {
+        current.insert(
+            "reserved_cycles_limit".to_string(),
+            reserved_cycles_limit.0.to_string(),
+        );
+    }
+    if let Some(wasm_memory_limit) = &settings.wasm_memory_limit {
+        current.insert(
+            "wasm_memory_limit".to_string(),
+            wasm_memory_limit.0.to_string(),
+        );
+    }
+    current
+}
+
+/// The settings configured in dfx.json that differ from the current settings of a canister.
+/// Settings that dfx.json does not configure are left as they are.
+fn settings_changes(
+    planned: BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) -> BTreeMap<String, SettingChange> {
+    planned
+        .into_iter()
+        .filter_map(|(name, planned)| {
+            let current = current
+                .get(&name)
+                .cloned()
+                .unwrap_or_else(|| "none".to_string());
+            (current != planned).then_some((name, SettingChange { current, planned }))
+        })
+        .collect()
+}
+
+fn sorted_controllers(status: &StatusCallResult) -> Vec<Principal> {
+    let mut controllers = status.settings.controllers.clone();
+    controllers.sort();
+    controllers
+}
+
+#[context("Failed to compute the hash of the module of canister '{}'.", canister_info.get_name())]
+fn built_module_hash(canister_info: &CanisterInfo) -> DfxResult<String> {
+    let wasm_module = dfx_core::fs::read(&canister_info.get_build_wasm_path())?;
+    Ok(hex::encode(Sha256::digest(wasm_module)))
+}
+
+/// Resolve what deploying the (already built) canisters would do, without changing anything.
+#[context("Failed to make a deploy plan.")]
+#[allow(clippy::too_many_arguments)]
+pub async fn make_deploy_plan(
+    env: &dyn Environment,
+    some_canister: Option<&str>,
+    selected_canisters: Option<&[String]>,
+    canister_names: &[String],
+    canister_id_store: &CanisterIdStore,
+    config: &Config,
+    call_sender: &CallSender,
+    argument: Option<&str>,
+    argument_type: Option<&str>,
+    with_cycles: Option<u128>,
+    force_reinstall: bool,
+    upgrade_unchanged: bool,
+    no_asset_upgrade: bool,
+) -> DfxResult<DeployPlan> {
+    let agent = env.get_agent();
+    let exact_module_hashes = canister_names
+        .iter()
+        .all(|name| canister_id_store.find(name).is_some());
+
+    let mut canisters = vec![];
+    for canister_name in canister_names {
+        let canister_id = canister_id_store.find(canister_name);
+        let canister_info = CanisterInfo::load(config, canister_name, canister_id)?;
+        // A module embeds the IDs of the canisters it depends on,
+        // so it is final once the canister and its dependencies exist.
+        let module_is_final = config
+            .get_config()
+            .get_canister_names_with_dependencies(Some(canister_name))?
+            .iter()
+            .all(|name| canister_id_store.find(name).is_some() || !canister_names.contains(name));
+        let module_hash = if module_is_final {
+            Some(built_module_hash(&canister_info)?)
+        } else {
+            None
+        };
+
+        let Some(canister_id) = canister_id else {
+            canisters.push(CanisterPlan {
+                name: canister_name.clone(),
+                canister_id: None,
+                create: Some(CreatePlan {
+                    cycles: with_cycles,
+                    settings: planned_settings(config, canister_name)?,
+                }),
+                action: InstallAction::Install,
+                installed_module_hash: None,
+                module_hash,
+                candid_compatibility: None,
+                stable_compatibility: None,
+                controllers: None,
+                settings_changes: BTreeMap::new(),
+            });
+            continue;
+        };
+
+        let status = get_canister_status(env, canister_id, call_sender).await?;
+        let settings_changes = settings_changes(
+            planned_settings(config, canister_name)?,
+            &current_settings(&status),
+        );
+
+        let installed_module_hash = read_state_tree_canister_module_hash(agent, canister_id)
+            .await?
+            .map(hex::encode);
+        let action = match &installed_module_hash {
+            None => InstallAction::Install,
+            Some(_) if force_reinstall => InstallAction::Reinstall,
+            Some(installed) if module_hash.as_ref() == Some(installed) && !upgrade_unchanged => {
+                InstallAction::Skip
+            }
+            Some(_) => InstallAction::Upgrade,
+        };
+
+        let mut candid_compatibility = None;
+        let mut stable_compatibility = None;
+        if matches!(action, InstallAction::Upgrade | InstallAction::Reinstall) {
+            if let Some(candid) = read_module_metadata(agent, canister_id, "candid:service").await {
+                candid_compatibility =
+                    Some(check_candid_compatibility(&canister_info, &candid).into());
+            }
+        }
+        if action == InstallAction::Upgrade && canister_info.is_motoko() {
+            if let Some(stable_types) =
+                read_module_metadata(agent, canister_id, "motoko:stable-types").await
+            {
+                stable_compatibility =
+                    Some(check_stable_compatibility(&canister_info, env, &stable_types).into());
+            }
+        }
+
+        canisters.push(CanisterPlan {
+            name: canister_name.clone(),
+            canister_id: Some(canister_id),
+            create: None,
+            action,
+            installed_module_hash,
+            module_hash,
+            candid_compatibility,
+            stable_compatibility,
+            controllers: Some(sorted_controllers(&status)),
+            settings_changes,
+        });
+    }
+
+    Ok(DeployPlan {
+        network: env.get_network_descriptor().name.clone(),
+        identity: env.get_selected_identity_principal(),
+        canister: some_canister.map(String::from),
+        selected_canisters: selected_canisters.map(<[String]>::to_vec),
+        argument: argument.map(String::from),
+        argument_type: argument_type.map(String::from),
+        force_reinstall,
+        upgrade_unchanged,
+        no_asset_upgrade,
+        exact_module_hashes,
+        canisters,
+    })
+}
+
+#[context("Failed to load deploy plan from {}.", path.display())]
+pub fn load_deploy_plan(path: &Path) -> DfxResult<DeployPlan> {
+    Ok(dfx_core::json::load_json_file(path)?)
+}
+
+/// Check that the plan is applied to the network and with the identity it was made for.
+pub fn verify_plan_target(env: &dyn Environment, plan: &DeployPlan) -> DfxResult {
+    let network = &env.get_network_descriptor().name;
+    if &plan.network != network {
+        bail!(
+            "The plan was made for network '{}', not '{}'.",
+            plan.network,
+            network
+        );
+    }
+    if plan.identity != env.get_selected_identity_principal() {
+        bail!("The plan was made with a different identity.");
+    }
+    Ok(())
+}
+
+/// Check that the network and the project are still as they were when the plan was made.
+#[context("The deploy plan no longer applies.")]
+pub async fn verify_deploy_plan(
+    env: &dyn Environment,
+    plan: &DeployPlan,
+    canister_names: &[String],
+    canister_id_store: &CanisterIdStore,
+    config: &Config,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let planned_names = plan.canisters.iter().map(|c| &c.name).collect_vec();
+    if planned_names != canister_names.iter().collect_vec() {
+        bail!(
+            "The plan deploys canisters {}, but the project now deploys {}.",
+            planned_names.iter().join(", "),
+            canister_names.join(", ")
+        );
+    }
+
+    let agent = env.get_agent();
+    for canister in &plan.canisters {
+        let name = &canister.name;
+        let canister_id = canister_id_store.find(name);
+        if canister_id != canister.canister_id {
+            match canister_id {
+                Some(id) => bail!("Canister {name} has been created with ID {id}."),
+                None => bail!("Canister {name} does not exist anymore."),
+            }
+        }
+        if let Some(create) = &canister.create {
+            if planned_settings(config, name)? != create.settings {
+                bail!("The settings of canister {name} in dfx.json have changed.");
+            }
+        }
+        if let Some(canister_id) = canister_id {
+            let installed_module_hash = read_state_tree_canister_module_hash(agent, canister_id)
+                .await?
+                .map(hex::encode);
+            if installed_module_hash != canister.installed_module_hash {
+                bail!(
+                    "The module installed in canister {name} has changed from {} to {}.",
+                    short_hash(&canister.installed_module_hash),
+                    short_hash(&installed_module_hash)
+                );
+            }
+            let status = get_canister_status(env, canister_id, call_sender).await?;
+            if Some(sorted_controllers(&status)) != canister.controllers {
+                bail!("The controllers of canister {name} have changed.");
+            }
+            let settings_changes =
+                settings_changes(planned_settings(config, name)?, &current_settings(&status));
+            if settings_changes != canister.settings_changes {
+                bail!("The settings of canister {name} have changed.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that the canisters built for applying the plan are the modules that were planned.
+pub fn verify_planned_modules(
+    plan: &DeployPlan,
+    canister_id_store: &CanisterIdStore,
+    config: &Config,
+) -> DfxResult {
+    for canister in &plan.canisters {
+        let Some(planned) = &canister.module_hash else {
+            continue;
+        };
+        let canister_id = canister_id_store.get(&canister.name)?;
+        let canister_info = CanisterInfo::load(config, &canister.name, Some(canister_id))?;
+        let built = built_module_hash(&canister_info)?;
+        if &built != planned {
+            bail!(
+                "The module built for canister {} ({}) is not the planned module ({}).",
+                canister.name,
+                built,
+                planned
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_round_trips_through_json() {
+        let plan = DeployPlan {
+            network: "local".to_string(),
+            identity: Some(Principal::anonymous()),
+            canister: None,
+            selected_canisters: Some(vec!["backend".to_string()]),
+            argument: None,
+            argument_type: None,
+            force_reinstall: false,
+            upgrade_unchanged: false,
+            no_asset_upgrade: false,
+            exact_module_hashes: true,
+            canisters: vec![CanisterPlan {
+                name: "backend".to_string(),
+                canister_id: Some(Principal::management_canister()),
+                create: None,
+                action: InstallAction::Upgrade,
+                installed_module_hash: Some("aa".repeat(32)),
+                module_hash: Some("bb".repeat(32)),
+                candid_compatibility: Some(CompatibilityCheck::Incompatible(
+                    "method greet is missing".to_string(),
+                )),
+                stable_compatibility: Some(CompatibilityCheck::Compatible),
+                controllers: Some(vec![Principal::anonymous()]),
+                settings_changes: BTreeMap::from([(
+                    "freezing_threshold".to_string(),
+                    SettingChange {
+                        current: "2592000".to_string(),
+                        planned: "86400".to_string(),
+                    },
+                )]),
+            }],
+        };
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["canisters"][0]["action"], "upgrade");
+        assert_eq!(
+            json["canisters"][0]["candid_compatibility"]["result"],
+            "incompatible"
+        );
+        assert_eq!(serde_json::from_value::<DeployPlan>(json).unwrap(), plan);
+        assert!(plan
+            .to_string()
+            .contains("upgrade module bbbbbbbbbbbb (installed: aaaaaaaaaaaa)"));
+        assert!(plan
+            .to_string()
+            .contains("update settings\n      freezing_threshold: 2592000 -> 86400\n"));
+    }
+
+    #[test]
+    fn modules_built_after_creation_have_no_hash() {
+        let plan = DeployPlan {
+            network: "local".to_string(),
+            identity: None,
+            canister: None,
+            selected_canisters: None,
+            argument: None,
+            argument_type: None,
+            force_reinstall: false,
+            upgrade_unchanged: false,
+            no_asset_upgrade: false,
+            exact_module_hashes: false,
+            canisters: vec![CanisterPlan {
+                name: "frontend".to_string(),
+                canister_id: Some(Principal::management_canister()),
+                create: None,
+                action: InstallAction::Upgrade,
+                installed_module_hash: Some("aa".repeat(32)),
+                module_hash: None,
+                candid_compatibility: None,
+                stable_compatibility: None,
+                controllers: None,
+                settings_changes: BTreeMap::new(),
+            }],
+        };
+        assert!(plan.to_string().contains(
+            "upgrade module built after the canisters are created (installed: aaaaaaaaaaaa)"
+        ));
+    }
+
+    #[test]
+    fn only_configured_settings_that_differ_change() {
+        let current = BTreeMap::from([
+            ("compute_allocation".to_string(), "0".to_string()),
+            ("memory_allocation".to_string(), "0".to_string()),
+            ("freezing_threshold".to_string(), "2592000".to_string()),
+        ]);
+        let planned = BTreeMap::from([
+            ("compute_allocation".to_string(), "10".to_string()),
+            ("freezing_threshold".to_string(), "2592000".to_string()),
+            ("wasm_memory_limit".to_string(), "1073741824".to_string()),
+        ]);
+        assert_eq!(
+            settings_changes(planned, &current),
+            BTreeMap::from([
+                (
+                    "compute_allocation".to_string(),
+                    SettingChange {
+                        current: "0".to_string(),
+                        planned: "10".to_string(),
+                    }
+                ),
+                (
+                    "wasm_memory_limit".to_string(),
+                    SettingChange {
+                        current: "none".to_string(),
+                        planned: "1073741824".to_string(),
+                    }
+                ),
+            ])
+        );
+        assert!(settings_changes(BTreeMap::new(), &current).is_empty());
+    }
+}
@@ -6,6 +6,9 @@ use crate::lib::installers::assets::post_install_store_assets;
 use crate::lib::models::canister::CanisterPool;
 use crate::lib::named_canister;
 use crate::lib::operations::canister::all_project_canisters_with_ids;
+use crate::lib::operations::canister::deploy_hooks::{
+    run_failure_hooks, run_hooks, Hook, HookContext,
+};
 use crate::lib::operations::canister::motoko_playground::authorize_asset_uploader;
 use crate::lib::state_tree::canister_info::read_state_tree_canister_module_hash;
 use crate::util::assets::wallet_wasm;
@@ -51,6 +54,7 @@ pub async fn install_canister(
     env_file: Option<&Path>,
     no_asset_upgrade: bool,
     always_assist: bool,
+    run_project_hooks: bool,
 ) -> DfxResult {
     let log = env.get_logger();
     let agent = env.get_agent();
@@ -125,151 +129,204 @@ pub async fn install_canister(
     let new_hash = Sha256::digest(&wasm_module);
     debug!(log, "New wasm module hash: {}", hex::encode(new_hash));
 
-    if matches!(mode, InstallMode::Upgrade { .. })
+    let module_unchanged = matches!(mode, InstallMode::Upgrade { .. })
         && matches!(&installed_module_hash, Some(old_hash) if old_hash[..] == new_hash[..])
-        && !upgrade_unchanged
-    {
-        println!(
-            "Module hash {} is already installed.",
-            hex::encode(installed_module_hash.as_ref().unwrap())
-        );
-    } else if !(canister_info.is_assets() && no_asset_upgrade) {
-        let idl_path = canister_info.get_constructor_idl_path();
-        let init_type = if wasm_path_override.is_some() {
-            None
-        } else {
-            get_candid_init_type(&idl_path)
-        };
+        && !upgrade_unchanged;
+    let installs_code = !module_unchanged && !(canister_info.is_assets() && no_asset_upgrade);
+    let config = env.get_config()?;
+    let project_root = canister_info.get_workspace_root();
+    // Hooks run when code is installed in a canister of a project.
+    let hook_context = config.as_ref().filter(|_| installs_code).map(|config| {
+        HookContext::new(env, config, canister_id_store).with_canister(
+            canister_name,
+            canister_id,
+            installed_module_hash.as_deref(),
+            &new_hash,
+            &mode,
+        )
+    });
+    let hook_context = hook_context.as_ref();
+
+    let result: DfxResult = async {
+        if let (Some(config), Some(context)) = (&config, hook_context) {
+            if matches!(mode, InstallMode::Upgrade { .. }) {
+                // The hooks of the project only run with `dfx deploy`.
+                if run_project_hooks {
+                    run_hooks(
+                        env,
+                        Hook::PreUpgradeCheck,
+                        &config.get_config().hooks,
+                        project_root,
+                        context,
+                    )?;
+                }
+                run_hooks(
+                    env,
+                    Hook::PreUpgradeCheck,
+                    canister_info.get_hooks(),
+                    project_root,
+                    context,
+                )?;
+            }
+            run_hooks(env, Hook::PreDeploy, canister_info.get_hooks(), project_root, context)?;
+        }
+
+        if module_unchanged {
+            println!(
+                "Module hash {} is already installed.",
+                hex::encode(installed_module_hash.as_ref().unwrap())
+            );
+        } else if installs_code {
+            let idl_path = canister_info.get_constructor_idl_path();
+            let init_type = if wasm_path_override.is_some() {
+                None
+            } else {
+                get_candid_init_type(&idl_path)
+            };
 
-        // The argument and argument_type from the CLI take precedence over the dfx.json configuration.
-        let argument_from_json = canister_info.get_init_arg()?;
-        let (argument, argument_type) = match (argument_from_cli, &argument_from_json) {
-            (Some(a_cli), Some(a_json)) => {
-                // We want to warn the user when the argument from CLI and json are different.
-                // There are two cases to consider:
-                // 1. The argument from CLI is in raw format, while the argument from json is always in Candid format.
-                // 2. Both arguments are in Candid format, but they are different.
-                if argument_type_from_cli == Some("raw") || a_cli != a_json {
-                    warn!(
-                        log,
-                        "Canister '{0}' has init_arg/init_arg_file in dfx.json: {1},
+            // The argument and argument_type from the CLI take precedence over the dfx.json configuration.
+            let argument_from_json = canister_info.get_init_arg()?;
+            let (argument, argument_type) = match (argument_from_cli, &argument_from_json) {
+                (Some(a_cli), Some(a_json)) => {
+                    // We want to warn the user when the argument from CLI and json are different.
+                    // There are two cases to consider:
+                    // 1. The argument from CLI is in raw format, while the argument from json is always in Candid format.
+                    // 2. Both arguments are in Candid format, but they are different.
+                    if argument_type_from_cli == Some("raw") || a_cli != a_json {
+                        warn!(
+                            log,
+                            "Canister '{0}' has init_arg/init_arg_file in dfx.json: {1},
 which is different from the one specified in the command line: {2}.
 The command line value will be used.",
-                        canister_info.get_name(),
-                        a_json,
-                        a_cli
-                    );
+                            canister_info.get_name(),
+                            a_json,
+                            a_cli
+                        );
+                    }
+                    (argument_from_cli, argument_type_from_cli)
                 }
-                (argument_from_cli, argument_type_from_cli)
-            }
-            (Some(_), None) => (argument_from_cli, argument_type_from_cli),
-            (None, Some(a_json)) => (Some(a_json.as_str()), Some("idl")), // `init_arg` in dfx.json is always in Candid format
-            (None, None) => (None, None),
-        };
-        let install_args = blob_from_arguments(
-            Some(env),
-            argument,
-            None,
-            argument_type,
-            &init_type,
-            true,
-            always_assist,
-        )?;
-        if let Some(timestamp) = canister_id_store.get_timestamp(canister_info.get_name()) {
-            let new_timestamp = playground_install_code(
-                env,
-                canister_id,
-                timestamp,
-                &install_args,
-                &wasm_module,
-                mode,
-                canister_info.is_assets(),
-            )
-            .await?;
-            canister_id_store.add(
-                canister_info.get_name(),
-                &canister_id.to_string(),
-                Some(new_timestamp),
+                (Some(_), None) => (argument_from_cli, argument_type_from_cli),
+                (None, Some(a_json)) => (Some(a_json.as_str()), Some("idl")), // `init_arg` in dfx.json is always in Candid format
+                (None, None) => (None, None),
+            };
+            let install_args = blob_from_arguments(
+                Some(env),
+                argument,
+                None,
+                argument_type,
+                &init_type,
+                true,
+                always_assist,
             )?;
-        } else {
-            install_canister_wasm(
-                agent,
-                canister_id,
-                Some(canister_info.get_name()),
-                &install_args,
-                mode,
-                call_sender,
-                wasm_module,
-                skip_consent,
-            )
-            .await?;
+            if let Some(timestamp) = canister_id_store.get_timestamp(canister_info.get_name()) {
+                let new_timestamp = playground_install_code(
+                    env,
+                    canister_id,
+                    timestamp,
+                    &install_args,
+                    &wasm_module,
+                    mode,
+                    canister_info.is_assets(),
+                )
+                .await?;
+                canister_id_store.add(
+                    canister_info.get_name(),
+                    &canister_id.to_string(),
+                    Some(new_timestamp),
+                )?;
+            } else {
+                install_canister_wasm(
+                    agent,
+                    canister_id,
+                    Some(canister_info.get_name()),
+                    &install_args,
+                    mode,
+                    call_sender,
+                    wasm_module,
+                    skip_consent,
+                )
+                .await?;
+            }
         }
-    }
 
-    wait_for_module_hash(
-        env,
-        agent,
-        canister_id,
-        installed_module_hash.as_deref(),
-        &new_hash,
-    )
-    .await?;
+        wait_for_module_hash(
+            env,
+            agent,
+            canister_id,
+            installed_module_hash.as_deref(),
+            &new_hash,
+        )
+        .await?;
+
+        if canister_info.is_assets() {
+            if let Some(canister_timeout) = canister_id_store.get_timestamp(canister_info.get_name()) {
+                // playground installed the code, so playground has to authorize call_sender to upload files
+                let uploader_principal = env
+                    .get_selected_identity_principal()
+                    .context("Failed to figure out selected identity's principal.")?;
+                authorize_asset_uploader(
+                    env,
+                    canister_info.get_canister_id()?,
+                    canister_timeout,
+                    &uploader_principal,
+                )
+                .await?;
+            }
+            if let CallSender::Wallet(wallet_id) = call_sender {
+                let wallet = build_wallet_canister(*wallet_id, agent).await?;
+                let identity_name = env.get_selected_identity().expect("No selected identity.");
+                info!(
+                    log,
+                    "Authorizing our identity ({}) to the asset canister...", identity_name
+                );
+                let self_id = env
+                    .get_selected_identity_principal()
+                    .expect("Selected identity not instantiated.");
+                // Before storing assets, make sure the DFX principal is in there first.
+                wallet
+                    .call(
+                        canister_id,
+                        "authorize",
+                        Argument::from_candid((self_id,)),
+                        0,
+                    )
+                    .call_and_wait()
+                    .await
+                    .context("Failed to authorize your principal with the canister. You can still control the canister by using your wallet with the --wallet flag.")?;
+            };
 
-    if canister_info.is_assets() {
-        if let Some(canister_timeout) = canister_id_store.get_timestamp(canister_info.get_name()) {
-            // playground installed the code, so playground has to authorize call_sender to upload files
-            let uploader_principal = env
-                .get_selected_identity_principal()
-                .context("Failed to figure out selected identity's principal.")?;
-            authorize_asset_uploader(
+            info!(log, "Uploading assets to asset canister...");
+            post_install_store_assets(canister_info, agent, log).await?;
+        }
+        if !canister_info.get_post_install().is_empty() {
+            run_post_install_tasks(
                 env,
-                canister_info.get_canister_id()?,
-                canister_timeout,
-                &uploader_principal,
-            )
-            .await?;
+                canister_info,
+                network,
+                pool,
+                env_file.or_else(|| config.as_ref()?.get_config().output_env_file.as_deref()),
+            )?;
         }
-        if let CallSender::Wallet(wallet_id) = call_sender {
-            let wallet = build_wallet_canister(*wallet_id, agent).await?;
-            let identity_name = env.get_selected_identity().expect("No selected identity.");
-            info!(
-                log,
-                "Authorizing our identity ({}) to the asset canister...", identity_name
-            );
-            let self_id = env
-                .get_selected_identity_principal()
-                .expect("Selected identity not instantiated.");
-            // Before storing assets, make sure the DFX principal is in there first.
-            wallet
-                .call(
-                    canister_id,
-                    "authorize",
-                    Argument::from_candid((self_id,)),
-                    0,
-                )
-                .call_and_wait()
-                .await
-                .context("Failed to authorize your principal with the canister. You can still control the canister by using your wallet with the --wallet flag.")?;
-        };
 
-        info!(log, "Uploading assets to asset canister...");
-        post_install_store_assets(canister_info, agent, log).await?;
+        if let Some(context) = hook_context {
+            run_hooks(env, Hook::PostDeploy, canister_info.get_hooks(), project_root, context)?;
+        }
+        Ok(())
     }
-    if !canister_info.get_post_install().is_empty() {
-        let config = env.get_config()?;
-        run_post_install_tasks(
+    .await;
+    match (result, hook_context) {
+        (Err(err), Some(context)) => Err(run_failure_hooks(
             env,
-            canister_info,
-            network,
-            pool,
-            env_file.or_else(|| config.as_ref()?.get_config().output_env_file.as_deref()),
-        )?;
+            canister_info.get_hooks(),
+            project_root,
+            context,
+            err,
+        )),
+        (result, _) => result,
     }
-
-    Ok(())
 }
 
-fn check_candid_compatibility(
+pub(crate) fn check_candid_compatibility(
     canister_info: &CanisterInfo,
     candid: &str,
 ) -> anyhow::Result<Option<String>> {
@@ -370,7 +427,7 @@ async fn wait_for_module_hash(
     Ok(())
 }
 
-fn check_stable_compatibility(
+pub(crate) fn check_stable_compatibility(
     canister_info: &CanisterInfo,
     env: &dyn Environment,
     stable_types: &str,
@@ -1,5 +1,7 @@
 pub(crate) mod create_canister;
 pub(crate) mod deploy_canisters;
+pub(crate) mod deploy_hooks;
+pub(crate) mod deploy_plan;
 pub(crate) mod install_canister;
 pub mod motoko_playground;
 
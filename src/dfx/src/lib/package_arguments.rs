@@ -1,3 +1,4 @@
+use crate::lib::builders::ResolvedMotokoPackage;
 use crate::lib::error::{BuildError, DfxError, DfxResult};
 use anyhow::{anyhow, bail};
 use dfx_core::config::cache::Cache;
@@ -6,11 +7,37 @@ use std::process::Command;
 
 /// Package arguments for moc or mo-ide as returned by
 /// a package tool like https://github.com/kritzcreek/vessel
-/// or, if there is no package tool, the base library.
+/// or, if there is no package tool, the base library,
+/// followed by the packages resolved by dfx.
 pub type PackageArguments = Vec<String>;
 
 #[context("Failed to load package arguments.")]
-pub fn load(cache: &dyn Cache, packtool: &Option<String>) -> DfxResult<PackageArguments> {
+pub fn load(
+    cache: &dyn Cache,
+    packtool: &Option<String>,
+    packages: &[ResolvedMotokoPackage],
+) -> DfxResult<PackageArguments> {
+    let mut package_arguments =
+        if packtool.is_none() && packages.iter().any(|package| package.name == "base") {
+            vec![]
+        } else {
+            load_packtool(cache, packtool)?
+        };
+    for package in packages {
+        let path = package
+            .path
+            .to_str()
+            .ok_or_else(|| anyhow!("Path contains invalid Unicode data."))?;
+        package_arguments.extend([
+            String::from("--package"),
+            package.name.clone(),
+            path.to_string(),
+        ]);
+    }
+    Ok(package_arguments)
+}
+
+fn load_packtool(cache: &dyn Cache, packtool: &Option<String>) -> DfxResult<PackageArguments> {
     if packtool.is_none() {
         let stdlib_path = cache
             .get_binary_command_path("base")?
@@ -1,2 +1,3 @@
 pub mod import;
 pub mod network_mappings;
+pub mod templates;
@@ -0,0 +1,350 @@
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use anyhow::{bail, Context};
+use dfx_core::json::load_json_file;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Input;
+use flate2::read::GzDecoder;
+use fn_error_context::context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tar::Archive;
+use tempfile::TempDir;
+
+pub const TEMPLATE_MANIFEST_FILE_NAME: &str = "dfx-template.json";
+
+/// The `dfx-template.json` at the root of a project template.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateManifest {
+    /// A one-line description of the template.
+    pub description: Option<String>,
+
+    /// Variables that are replaced in file contents as `{name}` and in paths as `__name__`,
+    /// in addition to the variables of the built-in templates such as `project_name`.
+    #[serde(default)]
+    pub variables: BTreeMap<String, TemplateVariable>,
+
+    /// Directories, relative to the template, whose files are copied into the new project in order.
+    /// Files ending in `.json-patch` or `.patch` patch a file created by an earlier directory.
+    #[serde(default = "default_files")]
+    pub files: Vec<PathBuf>,
+
+    /// Commands that are run with `sh -c` in the new project after its files are created.
+    #[serde(default)]
+    pub post_create: Vec<String>,
+}
+
+fn default_files() -> Vec<PathBuf> {
+    vec![PathBuf::from("files")]
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateVariable {
+    /// The question to ask when the value is not passed with `--var`.
+    pub prompt: Option<String>,
+
+    /// The value to use when the value is not passed with `--var` and there is nobody to ask.
+    pub default: Option<String>,
+}
+
+/// Where `dfx new --template` gets the template from.
+#[derive(Debug, PartialEq, Eq)]
+enum TemplateSource {
+    Directory(PathBuf),
+    /// A local path or an http(s) URL.
+    Archive(String),
+    Git {
+        url: String,
+        reference: Option<String>,
+    },
+    Extension(String),
+}
+
+impl TemplateSource {
+    fn parse(template: &str) -> Self {
+        if template.ends_with(".tar.gz") || template.ends_with(".tgz") {
+            return Self::Archive(template.to_string());
+        }
+        let is_git = ["https://", "http://", "ssh://", "git://", "git@"]
+            .iter()
+            .any(|prefix| template.starts_with(prefix))
+            || template.split('#').next().unwrap().ends_with(".git");
+        if is_git {
+            let (url, reference) = match template.split_once('#') {
+                Some((url, reference)) => (url, Some(reference.to_string())),
+                None => (template, None),
+            };
+            return Self::Git {
+                url: url.to_string(),
+                reference,
+            };
+        }
+        let path = Path::new(template);
+        if path.exists() || path.components().count() > 1 || template.starts_with('.') {
+            Self::Directory(path.to_path_buf())
+        } else {
+            Self::Extension(template.to_string())
+        }
+    }
+
+    fn is_remote(&self) -> bool {
+        match self {
+            Self::Archive(archive) => {
+                archive.starts_with("https://") || archive.starts_with("http://")
+            }
+            Self::Git { .. } => true,
+            Self::Directory(_) | Self::Extension(_) => false,
+        }
+    }
+}
+
+/// A project template that is ready to be applied.
+pub struct ProjectTemplate {
+    pub root: PathBuf,
+    pub manifest: TemplateManifest,
+    /// Whether the template was downloaded or cloned, rather than found on this machine.
+    pub remote: bool,
+    // Archives and repositories are extracted here, and deleted along with the template.
+    _temp_dir: Option<TempDir>,
+}
+
+impl ProjectTemplate {
+    #[context("Failed to get project template '{}'.", template)]
+    pub fn fetch(env: &dyn Environment, template: &str) -> DfxResult<Self> {
+        let source = TemplateSource::parse(template);
+        let remote = source.is_remote();
+        let (root, temp_dir) = match source {
+            TemplateSource::Directory(dir) => (dir, None),
+            TemplateSource::Archive(archive) => {
+                let temp_dir =
+                    tempfile::tempdir().context("Failed to create temporary directory.")?;
+                let content: Box<dyn Read> =
+                    if archive.starts_with("https://") || archive.starts_with("http://") {
+                        let response = reqwest::blocking::get(&archive)
+                            .and_then(|response| response.error_for_status())
+                            .with_context(|| format!("Failed to download {archive}."))?;
+                        Box::new(response)
+                    } else {
+                        Box::new(
+                            std::fs::File::open(&archive)
+                                .with_context(|| format!("Failed to open {archive}."))?,
+                        )
+                    };
+                Archive::new(GzDecoder::new(content))
+                    .unpack(temp_dir.path())
+                    .with_context(|| format!("Failed to unpack {archive}."))?;
+                (find_template_root(temp_dir.path()), Some(temp_dir))
+            }
+            TemplateSource::Git { url, reference } => {
+                let temp_dir =
+                    tempfile::tempdir().context("Failed to create temporary directory.")?;
+                let mut git = Command::new("git");
+                git.args(["clone", "--depth", "1", "--quiet"]);
+                if let Some(reference) = &reference {
+                    git.args(["--branch", reference]);
+                }
+                let status = git
+                    .arg(&url)
+                    .arg(temp_dir.path())
+                    .stdin(Stdio::null())
+                    .status()
+                    .context("Failed to run 'git clone'. Is git installed?")?;
+                if !status.success() {
+                    bail!("Failed to clone {url}.");
+                }
+                (temp_dir.path().to_path_buf(), Some(temp_dir))
+            }
+            TemplateSource::Extension(name) => {
+                let templates = env.get_extension_manager().list_project_templates()?;
+                let Some(extension_template) = templates.get(&name) else {
+                    let available = if templates.is_empty() {
+                        "No installed extension provides project templates.".to_string()
+                    } else {
+                        let names: Vec<String> = templates
+                            .iter()
+                            .map(|(name, template)| match &template.description {
+                                Some(description) => format!("{name} ({description})"),
+                                None => name.clone(),
+                            })
+                            .collect();
+                        format!("Templates provided by extensions: {}", names.join(", "))
+                    };
+                    bail!("'{name}' is not a directory, archive, git repository, or template provided by an extension. {available}");
+                };
+                (extension_template.path.clone(), None)
+            }
+        };
+
+        let manifest_path = root.join(TEMPLATE_MANIFEST_FILE_NAME);
+        if !manifest_path.exists() {
+            bail!("{} does not exist.", manifest_path.display());
+        }
+        let manifest: TemplateManifest = load_json_file(&manifest_path)?;
+        for files in &manifest.files {
+            if files.is_absolute() || !root.join(files).is_dir() {
+                bail!(
+                    "The files directory {} of the template does not exist.",
+                    files.display()
+                );
+            }
+        }
+        Ok(Self {
+            root,
+            manifest,
+            remote,
+            _temp_dir: temp_dir,
+        })
+    }
+}
+
+/// Archives often contain a single directory with the template, as GitHub's source archives do.
+fn find_template_root(dir: &Path) -> PathBuf {
+    if dir.join(TEMPLATE_MANIFEST_FILE_NAME).exists() {
+        return dir.to_path_buf();
+    }
+    let entries: Vec<_> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    match entries.as_slice() {
+        [single] if single.is_dir() => single.clone(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+impl TemplateManifest {
+    /// The values of the template variables: passed with `--var`, entered at a prompt, or the default.
+    pub fn resolve_variables(
+        &self,
+        values: &[(String, String)],
+        interactive: bool,
+    ) -> DfxResult<BTreeMap<String, String>> {
+        for (name, _) in values {
+            if !self.variables.contains_key(name) {
+                bail!("The template has no variable '{name}'.");
+            }
+        }
+        let theme = ColorfulTheme::default();
+        let mut resolved = BTreeMap::new();
+        for (name, variable) in &self.variables {
+            let value = if let Some((_, value)) = values.iter().rev().find(|(n, _)| n == name) {
+                value.clone()
+            } else if interactive {
+                let mut input = Input::<String>::with_theme(&theme)
+                    .with_prompt(variable.prompt.as_deref().unwrap_or(name));
+                if let Some(default) = &variable.default {
+                    input = input.default(default.clone());
+                }
+                input.interact_text()?
+            } else if let Some(default) = &variable.default {
+                default.clone()
+            } else {
+                bail!("The template variable '{name}' has no default. Pass it with --var {name}=<value>.");
+            };
+            resolved.insert(name.clone(), value);
+        }
+        Ok(resolved)
+    }
+}
+
+/// Parses a `--var` argument of the form `<name>=<value>`.
+pub fn parse_template_variable(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("Expected <name>=<value>, got '{arg}'.")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_template_sources() {
+        assert_eq!(
+            TemplateSource::parse("https://github.com/acme/starter.git#v2"),
+            TemplateSource::Git {
+                url: "https://github.com/acme/starter.git".to_string(),
+                reference: Some("v2".to_string())
+            }
+        );
+        assert_eq!(
+            TemplateSource::parse("git@github.com:acme/starter.git"),
+            TemplateSource::Git {
+                url: "git@github.com:acme/starter.git".to_string(),
+                reference: None
+            }
+        );
+        assert_eq!(
+            TemplateSource::parse("../starter.tar.gz"),
+            TemplateSource::Archive("../starter.tar.gz".to_string())
+        );
+        assert_eq!(
+            TemplateSource::parse("https://example.com/starter.tgz"),
+            TemplateSource::Archive("https://example.com/starter.tgz".to_string())
+        );
+        assert_eq!(
+            TemplateSource::parse("./starter"),
+            TemplateSource::Directory(PathBuf::from("./starter"))
+        );
+        assert_eq!(
+            TemplateSource::parse("templates/starter"),
+            TemplateSource::Directory(PathBuf::from("templates/starter"))
+        );
+        assert_eq!(
+            TemplateSource::parse("acme-starter"),
+            TemplateSource::Extension("acme-starter".to_string())
+        );
+    }
+
+    #[test]
+    fn remote_template_sources() {
+        assert!(TemplateSource::parse("https://github.com/acme/starter.git").is_remote());
+        assert!(TemplateSource::parse("https://example.com/starter.tgz").is_remote());
+        assert!(!TemplateSource::parse("../starter.tar.gz").is_remote());
+        assert!(!TemplateSource::parse("./starter").is_remote());
+        assert!(!TemplateSource::parse("acme-starter").is_remote());
+    }
+
+    #[test]
+    fn resolves_variables() {
+        let manifest: TemplateManifest = serde_json::from_str(
+            r#"{
+                "variables": {
+                    "company": { "prompt": "Company name", "default": "Acme" },
+                    "team": {}
+                },
+                "post_create": ["npm install"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.files, vec![PathBuf::from("files")]);
+
+        let values = vec![("team".to_string(), "infra".to_string())];
+        let resolved = manifest.resolve_variables(&values, false).unwrap();
+        assert_eq!(resolved["company"], "Acme");
+        assert_eq!(resolved["team"], "infra");
+
+        assert!(manifest.resolve_variables(&[], false).is_err());
+        let unknown = vec![("other".to_string(), "x".to_string())];
+        assert!(manifest.resolve_variables(&unknown, false).is_err());
+    }
+
+    #[test]
+    fn parses_variable_arguments() {
+        assert_eq!(
+            parse_template_variable("company=Acme Corp").unwrap(),
+            ("company".to_string(), "Acme Corp".to_string())
+        );
+        assert_eq!(
+            parse_template_variable("url=https://a.b/?c=d").unwrap(),
+            ("url".to_string(), "https://a.b/?c=d".to_string())
+        );
+        assert!(parse_template_variable("company").is_err());
+        assert!(parse_template_variable("=x").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::lib::error::DfxResult;
+use anyhow::bail;
+use clap::Args;
+use dfx_core::config::model::dfinity::ConfigInterface;
+
+/// Selects canisters by the `groups` and `tags` of dfx.json.
+#[derive(Args, Clone, Debug, Default)]
+pub struct CanisterGroupOpt {
+    /// Selects the canisters of a group in the `groups` of dfx.json.
+    #[arg(long, value_name = "GROUP")]
+    group: Option<String>,
+
+    /// Selects the canisters that have this tag in their `tags` in dfx.json.
+    #[arg(long, value_name = "TAG", conflicts_with = "group")]
+    tag: Option<String>,
+}
+
+impl CanisterGroupOpt {
+    pub fn is_present(&self) -> bool {
+        self.group.is_some() || self.tag.is_some()
+    }
+
+    /// The names of the selected canisters, or all canisters of dfx.json if neither --group nor --tag is given.
+    pub fn select_or_all(&self, config: &ConfigInterface) -> DfxResult<Vec<String>> {
+        Ok(match self.select(config)? {
+            Some(canisters) => canisters,
+            None => config
+                .canisters
+                .iter()
+                .flat_map(|canisters| canisters.keys().cloned())
+                .collect(),
+        })
+    }
+
+    /// The names of the selected canisters, or None if neither --group nor --tag is given.
+    pub fn select(&self, config: &ConfigInterface) -> DfxResult<Option<Vec<String>>> {
+        let canisters = config.canisters.clone().unwrap_or_default();
+        if let Some(group) = &self.group {
+            let Some(members) = config.groups.get(group) else {
+                bail!("Group '{group}' is not defined in the groups of dfx.json.");
+            };
+            for member in members {
+                if !canisters.contains_key(member) {
+                    bail!("Canister '{member}' of group '{group}' is not defined in dfx.json.");
+                }
+            }
+            Ok(Some(members.clone()))
+        } else if let Some(tag) = &self.tag {
+            let tagged: Vec<_> = canisters
+                .iter()
+                .filter(|(_, canister)| canister.tags.contains(tag))
+                .map(|(name, _)| name.clone())
+                .collect();
+            if tagged.is_empty() {
+                bail!("No canister in dfx.json has the tag '{tag}'.");
+            }
+            Ok(Some(tagged))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_groups_and_tags() {
+        let config: ConfigInterface = serde_json::from_str(
+            r#"{
+            "groups": { "backend": ["users", "orders"], "broken": ["missing"] },
+            "canisters": {
+                "users": { "main": "users.mo", "tags": ["shard"] },
+                "orders": { "main": "orders.mo", "tags": ["shard", "payments"] },
+                "frontend": { "type": "assets", "source": ["dist"] }
+            }
+        }"#,
+        )
+        .unwrap();
+        let select = |group: Option<&str>, tag: Option<&str>| {
+            CanisterGroupOpt {
+                group: group.map(str::to_string),
+                tag: tag.map(str::to_string),
+            }
+            .select(&config)
+        };
+
+        assert_eq!(select(None, None).unwrap(), None);
+        assert_eq!(
+            select(Some("backend"), None).unwrap(),
+            Some(vec!["users".to_string(), "orders".to_string()])
+        );
+        assert_eq!(
+            select(None, Some("shard")).unwrap(),
+            Some(vec!["orders".to_string(), "users".to_string()])
+        );
+        assert!(select(Some("frontend"), None).is_err());
+        assert!(select(Some("broken"), None).is_err());
+        assert!(select(None, Some("none")).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use anstyle::{AnsiColor, Style};
 use clap::builder::Styles;
 
 pub mod argument_from_cli;
+pub mod canister_group_opt;
 pub mod parsers;
 pub mod subnet_selection_opt;
 
@@ -9,7 +9,6 @@ use candid::types::{value::IDLValue, Function, Type, TypeEnv, TypeInner};
 use candid::{Decode, Encode, IDLArgs, Principal};
 use candid_parser::error::pretty_wrap;
 use candid_parser::utils::CandidSource;
-use dfx_core::fs::create_dir_all;
 use fn_error_context::context;
 use idl2json::{idl2json, Idl2JsonOptions};
 use num_traits::FromPrimitive;
@@ -78,14 +77,7 @@ pub fn print_idl_blob(
             println!("{}", hex_string);
         }
         "idl" | "pp" | "json" => {
-            let result = match method_type {
-                None => candid::IDLArgs::from_bytes(blob),
-                Some((env, func)) => candid::IDLArgs::from_bytes_with_types(blob, env, &func.rets),
-            };
-            if result.is_err() {
-                let hex_string = hex::encode(blob);
-                eprintln!("Error deserializing blob 0x{}", hex_string);
-            }
+            let result = decode_idl_blob(blob, method_type);
             if output_type == "idl" {
                 println!("{:?}", result?);
             } else if output_type == "json" {
@@ -100,6 +92,22 @@ pub fn print_idl_blob(
     Ok(())
 }
 
+/// Decodes the reply of a method, with its return types if they are known.
+pub fn decode_idl_blob(
+    blob: &[u8],
+    method_type: &Option<(TypeEnv, Function)>,
+) -> DfxResult<IDLArgs> {
+    let result = match method_type {
+        None => IDLArgs::from_bytes(blob),
+        Some((env, func)) => IDLArgs::from_bytes_with_types(blob, env, &func.rets),
+    };
+    if result.is_err() {
+        let hex_string = hex::encode(blob);
+        eprintln!("Error deserializing blob 0x{}", hex_string);
+    }
+    Ok(result?)
+}
+
 /// Candid typically comes as a tuple of values.  This converts a single value in such a tuple.
 fn convert_one(idl_value: &IDLValue) -> DfxResult<String> {
     let json_value = idl2json(idl_value, &Idl2JsonOptions::default());
@@ -151,6 +159,20 @@ pub async fn fetch_remote_did_file(
     )
 }
 
+/// Finds the type of a method of a canister in the `candid:service` metadata of the canister,
+/// or else in the Candid file of the project.
+pub async fn get_candid_type_of_canister(
+    agent: &ic_agent::Agent,
+    canister_id: Principal,
+    local_candid_path: Option<&Path>,
+    method_name: &str,
+) -> Option<(TypeEnv, Function)> {
+    match fetch_remote_did_file(agent, canister_id).await {
+        Some(did) => get_candid_type(CandidSource::Text(&did), method_name),
+        None => get_candid_type(CandidSource::File(local_candid_path?), method_name),
+    }
+}
+
 /// Parse IDL file into TypeEnv. This is a best effort function: it will succeed if
 /// the IDL file can be parsed and type checked in Rust parser, and has an
 /// actor in the IDL file. If anything fails, it returns None.
@@ -397,15 +419,6 @@ pub fn pretty_thousand_separators(num: String) -> String {
         .collect::<_>()
 }
 
-#[context("Failed to download {} to {}.", from, to.display())]
-pub async fn download_file_to_path(from: &Url, to: &Path) -> DfxResult {
-    let parent_dir = to.parent().unwrap();
-    create_dir_all(parent_dir)?;
-    let body = download_file(from).await?;
-    dfx_core::fs::write(to, body)?;
-    Ok(())
-}
-
 #[context("Failed to download from url: {}.", from)]
 pub async fn download_file(from: &Url) -> DfxResult<Vec<u8>> {
     let client = reqwest::Client::builder()
@@ -1,15 +1,18 @@
 //! Applies patches in the [Unified Format](https://www.gnu.org/software/diffutils/manual/html_node/Detailed-Unified.html) to files.
 
-// This will eventually be its own package with more fleshed-out features, but currently just has the bare minimum to implement `dfx new` against it.
+// This will eventually be its own package with more fleshed-out features, but currently just has what `dfx new` and its templates need.
 // Missing spots are marked `todo:` with a corresponding `mvp:` section explaining why it doesn't need to be in yet.
 
-use std::ops::Range;
+use std::io;
+use std::path::{Component, Path, PathBuf};
 
 // todo: reimplement ::patch. benefits: non-borrowed error, binary patching, consistent newlining, validation of non-overlapping and sortedness.
 // mvp: borrowed errors can be formatted into anyhow, all our patches are text patches to text files in a reasonable format
-use patch::{Hunk, Line, Patch};
+use patch::{Line, Patch};
 use thiserror::Error;
 
+const DEV_NULL: &str = "/dev/null";
+
 /// Applies a single-file patch to `content`.
 ///
 /// File paths in the patch file are ignored. Equivalent to `Settings::default().apply_to(patch, content)`.
@@ -22,8 +25,10 @@ pub fn apply_to(patch: &Patch, content: &str) -> Result<String, MismatchError> {
 pub struct Settings {
     ignore_line_numbers: bool,
     whitespace_insensitive: bool,
-    // todo: implement multi-patch application to directory. mvp: our patch files are specific
-    _reject_relative_path_segments: bool,
+    reject_relative_path_segments: bool,
+    fuzz: usize,
+    reverse: bool,
+    strip: usize,
 }
 
 impl Settings {
@@ -31,15 +36,20 @@ impl Settings {
     ///
     /// * The line numbers listed in the patch must be the location in the content
     /// * A whitespace-only difference in the content does not invalidate the patch
+    /// * All context lines must match
     /// * File paths in multi-file patches are disallowed from containing `..`
     pub fn new() -> Self {
         Self {
             ignore_line_numbers: false,
             whitespace_insensitive: true,
-            _reject_relative_path_segments: true,
+            reject_relative_path_segments: true,
+            fuzz: 0,
+            reverse: false,
+            strip: 0,
         }
     }
     /// Allows line numbers to differ between the patch file and the content.
+    /// Each hunk is applied at the matching location closest to its line number.
     pub fn ignore_line_numbers(self) -> Self {
         Self {
             ignore_line_numbers: true,
@@ -56,143 +66,398 @@ impl Settings {
     /// Allows `..` in file paths in multi-file patches (not recommended).
     pub fn allow_relative_path_segments(self) -> Self {
         Self {
-            _reject_relative_path_segments: false,
+            reject_relative_path_segments: false,
+            ..self
+        }
+    }
+    /// Allows up to `fuzz` context lines at the beginning and end of each hunk to not match the content,
+    /// like the fuzz factor of GNU patch.
+    pub fn fuzz(self, fuzz: usize) -> Self {
+        Self { fuzz, ..self }
+    }
+    /// Applies the patch in reverse, undoing it.
+    pub fn reverse(self) -> Self {
+        Self {
+            reverse: true,
+            ..self
+        }
+    }
+    /// Strips the first `components` components from file paths in multi-file patches,
+    /// e.g. 1 for the `a/` and `b/` prefixes of `git diff`.
+    pub fn strip(self, components: usize) -> Self {
+        Self {
+            strip: components,
             ..self
         }
     }
     /// Applies a single-file patch to `content`. File paths in the patch file are ignored.
     // todo: use an iterator instead of returning String. mvp: our files are small.
     pub fn apply_to(&self, patch: &Patch, content: &str) -> Result<String, MismatchError> {
-        assert!(is_patch_coherent(patch));
-        let original_content = content;
-        let mut expected_lines = vec![];
-        let mut patched_up_to = 0;
-        let mut patched_content = String::new();
-        for hunk in &patch.hunks {
-            // first, assemble the list of lines we expect to find in `content`
-            expected_lines.clear();
-            expected_lines.reserve(hunk.lines.len());
-            for hunk_line in &hunk.lines {
-                if let &Line::Context(s) | &Line::Remove(s) = hunk_line {
-                    expected_lines.push(s);
+        let (patched_content, rejected) = self.apply_hunks(patch, content)?;
+        match rejected.into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => Ok(patched_content),
+        }
+    }
+
+    /// Applies a multi-file patch, as produced by `diff -ruN` or `git diff`, to the files in `dir`.
+    ///
+    /// Files whose old path is `/dev/null` are created, and files whose new path is `/dev/null` are deleted.
+    /// All paths are checked before any file is changed. Hunks that cannot be applied are written to
+    /// a `.rej` file next to the file they belong to, and the other hunks are applied.
+    pub fn apply_to_dir(&self, patches: &[Patch], dir: &Path) -> Result<(), ApplyToDirError> {
+        let mut targets = Vec::with_capacity(patches.len());
+        for patch in patches {
+            let (old, new) = if self.reverse {
+                (&patch.new.path, &patch.old.path)
+            } else {
+                (&patch.old.path, &patch.new.path)
+            };
+            let old = self.resolve_path(dir, old)?;
+            let new = self.resolve_path(dir, new)?;
+            match (&old, &new) {
+                (None, None) => {}
+                (None, Some(new)) if new.exists() => {
+                    return Err(ApplyToDirError::AlreadyExists(new.clone()))
+                }
+                (Some(old), _) if !old.is_file() => {
+                    return Err(ApplyToDirError::NotFound(old.clone()))
                 }
+                _ => {}
             }
-            // second, find and attempt to match them
-            let found_range = if self.ignore_line_numbers {
-                // todo: implement line-number-agnostic patching. mvp: all our patch files have exact line numbers known.
-                unimplemented!()
+            targets.push((old, new));
+        }
+
+        let mut rejected_files = vec![];
+        for (patch, (old, new)) in patches.iter().zip(targets) {
+            let Some(path) = new.as_ref().or(old.as_ref()) else {
+                continue;
+            };
+            let content = match &old {
+                Some(old) => {
+                    std::fs::read_to_string(old).map_err(|source| ApplyToDirError::ReadFailed {
+                        path: old.clone(),
+                        source,
+                    })?
+                }
+                None => String::new(),
+            };
+            let (patched_content, rejected) = self
+                .apply_hunks(patch, &content)
+                .map_err(|error| ApplyToDirError::InvalidPatch(path.clone(), error))?;
+
+            if new.is_none() && rejected.is_empty() && patched_content.is_empty() {
+                remove_file(path)?;
             } else {
-                self.find_fixed_range(original_content, hunk, &expected_lines)?
+                write_file(path, &patched_content)?;
+                if let (Some(old), Some(new)) = (&old, &new) {
+                    if old != new {
+                        remove_file(old)?;
+                    }
+                }
+            }
+
+            if !rejected.is_empty() {
+                let mut reject_path = path.clone().into_os_string();
+                reject_path.push(".rej");
+                let reject_path = PathBuf::from(reject_path);
+                let indices: Vec<usize> = rejected.iter().map(|(index, _)| *index).collect();
+                write_file(&reject_path, &format_hunks(patch, &indices))?;
+                rejected_files.push(RejectedFile {
+                    path: path.clone(),
+                    reject_path,
+                    errors: rejected.into_iter().map(|(_, error)| error).collect(),
+                });
+            }
+        }
+        if rejected_files.is_empty() {
+            Ok(())
+        } else {
+            Err(ApplyToDirError::HunksRejected(rejected_files))
+        }
+    }
+
+    /// The path of a file in a multi-file patch, or `None` for `/dev/null`.
+    fn resolve_path(&self, dir: &Path, path: &str) -> Result<Option<PathBuf>, ApplyToDirError> {
+        if path == DEV_NULL {
+            return Ok(None);
+        }
+        let unsafe_path = || ApplyToDirError::UnsafePath(path.to_string());
+        let mut relative = PathBuf::new();
+        for component in Path::new(path).components().skip(self.strip) {
+            match component {
+                Component::Normal(c) => relative.push(c),
+                Component::CurDir => {}
+                Component::ParentDir if !self.reject_relative_path_segments => relative.push(".."),
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(unsafe_path())
+                }
+            }
+        }
+        if relative.as_os_str().is_empty() || Path::new(path).is_absolute() {
+            return Err(unsafe_path());
+        }
+        Ok(Some(dir.join(relative)))
+    }
+
+    /// Applies the hunks that match, and returns the patched content along with the hunks that don't.
+    fn apply_hunks(
+        &self,
+        patch: &Patch,
+        content: &str,
+    ) -> Result<(String, Vec<(usize, MismatchError)>), MismatchError> {
+        let hunks = self.hunks(patch);
+        // each patch file should be sorted and non-overlapping
+        if !hunks
+            .windows(2)
+            .all(|h| h[0].old_start + h[0].old_len() <= h[1].old_start)
+        {
+            return Err(MismatchError::UnsortedHunks);
+        }
+        let offsets = line_offsets(content);
+        let mut patched_content = String::new();
+        let mut patched_up_to = 0;
+        let mut rejected = vec![];
+        for (index, hunk) in hunks.iter().enumerate() {
+            let (start, lines) = match self.find_hunk(content, &offsets, hunk, patched_up_to) {
+                Ok(found) => found,
+                Err(error) => {
+                    rejected.push((index, error));
+                    continue;
+                }
             };
-            // first copy to the output all the content between either the last patched range or the beginning of the file, and the beginning of this patch
-            patched_content.push_str(&original_content[patched_up_to..found_range.start]);
-            patched_up_to = found_range.end;
+            // first copy to the output all the content between either the last patched range or the beginning of the file, and the beginning of this hunk
+            patched_content.push_str(&content[offsets[patched_up_to]..offsets[start]]);
             // then interleave the context lines with the added lines
-            self.patch_content(&original_content[found_range], hunk, &mut patched_content);
-        }
-        // finally, copy everything between the final patch and the end of the file
-        patched_content.push_str(&original_content[patched_up_to..]);
-        Ok(patched_content)
-    }
-
-    fn patch_content(&self, original_content: &str, hunk: &Hunk, patched_content: &mut String) {
-        let mut orig_lines = original_content.lines();
-        for hunk_line in &hunk.lines {
-            match *hunk_line {
-                Line::Context(_) => {
-                    // in the case of a context line, push the original line, not the one from the patch file
-                    // this may be a whitespace-insensitive patch, and we don't want to modify any lines that aren't marked `-`
-                    patched_content.push_str(orig_lines.next().unwrap());
-                    patched_content.push('\n');
+            let mut line = start;
+            for hunk_line in lines {
+                match *hunk_line {
+                    HunkLine::Context(_) => {
+                        // in the case of a context line, push the original line, not the one from the patch file
+                        // this may be a whitespace-insensitive patch, and we don't want to modify any lines that aren't marked `-`
+                        patched_content.push_str(&content[offsets[line]..offsets[line + 1]]);
+                        line += 1;
+                    }
+                    HunkLine::Add(s) => {
+                        patched_content.push_str(s);
+                        patched_content.push('\n');
+                    }
+                    HunkLine::Remove(_) => line += 1,
                 }
-                Line::Add(s) => {
-                    patched_content.push_str(s);
-                    patched_content.push('\n');
-                }
-                Line::Remove(_) => {
-                    orig_lines.next().unwrap();
+            }
+            patched_up_to = line;
+        }
+        // finally, copy everything between the final hunk and the end of the file
+        patched_content.push_str(&content[offsets[patched_up_to]..]);
+        Ok((patched_content, rejected))
+    }
+
+    fn hunks<'a>(&self, patch: &Patch<'a>) -> Vec<HunkView<'a>> {
+        patch
+            .hunks
+            .iter()
+            .map(|hunk| {
+                let old_range = if self.reverse {
+                    &hunk.new_range
+                } else {
+                    &hunk.old_range
+                };
+                // line numbers are all 1-indexed, except that an empty range starts after the line it names
+                let old_start = if old_range.count == 0 {
+                    old_range.start as usize
+                } else {
+                    old_range.start.saturating_sub(1) as usize
+                };
+                let lines = hunk
+                    .lines
+                    .iter()
+                    .map(|line| match *line {
+                        Line::Context(s) => HunkLine::Context(s),
+                        Line::Add(s) if self.reverse => HunkLine::Remove(s),
+                        Line::Add(s) => HunkLine::Add(s),
+                        Line::Remove(s) if self.reverse => HunkLine::Add(s),
+                        Line::Remove(s) => HunkLine::Remove(s),
+                    })
+                    .collect();
+                HunkView { old_start, lines }
+            })
+            .collect()
+    }
+
+    /// Finds the line where the hunk applies, at or after `min_line`, along with the lines of the hunk to apply there.
+    /// With fuzz, up to that many context lines are dropped from the beginning and end of the hunk.
+    fn find_hunk<'h, 'a>(
+        &self,
+        content: &str,
+        offsets: &[usize],
+        hunk: &'h HunkView<'a>,
+        min_line: usize,
+    ) -> Result<(usize, &'h [HunkLine<'a>]), MismatchError> {
+        let line_count = offsets.len() - 1;
+        let is_context = |line: &&HunkLine| matches!(line, HunkLine::Context(_));
+        let leading_context = hunk.lines.iter().take_while(is_context).count();
+        let trailing_context = hunk.lines.iter().rev().take_while(is_context).count();
+        let mut first_error = None;
+        for fuzz in 0..=self.fuzz {
+            let front = fuzz.min(leading_context);
+            let back = fuzz.min(trailing_context);
+            if fuzz > 0 && front + back >= hunk.lines.len() {
+                break;
+            }
+            let lines = &hunk.lines[front..hunk.lines.len() - back];
+            let expected_lines: Vec<&str> = lines
+                .iter()
+                .filter_map(|line| match *line {
+                    HunkLine::Context(s) | HunkLine::Remove(s) => Some(s),
+                    HunkLine::Add(_) => None,
+                })
+                .collect();
+            let expected_start = hunk.old_start + front;
+            let candidates: Box<dyn Iterator<Item = usize>> = if self.ignore_line_numbers {
+                Box::new(lines_by_distance(expected_start, line_count))
+            } else {
+                Box::new(std::iter::once(expected_start))
+            };
+            for start in candidates.filter(|start| *start >= min_line) {
+                match self.check_lines(content, offsets, start, &expected_lines) {
+                    Ok(()) => return Ok((start, lines)),
+                    Err(error) => {
+                        first_error.get_or_insert(error);
+                    }
                 }
             }
         }
+        Err(first_error.unwrap_or(MismatchError::NotEnoughLines {
+            expected: hunk.old_start + hunk.old_len(),
+            found: line_count,
+        }))
     }
 
-    fn find_fixed_range(
+    fn check_lines(
         &self,
-        original_content: &str,
-        hunk: &Hunk,
+        content: &str,
+        offsets: &[usize],
+        start: usize,
         expected_lines: &[&str],
-    ) -> Result<Range<usize>, MismatchError> {
-        let mut line_indices = original_content
-            .match_indices('\n')
-            .map(|(newline_index, _)| newline_index + 1);
-        // line numbers are all 1-indexed - this is specifically the parsed number
-        let start_line = hunk.old_range.start as usize - 1;
-        // line n starts one past the n-1th newline
-        let start = if start_line == 0 {
-            0
-        } else {
-            line_indices
-                .nth(start_line - 1)
-                .ok_or_else(|| MismatchError::NotEnoughLines {
-                    expected: start_line - 1,
-                    found: original_content.lines().count(),
-                })?
-        };
-        // start..end should be the byte range in `content` to be patched
-        let end = line_indices.nth(expected_lines.len() - 1).ok_or_else(|| {
-            MismatchError::NotEnoughLines {
-                expected: start_line + expected_lines.len() - 1,
-                found: original_content.lines().count(),
-            }
-        })?;
-        check_equal_range(
-            &original_content[start..end],
-            expected_lines,
-            self.whitespace_insensitive,
-            start_line,
-        )?;
-        Ok(start..end)
-    }
-}
-
-fn is_patch_coherent(patch: &Patch) -> bool {
-    // each patch file should be sorted and non-overlapping
-    patch
-        .hunks
-        .iter()
-        .zip(&patch.hunks[1..])
-        .all(|(h1, h2)| h1.old_range.start + h1.old_range.count < h2.old_range.start)
-}
-fn check_equal_range(
-    content: &str,
-    lines: &[&str],
-    whitespace_insensitive: bool,
-    context_line_number: usize,
-) -> Result<(), MismatchError> {
-    for (i, (from_content, &from_patch)) in content.lines().zip(lines).enumerate() {
-        if whitespace_insensitive {
-            if !from_content
-                .chars()
-                .filter(|ch| !ch.is_whitespace())
-                .eq(from_patch.chars().filter(|ch| !ch.is_whitespace()))
-            {
+    ) -> Result<(), MismatchError> {
+        let line_count = offsets.len() - 1;
+        if start + expected_lines.len() > line_count {
+            return Err(MismatchError::NotEnoughLines {
+                expected: start + expected_lines.len(),
+                found: line_count,
+            });
+        }
+        for (i, &from_patch) in expected_lines.iter().enumerate() {
+            let from_content = line_at(content, offsets, start + i);
+            let equal = if self.whitespace_insensitive {
+                from_content
+                    .chars()
+                    .filter(|ch| !ch.is_whitespace())
+                    .eq(from_patch.chars().filter(|ch| !ch.is_whitespace()))
+            } else {
+                from_content == from_patch
+            };
+            if !equal {
                 return Err(MismatchError::LineMismatch {
                     from_content: from_content.to_string(),
                     from_patch: from_patch.to_string(),
-                    whitespace_insensitive,
-                    line: i + context_line_number,
+                    whitespace_insensitive: self.whitespace_insensitive,
+                    line: start + i + 1,
                 });
             }
-        } else if from_content != from_patch {
-            return Err(MismatchError::LineMismatch {
-                from_content: from_content.to_string(),
-                from_patch: from_patch.to_string(),
-                whitespace_insensitive,
-                line: i + context_line_number,
-            });
+        }
+        Ok(())
+    }
+}
+
+/// A hunk with the old and new side swapped when applying in reverse.
+struct HunkView<'a> {
+    /// The 0-indexed line where the hunk starts in the content to patch.
+    old_start: usize,
+    lines: Vec<HunkLine<'a>>,
+}
+
+enum HunkLine<'a> {
+    Context(&'a str),
+    Add(&'a str),
+    Remove(&'a str),
+}
+
+impl HunkView<'_> {
+    fn old_len(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| !matches!(line, HunkLine::Add(_)))
+            .count()
+    }
+}
+
+/// The byte offset of the start of each line, followed by the end of the content.
+fn line_offsets(content: &str) -> Vec<usize> {
+    let mut offsets = vec![0];
+    offsets.extend(content.match_indices('\n').map(|(index, _)| index + 1));
+    if !content.ends_with('\n') && !content.is_empty() {
+        offsets.push(content.len());
+    }
+    offsets
+}
+
+fn line_at<'a>(content: &'a str, offsets: &[usize], line: usize) -> &'a str {
+    let line = &content[offsets[line]..offsets[line + 1]];
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// All lines of the content, ordered by their distance from `line`.
+fn lines_by_distance(line: usize, line_count: usize) -> impl Iterator<Item = usize> {
+    (0..=line.max(line_count)).flat_map(move |distance| {
+        let after = Some(line + distance).filter(|after| *after <= line_count);
+        let before = line.checked_sub(distance).filter(|_| distance > 0);
+        after.into_iter().chain(before)
+    })
+}
+
+/// Formats the hunks with the given indices as a patch, as for a `.rej` file.
+fn format_hunks(patch: &Patch, indices: &[usize]) -> String {
+    let mut formatted = format!("--- {}\n+++ {}\n", patch.old.path, patch.new.path);
+    for &index in indices {
+        let hunk = &patch.hunks[index];
+        formatted.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk.old_range.start, hunk.old_range.count, hunk.new_range.start, hunk.new_range.count
+        ));
+        for line in &hunk.lines {
+            let (prefix, s) = match *line {
+                Line::Context(s) => (' ', s),
+                Line::Add(s) => ('+', s),
+                Line::Remove(s) => ('-', s),
+            };
+            formatted.push(prefix);
+            formatted.push_str(s);
+            formatted.push('\n');
         }
     }
-    Ok(())
+    formatted
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), ApplyToDirError> {
+    let write = || {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)
+    };
+    write().map_err(|source| ApplyToDirError::WriteFailed {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn remove_file(path: &Path) -> Result<(), ApplyToDirError> {
+    std::fs::remove_file(path).map_err(|source| ApplyToDirError::RemoveFailed {
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
 impl Default for Settings {
@@ -207,7 +472,7 @@ pub enum MismatchError {
         "File too short: attempted to patch line {expected}, but file was only {found} lines long"
     )]
     NotEnoughLines { expected: usize, found: usize },
-    #[error("Mismatch between context/removal line and file at line {line}: {from_patch:?} (patch) {op} {from_content:?} (content)", 
+    #[error("Mismatch between context/removal line and file at line {line}: {from_patch:?} (patch) {op} {from_content:?} (content)",
         op = if *.whitespace_insensitive { "!~" } else { "!=" })]
     LineMismatch {
         from_patch: String,
@@ -215,4 +480,51 @@ pub enum MismatchError {
         whitespace_insensitive: bool,
         line: usize,
     },
+    #[error("The hunks of the patch are not sorted, or overlap")]
+    UnsortedHunks,
+}
+
+/// A file with hunks that could not be applied.
+#[derive(Debug)]
+pub struct RejectedFile {
+    /// The patched file.
+    pub path: PathBuf,
+    /// The `.rej` file that the rejected hunks were written to.
+    pub reject_path: PathBuf,
+    /// Why each hunk was rejected.
+    pub errors: Vec<MismatchError>,
+}
+
+#[derive(Debug, Error)]
+pub enum ApplyToDirError {
+    #[error(
+        "Refusing to patch {0}: paths in patches must be relative and stay inside the directory"
+    )]
+    UnsafePath(String),
+    #[error("Cannot create {0}: it already exists")]
+    AlreadyExists(PathBuf),
+    #[error("Cannot patch {0}: it does not exist")]
+    NotFound(PathBuf),
+    #[error("Invalid patch for {0}")]
+    InvalidPatch(PathBuf, #[source] MismatchError),
+    #[error("Failed to read {path}")]
+    ReadFailed {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to write {path}")]
+    WriteFailed {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Failed to remove {path}")]
+    RemoveFailed {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Some hunks could not be applied, see {}", .0.iter().map(|file| file.reject_path.display().to_string()).collect::<Vec<_>>().join(", "))]
+    HunksRejected(Vec<RejectedFile>),
 }
@@ -1,5 +1,6 @@
-use apply_patch::apply_to;
+use apply_patch::{apply_to, ApplyToDirError, MismatchError, Settings};
 use patch::Patch;
+use std::fs;
 
 #[test]
 fn lao_tzu() {
@@ -10,3 +11,157 @@ fn lao_tzu() {
     let patched = apply_to(&patch, lao).unwrap();
     assert_eq!(patched, tzu);
 }
+
+#[test]
+fn lao_tzu_reverse() {
+    let patch = include_str!("example.patch");
+    let lao = include_str!("lao.txt");
+    let tzu = include_str!("tzu.txt");
+    let patch = Patch::from_single(patch).unwrap();
+    let unpatched = Settings::new().reverse().apply_to(&patch, tzu).unwrap();
+    assert_eq!(unpatched, lao);
+}
+
+#[test]
+fn offset_and_fuzz() {
+    let patch = include_str!("example.patch");
+    let lao = include_str!("lao.txt");
+    let tzu = include_str!("tzu.txt");
+    let patch = Patch::from_single(patch).unwrap();
+
+    // Two lines were added at the top, so the line numbers of the patch are off by two.
+    let shifted_lao = format!("Preface\n\n{lao}");
+    assert!(apply_to(&patch, &shifted_lao).is_err());
+    let patched = Settings::new()
+        .ignore_line_numbers()
+        .apply_to(&patch, &shifted_lao)
+        .unwrap();
+    assert_eq!(patched, format!("Preface\n\n{tzu}"));
+
+    // The last context line of the first hunk was changed.
+    let edited_lao = lao.replace(
+        "And let there always be being,",
+        "And let there always be something,",
+    );
+    let edited_tzu = tzu.replace(
+        "And let there always be being,",
+        "And let there always be something,",
+    );
+    assert!(matches!(
+        Settings::new().apply_to(&patch, &edited_lao),
+        Err(MismatchError::LineMismatch { .. })
+    ));
+    let patched = Settings::new()
+        .fuzz(1)
+        .apply_to(&patch, &edited_lao)
+        .unwrap();
+    assert_eq!(patched, edited_tzu);
+}
+
+const MULTI_FILE_PATCH: &str = "\
+--- a/src/main.mo
++++ b/src/main.mo
+@@ -1,3 +1,3 @@
+ actor {
+-  public query func greet() : async Text { \"Hello\" };
++  public query func greet() : async Text { \"Hello, world\" };
+ };
+--- /dev/null
++++ b/src/new.txt
+@@ -0,0 +1,2 @@
++first
++second
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-obsolete
+";
+
+#[test]
+fn multi_file_patch() {
+    let dir = tempfile::tempdir().unwrap();
+    let main = "actor {\n  public query func greet() : async Text { \"Hello\" };\n};\n";
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/main.mo"), main).unwrap();
+    fs::write(dir.path().join("old.txt"), "obsolete\n").unwrap();
+    let patches = Patch::from_multiple(MULTI_FILE_PATCH).unwrap();
+
+    // Without stripping the a/ and b/ prefixes, the files are not found.
+    assert!(matches!(
+        Settings::new().apply_to_dir(&patches, dir.path()),
+        Err(ApplyToDirError::NotFound(_))
+    ));
+
+    Settings::new()
+        .strip(1)
+        .apply_to_dir(&patches, dir.path())
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("src/main.mo")).unwrap(),
+        main.replace("\"Hello\"", "\"Hello, world\"")
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("src/new.txt")).unwrap(),
+        "first\nsecond\n"
+    );
+    assert!(!dir.path().join("old.txt").exists());
+
+    Settings::new()
+        .strip(1)
+        .reverse()
+        .apply_to_dir(&patches, dir.path())
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("src/main.mo")).unwrap(),
+        main
+    );
+    assert!(!dir.path().join("src/new.txt").exists());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("old.txt")).unwrap(),
+        "obsolete\n"
+    );
+}
+
+#[test]
+fn rejected_hunks_are_written_to_rej_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/main.mo"),
+        "actor {\n  // changed\n};\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("old.txt"), "obsolete\n").unwrap();
+    let patches = Patch::from_multiple(MULTI_FILE_PATCH).unwrap();
+
+    let Err(ApplyToDirError::HunksRejected(rejected)) =
+        Settings::new().strip(1).apply_to_dir(&patches, dir.path())
+    else {
+        panic!("expected the hunk of src/main.mo to be rejected");
+    };
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].path, dir.path().join("src/main.mo"));
+    let rej = fs::read_to_string(dir.path().join("src/main.mo.rej")).unwrap();
+    assert!(rej.starts_with("--- a/src/main.mo\n+++ b/src/main.mo\n@@ -1,3 +1,3 @@\n"));
+    assert!(rej.contains("+  public query func greet() : async Text { \"Hello, world\" };\n"));
+    // The other files were patched.
+    assert!(dir.path().join("src/new.txt").exists());
+    assert!(!dir.path().join("old.txt").exists());
+}
+
+#[test]
+fn unsafe_paths_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    for path in ["../outside.txt", "/etc/passwd", "src/../../outside.txt"] {
+        let patch = format!("--- /dev/null\n+++ {path}\n@@ -0,0 +1 @@\n+content\n");
+        let patches = Patch::from_multiple(&patch).unwrap();
+        assert!(
+            matches!(
+                Settings::new().apply_to_dir(&patches, dir.path()),
+                Err(ApplyToDirError::UnsafePath(_))
+            ),
+            "{path}"
+        );
+    }
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}